@@ -0,0 +1,337 @@
+use std::collections::{BTreeMap, HashMap};
+use std::hint::black_box;
+use std::time::Duration;
+
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+#[cfg(feature = "fast")]
+use red_black_tree::data_structure::red_black_tree::RedBlackTreeFast;
+use red_black_tree::data_structure::avl_tree::AvlTree;
+use red_black_tree::data_structure::llrb::LlrbTree;
+use red_black_tree::data_structure::red_black_tree::{PersistentRedBlackTree, RedBlackTree, RedBlackTreeArena, RedBlackTreeTopDown};
+use red_black_tree::data_structure::skip_list::SkipList;
+use red_black_tree::data_structure::treap::Treap;
+
+const SIZES: [u64; 3] = [1_000, 10_000, 100_000];
+
+///各实现共同的操作，值与键相同
+trait BenchMap {
+    const NAME: &'static str;
+    //HashMap无序，不参与有序迭代
+    const ORDERED: bool = true;
+    fn empty() -> Self;
+    fn put(&mut self, key: u64);
+    fn has(&self, key: &u64) -> bool;
+    fn remove_key(&mut self, key: &u64);
+    ///按键序遍历并求和
+    fn sum(&self) -> u64;
+}
+
+impl BenchMap for RedBlackTree<u64, u64> {
+    const NAME: &'static str = "RedBlackTree";
+    fn empty() -> Self {
+        RedBlackTree::new()
+    }
+    fn put(&mut self, key: u64) {
+        self.insert(key, key);
+    }
+    fn has(&self, key: &u64) -> bool {
+        self.contains(key)
+    }
+    fn remove_key(&mut self, key: &u64) {
+        self.delete(key);
+    }
+    fn sum(&self) -> u64 {
+        self.iter().map(|(_, value)| value).sum()
+    }
+}
+
+impl BenchMap for RedBlackTreeArena<u64, u64> {
+    const NAME: &'static str = "RedBlackTreeArena";
+    fn empty() -> Self {
+        RedBlackTreeArena::new()
+    }
+    fn put(&mut self, key: u64) {
+        self.insert(key, key);
+    }
+    fn has(&self, key: &u64) -> bool {
+        self.contains(key)
+    }
+    fn remove_key(&mut self, key: &u64) {
+        self.delete(key);
+    }
+    fn sum(&self) -> u64 {
+        self.iter().map(|(_, value)| value).sum()
+    }
+}
+
+impl BenchMap for RedBlackTreeTopDown<u64, u64> {
+    const NAME: &'static str = "RedBlackTreeTopDown";
+    fn empty() -> Self {
+        RedBlackTreeTopDown::new()
+    }
+    fn put(&mut self, key: u64) {
+        self.insert(key, key);
+    }
+    fn has(&self, key: &u64) -> bool {
+        self.contains(key)
+    }
+    fn remove_key(&mut self, key: &u64) {
+        self.delete(key);
+    }
+    fn sum(&self) -> u64 {
+        self.iter().map(|(_, value)| value).sum()
+    }
+}
+
+impl BenchMap for PersistentRedBlackTree<u64, u64> {
+    const NAME: &'static str = "PersistentRedBlackTree";
+    fn empty() -> Self {
+        PersistentRedBlackTree::new()
+    }
+    fn put(&mut self, key: u64) {
+        self.insert(key, key);
+    }
+    fn has(&self, key: &u64) -> bool {
+        self.contains(key)
+    }
+    fn remove_key(&mut self, key: &u64) {
+        self.delete(key);
+    }
+    fn sum(&self) -> u64 {
+        self.iter().map(|(_, value)| value).sum()
+    }
+}
+
+impl BenchMap for LlrbTree<u64, u64> {
+    const NAME: &'static str = "LlrbTree";
+    fn empty() -> Self {
+        LlrbTree::new()
+    }
+    fn put(&mut self, key: u64) {
+        self.insert(key, key);
+    }
+    fn has(&self, key: &u64) -> bool {
+        self.contains(key)
+    }
+    fn remove_key(&mut self, key: &u64) {
+        self.delete(key);
+    }
+    fn sum(&self) -> u64 {
+        self.iter().map(|(_, value)| value).sum()
+    }
+}
+
+impl BenchMap for AvlTree<u64, u64> {
+    const NAME: &'static str = "AvlTree";
+    fn empty() -> Self {
+        AvlTree::new()
+    }
+    fn put(&mut self, key: u64) {
+        self.insert(key, key);
+    }
+    fn has(&self, key: &u64) -> bool {
+        self.contains(key)
+    }
+    fn remove_key(&mut self, key: &u64) {
+        self.delete(key);
+    }
+    fn sum(&self) -> u64 {
+        self.iter().map(|(_, value)| value).sum()
+    }
+}
+
+impl BenchMap for Treap<u64, u64> {
+    const NAME: &'static str = "Treap";
+    fn empty() -> Self {
+        Treap::new()
+    }
+    fn put(&mut self, key: u64) {
+        self.insert(key, key);
+    }
+    fn has(&self, key: &u64) -> bool {
+        self.contains(key)
+    }
+    fn remove_key(&mut self, key: &u64) {
+        self.delete(key);
+    }
+    fn sum(&self) -> u64 {
+        self.iter().map(|(_, value)| value).sum()
+    }
+}
+
+impl BenchMap for SkipList<u64, u64> {
+    const NAME: &'static str = "SkipList";
+    fn empty() -> Self {
+        SkipList::new()
+    }
+    fn put(&mut self, key: u64) {
+        self.insert(key, key);
+    }
+    fn has(&self, key: &u64) -> bool {
+        self.contains(key)
+    }
+    fn remove_key(&mut self, key: &u64) {
+        self.delete(key);
+    }
+    fn sum(&self) -> u64 {
+        self.iter().map(|(_, value)| value).sum()
+    }
+}
+
+#[cfg(feature = "fast")]
+impl BenchMap for RedBlackTreeFast<u64, u64> {
+    const NAME: &'static str = "RedBlackTreeFast";
+    fn empty() -> Self {
+        RedBlackTreeFast::new()
+    }
+    fn put(&mut self, key: u64) {
+        self.insert(key, key);
+    }
+    fn has(&self, key: &u64) -> bool {
+        self.contains(key)
+    }
+    fn remove_key(&mut self, key: &u64) {
+        self.delete(key);
+    }
+    fn sum(&self) -> u64 {
+        self.iter().map(|(_, value)| value).sum()
+    }
+}
+
+impl BenchMap for BTreeMap<u64, u64> {
+    const NAME: &'static str = "BTreeMap";
+    fn empty() -> Self {
+        BTreeMap::new()
+    }
+    fn put(&mut self, key: u64) {
+        self.insert(key, key);
+    }
+    fn has(&self, key: &u64) -> bool {
+        self.contains_key(key)
+    }
+    fn remove_key(&mut self, key: &u64) {
+        self.remove(key);
+    }
+    fn sum(&self) -> u64 {
+        self.values().sum()
+    }
+}
+
+impl BenchMap for HashMap<u64, u64> {
+    const NAME: &'static str = "HashMap";
+    const ORDERED: bool = false;
+    fn empty() -> Self {
+        HashMap::new()
+    }
+    fn put(&mut self, key: u64) {
+        self.insert(key, key);
+    }
+    fn has(&self, key: &u64) -> bool {
+        self.contains_key(key)
+    }
+    fn remove_key(&mut self, key: &u64) {
+        self.remove(key);
+    }
+    fn sum(&self) -> u64 {
+        self.values().sum()
+    }
+}
+
+///乱序的0..n
+fn shuffled(n: u64) -> Vec<u64> {
+    let mut keys: Vec<u64> = (0..n).collect();
+    keys.shuffle(&mut StdRng::seed_from_u64(n));
+    keys
+}
+
+fn build<M: BenchMap>(keys: &[u64]) -> M {
+    let mut map = M::empty();
+    for key in keys {
+        map.put(*key);
+    }
+    map
+}
+
+/// 混合负载：50%插入，30%查找，20%删除
+/// 预先生成操作序列，计时只包含对集合的操作
+fn mixed_ops(n: u64) -> Vec<(u8, u64)> {
+    let mut rng = StdRng::seed_from_u64(n ^ 0x2034);
+    (0..n).map(|_| (rng.gen_range(0..10), rng.gen_range(0..n * 2))).collect()
+}
+
+fn bench_map<M: BenchMap>(c: &mut Criterion) {
+    for n in SIZES {
+        let keys = shuffled(n);
+        let lookups = shuffled(n * 2);
+        let ops = mixed_ops(n);
+        let prebuilt: M = build(&keys);
+
+        c.benchmark_group(format!("insert/{}", n)).bench_function(BenchmarkId::from_parameter(M::NAME), |b| {
+            b.iter(|| build::<M>(black_box(&keys)))
+        });
+        //一半命中，一半未命中
+        c.benchmark_group(format!("lookup/{}", n)).bench_function(BenchmarkId::from_parameter(M::NAME), |b| {
+            b.iter(|| lookups.iter().filter(|key| prebuilt.has(key)).count())
+        });
+        c.benchmark_group(format!("delete/{}", n)).bench_function(BenchmarkId::from_parameter(M::NAME), |b| {
+            b.iter_batched(
+                || build::<M>(&keys),
+                |mut map| {
+                    for key in &keys {
+                        map.remove_key(key);
+                    }
+                    map
+                },
+                BatchSize::LargeInput,
+            )
+        });
+        if M::ORDERED {
+            c.benchmark_group(format!("iterate/{}", n)).bench_function(BenchmarkId::from_parameter(M::NAME), |b| {
+                b.iter(|| prebuilt.sum())
+            });
+        }
+        c.benchmark_group(format!("mixed/{}", n)).bench_function(BenchmarkId::from_parameter(M::NAME), |b| {
+            b.iter_batched(
+                || build::<M>(&keys),
+                |mut map| {
+                    let mut hits = 0;
+                    for (op, key) in &ops {
+                        match op {
+                            0..=4 => map.put(*key),
+                            5..=7 => hits += usize::from(map.has(key)),
+                            _ => map.remove_key(key),
+                        }
+                    }
+                    (map, hits)
+                },
+                BatchSize::LargeInput,
+            )
+        });
+    }
+}
+
+fn compare(c: &mut Criterion) {
+    bench_map::<RedBlackTree<u64, u64>>(c);
+    bench_map::<RedBlackTreeArena<u64, u64>>(c);
+    bench_map::<RedBlackTreeTopDown<u64, u64>>(c);
+    bench_map::<PersistentRedBlackTree<u64, u64>>(c);
+    bench_map::<LlrbTree<u64, u64>>(c);
+    bench_map::<AvlTree<u64, u64>>(c);
+    bench_map::<Treap<u64, u64>>(c);
+    bench_map::<SkipList<u64, u64>>(c);
+    #[cfg(feature = "fast")]
+    bench_map::<RedBlackTreeFast<u64, u64>>(c);
+    bench_map::<BTreeMap<u64, u64>>(c);
+    bench_map::<HashMap<u64, u64>>(c);
+}
+
+criterion_group! {
+    name = benches;
+    //实现与规模较多，缩短单项时间
+    config = Criterion::default().sample_size(20).warm_up_time(Duration::from_secs(1)).measurement_time(Duration::from_secs(2));
+    targets = compare
+}
+criterion_main!(benches);
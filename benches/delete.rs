@@ -0,0 +1,68 @@
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use criterion::{BenchmarkId, Criterion};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use red_black_tree::data_structure::red_black_tree::RedBlackTree;
+
+//规模从小到大，最大规模为一千万
+const SIZES: [u64; 3] = [1_000, 100_000, 10_000_000];
+//门禁采样的删除次数
+const GATE_SAMPLES: u32 = 200_000;
+//缓存未命中等常数因素的容忍倍数，线性退化时比值远超此值
+const GATE_SLACK: f64 = 16.0;
+
+///乱序插入0..n，构造树
+fn build(n: u64, rng: &mut StdRng) -> RedBlackTree<u64, u64> {
+    let mut keys: Vec<u64> = (0..n).collect();
+    keys.shuffle(rng);
+    let mut rbt = RedBlackTree::new();
+    for key in keys {
+        rbt.insert(key, key);
+    }
+    rbt
+}
+
+///随机删除iters次，只计删除耗时，删除后插回以保持规模不变
+fn timed_deletes(rbt: &mut RedBlackTree<u64, u64>, n: u64, iters: u64, rng: &mut StdRng) -> Duration {
+    let mut elapsed = Duration::ZERO;
+    for _ in 0..iters {
+        let key = rng.gen_range(0..n);
+        let start = Instant::now();
+        rbt.delete(black_box(&key));
+        elapsed += start.elapsed();
+        rbt.insert(key, key);
+    }
+    elapsed
+}
+
+fn main() {
+    let mut criterion = Criterion::default().configure_from_args();
+    let mut rng = StdRng::seed_from_u64(2017);
+    //每个规模的平均删除延迟(纳秒)
+    let mut latencies = Vec::new();
+    {
+        let mut group = criterion.benchmark_group("delete");
+        for n in SIZES {
+            let mut rbt = build(n, &mut rng);
+            group.bench_function(BenchmarkId::from_parameter(n), |b| {
+                b.iter_custom(|iters| timed_deletes(&mut rbt, n, iters, &mut rng))
+            });
+            let total = timed_deletes(&mut rbt, n, GATE_SAMPLES as u64, &mut rng);
+            latencies.push((n, total.as_nanos() as f64 / GATE_SAMPLES as f64));
+        }
+        group.finish();
+    }
+    criterion.final_summary();
+
+    //门禁：最大规模与最小规模的延迟比值不超过对数比值乘以容忍倍数
+    let (small_n, small_ns) = latencies[0];
+    for &(n, ns) in &latencies {
+        let bound = GATE_SLACK * (n as f64).log2() / (small_n as f64).log2();
+        let ratio = ns / small_ns;
+        println!("delete n={:>10} mean={:>8.1}ns ratio={:>6.2} bound={:>6.2}", n, ns, ratio, bound);
+        assert!(ratio <= bound, "delete latency at n={} is {:.2}x of n={}, exceeds logarithmic bound {:.2}", n, ratio, small_n, bound);
+    }
+}
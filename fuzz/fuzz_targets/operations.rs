@@ -0,0 +1,40 @@
+//! 把任意字节解码为操作序列，同时作用于红黑树和BTreeMap模型
+//! 每三个字节为一个操作：操作码、键、值，键只取一个字节以保证大量重复键和命中删除
+//! 每次修改后校验红黑树性质，并比对结果
+
+#![no_main]
+
+use std::collections::BTreeMap;
+
+use libfuzzer_sys::fuzz_target;
+use red_black_tree::data_structure::red_black_tree::RedBlackTree;
+
+fuzz_target!(|data: &[u8]| {
+    let mut tree = RedBlackTree::new();
+    let mut model = BTreeMap::new();
+    for chunk in data.chunks_exact(3) {
+        let (key, value) = (chunk[1], chunk[2]);
+        match chunk[0] % 8 {
+            0 | 1 => {
+                //默认策略下重复键保留原值
+                tree.insert(key, value);
+                model.entry(key).or_insert(value);
+            }
+            2 => assert_eq!(tree.insert_or_replace(key, value), model.insert(key, value)),
+            3 | 4 => {
+                tree.delete(&key);
+                model.remove(&key);
+            }
+            5 => {
+                assert_eq!(tree.get(&key), model.get(&key).copied());
+                assert_eq!(tree.rank(&key), model.range(..key).count());
+                continue;
+            }
+            6 => assert_eq!(tree.pop_first(), model.pop_first()),
+            _ => assert_eq!(tree.pop_last(), model.pop_last()),
+        }
+        assert_eq!(tree.validate(), Ok(()));
+        assert_eq!(tree.len(), model.len());
+    }
+    assert!(tree.iter().eq(model.into_iter()));
+});
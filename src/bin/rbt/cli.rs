@@ -0,0 +1,117 @@
+//! 命令行程序的子命令与共用的操作定义
+
+use std::fmt;
+use std::str::FromStr;
+
+use rand::Rng;
+
+pub mod bench;
+pub mod compare;
+pub mod repl;
+pub mod run;
+pub mod stress;
+
+/// 对键的一次操作
+/// 以 `insert 5`、`delete 5`、`get 5` 的文本形式输出与解析
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Op {
+    Insert(u64),
+    Delete(u64),
+    Get(u64),
+}
+
+impl fmt::Display for Op {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Op::Insert(key) => write!(f, "insert {}", key),
+            Op::Delete(key) => write!(f, "delete {}", key),
+            Op::Get(key) => write!(f, "get {}", key),
+        }
+    }
+}
+
+impl FromStr for Op {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut words = text.split_whitespace();
+        let (command, key) = match (words.next(), words.next(), words.next()) {
+            (Some(command), Some(key), None) => (command, key.parse().map_err(|_| format!("invalid key {:?}", key))?),
+            _ => return Err(format!("expected `<command> <key>`, got {:?}", text)),
+        };
+        match command {
+            "insert" => Ok(Op::Insert(key)),
+            "delete" => Ok(Op::Delete(key)),
+            "get" => Ok(Op::Get(key)),
+            _ => Err(format!("unknown command {:?}", command)),
+        }
+    }
+}
+
+/// 插入、删除、查找的权重
+/// 以 `50,30,20` 的形式解析
+#[derive(Clone, Copy, Debug)]
+pub struct Mix {
+    insert: u32,
+    delete: u32,
+    get: u32,
+}
+
+impl Mix {
+    ///按权重随机生成一次操作，键取自1..=key_range
+    pub fn sample<R: Rng>(&self, rng: &mut R, key_range: u64) -> Op {
+        let key = rng.gen_range(1..=key_range.max(1));
+        let roll = rng.gen_range(0..self.insert + self.delete + self.get);
+        if roll < self.insert {
+            Op::Insert(key)
+        } else if roll < self.insert + self.delete {
+            Op::Delete(key)
+        } else {
+            Op::Get(key)
+        }
+    }
+}
+
+impl fmt::Display for Mix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{},{},{}", self.insert, self.delete, self.get)
+    }
+}
+
+impl FromStr for Mix {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let weights: Vec<u32> = text.split(',').map(|weight| weight.trim().parse().map_err(|_| format!("invalid weight {:?}", weight))).collect::<Result<_, _>>()?;
+        match weights[..] {
+            [insert, delete, get] if insert.checked_add(delete).and_then(|sum| sum.checked_add(get)).is_some_and(|sum| sum > 0) => Ok(Mix { insert, delete, get }),
+            [_, _, _] => Err("weights must not all be zero".to_string()),
+            _ => Err("expected three weights: insert,delete,get".to_string()),
+        }
+    }
+}
+
+///给定种子或随机选取，随机选取时输出，便于复现
+pub fn resolve_seed(seed: Option<u64>, quiet: bool) -> u64 {
+    let seed = seed.unwrap_or_else(|| rand::thread_rng().gen());
+    if !quiet {
+        println!("seed={}", seed);
+    }
+    seed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Mix, Op};
+
+    #[test]
+    fn ops_round_trip_through_text() {
+        for op in [Op::Insert(5), Op::Delete(0), Op::Get(u64::MAX)] {
+            assert_eq!(op.to_string().parse(), Ok(op));
+        }
+        assert!("insert".parse::<Op>().is_err());
+        assert!("push 1".parse::<Op>().is_err());
+        assert!("0,0,0".parse::<Mix>().is_err());
+        assert!("1,2".parse::<Mix>().is_err());
+    }
+}
@@ -0,0 +1,134 @@
+//! 快速基准
+//! 每个负载在预先填充的新树上按权重执行随机操作，逐次计时，按操作类型输出吞吐量与延迟分位数
+//! 输出CSV或JSON，便于在目标机器上直接作图；需要严格的统计时使用cargo bench
+
+use std::hint::black_box;
+use std::process::ExitCode;
+use std::time::{Duration, Instant};
+
+use clap::{Args, ValueEnum};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use red_black_tree::data_structure::red_black_tree::RedBlackTree;
+
+use super::{resolve_seed, Mix, Op};
+
+#[derive(Args)]
+pub struct BenchArgs {
+    ///每个负载的操作次数
+    #[arg(short = 'n', long, default_value_t = 1_000_000)]
+    ops: usize,
+    ///开始计时前插入的随机键数量
+    #[arg(short, long, default_value_t = 100_000)]
+    preload: usize,
+    ///键的取值范围为1..=key_range
+    #[arg(short, long, default_value_t = 1_000_000)]
+    key_range: u64,
+    ///随机数种子，缺省时随机选取
+    #[arg(short, long)]
+    seed: Option<u64>,
+    ///负载的插入、删除、查找权重，可重复指定多个负载
+    #[arg(short, long, default_values = ["100,0,0", "0,0,100", "0,100,0", "50,30,20"])]
+    mix: Vec<Mix>,
+    ///输出格式
+    #[arg(short, long, value_enum, default_value_t = Format::Csv)]
+    format: Format,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+    Csv,
+    Json,
+}
+
+/// 一行结果
+struct Row {
+    workload: Mix,
+    op: &'static str,
+    count: usize,
+    ops_per_sec: f64,
+    //p50、p90、p99、p99.9与最大值，纳秒
+    percentiles: [u128; 5],
+}
+
+const COLUMNS: [&str; 5] = ["p50_ns", "p90_ns", "p99_ns", "p999_ns", "max_ns"];
+
+pub fn bench(args: BenchArgs) -> ExitCode {
+    //种子输出到标准错误，不混入结果
+    let seed = resolve_seed(args.seed, true);
+    eprintln!("seed={}", seed);
+    let mut rows = Vec::new();
+    for &workload in &args.mix {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut tree = RedBlackTree::new();
+        for _ in 0..args.preload {
+            let key = rng.gen_range(1..=args.key_range.max(1));
+            tree.insert(key, key);
+        }
+        let ops: Vec<Op> = (0..args.ops).map(|_| workload.sample(&mut rng, args.key_range)).collect();
+        let mut latencies: [Vec<Duration>; 3] = Default::default();
+        let start = Instant::now();
+        for op in ops {
+            let begin = Instant::now();
+            let slot = match op {
+                Op::Insert(key) => {
+                    tree.insert(key, key);
+                    0
+                }
+                Op::Delete(key) => {
+                    tree.delete(&key);
+                    1
+                }
+                Op::Get(key) => {
+                    black_box(tree.get(&key));
+                    2
+                }
+            };
+            latencies[slot].push(begin.elapsed());
+        }
+        let elapsed = start.elapsed();
+        let mut all: Vec<Duration> = latencies.iter().flatten().copied().collect();
+        for (op, samples) in ["insert", "delete", "get"].into_iter().zip(&mut latencies) {
+            if !samples.is_empty() {
+                let busy: Duration = samples.iter().sum();
+                rows.push(row(workload, op, samples, busy));
+            }
+        }
+        if !all.is_empty() {
+            rows.push(row(workload, "all", &mut all, elapsed));
+        }
+    }
+    match args.format {
+        Format::Csv => {
+            println!("workload,op,count,ops_per_sec,{}", COLUMNS.join(","));
+            for row in &rows {
+                let percentiles: Vec<String> = row.percentiles.iter().map(u128::to_string).collect();
+                println!("\"{}\",{},{},{:.0},{}", row.workload, row.op, row.count, row.ops_per_sec, percentiles.join(","));
+            }
+        }
+        Format::Json => {
+            let objects: Vec<String> = rows
+                .iter()
+                .map(|row| {
+                    let percentiles: Vec<String> = COLUMNS.iter().zip(row.percentiles).map(|(column, value)| format!("\"{}\":{}", column, value)).collect();
+                    format!("{{\"workload\":\"{}\",\"op\":\"{}\",\"count\":{},\"ops_per_sec\":{:.0},{}}}", row.workload, row.op, row.count, row.ops_per_sec, percentiles.join(","))
+                })
+                .collect();
+            println!("[{}]", objects.join(","));
+        }
+    }
+    ExitCode::SUCCESS
+}
+
+///由延迟样本计算一行结果，吞吐量为样本数除以total
+fn row(workload: Mix, op: &'static str, samples: &mut [Duration], total: Duration) -> Row {
+    samples.sort_unstable();
+    let at = |quantile: f64| samples[((samples.len() - 1) as f64 * quantile).round() as usize].as_nanos();
+    Row {
+        workload,
+        op,
+        count: samples.len(),
+        ops_per_sec: samples.len() as f64 / total.as_secs_f64().max(f64::MIN_POSITIVE),
+        percentiles: [at(0.5), at(0.9), at(0.99), at(0.999), at(1.0)],
+    }
+}
@@ -0,0 +1,101 @@
+//! 与BTreeMap的行为对比
+//! 同一个由种子确定的操作序列同时作用于红黑树与BTreeMap，查找结果逐次比对
+//! 每批操作后比对最小键、最大键、随机键的查找与随机区间的内容，并校验红黑树性质
+
+use std::collections::BTreeMap;
+use std::process::ExitCode;
+
+use clap::Args;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use red_black_tree::data_structure::red_black_tree::RedBlackTree;
+
+use super::{resolve_seed, Mix, Op};
+
+#[derive(Args)]
+pub struct CompareArgs {
+    ///随机数种子，缺省时随机选取并输出
+    #[arg(short, long)]
+    seed: Option<u64>,
+    ///操作次数
+    #[arg(short = 'n', long, default_value_t = 100_000)]
+    ops: u64,
+    ///键的取值范围为1..=key_range
+    #[arg(short, long, default_value_t = 10_000)]
+    key_range: u64,
+    ///插入、删除、查找的权重
+    #[arg(short, long, default_value = "50,30,20")]
+    mix: Mix,
+    ///每批的操作次数
+    #[arg(short, long, default_value_t = 1000)]
+    batch: u64,
+    ///每批后抽查的键与区间数量
+    #[arg(long, default_value_t = 32)]
+    probes: usize,
+}
+
+pub fn compare(args: CompareArgs) -> ExitCode {
+    let seed = resolve_seed(args.seed, false);
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut tree = RedBlackTree::new();
+    let mut model = BTreeMap::new();
+    let key_range = args.key_range.max(1);
+    let batch = args.batch.max(1);
+    for count in 1..=args.ops {
+        let op = args.mix.sample(&mut rng, key_range);
+        match op {
+            Op::Insert(key) => {
+                //插入已存在的键时两者都保留原值
+                tree.insert(key, count);
+                model.entry(key).or_insert(count);
+            }
+            Op::Delete(key) => {
+                tree.delete(&key);
+                model.remove(&key);
+            }
+            Op::Get(key) => {
+                if let Err(message) = expect_eq("get", tree.get(&key), model.get(&key).copied()) {
+                    return mismatch(seed, count, &op.to_string(), &message);
+                }
+            }
+        }
+        if count.is_multiple_of(batch) || count == args.ops {
+            if let Err(message) = check_batch(&tree, &model, &mut rng, key_range, args.probes) {
+                return mismatch(seed, count, "batch check", &message);
+            }
+            progress!("ops={} size={}", count, tree.len());
+        }
+    }
+    println!("{} ops matched BTreeMap", args.ops);
+    ExitCode::SUCCESS
+}
+
+///批次结束后的比对
+fn check_batch<R: Rng>(tree: &RedBlackTree<u64, u64>, model: &BTreeMap<u64, u64>, rng: &mut R, key_range: u64, probes: usize) -> Result<(), String> {
+    expect_eq("len", tree.len(), model.len())?;
+    expect_eq("min", tree.first(), model.first_key_value().map(|(key, value)| (*key, *value)))?;
+    expect_eq("max", tree.last(), model.last_key_value().map(|(key, value)| (*key, *value)))?;
+    for _ in 0..probes {
+        let key = rng.gen_range(1..=key_range);
+        expect_eq(&format!("get {}", key), tree.get(&key), model.get(&key).copied())?;
+        let (start, end) = (rng.gen_range(1..=key_range), rng.gen_range(1..=key_range));
+        let (start, end) = (start.min(end), start.max(end));
+        let actual: Vec<_> = tree.range(start..end).collect();
+        let expected: Vec<_> = model.range(start..end).map(|(key, value)| (*key, *value)).collect();
+        expect_eq(&format!("range {}..{}", start, end), actual, expected)?;
+    }
+    tree.validate().map_err(|violation| violation.to_string())
+}
+
+fn expect_eq<T: PartialEq + std::fmt::Debug>(what: &str, actual: T, expected: T) -> Result<(), String> {
+    if actual == expected {
+        Ok(())
+    } else {
+        Err(format!("{}: red black tree {:?}, BTreeMap {:?}", what, actual, expected))
+    }
+}
+
+fn mismatch(seed: u64, count: u64, during: &str, message: &str) -> ExitCode {
+    eprintln!("mismatch at op #{} ({}) seed={}: {}", count, during, seed, message);
+    ExitCode::FAILURE
+}
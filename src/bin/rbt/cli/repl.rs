@@ -0,0 +1,67 @@
+//! 交互式REPL
+//! 逐行读取命令作用于一棵u64键的树，标准输入为终端时显示提示符
+//! 也可从管道读入，例如重放stress输出的失败前缀
+
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::process::ExitCode;
+
+use red_black_tree::data_structure::red_black_tree::RedBlackTree;
+
+use super::Op;
+
+const HELP: &str = "\
+insert <key>   插入键
+delete <key>   删除键
+get <key>      查找键及其排名
+print          横向打印树结构
+validate       校验红黑树性质
+dot            输出Graphviz的dot格式
+json           输出JSON结构
+len            节点数量
+clear          清空
+help           显示本帮助
+quit           退出";
+
+pub fn repl() -> ExitCode {
+    let interactive = io::stdin().is_terminal();
+    let mut tree = RedBlackTree::<u64, ()>::new();
+    if interactive {
+        println!("{}", HELP);
+    }
+    let mut lines = io::stdin().lock().lines();
+    loop {
+        if interactive {
+            print!("> ");
+            io::stdout().flush().expect("stdout is writable");
+        }
+        let line = match lines.next() {
+            None => return ExitCode::SUCCESS,
+            Some(Ok(line)) => line,
+            Some(Err(error)) => {
+                eprintln!("{}", error);
+                return ExitCode::FAILURE;
+            }
+        };
+        match line.trim() {
+            "" => {}
+            "print" => print!("{}", tree),
+            "validate" => match tree.validate() {
+                Ok(()) => println!("ok"),
+                Err(violation) => println!("violation: {}", violation),
+            },
+            "dot" => print!("{}", tree.to_dot()),
+            "json" => println!("{}", tree.to_json()),
+            "len" => println!("{}", tree.len()),
+            "clear" => tree.clear(),
+            "help" => println!("{}", HELP),
+            "quit" | "exit" => return ExitCode::SUCCESS,
+            command => match command.parse() {
+                Ok(Op::Insert(key)) => tree.insert(key, ()),
+                Ok(Op::Delete(key)) => tree.delete(&key),
+                Ok(Op::Get(key)) if tree.contains(&key) => println!("found, rank {}", tree.rank(&key)),
+                Ok(Op::Get(_)) => println!("not found"),
+                Err(error) => println!("{}，输入help查看命令", error),
+            },
+        }
+    }
+}
@@ -0,0 +1,88 @@
+//! 随机负载
+//! 随机的插入、删除、查找同时作用于红黑树和HashMap，比对大小并定期校验红黑树性质
+
+use std::collections::HashMap;
+use std::process::ExitCode;
+use std::time::Instant;
+
+use clap::Args;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use red_black_tree::data_structure::red_black_tree::RedBlackTree;
+
+use super::{resolve_seed, Mix, Op};
+
+#[derive(Args)]
+pub struct RunArgs {
+    ///操作次数
+    #[arg(short = 'n', long, default_value_t = 100_000)]
+    ops: u64,
+    ///键的取值范围为1..=key_range
+    #[arg(short, long, default_value_t = 100_000)]
+    key_range: u64,
+    ///随机数种子，缺省时随机选取并输出，便于复现
+    #[arg(short, long)]
+    seed: Option<u64>,
+    ///插入、删除、查找的权重
+    #[arg(short, long, default_value = "50,30,20")]
+    mix: Mix,
+    ///每隔多少次操作全量校验一次，全量校验为O(n)
+    #[arg(long, default_value_t = 1000)]
+    validate_every: u64,
+    ///关闭校验
+    #[arg(long)]
+    no_validate: bool,
+    ///只输出错误
+    #[arg(short, long)]
+    quiet: bool,
+}
+
+pub fn run(args: RunArgs) -> ExitCode {
+    let seed = resolve_seed(args.seed, args.quiet);
+    let mut rng = StdRng::seed_from_u64(seed);
+    //红黑树
+    let mut rbt = RedBlackTree::new();
+    //辅助验证 HashMap
+    let mut map = HashMap::new();
+    let validate_every = if args.no_validate { 0 } else { args.validate_every };
+    let (mut inserts, mut deletes, mut gets) = (0u64, 0u64, 0u64);
+    let start = Instant::now();
+    for count in 1..=args.ops {
+        match args.mix.sample(&mut rng, args.key_range) {
+            Op::Insert(key) => {
+                rbt.insert(key, key);
+                map.insert(key, key);
+                inserts += 1;
+            }
+            Op::Delete(key) => {
+                rbt.delete(&key);
+                map.remove(&key);
+                deletes += 1;
+            }
+            Op::Get(key) => {
+                if rbt.get(&key) != map.get(&key).copied() {
+                    eprintln!("查找逻辑出错了: key={} seed={}", key, seed);
+                    return ExitCode::FAILURE;
+                }
+                gets += 1;
+            }
+        }
+        if rbt.len() != map.len() {
+            eprintln!("插入删除逻辑出错了: 第{}次操作 seed={}", count, seed);
+            return ExitCode::FAILURE;
+        }
+        if validate_every != 0 && count.is_multiple_of(validate_every) {
+            if let Err(violation) = rbt.validate() {
+                eprintln!("红黑树性质被破坏: {} 第{}次操作 seed={}", violation, count, seed);
+                return ExitCode::FAILURE;
+            }
+            if !args.quiet {
+                progress!("ops={} size={}", count, rbt.len());
+            }
+        }
+    }
+    if !args.quiet {
+        println!("inserts={} deletes={} gets={} size={} elapsed={:?}", inserts, deletes, gets, rbt.len(), start.elapsed());
+    }
+    ExitCode::SUCCESS
+}
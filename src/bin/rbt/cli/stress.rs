@@ -0,0 +1,94 @@
+//! 可复现的压力测试
+//! 由种子确定整个操作序列，每次操作后与BTreeMap比对并全量校验红黑树性质，panic也视为失败
+//! 逐次检查，第一次失败时已执行的操作即最短的失败前缀，输出种子与该前缀，每行一次操作
+
+use std::collections::BTreeMap;
+use std::panic::{self, AssertUnwindSafe};
+use std::process::ExitCode;
+
+use clap::Args;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use red_black_tree::data_structure::red_black_tree::RedBlackTree;
+
+use super::{resolve_seed, Mix, Op};
+
+#[derive(Args)]
+pub struct StressArgs {
+    ///随机数种子，缺省时随机选取并输出
+    #[arg(short, long)]
+    seed: Option<u64>,
+    ///运行的序列数量，第i个序列的种子为seed + i
+    #[arg(short, long, default_value_t = 1)]
+    runs: u64,
+    ///每个序列的操作次数
+    #[arg(short = 'n', long, default_value_t = 100_000)]
+    ops: usize,
+    ///键的取值范围为1..=key_range，较小的范围使树保持在可全量校验的规模并产生大量重复键
+    #[arg(short, long, default_value_t = 1000)]
+    key_range: u64,
+    ///插入、删除、查找的权重
+    #[arg(short, long, default_value = "45,45,10")]
+    mix: Mix,
+}
+
+/// 失败的位置与原因
+struct Failure {
+    index: usize,
+    reason: String,
+}
+
+pub fn stress(args: StressArgs) -> ExitCode {
+    let seed = resolve_seed(args.seed, false);
+    for run in 0..args.runs {
+        let run_seed = seed.wrapping_add(run);
+        let mut rng = StdRng::seed_from_u64(run_seed);
+        let ops: Vec<Op> = (0..args.ops).map(|_| args.mix.sample(&mut rng, args.key_range)).collect();
+        if let Err(failure) = check(&ops) {
+            println!("failed: seed={} op #{}: {}", run_seed, failure.index, failure.reason);
+            println!("minimal failing prefix ({} ops):", failure.index + 1);
+            for op in &ops[..=failure.index] {
+                println!("{}", op);
+            }
+            return ExitCode::FAILURE;
+        }
+        progress!("seed={} passed {} ops", run_seed, args.ops);
+    }
+    println!("passed {} runs", args.runs);
+    ExitCode::SUCCESS
+}
+
+///依次执行并在每次操作后检查，返回第一次失败
+fn check(ops: &[Op]) -> Result<(), Failure> {
+    let mut tree = RedBlackTree::new();
+    let mut model = BTreeMap::new();
+    for (index, op) in ops.iter().enumerate() {
+        let fail = |reason: String| Failure { index, reason };
+        let result = panic::catch_unwind(AssertUnwindSafe(|| match *op {
+            Op::Insert(key) => {
+                tree.insert(key, key);
+                model.entry(key).or_insert(key);
+                None
+            }
+            Op::Delete(key) => {
+                tree.delete(&key);
+                model.remove(&key);
+                None
+            }
+            Op::Get(key) => Some((tree.get(&key), model.get(&key).copied())),
+        }));
+        match result {
+            Err(payload) => {
+                let message = payload.downcast_ref::<&str>().map(|message| message.to_string()).or_else(|| payload.downcast_ref::<String>().cloned());
+                return Err(fail(format!("panicked: {}", message.unwrap_or_default())));
+            }
+            Ok(Some((actual, expected))) if actual != expected => return Err(fail(format!("get returned {:?}, expected {:?}", actual, expected))),
+            Ok(_) => {}
+        }
+        if tree.len() != model.len() {
+            return Err(fail(format!("len is {}, expected {}", tree.len(), model.len())));
+        }
+        tree.validate().map_err(|violation| fail(violation.to_string()))?;
+    }
+    Ok(())
+}
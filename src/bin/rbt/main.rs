@@ -0,0 +1,83 @@
+use std::process::ExitCode;
+
+use clap::{Parser, Subcommand};
+
+use cli::bench::{bench, BenchArgs};
+use cli::compare::{compare, CompareArgs};
+use cli::repl::repl;
+use cli::run::{run, RunArgs};
+use cli::stress::{stress, StressArgs};
+
+///进度输出，开启log特性时以debug级别写入log，否则输出到标准输出
+macro_rules! progress {
+    ($($arg:tt)*) => {{
+        #[cfg(feature = "log")]
+        log::debug!($($arg)*);
+        #[cfg(not(feature = "log"))]
+        println!($($arg)*);
+    }};
+}
+
+mod cli;
+
+///输出到标准错误的日志，RUST_LOG=debug时显示进度
+#[cfg(feature = "log")]
+struct StderrLogger;
+
+#[cfg(feature = "log")]
+impl log::Log for StderrLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &log::Record) {
+        if self.enabled(record.metadata()) {
+            eprintln!("[{}] {}", record.level(), record.args());
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+/// 红黑树命令行程序
+/// 不带子命令时运行随机负载，见run的参数
+#[derive(Parser)]
+#[command(version, args_conflicts_with_subcommands = true)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+    #[command(flatten)]
+    run: RunArgs,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    ///随机的插入、删除、查找同时作用于红黑树和HashMap，比对大小并定期校验红黑树性质
+    Run(RunArgs),
+    ///由种子确定的压力测试，每次操作后校验，失败时输出种子与最短的失败前缀
+    Stress(StressArgs),
+    ///交互式REPL，输入insert 5、delete 5、print、dot等命令观察树的变化
+    Repl,
+    ///基准测试，按操作类型输出吞吐量与延迟分位数，格式为CSV或JSON
+    Bench(BenchArgs),
+    ///同一个由种子确定的操作序列同时作用于红黑树与BTreeMap，逐批比对查找、区间与最值
+    Compare(CompareArgs),
+}
+
+fn main() -> ExitCode {
+    #[cfg(feature = "log")]
+    {
+        let level = std::env::var("RUST_LOG").ok().and_then(|level| level.parse().ok()).unwrap_or(log::LevelFilter::Info);
+        log::set_logger(&StderrLogger).expect("logger is set once");
+        log::set_max_level(level);
+    }
+    let cli = Cli::parse();
+    match cli.command {
+        None => run(cli.run),
+        Some(Command::Run(args)) => run(args),
+        Some(Command::Stress(args)) => stress(args),
+        Some(Command::Repl) => repl(),
+        Some(Command::Bench(args)) => bench(args),
+        Some(Command::Compare(args)) => compare(args),
+    }
+}
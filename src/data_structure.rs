@@ -1 +1,7 @@
-pub mod red_black_tree;
\ No newline at end of file
+pub mod avl_tree;
+mod inorder;
+pub mod llrb;
+pub mod ordered_map;
+pub mod red_black_tree;
+pub mod skip_list;
+pub mod treap;
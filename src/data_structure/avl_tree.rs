@@ -0,0 +1,439 @@
+//! AVL树
+//! 任意节点左右子树高度差不超过1，比红黑树平衡更严格，查找路径更短，插入删除的旋转更多
+//! 节点以Box独占子节点并缓存子树高度，递归下降后沿返回路径更新高度并旋转
+//! 迭代器与校验结果与红黑树共用
+
+use alloc::boxed::Box;
+use core::cmp::Ordering;
+use core::fmt;
+use core::ops::RangeBounds;
+
+use super::inorder::{BinaryNode, InorderIter};
+use super::red_black_tree::InvariantViolation;
+
+type Link<K, V> = Option<Box<AvlNode<K, V>>>;
+
+struct AvlNode<K, V> {
+    key: K,
+    value: V,
+    left: Link<K, V>,
+    right: Link<K, V>,
+    //子树高度，叶子为1
+    height: usize,
+}
+
+impl<K, V> BinaryNode for AvlNode<K, V> {
+    type Key = K;
+    type Value = V;
+
+    fn entry(&self) -> (&K, &V) {
+        (&self.key, &self.value)
+    }
+
+    fn left(&self) -> Option<&Self> {
+        self.left.as_deref()
+    }
+
+    fn right(&self) -> Option<&Self> {
+        self.right.as_deref()
+    }
+}
+
+pub struct AvlTree<K, V> {
+    root: Link<K, V>,
+    len: usize,
+}
+
+///空子树高度为0
+fn height<K, V>(link: &Link<K, V>) -> usize {
+    link.as_ref().map_or(0, |node| node.height)
+}
+
+fn update_height<K, V>(node: &mut AvlNode<K, V>) {
+    node.height = height(&node.left).max(height(&node.right)) + 1;
+}
+
+///左旋，右子节点上升
+fn rotate_left<K, V>(mut node: Box<AvlNode<K, V>>) -> Box<AvlNode<K, V>> {
+    let mut right = node.right.take().expect("rotate left without right child");
+    node.right = right.left.take();
+    update_height(&mut node);
+    right.left = Some(node);
+    update_height(&mut right);
+    right
+}
+
+///右旋，左子节点上升
+fn rotate_right<K, V>(mut node: Box<AvlNode<K, V>>) -> Box<AvlNode<K, V>> {
+    let mut left = node.left.take().expect("rotate right without left child");
+    node.left = left.right.take();
+    update_height(&mut node);
+    left.right = Some(node);
+    update_height(&mut left);
+    left
+}
+
+/// 更新高度，高度差为2时旋转
+/// 较高子树的内侧更高(LR、RL)时先旋转子节点
+fn rebalance<K, V>(mut node: Box<AvlNode<K, V>>) -> Box<AvlNode<K, V>> {
+    update_height(&mut node);
+    let (left, right) = (height(&node.left), height(&node.right));
+    if left > right + 1 {
+        let son = node.left.as_ref().expect("left subtree is higher");
+        if height(&son.right) > height(&son.left) {
+            node.left = node.left.take().map(rotate_left);
+        }
+        rotate_right(node)
+    } else if right > left + 1 {
+        let son = node.right.as_ref().expect("right subtree is higher");
+        if height(&son.left) > height(&son.right) {
+            node.right = node.right.take().map(rotate_right);
+        }
+        rotate_left(node)
+    } else {
+        node
+    }
+}
+
+/// 插入，返回子树新的根与未保留的值
+/// 键已存在时replace为true则替换并返回旧值，否则返回新值
+fn insert_node<K: Ord, V>(link: Link<K, V>, key: K, value: V, replace: bool) -> (Box<AvlNode<K, V>>, Option<V>) {
+    let Some(mut node) = link else {
+        return (Box::new(AvlNode { key, value, left: None, right: None, height: 1 }), None);
+    };
+    let displaced = match key.cmp(&node.key) {
+        Ordering::Less => {
+            let (left, displaced) = insert_node(node.left.take(), key, value, replace);
+            node.left = Some(left);
+            displaced
+        }
+        Ordering::Greater => {
+            let (right, displaced) = insert_node(node.right.take(), key, value, replace);
+            node.right = Some(right);
+            displaced
+        }
+        Ordering::Equal if replace => {
+            let old = core::mem::replace(&mut node.value, value);
+            return (node, Some(old));
+        }
+        Ordering::Equal => return (node, Some(value)),
+    };
+    (rebalance(node), displaced)
+}
+
+///删除子树最小节点，返回子树新的根与被删除的节点
+fn remove_min<K, V>(mut node: Box<AvlNode<K, V>>) -> (Link<K, V>, Box<AvlNode<K, V>>) {
+    match node.left.take() {
+        None => {
+            let right = node.right.take();
+            (right, node)
+        }
+        Some(left) => {
+            let (left, min) = remove_min(left);
+            node.left = left;
+            (Some(rebalance(node)), min)
+        }
+    }
+}
+
+fn remove_max<K, V>(mut node: Box<AvlNode<K, V>>) -> (Link<K, V>, Box<AvlNode<K, V>>) {
+    match node.right.take() {
+        None => {
+            let left = node.left.take();
+            (left, node)
+        }
+        Some(right) => {
+            let (right, max) = remove_max(right);
+            node.right = right;
+            (Some(rebalance(node)), max)
+        }
+    }
+}
+
+/// 删除key，返回子树新的根与被删除的键值对
+/// 有两个子节点时以右子树最小节点取代
+fn remove_node<K: Ord, V>(link: Link<K, V>, key: &K) -> (Link<K, V>, Option<(K, V)>) {
+    let Some(mut node) = link else {
+        return (None, None);
+    };
+    let removed = match key.cmp(&node.key) {
+        Ordering::Less => {
+            let (left, removed) = remove_node(node.left.take(), key);
+            node.left = left;
+            removed
+        }
+        Ordering::Greater => {
+            let (right, removed) = remove_node(node.right.take(), key);
+            node.right = right;
+            removed
+        }
+        Ordering::Equal => {
+            let Some(right) = node.right.take() else {
+                return (node.left.take(), Some((node.key, node.value)));
+            };
+            let (right, min) = remove_min(right);
+            node.right = right;
+            let key = core::mem::replace(&mut node.key, min.key);
+            let value = core::mem::replace(&mut node.value, min.value);
+            Some((key, value))
+        }
+    };
+    //未命中时子树不变，无需调平
+    if removed.is_none() {
+        return (Some(node), None);
+    }
+    (Some(rebalance(node)), removed)
+}
+
+impl<K: Ord, V> AvlTree<K, V> {
+    pub fn new() -> Self {
+        AvlTree { root: None, len: 0 }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn clear(&mut self) {
+        self.root = None;
+        self.len = 0;
+    }
+
+    ///树高，空树为0，O(1)
+    pub fn height(&self) -> usize {
+        height(&self.root)
+    }
+
+    ///插入键值对，键已存在时保留原值
+    pub fn insert(&mut self, key: K, value: V) {
+        self.insert_with(key, value, false);
+    }
+
+    ///插入或替换，返回旧值
+    pub fn insert_or_replace(&mut self, key: K, value: V) -> Option<V> {
+        self.insert_with(key, value, true)
+    }
+
+    fn insert_with(&mut self, key: K, value: V, replace: bool) -> Option<V> {
+        let (root, displaced) = insert_node(self.root.take(), key, value, replace);
+        self.root = Some(root);
+        if displaced.is_none() {
+            self.len += 1;
+        }
+        displaced.filter(|_| replace)
+    }
+
+    ///删除键，键不存在时忽略
+    pub fn delete(&mut self, key: &K) {
+        self.remove(key);
+    }
+
+    ///删除键并返回值
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let (root, removed) = remove_node(self.root.take(), key);
+        self.root = root;
+        removed.map(|(_, value)| {
+            self.len -= 1;
+            value
+        })
+    }
+
+    pub fn pop_first(&mut self) -> Option<(K, V)> {
+        let (root, min) = remove_min(self.root.take()?);
+        self.root = root;
+        self.len -= 1;
+        Some((min.key, min.value))
+    }
+
+    pub fn pop_last(&mut self) -> Option<(K, V)> {
+        let (root, max) = remove_max(self.root.take()?);
+        self.root = root;
+        self.len -= 1;
+        Some((max.key, max.value))
+    }
+
+    fn find(&self, key: &K) -> Option<&AvlNode<K, V>> {
+        let mut cur = self.root.as_deref();
+        while let Some(node) = cur {
+            cur = match key.cmp(&node.key) {
+                Ordering::Equal => return Some(node),
+                Ordering::Less => node.left.as_deref(),
+                Ordering::Greater => node.right.as_deref(),
+            };
+        }
+        None
+    }
+
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.find(key).map(|node| &node.value)
+    }
+
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        let mut cur = self.root.as_deref_mut();
+        while let Some(node) = cur {
+            cur = match key.cmp(&node.key) {
+                Ordering::Equal => return Some(&mut node.value),
+                Ordering::Less => node.left.as_deref_mut(),
+                Ordering::Greater => node.right.as_deref_mut(),
+            };
+        }
+        None
+    }
+
+    pub fn contains(&self, key: &K) -> bool {
+        self.find(key).is_some()
+    }
+
+    pub fn get_key_value(&self, key: &K) -> Option<(&K, &V)> {
+        self.find(key).map(|node| (&node.key, &node.value))
+    }
+
+    pub fn first(&self) -> Option<(&K, &V)> {
+        self.iter().next()
+    }
+
+    pub fn last(&self) -> Option<(&K, &V)> {
+        self.iter().next_back()
+    }
+
+    ///中序迭代，产出借用
+    pub fn iter(&self) -> AvlIter<'_, K, V> {
+        AvlIter { iter: InorderIter::new(self.root.as_deref(), self.len) }
+    }
+
+    ///按键升序迭代范围内的条目，两端沿查找路径定位，O(log n)
+    pub fn range<R: RangeBounds<K>>(&self, range: R) -> AvlIter<'_, K, V> {
+        AvlIter { iter: InorderIter::range(self.root.as_deref(), range) }
+    }
+
+    /// 校验AVL树性质：顺序、缓存的高度、左右子树高度差不超过1
+    /// 违反的性质以红黑树的InvariantViolation表示
+    pub fn validate(&self) -> Result<(), InvariantViolation<K>>
+    where
+        K: Clone,
+    {
+        let mut count = 0;
+        Self::validate_node(&self.root, None, None, &mut count)?;
+        if count != self.len {
+            return Err(InvariantViolation::LengthMismatch { expected: self.len, actual: count });
+        }
+        Ok(())
+    }
+
+    ///递归校验子树，返回子树高度
+    fn validate_node(link: &Link<K, V>, lower: Option<&K>, upper: Option<&K>, count: &mut usize) -> Result<usize, InvariantViolation<K>>
+    where
+        K: Clone,
+    {
+        let Some(node) = link else {
+            return Ok(0);
+        };
+        *count += 1;
+        if lower.is_some_and(|lower| node.key <= *lower) || upper.is_some_and(|upper| node.key >= *upper) {
+            return Err(InvariantViolation::OutOfOrder { key: node.key.clone() });
+        }
+        let left = Self::validate_node(&node.left, lower, Some(&node.key), count)?;
+        let right = Self::validate_node(&node.right, Some(&node.key), upper, count)?;
+        if left.abs_diff(right) > 1 {
+            return Err(InvariantViolation::HeightImbalance { key: node.key.clone(), left, right });
+        }
+        let actual = left.max(right) + 1;
+        if node.height != actual {
+            return Err(InvariantViolation::SubtreeHeight { key: node.key.clone(), expected: node.height, actual });
+        }
+        Ok(actual)
+    }
+}
+
+impl<K: Ord, V> Default for AvlTree<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Ord + fmt::Debug, V: fmt::Debug> fmt::Debug for AvlTree<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+/// 中序迭代器
+pub struct AvlIter<'a, K, V> {
+    iter: InorderIter<'a, AvlNode<K, V>>,
+}
+
+impl<'a, K, V> Iterator for AvlIter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<K, V> DoubleEndedIterator for AvlIter<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.iter.next_back()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+
+    use super::AvlTree;
+
+    #[derive(Debug, Clone)]
+    enum Op {
+        Insert(u8, u32),
+        InsertOrReplace(u8, u32),
+        Delete(u8),
+        PopFirst,
+        PopLast,
+    }
+
+    fn op() -> impl Strategy<Value = Op> {
+        prop_oneof![
+            4 => (any::<u8>(), any::<u32>()).prop_map(|(key, value)| Op::Insert(key % 64, value)),
+            2 => (any::<u8>(), any::<u32>()).prop_map(|(key, value)| Op::InsertOrReplace(key % 64, value)),
+            4 => any::<u8>().prop_map(|key| Op::Delete(key % 64)),
+            1 => Just(Op::PopFirst),
+            1 => Just(Op::PopLast),
+        ]
+    }
+
+    proptest! {
+        #[test]
+        fn matches_btree_map(ops in vec(op(), 0..300)) {
+            let mut tree = AvlTree::new();
+            let mut model = BTreeMap::new();
+            for op in ops {
+                match op {
+                    Op::Insert(key, value) => {
+                        tree.insert(key, value);
+                        model.entry(key).or_insert(value);
+                    }
+                    Op::InsertOrReplace(key, value) => prop_assert_eq!(tree.insert_or_replace(key, value), model.insert(key, value)),
+                    Op::Delete(key) => prop_assert_eq!(tree.remove(&key), model.remove(&key)),
+                    Op::PopFirst => prop_assert_eq!(tree.pop_first(), model.pop_first()),
+                    Op::PopLast => prop_assert_eq!(tree.pop_last(), model.pop_last()),
+                }
+                prop_assert_eq!(tree.validate(), Ok(()));
+                prop_assert!(tree.iter().eq(model.iter()));
+                prop_assert!(tree.iter().rev().eq(model.iter().rev()));
+                //高度上界1.44log2(n+2)
+                prop_assert!(tree.height() as f64 <= 1.45 * ((tree.len() + 2) as f64).log2());
+            }
+        }
+    }
+}
@@ -0,0 +1,130 @@
+//! 无父节点指针的二叉搜索树共用的中序迭代
+//! 前后两端各用一个栈记录未访问的祖先，LlrbTree与AvlTree的迭代器都基于此
+
+///以Box独占子节点的二叉树节点
+pub(crate) trait BinaryNode {
+    type Key;
+    type Value;
+
+    fn entry(&self) -> (&Self::Key, &Self::Value);
+    fn left(&self) -> Option<&Self>;
+    fn right(&self) -> Option<&Self>;
+}
+
+/// 中序迭代器，产出借用
+/// front栈顶为下一个产出的节点，back栈顶为最后一个产出的节点，两端相遇时迭代结束
+/// remaining为剩余数量，范围迭代时未知
+pub(crate) struct InorderIter<'a, N> {
+    front: Vec<&'a N>,
+    back: Vec<&'a N>,
+    remaining: Option<usize>,
+}
+
+impl<'a, N: BinaryNode> InorderIter<'a, N> {
+    ///len为树的节点数量
+    pub(crate) fn new(root: Option<&'a N>, len: usize) -> Self {
+        let mut iter = InorderIter { front: Vec::new(), back: Vec::new(), remaining: Some(len) };
+        iter.descend_left(root);
+        iter.descend_right(root);
+        iter
+    }
+
+    /// 范围迭代器，两端各沿查找路径下降一次，O(log n)
+    /// front只压入满足下界的祖先，back只压入满足上界的祖先
+    pub(crate) fn range<R: RangeBounds<N::Key>>(root: Option<&'a N>, range: R) -> Self
+    where
+        N::Key: Ord,
+    {
+        let mut iter = InorderIter { front: Vec::new(), back: Vec::new(), remaining: None };
+        let mut cur = root;
+        while let Some(node) = cur {
+            cur = if after_start(range.start_bound(), node.entry().0) {
+                iter.front.push(node);
+                node.left()
+            } else {
+                node.right()
+            };
+        }
+        cur = root;
+        while let Some(node) = cur {
+            cur = if before_end(range.end_bound(), node.entry().0) {
+                iter.back.push(node);
+                node.right()
+            } else {
+                node.left()
+            };
+        }
+        //范围内没有节点，或起点在终点之后
+        match (iter.front.last(), iter.back.last()) {
+            (Some(first), Some(last)) if first.entry().0 <= last.entry().0 => {}
+            _ => iter.finish(),
+        }
+        iter
+    }
+
+    fn finish(&mut self) {
+        self.front.clear();
+        self.back.clear();
+        self.remaining = Some(0);
+    }
+
+    fn descend_left(&mut self, mut cur: Option<&'a N>) {
+        while let Some(node) = cur {
+            self.front.push(node);
+            cur = node.left();
+        }
+    }
+
+    fn descend_right(&mut self, mut cur: Option<&'a N>) {
+        while let Some(node) = cur {
+            self.back.push(node);
+            cur = node.right();
+        }
+    }
+}
+
+impl<'a, N: BinaryNode> Iterator for InorderIter<'a, N> {
+    type Item = (&'a N::Key, &'a N::Value);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.front.pop()?;
+        if let Some(remaining) = &mut self.remaining {
+            *remaining -= 1;
+        }
+        //与另一端相遇，这是最后一个节点
+        if self.back.last().is_some_and(|last| ptr::eq(*last, node)) {
+            self.finish();
+        } else {
+            self.descend_left(node.right());
+        }
+        Some(node.entry())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self.remaining {
+            Some(remaining) => (remaining, Some(remaining)),
+            None => (usize::from(!self.front.is_empty()), None),
+        }
+    }
+}
+
+impl<N: BinaryNode> DoubleEndedIterator for InorderIter<'_, N> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let node = self.back.pop()?;
+        if let Some(remaining) = &mut self.remaining {
+            *remaining -= 1;
+        }
+        if self.front.last().is_some_and(|first| ptr::eq(*first, node)) {
+            self.finish();
+        } else {
+            self.descend_right(node.left());
+        }
+        Some(node.entry())
+    }
+}
+
+use alloc::vec::Vec;
+use core::ops::RangeBounds;
+use core::ptr;
+
+use super::ordered_map::{after_start, before_end};
@@ -0,0 +1,486 @@
+//! 左倾红黑树(LLRB)
+//! Sedgewick提出的变体：红色链接只能出现在左侧，与2-3树一一对应
+//! 插入、删除均为递归下降后沿返回路径修正，无需父节点指针，情况数远少于经典红黑树
+//! 节点以Box独占子节点，读接口可直接返回借用
+
+use alloc::boxed::Box;
+use core::cmp::Ordering;
+use core::fmt;
+use core::ops::RangeBounds;
+
+use super::inorder::{BinaryNode, InorderIter};
+use super::red_black_tree::{Color, InvariantViolation};
+
+type Link<K, V> = Option<Box<LlrbNode<K, V>>>;
+
+struct LlrbNode<K, V> {
+    key: K,
+    value: V,
+    left: Link<K, V>,
+    right: Link<K, V>,
+    //指向该节点的链接的颜色
+    color: Color,
+}
+
+impl<K, V> BinaryNode for LlrbNode<K, V> {
+    type Key = K;
+    type Value = V;
+
+    fn entry(&self) -> (&K, &V) {
+        (&self.key, &self.value)
+    }
+
+    fn left(&self) -> Option<&Self> {
+        self.left.as_deref()
+    }
+
+    fn right(&self) -> Option<&Self> {
+        self.right.as_deref()
+    }
+}
+
+pub struct LlrbTree<K, V> {
+    root: Link<K, V>,
+    len: usize,
+}
+
+fn is_red<K, V>(link: &Link<K, V>) -> bool {
+    link.as_ref().is_some_and(|node| node.color == Color::Red)
+}
+
+///左子节点的左子节点是否为红色
+fn is_left_left_red<K, V>(link: &Link<K, V>) -> bool {
+    link.as_ref().is_some_and(|node| is_red(&node.left))
+}
+
+fn flip(color: Color) -> Color {
+    match color {
+        Color::Red => Color::Black,
+        Color::Black => Color::Red,
+    }
+}
+
+///左旋，右侧的红色链接转到左侧
+fn rotate_left<K, V>(mut node: Box<LlrbNode<K, V>>) -> Box<LlrbNode<K, V>> {
+    let mut right = node.right.take().expect("rotate left without right child");
+    node.right = right.left.take();
+    right.color = node.color;
+    node.color = Color::Red;
+    right.left = Some(node);
+    right
+}
+
+///右旋，左侧的红色链接转到右侧
+fn rotate_right<K, V>(mut node: Box<LlrbNode<K, V>>) -> Box<LlrbNode<K, V>> {
+    let mut left = node.left.take().expect("rotate right without left child");
+    node.left = left.right.take();
+    left.color = node.color;
+    node.color = Color::Red;
+    left.right = Some(node);
+    left
+}
+
+///翻转节点与两个子节点的颜色，相当于拆分或合并4-节点
+fn flip_colors<K, V>(node: &mut LlrbNode<K, V>) {
+    node.color = flip(node.color);
+    for son in [&mut node.left, &mut node.right].into_iter().flatten() {
+        son.color = flip(son.color);
+    }
+}
+
+///沿返回路径恢复左倾性质
+fn fix_up<K, V>(mut node: Box<LlrbNode<K, V>>) -> Box<LlrbNode<K, V>> {
+    if is_red(&node.right) && !is_red(&node.left) {
+        node = rotate_left(node);
+    }
+    if is_red(&node.left) && is_left_left_red(&node.left) {
+        node = rotate_right(node);
+    }
+    if is_red(&node.left) && is_red(&node.right) {
+        flip_colors(&mut node);
+    }
+    node
+}
+
+///保证左子节点或其左子节点为红色，以便向左下降删除
+fn move_red_left<K, V>(mut node: Box<LlrbNode<K, V>>) -> Box<LlrbNode<K, V>> {
+    flip_colors(&mut node);
+    if node.right.as_ref().is_some_and(|right| is_red(&right.left)) {
+        node.right = node.right.take().map(rotate_right);
+        node = rotate_left(node);
+        flip_colors(&mut node);
+    }
+    node
+}
+
+///保证右子节点或其左子节点为红色，以便向右下降删除
+fn move_red_right<K, V>(mut node: Box<LlrbNode<K, V>>) -> Box<LlrbNode<K, V>> {
+    flip_colors(&mut node);
+    if is_left_left_red(&node.left) {
+        node = rotate_right(node);
+        flip_colors(&mut node);
+    }
+    node
+}
+
+/// 插入，返回子树新的根与未保留的值
+/// 键已存在时replace为true则替换并返回旧值，否则返回新值
+fn insert_node<K: Ord, V>(link: Link<K, V>, key: K, value: V, replace: bool) -> (Box<LlrbNode<K, V>>, Option<V>) {
+    let Some(mut node) = link else {
+        return (Box::new(LlrbNode { key, value, left: None, right: None, color: Color::Red }), None);
+    };
+    let displaced = match key.cmp(&node.key) {
+        Ordering::Less => {
+            let (left, displaced) = insert_node(node.left.take(), key, value, replace);
+            node.left = Some(left);
+            displaced
+        }
+        Ordering::Greater => {
+            let (right, displaced) = insert_node(node.right.take(), key, value, replace);
+            node.right = Some(right);
+            displaced
+        }
+        Ordering::Equal if replace => Some(core::mem::replace(&mut node.value, value)),
+        Ordering::Equal => Some(value),
+    };
+    (fix_up(node), displaced)
+}
+
+///删除子树最小节点，返回子树新的根与被删除的节点
+fn delete_min<K, V>(mut node: Box<LlrbNode<K, V>>) -> (Link<K, V>, Box<LlrbNode<K, V>>) {
+    if node.left.is_none() {
+        //左倾，没有左子节点时也没有右子节点
+        return (None, node);
+    }
+    if !is_red(&node.left) && !is_left_left_red(&node.left) {
+        node = move_red_left(node);
+    }
+    let (left, min) = delete_min(node.left.take().expect("left child checked above"));
+    node.left = left;
+    (Some(fix_up(node)), min)
+}
+
+fn delete_max<K, V>(mut node: Box<LlrbNode<K, V>>) -> (Link<K, V>, Box<LlrbNode<K, V>>) {
+    if is_red(&node.left) {
+        node = rotate_right(node);
+    }
+    if node.right.is_none() {
+        let left = node.left.take();
+        return (left, node);
+    }
+    if !is_red(&node.right) && !is_left_left_red(&node.right) {
+        node = move_red_right(node);
+    }
+    let (right, max) = delete_max(node.right.take().expect("right child checked above"));
+    node.right = right;
+    (Some(fix_up(node)), max)
+}
+
+/// 删除key，调用方需保证key存在
+/// 下降时保证当前节点或其左子节点为红色，到达底部时被删除的节点不是2-节点
+fn delete_node<K: Ord, V>(mut node: Box<LlrbNode<K, V>>, key: &K) -> (Link<K, V>, (K, V)) {
+    let removed;
+    if *key < node.key {
+        if !is_red(&node.left) && !is_left_left_red(&node.left) {
+            node = move_red_left(node);
+        }
+        let (left, entry) = delete_node(node.left.take().expect("key exists in left subtree"), key);
+        node.left = left;
+        removed = entry;
+    } else {
+        if is_red(&node.left) {
+            node = rotate_right(node);
+        }
+        if *key == node.key && node.right.is_none() {
+            return (None, (node.key, node.value));
+        }
+        if !is_red(&node.right) && !is_left_left_red(&node.right) {
+            node = move_red_right(node);
+        }
+        if *key == node.key {
+            //以右子树最小节点取代当前节点
+            let (right, min) = delete_min(node.right.take().expect("right child exists"));
+            node.right = right;
+            let key = core::mem::replace(&mut node.key, min.key);
+            let value = core::mem::replace(&mut node.value, min.value);
+            removed = (key, value);
+        } else {
+            let (right, entry) = delete_node(node.right.take().expect("key exists in right subtree"), key);
+            node.right = right;
+            removed = entry;
+        }
+    }
+    (Some(fix_up(node)), removed)
+}
+
+impl<K: Ord, V> LlrbTree<K, V> {
+    pub fn new() -> Self {
+        LlrbTree { root: None, len: 0 }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn clear(&mut self) {
+        self.root = None;
+        self.len = 0;
+    }
+
+    ///插入键值对，键已存在时保留原值
+    pub fn insert(&mut self, key: K, value: V) {
+        self.insert_with(key, value, false);
+    }
+
+    ///插入或替换，返回旧值
+    pub fn insert_or_replace(&mut self, key: K, value: V) -> Option<V> {
+        self.insert_with(key, value, true)
+    }
+
+    fn insert_with(&mut self, key: K, value: V, replace: bool) -> Option<V> {
+        let (mut root, displaced) = insert_node(self.root.take(), key, value, replace);
+        root.color = Color::Black;
+        self.root = Some(root);
+        if displaced.is_none() {
+            self.len += 1;
+        }
+        displaced.filter(|_| replace)
+    }
+
+    ///删除键，键不存在时忽略
+    pub fn delete(&mut self, key: &K) {
+        self.remove(key);
+    }
+
+    ///删除键并返回值
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        if !self.contains(key) {
+            return None;
+        }
+        let (_, value) = self.remove_with(|root| delete_node(root, key));
+        Some(value)
+    }
+
+    pub fn pop_first(&mut self) -> Option<(K, V)> {
+        (!self.is_empty()).then(|| {
+            self.remove_with(|root| {
+                let (root, min) = delete_min(root);
+                (root, (min.key, min.value))
+            })
+        })
+    }
+
+    pub fn pop_last(&mut self) -> Option<(K, V)> {
+        (!self.is_empty()).then(|| {
+            self.remove_with(|root| {
+                let (root, max) = delete_max(root);
+                (root, (max.key, max.value))
+            })
+        })
+    }
+
+    /// 在非空树上执行删除
+    /// 根的两个子节点都为黑色时先把根染红，使下降的起点不是2-节点
+    fn remove_with<F: FnOnce(Box<LlrbNode<K, V>>) -> (Link<K, V>, (K, V))>(&mut self, delete: F) -> (K, V) {
+        let mut root = self.root.take().expect("remove from an empty tree");
+        if !is_red(&root.left) && !is_red(&root.right) {
+            root.color = Color::Red;
+        }
+        let (root, entry) = delete(root);
+        self.root = root;
+        if let Some(root) = &mut self.root {
+            root.color = Color::Black;
+        }
+        self.len -= 1;
+        entry
+    }
+
+    fn find(&self, key: &K) -> Option<&LlrbNode<K, V>> {
+        let mut cur = self.root.as_deref();
+        while let Some(node) = cur {
+            cur = match key.cmp(&node.key) {
+                Ordering::Equal => return Some(node),
+                Ordering::Less => node.left.as_deref(),
+                Ordering::Greater => node.right.as_deref(),
+            };
+        }
+        None
+    }
+
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.find(key).map(|node| &node.value)
+    }
+
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        let mut cur = self.root.as_deref_mut();
+        while let Some(node) = cur {
+            cur = match key.cmp(&node.key) {
+                Ordering::Equal => return Some(&mut node.value),
+                Ordering::Less => node.left.as_deref_mut(),
+                Ordering::Greater => node.right.as_deref_mut(),
+            };
+        }
+        None
+    }
+
+    pub fn contains(&self, key: &K) -> bool {
+        self.find(key).is_some()
+    }
+
+    pub fn get_key_value(&self, key: &K) -> Option<(&K, &V)> {
+        self.find(key).map(|node| (&node.key, &node.value))
+    }
+
+    pub fn first(&self) -> Option<(&K, &V)> {
+        self.iter().next()
+    }
+
+    pub fn last(&self) -> Option<(&K, &V)> {
+        self.iter().next_back()
+    }
+
+    ///中序迭代，产出借用
+    pub fn iter(&self) -> LlrbIter<'_, K, V> {
+        LlrbIter { iter: InorderIter::new(self.root.as_deref(), self.len) }
+    }
+
+    ///按键升序迭代范围内的条目，两端沿查找路径定位，O(log n)
+    pub fn range<R: RangeBounds<K>>(&self, range: R) -> LlrbIter<'_, K, V> {
+        LlrbIter { iter: InorderIter::range(self.root.as_deref(), range) }
+    }
+
+    /// 校验左倾红黑树性质
+    /// 除经典红黑树的性质外，红色节点只能是左子节点
+    pub fn validate(&self) -> Result<(), InvariantViolation<K>>
+    where
+        K: Clone,
+    {
+        if is_red(&self.root) {
+            return Err(InvariantViolation::RedRoot);
+        }
+        let mut count = 0;
+        Self::validate_node(&self.root, None, None, &mut count)?;
+        if count != self.len {
+            return Err(InvariantViolation::LengthMismatch { expected: self.len, actual: count });
+        }
+        Ok(())
+    }
+
+    ///递归校验子树，返回子树黑高(空节点黑高为1)
+    fn validate_node(link: &Link<K, V>, lower: Option<&K>, upper: Option<&K>, count: &mut usize) -> Result<usize, InvariantViolation<K>>
+    where
+        K: Clone,
+    {
+        let Some(node) = link else {
+            return Ok(1);
+        };
+        *count += 1;
+        if lower.is_some_and(|lower| node.key <= *lower) || upper.is_some_and(|upper| node.key >= *upper) {
+            return Err(InvariantViolation::OutOfOrder { key: node.key.clone() });
+        }
+        if is_red(&node.right) {
+            return Err(InvariantViolation::RightLeaningRed { key: node.key.clone() });
+        }
+        if node.color == Color::Red && is_red(&node.left) {
+            return Err(InvariantViolation::RedRed { key: node.key.clone() });
+        }
+        let left = Self::validate_node(&node.left, lower, Some(&node.key), count)?;
+        let right = Self::validate_node(&node.right, Some(&node.key), upper, count)?;
+        if left != right {
+            return Err(InvariantViolation::BlackHeight { key: node.key.clone(), left, right });
+        }
+        Ok(left + usize::from(node.color == Color::Black))
+    }
+}
+
+impl<K: Ord, V> Default for LlrbTree<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Ord + fmt::Debug, V: fmt::Debug> fmt::Debug for LlrbTree<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+/// 中序迭代器
+pub struct LlrbIter<'a, K, V> {
+    iter: InorderIter<'a, LlrbNode<K, V>>,
+}
+
+impl<'a, K, V> Iterator for LlrbIter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<K, V> DoubleEndedIterator for LlrbIter<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.iter.next_back()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+
+    use super::LlrbTree;
+
+    #[derive(Debug, Clone)]
+    enum Op {
+        Insert(u8, u32),
+        InsertOrReplace(u8, u32),
+        Delete(u8),
+        PopFirst,
+        PopLast,
+    }
+
+    fn op() -> impl Strategy<Value = Op> {
+        prop_oneof![
+            4 => (any::<u8>(), any::<u32>()).prop_map(|(key, value)| Op::Insert(key % 64, value)),
+            2 => (any::<u8>(), any::<u32>()).prop_map(|(key, value)| Op::InsertOrReplace(key % 64, value)),
+            4 => any::<u8>().prop_map(|key| Op::Delete(key % 64)),
+            1 => Just(Op::PopFirst),
+            1 => Just(Op::PopLast),
+        ]
+    }
+
+    proptest! {
+        #[test]
+        fn matches_btree_map(ops in vec(op(), 0..300)) {
+            let mut tree = LlrbTree::new();
+            let mut model = BTreeMap::new();
+            for op in ops {
+                match op {
+                    Op::Insert(key, value) => {
+                        tree.insert(key, value);
+                        model.entry(key).or_insert(value);
+                    }
+                    Op::InsertOrReplace(key, value) => prop_assert_eq!(tree.insert_or_replace(key, value), model.insert(key, value)),
+                    Op::Delete(key) => prop_assert_eq!(tree.remove(&key), model.remove(&key)),
+                    Op::PopFirst => prop_assert_eq!(tree.pop_first(), model.pop_first()),
+                    Op::PopLast => prop_assert_eq!(tree.pop_last(), model.pop_last()),
+                }
+                prop_assert_eq!(tree.validate(), Ok(()));
+                prop_assert_eq!(tree.len(), model.len());
+                prop_assert!(tree.iter().eq(model.iter()));
+                prop_assert!(tree.iter().rev().eq(model.iter().rev()));
+            }
+        }
+    }
+}
@@ -0,0 +1,747 @@
+//! 有序映射的公共接口
+//! 本crate的各种平衡树与跳表都实现此trait，泛型的基准、模糊测试与用户代码无需修改即可切换实现
+//! 各实现的读接口不同：RedBlackTree返回拷贝，其余返回借用
+//! trait以关联类型KeyRef、ValueRef抽象二者，均可借出&Key、&Value
+
+use core::borrow::Borrow;
+use core::ops::{Bound, RangeBounds};
+
+use super::avl_tree::{AvlIter, AvlTree};
+use super::llrb::{LlrbIter, LlrbTree};
+#[cfg(feature = "fast")]
+use super::red_black_tree::{FastIter, RedBlackTreeFast};
+use super::red_black_tree::{ArenaIter, Iter, PersistentIter, PersistentRedBlackTree, RawEntryMut, RedBlackTree, RedBlackTreeArena, RedBlackTreeTopDown, TopDownIter};
+use super::skip_list::{SkipIter, SkipList};
+use super::treap::{Treap, TreapIter};
+
+pub trait OrderedMap: Default {
+    type Key: Ord;
+    type Value;
+    ///查找与迭代产出的键，拷贝或借用
+    type KeyRef<'a>: Borrow<Self::Key>
+    where
+        Self: 'a;
+    ///查找与迭代产出的值，拷贝或借用
+    type ValueRef<'a>: Borrow<Self::Value>
+    where
+        Self: 'a;
+    type Iter<'a>: DoubleEndedIterator<Item = (Self::KeyRef<'a>, Self::ValueRef<'a>)>
+    where
+        Self: 'a;
+    type Range<'a>: DoubleEndedIterator<Item = (Self::KeyRef<'a>, Self::ValueRef<'a>)>
+    where
+        Self: 'a;
+
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    ///插入键值对，键已存在时保留原值，与重复键策略无关
+    fn insert(&mut self, key: Self::Key, value: Self::Value);
+
+    ///插入或替换，返回旧值
+    fn insert_or_replace(&mut self, key: Self::Key, value: Self::Value) -> Option<Self::Value>;
+
+    ///删除键并返回值
+    fn remove(&mut self, key: &Self::Key) -> Option<Self::Value>;
+
+    fn get(&self, key: &Self::Key) -> Option<Self::ValueRef<'_>>;
+
+    fn contains(&self, key: &Self::Key) -> bool;
+
+    fn pop_first(&mut self) -> Option<(Self::Key, Self::Value)>;
+
+    fn pop_last(&mut self) -> Option<(Self::Key, Self::Value)>;
+
+    ///中序迭代
+    fn iter(&self) -> Self::Iter<'_>;
+
+    /// 范围迭代，类似BTreeMap::range
+    /// 本crate的实现都沿查找路径以O(log n)定位两端，不逐个跳过范围外的元素
+    fn range<R: RangeBounds<Self::Key>>(&self, range: R) -> Self::Range<'_>;
+}
+
+///key是否满足范围的下界
+pub(crate) fn after_start<K: Ord>(start: Bound<&K>, key: &K) -> bool {
+    match start {
+        Bound::Included(start) => key >= start,
+        Bound::Excluded(start) => key > start,
+        Bound::Unbounded => true,
+    }
+}
+
+///key是否满足范围的上界
+pub(crate) fn before_end<K: Ord>(end: Bound<&K>, key: &K) -> bool {
+    match end {
+        Bound::Included(end) => key <= end,
+        Bound::Excluded(end) => key < end,
+        Bound::Unbounded => true,
+    }
+}
+
+impl<K: Ord + Clone, V: Clone> OrderedMap for RedBlackTree<K, V> {
+    type Key = K;
+    type Value = V;
+    type KeyRef<'a>
+        = K
+    where
+        Self: 'a;
+    type ValueRef<'a>
+        = V
+    where
+        Self: 'a;
+    type Iter<'a>
+        = Iter<'a, K, V>
+    where
+        Self: 'a;
+    type Range<'a>
+        = Iter<'a, K, V>
+    where
+        Self: 'a;
+
+    fn len(&self) -> usize {
+        RedBlackTree::len(self)
+    }
+
+    //不经过重复键策略，Overwrite、Count模式下也保留原值与计数
+    fn insert(&mut self, key: K, value: V) {
+        if let RawEntryMut::Vacant(vacant) = self.raw_entry_mut().from_key(&key) {
+            vacant.insert(key, value);
+        }
+    }
+
+    fn insert_or_replace(&mut self, key: K, value: V) -> Option<V> {
+        RedBlackTree::insert_or_replace(self, key, value)
+    }
+
+    //多重集模式下整体删除节点，与pop_first一致；返回值只有一份，无法只减少计数
+    fn remove(&mut self, key: &K) -> Option<V> {
+        match self.raw_entry_mut().from_key(key) {
+            RawEntryMut::Occupied(occupied) => Some(occupied.remove()),
+            RawEntryMut::Vacant(_) => None,
+        }
+    }
+
+    fn get(&self, key: &K) -> Option<V> {
+        RedBlackTree::get(self, key)
+    }
+
+    fn contains(&self, key: &K) -> bool {
+        RedBlackTree::contains(self, key)
+    }
+
+    fn pop_first(&mut self) -> Option<(K, V)> {
+        RedBlackTree::pop_first(self)
+    }
+
+    fn pop_last(&mut self) -> Option<(K, V)> {
+        RedBlackTree::pop_last(self)
+    }
+
+    fn iter(&self) -> Iter<'_, K, V> {
+        RedBlackTree::iter(self)
+    }
+
+    fn range<R: RangeBounds<K>>(&self, range: R) -> Iter<'_, K, V> {
+        RedBlackTree::range(self, range)
+    }
+}
+
+impl<K: Ord, V> OrderedMap for RedBlackTreeArena<K, V> {
+    type Key = K;
+    type Value = V;
+    type KeyRef<'a>
+        = &'a K
+    where
+        Self: 'a;
+    type ValueRef<'a>
+        = &'a V
+    where
+        Self: 'a;
+    type Iter<'a>
+        = ArenaIter<'a, K, V>
+    where
+        Self: 'a;
+    type Range<'a>
+        = ArenaIter<'a, K, V>
+    where
+        Self: 'a;
+
+    fn len(&self) -> usize {
+        RedBlackTreeArena::len(self)
+    }
+
+    fn insert(&mut self, key: K, value: V) {
+        RedBlackTreeArena::insert(self, key, value);
+    }
+
+    fn insert_or_replace(&mut self, key: K, value: V) -> Option<V> {
+        match self.get_mut(&key) {
+            Some(old) => Some(core::mem::replace(old, value)),
+            None => {
+                RedBlackTreeArena::insert(self, key, value);
+                None
+            }
+        }
+    }
+
+    fn remove(&mut self, key: &K) -> Option<V> {
+        RedBlackTreeArena::remove(self, key)
+    }
+
+    fn get(&self, key: &K) -> Option<&V> {
+        RedBlackTreeArena::get(self, key)
+    }
+
+    fn contains(&self, key: &K) -> bool {
+        RedBlackTreeArena::contains(self, key)
+    }
+
+    fn pop_first(&mut self) -> Option<(K, V)> {
+        RedBlackTreeArena::pop_first(self)
+    }
+
+    fn pop_last(&mut self) -> Option<(K, V)> {
+        RedBlackTreeArena::pop_last(self)
+    }
+
+    fn iter(&self) -> ArenaIter<'_, K, V> {
+        RedBlackTreeArena::iter(self)
+    }
+
+    fn range<R: RangeBounds<K>>(&self, range: R) -> ArenaIter<'_, K, V> {
+        RedBlackTreeArena::range(self, range)
+    }
+}
+
+impl<K: Ord, V> OrderedMap for RedBlackTreeTopDown<K, V> {
+    type Key = K;
+    type Value = V;
+    type KeyRef<'a>
+        = &'a K
+    where
+        Self: 'a;
+    type ValueRef<'a>
+        = &'a V
+    where
+        Self: 'a;
+    type Iter<'a>
+        = TopDownIter<'a, K, V>
+    where
+        Self: 'a;
+    type Range<'a>
+        = TopDownIter<'a, K, V>
+    where
+        Self: 'a;
+
+    fn len(&self) -> usize {
+        RedBlackTreeTopDown::len(self)
+    }
+
+    fn insert(&mut self, key: K, value: V) {
+        RedBlackTreeTopDown::insert(self, key, value);
+    }
+
+    fn insert_or_replace(&mut self, key: K, value: V) -> Option<V> {
+        match self.get_mut(&key) {
+            Some(old) => Some(core::mem::replace(old, value)),
+            None => {
+                RedBlackTreeTopDown::insert(self, key, value);
+                None
+            }
+        }
+    }
+
+    fn remove(&mut self, key: &K) -> Option<V> {
+        RedBlackTreeTopDown::remove(self, key)
+    }
+
+    fn get(&self, key: &K) -> Option<&V> {
+        RedBlackTreeTopDown::get(self, key)
+    }
+
+    fn contains(&self, key: &K) -> bool {
+        RedBlackTreeTopDown::contains(self, key)
+    }
+
+    fn pop_first(&mut self) -> Option<(K, V)> {
+        RedBlackTreeTopDown::pop_first(self)
+    }
+
+    fn pop_last(&mut self) -> Option<(K, V)> {
+        RedBlackTreeTopDown::pop_last(self)
+    }
+
+    fn iter(&self) -> TopDownIter<'_, K, V> {
+        RedBlackTreeTopDown::iter(self)
+    }
+
+    fn range<R: RangeBounds<K>>(&self, range: R) -> TopDownIter<'_, K, V> {
+        RedBlackTreeTopDown::range(self, range)
+    }
+}
+
+#[cfg(feature = "fast")]
+impl<K: Ord, V> OrderedMap for RedBlackTreeFast<K, V> {
+    type Key = K;
+    type Value = V;
+    type KeyRef<'a>
+        = &'a K
+    where
+        Self: 'a;
+    type ValueRef<'a>
+        = &'a V
+    where
+        Self: 'a;
+    type Iter<'a>
+        = FastIter<'a, K, V>
+    where
+        Self: 'a;
+    type Range<'a>
+        = FastIter<'a, K, V>
+    where
+        Self: 'a;
+
+    fn len(&self) -> usize {
+        RedBlackTreeFast::len(self)
+    }
+
+    fn insert(&mut self, key: K, value: V) {
+        RedBlackTreeFast::insert(self, key, value);
+    }
+
+    fn insert_or_replace(&mut self, key: K, value: V) -> Option<V> {
+        match self.get_mut(&key) {
+            Some(old) => Some(core::mem::replace(old, value)),
+            None => {
+                RedBlackTreeFast::insert(self, key, value);
+                None
+            }
+        }
+    }
+
+    fn remove(&mut self, key: &K) -> Option<V> {
+        RedBlackTreeFast::remove(self, key)
+    }
+
+    fn get(&self, key: &K) -> Option<&V> {
+        RedBlackTreeFast::get(self, key)
+    }
+
+    fn contains(&self, key: &K) -> bool {
+        RedBlackTreeFast::contains(self, key)
+    }
+
+    fn pop_first(&mut self) -> Option<(K, V)> {
+        RedBlackTreeFast::pop_first(self)
+    }
+
+    fn pop_last(&mut self) -> Option<(K, V)> {
+        RedBlackTreeFast::pop_last(self)
+    }
+
+    fn iter(&self) -> FastIter<'_, K, V> {
+        RedBlackTreeFast::iter(self)
+    }
+
+    fn range<R: RangeBounds<K>>(&self, range: R) -> FastIter<'_, K, V> {
+        RedBlackTreeFast::range(self, range)
+    }
+}
+
+impl<K: Ord + Clone, V: Clone> OrderedMap for PersistentRedBlackTree<K, V> {
+    type Key = K;
+    type Value = V;
+    type KeyRef<'a>
+        = &'a K
+    where
+        Self: 'a;
+    type ValueRef<'a>
+        = &'a V
+    where
+        Self: 'a;
+    type Iter<'a>
+        = PersistentIter<'a, K, V>
+    where
+        Self: 'a;
+    type Range<'a>
+        = PersistentIter<'a, K, V>
+    where
+        Self: 'a;
+
+    fn len(&self) -> usize {
+        PersistentRedBlackTree::len(self)
+    }
+
+    fn insert(&mut self, key: K, value: V) {
+        PersistentRedBlackTree::insert(self, key, value);
+    }
+
+    fn insert_or_replace(&mut self, key: K, value: V) -> Option<V> {
+        PersistentRedBlackTree::insert_or_replace(self, key, value)
+    }
+
+    fn remove(&mut self, key: &K) -> Option<V> {
+        PersistentRedBlackTree::remove(self, key)
+    }
+
+    fn get(&self, key: &K) -> Option<&V> {
+        PersistentRedBlackTree::get(self, key)
+    }
+
+    fn contains(&self, key: &K) -> bool {
+        PersistentRedBlackTree::contains(self, key)
+    }
+
+    fn pop_first(&mut self) -> Option<(K, V)> {
+        PersistentRedBlackTree::pop_first(self)
+    }
+
+    fn pop_last(&mut self) -> Option<(K, V)> {
+        PersistentRedBlackTree::pop_last(self)
+    }
+
+    fn iter(&self) -> PersistentIter<'_, K, V> {
+        PersistentRedBlackTree::iter(self)
+    }
+
+    fn range<R: RangeBounds<K>>(&self, range: R) -> PersistentIter<'_, K, V> {
+        PersistentRedBlackTree::range(self, range)
+    }
+}
+
+impl<K: Ord, V> OrderedMap for LlrbTree<K, V> {
+    type Key = K;
+    type Value = V;
+    type KeyRef<'a>
+        = &'a K
+    where
+        Self: 'a;
+    type ValueRef<'a>
+        = &'a V
+    where
+        Self: 'a;
+    type Iter<'a>
+        = LlrbIter<'a, K, V>
+    where
+        Self: 'a;
+    type Range<'a>
+        = LlrbIter<'a, K, V>
+    where
+        Self: 'a;
+
+    fn len(&self) -> usize {
+        LlrbTree::len(self)
+    }
+
+    fn insert(&mut self, key: K, value: V) {
+        LlrbTree::insert(self, key, value);
+    }
+
+    fn insert_or_replace(&mut self, key: K, value: V) -> Option<V> {
+        LlrbTree::insert_or_replace(self, key, value)
+    }
+
+    fn remove(&mut self, key: &K) -> Option<V> {
+        LlrbTree::remove(self, key)
+    }
+
+    fn get(&self, key: &K) -> Option<&V> {
+        LlrbTree::get(self, key)
+    }
+
+    fn contains(&self, key: &K) -> bool {
+        LlrbTree::contains(self, key)
+    }
+
+    fn pop_first(&mut self) -> Option<(K, V)> {
+        LlrbTree::pop_first(self)
+    }
+
+    fn pop_last(&mut self) -> Option<(K, V)> {
+        LlrbTree::pop_last(self)
+    }
+
+    fn iter(&self) -> LlrbIter<'_, K, V> {
+        LlrbTree::iter(self)
+    }
+
+    fn range<R: RangeBounds<K>>(&self, range: R) -> LlrbIter<'_, K, V> {
+        LlrbTree::range(self, range)
+    }
+}
+
+impl<K: Ord, V> OrderedMap for AvlTree<K, V> {
+    type Key = K;
+    type Value = V;
+    type KeyRef<'a>
+        = &'a K
+    where
+        Self: 'a;
+    type ValueRef<'a>
+        = &'a V
+    where
+        Self: 'a;
+    type Iter<'a>
+        = AvlIter<'a, K, V>
+    where
+        Self: 'a;
+    type Range<'a>
+        = AvlIter<'a, K, V>
+    where
+        Self: 'a;
+
+    fn len(&self) -> usize {
+        AvlTree::len(self)
+    }
+
+    fn insert(&mut self, key: K, value: V) {
+        AvlTree::insert(self, key, value);
+    }
+
+    fn insert_or_replace(&mut self, key: K, value: V) -> Option<V> {
+        AvlTree::insert_or_replace(self, key, value)
+    }
+
+    fn remove(&mut self, key: &K) -> Option<V> {
+        AvlTree::remove(self, key)
+    }
+
+    fn get(&self, key: &K) -> Option<&V> {
+        AvlTree::get(self, key)
+    }
+
+    fn contains(&self, key: &K) -> bool {
+        AvlTree::contains(self, key)
+    }
+
+    fn pop_first(&mut self) -> Option<(K, V)> {
+        AvlTree::pop_first(self)
+    }
+
+    fn pop_last(&mut self) -> Option<(K, V)> {
+        AvlTree::pop_last(self)
+    }
+
+    fn iter(&self) -> AvlIter<'_, K, V> {
+        AvlTree::iter(self)
+    }
+
+    fn range<R: RangeBounds<K>>(&self, range: R) -> AvlIter<'_, K, V> {
+        AvlTree::range(self, range)
+    }
+}
+
+impl<K: Ord, V> OrderedMap for Treap<K, V> {
+    type Key = K;
+    type Value = V;
+    type KeyRef<'a>
+        = &'a K
+    where
+        Self: 'a;
+    type ValueRef<'a>
+        = &'a V
+    where
+        Self: 'a;
+    type Iter<'a>
+        = TreapIter<'a, K, V>
+    where
+        Self: 'a;
+    type Range<'a>
+        = TreapIter<'a, K, V>
+    where
+        Self: 'a;
+
+    fn len(&self) -> usize {
+        Treap::len(self)
+    }
+
+    fn insert(&mut self, key: K, value: V) {
+        Treap::insert(self, key, value);
+    }
+
+    fn insert_or_replace(&mut self, key: K, value: V) -> Option<V> {
+        Treap::insert_or_replace(self, key, value)
+    }
+
+    fn remove(&mut self, key: &K) -> Option<V> {
+        Treap::remove(self, key)
+    }
+
+    fn get(&self, key: &K) -> Option<&V> {
+        Treap::get(self, key)
+    }
+
+    fn contains(&self, key: &K) -> bool {
+        Treap::contains(self, key)
+    }
+
+    fn pop_first(&mut self) -> Option<(K, V)> {
+        Treap::pop_first(self)
+    }
+
+    fn pop_last(&mut self) -> Option<(K, V)> {
+        Treap::pop_last(self)
+    }
+
+    fn iter(&self) -> TreapIter<'_, K, V> {
+        Treap::iter(self)
+    }
+
+    fn range<R: RangeBounds<K>>(&self, range: R) -> TreapIter<'_, K, V> {
+        Treap::range(self, range)
+    }
+}
+
+impl<K: Ord, V> OrderedMap for SkipList<K, V> {
+    type Key = K;
+    type Value = V;
+    type KeyRef<'a>
+        = &'a K
+    where
+        Self: 'a;
+    type ValueRef<'a>
+        = &'a V
+    where
+        Self: 'a;
+    type Iter<'a>
+        = SkipIter<'a, K, V>
+    where
+        Self: 'a;
+    type Range<'a>
+        = SkipIter<'a, K, V>
+    where
+        Self: 'a;
+
+    fn len(&self) -> usize {
+        SkipList::len(self)
+    }
+
+    fn insert(&mut self, key: K, value: V) {
+        SkipList::insert(self, key, value);
+    }
+
+    fn insert_or_replace(&mut self, key: K, value: V) -> Option<V> {
+        SkipList::insert_or_replace(self, key, value)
+    }
+
+    fn remove(&mut self, key: &K) -> Option<V> {
+        SkipList::remove(self, key)
+    }
+
+    fn get(&self, key: &K) -> Option<&V> {
+        SkipList::get(self, key)
+    }
+
+    fn contains(&self, key: &K) -> bool {
+        SkipList::contains(self, key)
+    }
+
+    fn pop_first(&mut self) -> Option<(K, V)> {
+        SkipList::pop_first(self)
+    }
+
+    fn pop_last(&mut self) -> Option<(K, V)> {
+        SkipList::pop_last(self)
+    }
+
+    fn iter(&self) -> SkipIter<'_, K, V> {
+        SkipList::iter(self)
+    }
+
+    fn range<R: RangeBounds<K>>(&self, range: R) -> SkipIter<'_, K, V> {
+        SkipList::range(self, range)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Borrow;
+    use std::collections::BTreeMap;
+    use std::ops::Bound;
+
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    use super::super::avl_tree::AvlTree;
+    use super::super::llrb::LlrbTree;
+    use super::super::red_black_tree::{DuplicatePolicy, PersistentRedBlackTree, RedBlackTree, RedBlackTreeArena, RedBlackTreeTopDown};
+    use super::super::skip_list::SkipList;
+    use super::super::treap::Treap;
+    use super::OrderedMap;
+
+    ///同一随机操作序列作用于map与BTreeMap，逐步比对结果
+    fn run<M: OrderedMap<Key = u32, Value = u32>>() {
+        let mut rng = StdRng::seed_from_u64(2056);
+        let mut map = M::default();
+        let mut model = BTreeMap::new();
+        for step in 0..5_000 {
+            let key = rng.gen_range(0..300);
+            match rng.gen_range(0..10) {
+                0..=3 => {
+                    map.insert(key, step);
+                    model.entry(key).or_insert(step);
+                }
+                4 => assert_eq!(map.insert_or_replace(key, step), model.insert(key, step)),
+                5..=7 => assert_eq!(map.remove(&key), model.remove(&key)),
+                8 => assert_eq!(map.pop_first(), model.pop_first()),
+                _ => assert_eq!(map.pop_last(), model.pop_last()),
+            }
+            assert_eq!(map.contains(&key), model.contains_key(&key));
+            assert_eq!(map.get(&key).map(|value| *value.borrow()), model.get(&key).copied());
+            assert_eq!(map.len(), model.len());
+            if step % 100 == 0 {
+                let (low, high) = (key.min(key / 2 + 100), key.max(key / 2 + 100));
+                assert!(entries(map.iter()).eq(model.iter().map(|(k, v)| (*k, *v))));
+                assert!(entries(map.range(low..high)).eq(model.range(low..high).map(|(k, v)| (*k, *v))));
+                assert!(entries(map.range(low..=high).rev()).eq(model.range(low..=high).rev().map(|(k, v)| (*k, *v))));
+                assert!(entries(map.range(..low)).eq(model.range(..low).map(|(k, v)| (*k, *v))));
+                //两端交替推进直到相遇
+                let mut range = entries(map.range(low..=high));
+                let mut expected = model.range(low..=high).map(|(k, v)| (*k, *v));
+                loop {
+                    let (actual, expected) = if rng.gen_bool(0.5) { (range.next(), expected.next()) } else { (range.next_back(), expected.next_back()) };
+                    assert_eq!(actual, expected);
+                    if expected.is_none() {
+                        break;
+                    }
+                }
+                assert_eq!(range.next(), None);
+                //空范围与起点在终点之后的范围
+                assert_eq!(entries(map.range((Bound::Included(key), Bound::Excluded(key)))).next(), None);
+                assert_eq!(entries(map.range((Bound::Excluded(high), Bound::Included(low)))).next_back(), None);
+            }
+        }
+    }
+
+    ///把迭代产出的拷贝或借用统一为键值对
+    fn entries<KR: Borrow<u32>, VR: Borrow<u32>>(iter: impl DoubleEndedIterator<Item = (KR, VR)>) -> impl DoubleEndedIterator<Item = (u32, u32)> {
+        iter.map(|(key, value)| (*key.borrow(), *value.borrow()))
+    }
+
+    #[test]
+    fn implementations_agree() {
+        run::<RedBlackTree<u32, u32>>();
+        run::<RedBlackTreeArena<u32, u32>>();
+        run::<RedBlackTreeTopDown<u32, u32>>();
+        #[cfg(feature = "fast")]
+        run::<super::RedBlackTreeFast<u32, u32>>();
+        run::<PersistentRedBlackTree<u32, u32>>();
+        run::<LlrbTree<u32, u32>>();
+        run::<AvlTree<u32, u32>>();
+        run::<Treap<u32, u32>>();
+        run::<SkipList<u32, u32>>();
+    }
+
+    #[test]
+    fn insert_keeps_existing_value_under_any_policy() {
+        for policy in [DuplicatePolicy::Ignore, DuplicatePolicy::Overwrite, DuplicatePolicy::Count] {
+            let mut tree = RedBlackTree::with_duplicate_policy(policy);
+            OrderedMap::insert(&mut tree, 1, 10);
+            OrderedMap::insert(&mut tree, 1, 20);
+            assert_eq!((tree.get(&1), tree.count(&1)), (Some(10), 1), "{:?}", policy);
+        }
+    }
+}
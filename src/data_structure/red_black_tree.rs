@@ -1,27 +1,166 @@
+use alloc::format;
+use alloc::rc::{Rc, Weak};
+use alloc::vec::Vec;
+use core::cell::RefCell;
 use core::fmt;
-use std::rc::{Rc, Weak};
-use std::cell::RefCell;
-use std::option::Option::Some;
+use core::ops::{Bound, RangeBounds};
+use core::option::Option::Some;
 
-#[derive(PartialEq, Copy, Clone, Debug)]
-enum Color {
+pub mod arena;
+mod augment;
+mod bi_index;
+#[cfg(feature = "borsh")]
+mod binary;
+mod bulk;
+mod bytes;
+mod comparator;
+#[cfg(feature = "std")]
+mod concurrent;
+pub mod consistency;
+mod cursor;
+mod diff;
+mod dot;
+pub mod document;
+mod duplicate;
+mod entry;
+mod error;
+#[cfg(test)]
+mod exhaustive;
+#[cfg(feature = "fast")]
+pub mod fast;
+mod gaps;
+mod iter;
+mod join;
+mod json;
+mod multimap;
+mod multiset;
+mod node_ref;
+mod observer;
+mod order_statistic;
+pub mod persistent;
+mod priority_queue;
+#[cfg(feature = "raw")]
+pub mod raw;
+#[cfg(test)]
+mod property;
+mod quantile;
+mod raw_entry;
+mod recycle;
+mod refcount;
+#[cfg(test)]
+mod reference;
+mod sampling;
+#[cfg(feature = "serde")]
+mod serde_map;
+#[cfg(feature = "serde")]
+pub mod serde_structure;
+mod set;
+mod set_ops;
+mod slot_tree;
+mod sort;
+mod static_tree;
+mod stats;
+#[cfg(feature = "std")]
+mod sync;
+pub mod topdown;
+mod trace;
+mod validate;
+mod zip;
+
+#[cfg(feature = "sha2")]
+pub use arena::Sha256Hash;
+pub use arena::{ArenaIter, ArenaNodeRef, CanonicalEncode, ContentHash, MembershipProof, ProofStep, RedBlackTreeArena};
+#[cfg(feature = "rayon")]
+pub use arena::ArenaParIter;
+pub use augment::Augment;
+pub use bi_index::{BiIndexedMap, SecondaryRange};
+pub use bulk::ExtractIf;
+pub use comparator::{IterBy, OrderedBy, RedBlackTreeBy};
+#[cfg(feature = "std")]
+pub use concurrent::{ConcurrentRbMap, ShardSnapshot, SnapshotIter};
+pub use cursor::Cursor;
+pub use diff::{Delta, Diff};
+pub use document::Document;
+pub use duplicate::DuplicatePolicy;
+pub use entry::{Entry, OccupiedEntry, OccupiedError, VacantEntry};
+pub use error::TreeError;
+#[cfg(feature = "fast")]
+pub use fast::{FastIter, RedBlackTreeFast};
+pub use gaps::Gaps;
+pub use iter::{IntoIter, IntoKeys, IntoValues, Iter, Keys, Values, ValuesMut};
+pub use multimap::{MultiMapIter, RedBlackMultiMap};
+pub use multiset::{MultiSetIter, RedBlackMultiSet};
+pub use node_ref::NodeRef;
+pub use observer::Observer;
+pub use persistent::{PersistentIter, PersistentRedBlackTree};
+pub use priority_queue::{PriorityIter, TreePriorityQueue};
+pub use quantile::WindowedQuantile;
+pub use raw_entry::{RawEntryBuilderMut, RawEntryMut, RawOccupiedEntryMut, RawVacantEntryMut};
+pub use refcount::RefcountIssue;
+pub use set::{RedBlackTreeSet, SetIter, SetOperation};
+pub use sort::{sort_vec_via_tree, tree_sort};
+pub use static_tree::{Full, StaticIter, StaticRbTree};
+#[cfg(feature = "stats")]
+pub use stats::Stats;
+#[cfg(feature = "std")]
+pub use sync::{SnapshotStats, SyncRedBlackTree, TreeSnapshot};
+pub use topdown::{RedBlackTreeTopDown, TopDownIter};
+pub use trace::{Recorder, Trace, TraceNode, TraceShape, TreeEvent};
+pub use validate::InvariantViolation;
+pub(crate) use sampling::RandomSource;
+pub use zip::{EitherOrBoth, ZipSorted};
+
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Color {
     Red,
     Black,
 }
 
 #[derive(Debug)]
-struct Node {
-    key: i32,
-    parent: Option<Weak<RefCell<Node>>>,
-    left: Option<Rc<RefCell<Node>>>,
-    right: Option<Rc<RefCell<Node>>>,
+struct Node<K, V> {
+    key: K,
+    value: V,
+    parent: Option<Weak<RefCell<Node<K, V>>>>,
+    left: Option<Rc<RefCell<Node<K, V>>>>,
+    right: Option<Rc<RefCell<Node<K, V>>>>,
     color: Color,
+    //子树节点数量(含自身)，用于顺序统计
+    size: usize,
+    //键的重复次数，多重集模式下插入相同的键时累加
+    count: usize,
 }
 
-pub struct RedBlackTree {
-    root: Option<Rc<RefCell<Node>>>,
+/// 红黑树，节点以Rc<RefCell<..>>连接，读取的接口返回键值的拷贝
+///
+/// 借用安全：插入、删除与旋转中同时可变借用的节点总是互不相同(父与子、兄弟与父、后继与其父)，由红黑树的结构保证；
+/// &self的方法不可变借用节点，只在&mut self的方法中可变借用，因此合法的树上任何公开操作都不会出现BorrowMutError。
+/// 用户闭包、观察者与错误钩子只拿到键值的借用，无法重新进入树。
+/// 前提是K的Ord、K与V的Clone、Drop实现不访问同一棵树(例如经由键中共享的Rc或线程局部变量)：
+/// 它们在节点被借用期间调用，重新进入树时会因RefCell的借用冲突而panic。
+/// exhaustive测试枚举一定规模内全部合法的形状，逐一验证上述保证
+pub struct RedBlackTree<K, V> {
+    root: Option<Rc<RefCell<Node<K, V>>>>,
+    //节点数量，插入删除时维护
+    len: usize,
+    //抽样校验概率的倒数，0为关闭
+    validation_sampling: u32,
+    sampled_validator: Option<fn(&RedBlackTree<K, V>)>,
+    error_hook: Option<ErrorHook>,
+    //结构操作的观察者
+    observer: Option<Rc<dyn Observer<K>>>,
+    //随机源，供抽样等随机功能使用
+    random: sampling::RandomSource,
+    //插入已存在的键时的处理方式
+    duplicate_policy: DuplicatePolicy,
+    //删除后留待复用的节点
+    pool: recycle::NodePool<K, V>,
+    //调平统计
+    #[cfg(feature = "stats")]
+    stats: core::cell::Cell<Stats>,
 }
 
+#[allow(clippy::upper_case_acronyms)]
 #[derive(PartialEq, Copy, Clone, Debug)]
 enum InsertSituation {
     LL,
@@ -32,8 +171,11 @@ enum InsertSituation {
     Stable,
 }
 
-#[derive(PartialEq, Copy, Clone, Debug)]
-enum DeleteSituation {
+/// 删除黑色叶子后的调平情况，见delete_balance
+/// 第一个字母为父节点颜色，第二个为删除节点所在侧，其余为兄弟节点及其子节点(R红色，B黑色，E不存在，W任意)
+#[allow(clippy::upper_case_acronyms)]
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum DeleteSituation {
     RLRR,
     RLRE,
     RLER,
@@ -53,8 +195,11 @@ enum DeleteSituation {
     Stable,
 }
 
-#[derive(PartialEq, Copy, Clone, Debug)]
-enum DeleteRecursionSituation {
+/// 黑高减少向上传递时的调平情况，见delete_balance_recursion
+/// 第一个字母为失衡节点所在侧，第二个为父节点颜色，其余为兄弟节点及其子节点颜色(W任意)
+#[allow(clippy::upper_case_acronyms)]
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum DeleteRecursionSituation {
     LRBW,
     LRRB,
     LRRR,
@@ -72,70 +217,139 @@ enum DeleteRecursionSituation {
     Stable,
 }
 
-///
-impl RedBlackTree {
+///错误钩子
+type ErrorHook = Rc<dyn Fn(&dyn core::error::Error)>;
+
+///插入情况及其相关节点(爷节点，叔节点)
+type InsertSituationContext<K, V> = (InsertSituation, Rc<RefCell<Node<K, V>>>, Rc<RefCell<Node<K, V>>>);
+
+///删除情况及其相关节点(兄弟节点，兄弟左子节点，兄弟右子节点)
+type DeleteSituationContext<K, V> = (DeleteSituation, Rc<RefCell<Node<K, V>>>, Rc<RefCell<Node<K, V>>>, Rc<RefCell<Node<K, V>>>);
+
+///删除递归情况及其相关节点(父节点，兄弟节点，兄弟左子节点，兄弟右子节点)
+type DeleteRecursionSituationContext<K, V> = (DeleteRecursionSituation, Rc<RefCell<Node<K, V>>>, Rc<RefCell<Node<K, V>>>, Rc<RefCell<Node<K, V>>>, Rc<RefCell<Node<K, V>>>);
+
+impl<K: Ord, V> RedBlackTree<K, V> {
     pub fn new() -> Self {
-        RedBlackTree { root: None }
+        RedBlackTree {
+            root: None,
+            len: 0,
+            validation_sampling: 0,
+            sampled_validator: None,
+            error_hook: None,
+            observer: None,
+            random: sampling::RandomSource::default(),
+            duplicate_policy: DuplicatePolicy::Ignore,
+            pool: Default::default(),
+            #[cfg(feature = "stats")]
+            stats: Default::default(),
+        }
     }
 
-    pub fn insert(&mut self, key: i32) {
-        let node_rc = Rc::new(RefCell::new(Node {
+    /// 插入键值对，键已存在时按重复键策略处理，默认忽略
+    /// 需要得知键已存在时使用try_insert
+    pub fn insert(&mut self, key: K, value: V) {
+        //先沿查找路径找到空位，与raw_entry_mut().from_key相同，键已存在时不分配节点
+        let mut parent_option = None;
+        let mut ordering = core::cmp::Ordering::Equal;
+        let mut next_option = self.root.clone();
+        while let Some(cur_rc) = next_option {
+            ordering = key.cmp(&cur_rc.borrow().key);
+            self.record(|stats| stats.comparisons += 1);
+            next_option = match ordering {
+                core::cmp::Ordering::Less => cur_rc.borrow().left.clone(),
+                core::cmp::Ordering::Greater => cur_rc.borrow().right.clone(),
+                core::cmp::Ordering::Equal => {
+                    //键已存在，按重复键策略处理
+                    let mut cur = cur_rc.borrow_mut();
+                    match self.duplicate_policy {
+                        DuplicatePolicy::Ignore => {}
+                        DuplicatePolicy::Overwrite => cur.value = value,
+                        DuplicatePolicy::Count => cur.count += 1,
+                    }
+                    return;
+                }
+            };
+            parent_option = Some(cur_rc);
+        }
+        let node_rc = self.pool.allocate(Node {
             key,
+            value,
             parent: None,
             left: None,
             right: None,
             color: Color::Red,
-        }));
-        match &self.root {
+            size: 1,
+            count: 1,
+        });
+        match &parent_option {
             None => {
                 node_rc.borrow_mut().color = Color::Black;
+                self.notify(&node_rc, |observer, key| observer.on_insert(key));
                 self.root = Some(node_rc);
             }
-            Some(root) => {
-                let mut parent_rc = Rc::clone(root);
-                let son_rc = Rc::clone(&node_rc);
-                loop {
-                    //借用时候不能修改变量指向
-                    //加括号层级是为了限定parent_rc的可变借用范围，从而实现借用修改分离
-                    //cur_rc是括号外层级的变量，可以记住内层级的修改
-                    let cur_rc;
-                    {
-                        let mut parent = parent_rc.borrow_mut();
-                        cur_rc = if key < parent.key {
-                            match &parent.left {
-                                Some(son_ref) => {
-                                    Rc::clone(son_ref)
-                                }
-                                None => {
-                                    //插入新节点
-                                    node_rc.borrow_mut().parent = Some(Rc::downgrade(&parent_rc));
-                                    parent.left = Some(node_rc);
-                                    break;
-                                }
-                            }
-                        } else if key > parent.key {
-                            match &parent.right {
-                                Some(son_ref) => {
-                                    Rc::clone(son_ref)
-                                }
-                                None => {
-                                    //插入新节点
-                                    node_rc.borrow_mut().parent = Some(Rc::downgrade(&parent_rc));
-                                    parent.right = Some(node_rc);
-                                    break;
-                                }
-                            }
-                        } else {
-                            //相等情况暂不处理
-                            return;
-                        }
-                    }
-                    //借用结束再修改父节点
-                    parent_rc = Rc::clone(&cur_rc);
+            Some(parent_ref) => {
+                //插入新节点
+                node_rc.borrow_mut().parent = Some(Rc::downgrade(parent_ref));
+                if ordering == core::cmp::Ordering::Less {
+                    parent_ref.borrow_mut().left = Some(Rc::clone(&node_rc));
+                } else {
+                    parent_ref.borrow_mut().right = Some(Rc::clone(&node_rc));
                 }
-                self.insert_balance(&parent_rc, &son_rc)
+                self.notify(&node_rc, |observer, key| observer.on_insert(key));
+                Self::adjust_path_size(parent_ref, true);
+                self.insert_balance(parent_ref, &node_rc);
             }
         }
+        self.len += 1;
+        self.sample_validation();
+    }
+
+    ///删除键，键不存在时忽略
+    ///查找、子树计数调整与调平都只沿一条根路径进行，最坏O(log n)
+    ///多重集模式下键重复多次时只减少一次计数
+    ///键可以是K的借用形式，如以&str删除String键
+    pub fn delete<Q: Ord + ?Sized>(&mut self, key: &Q)
+    where
+        K: core::borrow::Borrow<Q>,
+    {
+        //找到删除节点
+        if let Some(target_rc) = self.find(key) {
+            self.delete_found(target_rc);
+        }
+    }
+
+    ///删除找到的节点，重复多次时只减少一次计数
+    fn delete_found(&mut self, target_rc: Rc<RefCell<Node<K, V>>>) {
+        {
+            let mut target = target_rc.borrow_mut();
+            if target.count > 1 {
+                target.count -= 1;
+                return;
+            }
+        }
+        self.delete_node(&target_rc);
+        self.sample_validation();
+        self.pool.recycle(target_rc);
+    }
+
+    ///拷贝节点的键值对
+    fn clone_entry(node_ref: &Rc<RefCell<Node<K, V>>>) -> (K, V)
+    where
+        K: Clone,
+        V: Clone,
+    {
+        let node = node_ref.borrow();
+        (node.key.clone(), node.value.clone())
+    }
+
+    /// 删除节点并取出键值对
+    fn remove_node(&mut self, target_rc: Rc<RefCell<Node<K, V>>>) -> (K, V) {
+        self.delete_node(&target_rc);
+        self.sample_validation();
+        //删除后节点已脱离树；游标、NodeRef等都借用树，&mut self期间不存在，调用方持有唯一的强引用
+        let target = self.pool.recycle(target_rc).expect("deleted node is still referenced");
+        (target.key, target.value)
     }
 
     /// 删除节点，脱离树，树节点不再指向删除节点
@@ -145,259 +359,415 @@ impl RedBlackTree {
     /// 2.删除节点只有一个子节点，且必定为红色
     /// 3.删除节点有两个子节点
     /// 通过转换，全部转换为情况一，删除节点转换为删除叶子节点
-    pub fn delete(&mut self, key: i32) {
-        //找到删除节点
-        let target_option = Self::find(&self.root, key);
-        if let Some(target_ref) = &target_option {
-            //为了提前释放target的借用
-            let mut target_parent_option = None;
-            let mut target_left_option = None;
-            let mut target_right_option = None;
-            let mut target_color = Color::Red;
-            {
-                let target = target_ref.borrow();
-                if let Some(target_parent_weak) = &target.parent {
-                    if let Some(target_parent_ref) = &target_parent_weak.upgrade() {
-                        target_parent_option = Some(Rc::clone(target_parent_ref));
-                    }
-                }
-                if let Some(target_left_ref) = &target.left {
-                    target_left_option = Some(Rc::clone(target_left_ref));
-                }
-                if let Some(target_right_ref) = &target.right {
-                    target_right_option = Some(Rc::clone(target_right_ref));
+    fn delete_node(&mut self, target_ref: &Rc<RefCell<Node<K, V>>>) {
+        self.notify(target_ref, |observer, key| observer.on_delete(key));
+        self.len -= 1;
+        //实际离开原位置的节点为删除节点或其后继节点，其祖先的子树节点数量减一
+        {
+            let removed_rc = match &target_ref.borrow().right {
+                Some(target_right_ref) if target_ref.borrow().left.is_some() => Self::find_minimum(target_right_ref),
+                _ => Rc::clone(target_ref),
+            };
+            let removed_parent_option = removed_rc.borrow().parent.as_ref().and_then(Weak::upgrade);
+            if let Some(removed_parent_ref) = &removed_parent_option {
+                Self::adjust_path_size(removed_parent_ref, false);
+            }
+        }
+        //为了提前释放target的借用
+        let mut target_parent_option = None;
+        let mut target_left_option = None;
+        let mut target_right_option = None;
+        let target_color;
+        {
+            let target = target_ref.borrow();
+            if let Some(target_parent_weak) = &target.parent {
+                if let Some(target_parent_ref) = &target_parent_weak.upgrade() {
+                    target_parent_option = Some(Rc::clone(target_parent_ref));
                 }
-                target_color = target.color;
             }
-            match (&target_left_option, &target_right_option) {
-                //1.删除节点没有子节点
-                // 处理根关系，斩断连接，删除黑色节点需要平衡
-                (None, None) => {
-                    match &target_parent_option {
-                        None => {
-                            self.root = None;
-                        }
-                        Some(parent_ref) => {
-                            {
-                                let mut parent = parent_ref.borrow_mut();
-                                if let Some(parent_left_ref) = &parent.left {
-                                    if Rc::ptr_eq(parent_left_ref, target_ref) {
-                                        parent.left = None;
-                                    }
-                                }
-                                if let Some(parent_right_ref) = &parent.right {
-                                    if Rc::ptr_eq(parent_right_ref, target_ref) {
-                                        parent.right = None;
-                                    }
-                                }
-                            }
-                            //删除黑色节点需要调平
-                            if target_color == Color::Black {
-                                self.delete_balance(parent_ref);
-                            }
-                        }
+            if let Some(target_left_ref) = &target.left {
+                target_left_option = Some(Rc::clone(target_left_ref));
+            }
+            if let Some(target_right_ref) = &target.right {
+                target_right_option = Some(Rc::clone(target_right_ref));
+            }
+            target_color = target.color;
+        }
+        match (&target_left_option, &target_right_option) {
+            //1.删除节点没有子节点
+            // 处理根关系，斩断连接，删除黑色节点需要平衡
+            (None, None) => {
+                match &target_parent_option {
+                    None => {
+                        self.root = None;
                     }
-                }
-                //2.删除节点只有一个子节点，则删除节点必为黑色，其子节点且必定为红色
-                // 李代桃僵，红色子节点代为离去即可，无需平衡
-                (Some(son_ref), None) | (None, Some(son_ref)) => {
-                    let mut son = son_ref.borrow_mut();
-                    son.color = Color::Black;
-                    match &target_parent_option {
-                        None => {
-                            self.root = Some(Rc::clone(son_ref));
-                        }
-                        Some(parent_ref) => {
+                    Some(parent_ref) => {
+                        {
                             let mut parent = parent_ref.borrow_mut();
-                            son.parent = Some(Rc::downgrade(parent_ref));
                             if let Some(parent_left_ref) = &parent.left {
                                 if Rc::ptr_eq(parent_left_ref, target_ref) {
-                                    parent.left = Some(Rc::clone(son_ref));
+                                    parent.left = None;
                                 }
                             }
                             if let Some(parent_right_ref) = &parent.right {
                                 if Rc::ptr_eq(parent_right_ref, target_ref) {
-                                    parent.right = Some(Rc::clone(son_ref));
+                                    parent.right = None;
                                 }
                             }
                         }
-                    }
-                }
-                //3.删除节点有两个子节点
-                // 右子树寻找后继节点，改为删除后继节点
-                // 后继节点如有子节点，则必为红色右子节点，李代桃僵即可，后继节点为黑色且没有子节点，需要平衡
-                (Some(target_left_ref), Some(target_right_ref)) => {
-                    //寻找后继节点
-                    let successor_rc = Self::find_minimum(target_right_ref);
-                    let successor_ref = &successor_rc;
-                    let mut need_balance = false;
-                    let mut successor_parent_rc = Rc::clone(successor_ref);
-                    let mut successor_right_option = None;
-                    {
-                        let successor = successor_rc.borrow();
-                        if let Some(successor_parent_weak) = &successor.parent {
-                            if let Some(successor_parent_ref) = &successor_parent_weak.upgrade() {
-                                successor_parent_rc = Rc::clone(successor_parent_ref);
-                            }
-                        }
-                        if let Some(successor_right_ref) = &successor.right {
-                            successor_right_option = Some(Rc::clone(successor_right_ref));
+                        //删除黑色节点需要调平
+                        if target_color == Color::Black {
+                            self.delete_balance(parent_ref);
                         }
                     }
-                    {
-                        //后继节点取代删除节点(左连接)
-                        successor_rc.borrow_mut().left = Some(Rc::clone(target_left_ref));
-                        target_left_ref.borrow_mut().parent = Some(Rc::downgrade(successor_ref));
+                }
+            }
+            //2.删除节点只有一个子节点，则删除节点必为黑色，其子节点且必定为红色
+            // 李代桃僵，红色子节点代为离去即可，无需平衡
+            (Some(son_ref), None) | (None, Some(son_ref)) => {
+                self.paint(son_ref, Color::Black);
+                let mut son = son_ref.borrow_mut();
+                match &target_parent_option {
+                    None => {
+                        //子节点成为根，必须清除指向删除节点的父指针，否则旋转时会被误判为非根
+                        son.parent = None;
+                        self.root = Some(Rc::clone(son_ref));
                     }
-                    {
-                        //后继节点取代删除节点(右连接)
-                        //后继节点必有父节点
-                        let mut successor = successor_ref.borrow_mut();
-                        let mut successor_parent = successor_parent_rc.borrow_mut();
-                        //如果后继节点有右子节点
-                        if let Some(successor_right_ref) = &successor_right_option {
-                            let mut successor_right = successor_right_ref.borrow_mut();
-                            //删除节点的右节点不是后继节点
-                            if !Rc::ptr_eq(successor_ref, target_right_ref) {
-                                //后继节点的右子节点补位
-                                successor_parent.left = successor.right.take();
-                                successor_right.parent = Some(Rc::downgrade(&successor_parent_rc));
-                                //后继节点取代删除节点(右连接)
-                                successor.right = Some(Rc::clone(target_right_ref));
-                                //处理特殊情况的借用,处理多次借用问题,删除节点的右子节点就是后继节点的父节点
-                                if Rc::ptr_eq(target_right_ref, &successor_parent_rc) {
-                                    successor_parent.parent = Some(Rc::downgrade(successor_ref));
-                                } else {
-                                    target_right_ref.borrow_mut().parent = Some(Rc::downgrade(successor_ref));
-                                }
-                            }
-                            successor_right.color = successor.color;
-                        } else {
-                            //删除节点的右节点不是后继节点
-                            if !Rc::ptr_eq(successor_ref, target_right_ref) {
-                                //后继节点的父节点的左节点置空
-                                successor_parent.left = None;
-                                //后继节点取代删除节点(右连接)
-                                successor.right = Some(Rc::clone(target_right_ref));
-                                if Rc::ptr_eq(target_right_ref, &successor_parent_rc) {
-                                    //处理特殊情况的借用,处理多次借用问题,删除节点的右子节点就是后继节点的父节点
-                                    successor_parent.parent = Some(Rc::downgrade(successor_ref));
-                                } else {
-                                    target_right_ref.borrow_mut().parent = Some(Rc::downgrade(successor_ref));
-                                }
+                    Some(parent_ref) => {
+                        let mut parent = parent_ref.borrow_mut();
+                        son.parent = Some(Rc::downgrade(parent_ref));
+                        if let Some(parent_left_ref) = &parent.left {
+                            if Rc::ptr_eq(parent_left_ref, target_ref) {
+                                parent.left = Some(Rc::clone(son_ref));
                             }
-                            //删除黑色节点需要调平
-                            if successor.color == Color::Black {
-                                need_balance = true;
+                        }
+                        if let Some(parent_right_ref) = &parent.right {
+                            if Rc::ptr_eq(parent_right_ref, target_ref) {
+                                parent.right = Some(Rc::clone(son_ref));
                             }
                         }
                     }
-                    //后继节点取代删除节点(上连接)
-                    match &target_parent_option {
-                        None => {
-                            self.root = Some(Rc::clone(successor_ref));
-                            successor_ref.borrow_mut().parent = None;
+                }
+            }
+            //3.删除节点有两个子节点
+            // 右子树寻找后继节点，改为删除后继节点
+            // 后继节点如有子节点，则必为红色右子节点，李代桃僵即可，后继节点为黑色且没有子节点，需要平衡
+            (Some(target_left_ref), Some(target_right_ref)) => {
+                //寻找后继节点
+                let successor_rc = Self::find_minimum(target_right_ref);
+                let successor_ref = &successor_rc;
+                let mut need_balance = false;
+                let mut successor_parent_rc = Rc::clone(successor_ref);
+                let mut successor_right_option = None;
+                {
+                    let successor = successor_rc.borrow();
+                    if let Some(successor_parent_weak) = &successor.parent {
+                        if let Some(successor_parent_ref) = &successor_parent_weak.upgrade() {
+                            successor_parent_rc = Rc::clone(successor_parent_ref);
                         }
-                        Some(parent_ref) => {
-                            let mut parent = parent_ref.borrow_mut();
-                            successor_rc.borrow_mut().parent = Some(Rc::downgrade(parent_ref));
-                            if let Some(parent_left_ref) = &parent.left {
-                                if Rc::ptr_eq(parent_left_ref, target_ref) {
-                                    parent.left = Some(Rc::clone(successor_ref));
-                                }
+                    }
+                    if let Some(successor_right_ref) = &successor.right {
+                        successor_right_option = Some(Rc::clone(successor_right_ref));
+                    }
+                }
+                {
+                    //后继节点取代删除节点(左连接)
+                    successor_rc.borrow_mut().left = Some(Rc::clone(target_left_ref));
+                    target_left_ref.borrow_mut().parent = Some(Rc::downgrade(successor_ref));
+                }
+                {
+                    //后继节点取代删除节点(右连接)
+                    //后继节点必有父节点
+                    let mut successor = successor_ref.borrow_mut();
+                    let mut successor_parent = successor_parent_rc.borrow_mut();
+                    //如果后继节点有右子节点
+                    if let Some(successor_right_ref) = &successor_right_option {
+                        let mut successor_right = successor_right_ref.borrow_mut();
+                        //删除节点的右节点不是后继节点
+                        if !Rc::ptr_eq(successor_ref, target_right_ref) {
+                            //后继节点的右子节点补位
+                            successor_parent.left = successor.right.take();
+                            successor_right.parent = Some(Rc::downgrade(&successor_parent_rc));
+                            //后继节点取代删除节点(右连接)
+                            successor.right = Some(Rc::clone(target_right_ref));
+                            //处理特殊情况的借用,处理多次借用问题,删除节点的右子节点就是后继节点的父节点
+                            if Rc::ptr_eq(target_right_ref, &successor_parent_rc) {
+                                successor_parent.parent = Some(Rc::downgrade(successor_ref));
+                            } else {
+                                target_right_ref.borrow_mut().parent = Some(Rc::downgrade(successor_ref));
                             }
-                            if let Some(parent_right_ref) = &parent.right {
-                                if Rc::ptr_eq(parent_right_ref, target_ref) {
-                                    parent.right = Some(Rc::clone(successor_ref));
-                                }
+                        }
+                        successor_right.color = successor.color;
+                    } else {
+                        //删除节点的右节点不是后继节点
+                        if !Rc::ptr_eq(successor_ref, target_right_ref) {
+                            //后继节点的父节点的左节点置空
+                            successor_parent.left = None;
+                            //后继节点取代删除节点(右连接)
+                            successor.right = Some(Rc::clone(target_right_ref));
+                            if Rc::ptr_eq(target_right_ref, &successor_parent_rc) {
+                                //处理特殊情况的借用,处理多次借用问题,删除节点的右子节点就是后继节点的父节点
+                                successor_parent.parent = Some(Rc::downgrade(successor_ref));
+                            } else {
+                                target_right_ref.borrow_mut().parent = Some(Rc::downgrade(successor_ref));
                             }
                         }
-                    }
-                    {
-                        //后继节点取代删除节点(颜色)
-                        successor_rc.borrow_mut().color = target_color;
-                    }
-                    //需要调平
-                    if need_balance {
-                        if Rc::ptr_eq(&successor_parent_rc, target_ref) {
-                            //处理后继节点的父节点就是目标节点的特殊情况，删除平衡方法需要传入删除节点的现父节点
-                            //多数情况下，后继节点的父节点就是删除节点的现父节点
-                            //只有目标节点的右节点就是后继节点且后继节点没有子节点的情况下，才会出现后继节点取代后继节点父节点的情况
-                            self.delete_balance(successor_ref);
-                        } else {
-                            self.delete_balance(&successor_parent_rc);
+                        //删除黑色节点需要调平
+                        if successor.color == Color::Black {
+                            need_balance = true;
                         }
                     }
                 }
-            }
-        }
-    }
-
-    pub fn get(&self, key: i32) -> Option<i32> {
-        match &self.root {
-            None => {
-                return None;
-            }
-            Some(root_ref) => {
-                let mut next_rc = Rc::clone(root_ref);
-                let mut cur_rc;
-                loop {
-                    cur_rc = next_rc;
-                    let cur_ref = &cur_rc;
-                    let cur = cur_ref.borrow();
-                    match key.cmp(&cur.key) {
-                        std::cmp::Ordering::Equal => {
-                            return Some(cur.key);
-                        }
-                        std::cmp::Ordering::Less => {
-                            match &cur.left {
-                                None => { return None; }
-                                Some(left_ref) => {
-                                    next_rc = Rc::clone(left_ref);
-                                }
+                //后继节点取代删除节点(上连接)
+                match &target_parent_option {
+                    None => {
+                        self.root = Some(Rc::clone(successor_ref));
+                        successor_ref.borrow_mut().parent = None;
+                    }
+                    Some(parent_ref) => {
+                        let mut parent = parent_ref.borrow_mut();
+                        successor_rc.borrow_mut().parent = Some(Rc::downgrade(parent_ref));
+                        if let Some(parent_left_ref) = &parent.left {
+                            if Rc::ptr_eq(parent_left_ref, target_ref) {
+                                parent.left = Some(Rc::clone(successor_ref));
                             }
                         }
-                        std::cmp::Ordering::Greater => {
-                            match &cur.right {
-                                None => { return None; }
-                                Some(right_ref) => {
-                                    next_rc = Rc::clone(right_ref);
-                                }
+                        if let Some(parent_right_ref) = &parent.right {
+                            if Rc::ptr_eq(parent_right_ref, target_ref) {
+                                parent.right = Some(Rc::clone(successor_ref));
                             }
                         }
                     }
                 }
+                {
+                    //后继节点取代删除节点(颜色，子树节点数量)
+                    successor_rc.borrow_mut().color = target_color;
+                    successor_rc.borrow_mut().size = target_ref.borrow().size;
+                }
+                //需要调平
+                if need_balance {
+                    if Rc::ptr_eq(&successor_parent_rc, target_ref) {
+                        //处理后继节点的父节点就是目标节点的特殊情况，删除平衡方法需要传入删除节点的现父节点
+                        //多数情况下，后继节点的父节点就是删除节点的现父节点
+                        //只有目标节点的右节点就是后继节点且后继节点没有子节点的情况下，才会出现后继节点取代后继节点父节点的情况
+                        self.delete_balance(successor_ref);
+                    } else {
+                        self.delete_balance(&successor_parent_rc);
+                    }
+                }
             }
         }
     }
 
+    /// 查找键对应的值
+    /// 节点位于RefCell内，无法返回借用，因此返回值的拷贝
+    /// 键可以是K的借用形式，如以&str查找String键、以&[u8]查找Vec<u8>键，无需构造临时的K
+    pub fn get<Q: Ord + ?Sized>(&self, key: &Q) -> Option<V>
+    where
+        K: core::borrow::Borrow<Q>,
+        V: Clone,
+    {
+        self.find(key).map(|node_rc| node_rc.borrow().value.clone())
+    }
+
+    ///是否包含键
+    pub fn contains<Q: Ord + ?Sized>(&self, key: &Q) -> bool
+    where
+        K: core::borrow::Borrow<Q>,
+    {
+        self.find(key).is_some()
+    }
+
+    /// 原地修改键对应的值，返回闭包的结果，键不存在时返回None
+    /// 节点位于RefCell内，无法借出&mut V，以闭包代替get_mut
+    pub fn update<Q: Ord + ?Sized, R, F: FnOnce(&mut V) -> R>(&mut self, key: &Q, f: F) -> Option<R>
+    where
+        K: core::borrow::Borrow<Q>,
+    {
+        self.find(key).map(|node_rc| f(&mut node_rc.borrow_mut().value))
+    }
+
+    /// 查找键值对，返回所在节点的句柄，不要求K、V可拷贝
+    /// 经NodeRef::entry借出树中实际存储的键与值，如以&str查找时取回String键
+    pub fn get_key_value<Q: Ord + ?Sized>(&self, key: &Q) -> Option<NodeRef<'_, K, V>>
+    where
+        K: core::borrow::Borrow<Q>,
+    {
+        self.get_node(key)
+    }
+
+    ///最小键值对
+    pub fn first(&self) -> Option<(K, V)>
+    where
+        K: Clone,
+        V: Clone,
+    {
+        self.root.as_ref().map(|root_ref| Self::clone_entry(&Self::find_minimum(root_ref)))
+    }
+
+    ///最大键值对
+    pub fn last(&self) -> Option<(K, V)>
+    where
+        K: Clone,
+        V: Clone,
+    {
+        self.root.as_ref().map(|root_ref| Self::clone_entry(&Self::find_maximum(root_ref)))
+    }
+
+    ///小于等于key的最大键值对，键可以是K的借用形式
+    pub fn floor<Q: Ord + ?Sized>(&self, key: &Q) -> Option<(K, V)>
+    where
+        K: core::borrow::Borrow<Q> + Clone,
+        V: Clone,
+    {
+        self.find_upper_bound(Bound::Included(key)).map(|node_rc| Self::clone_entry(&node_rc))
+    }
+
+    ///大于等于key的最小键值对，键可以是K的借用形式
+    pub fn ceiling<Q: Ord + ?Sized>(&self, key: &Q) -> Option<(K, V)>
+    where
+        K: core::borrow::Borrow<Q> + Clone,
+        V: Clone,
+    {
+        self.find_lower_bound(Bound::Included(key)).map(|node_rc| Self::clone_entry(&node_rc))
+    }
+
+    ///严格小于key的最大键值对，键可以是K的借用形式
+    pub fn strictly_lower<Q: Ord + ?Sized>(&self, key: &Q) -> Option<(K, V)>
+    where
+        K: core::borrow::Borrow<Q> + Clone,
+        V: Clone,
+    {
+        self.find_upper_bound(Bound::Excluded(key)).map(|node_rc| Self::clone_entry(&node_rc))
+    }
+
+    ///严格大于key的最小键值对，键可以是K的借用形式
+    pub fn strictly_higher<Q: Ord + ?Sized>(&self, key: &Q) -> Option<(K, V)>
+    where
+        K: core::borrow::Borrow<Q> + Clone,
+        V: Clone,
+    {
+        self.find_lower_bound(Bound::Excluded(key)).map(|node_rc| Self::clone_entry(&node_rc))
+    }
+
+    /// 删除并返回最小键值对，可作为优先队列使用
+    /// 多重集模式下连同全部重复整体删除节点，返回值只有一份，无法在不克隆的前提下逐个弹出；逐个删除用delete
+    pub fn pop_first(&mut self) -> Option<(K, V)> {
+        let node_rc = self.root.as_ref().map(Self::find_minimum)?;
+        Some(self.remove_node(node_rc))
+    }
+
+    /// 删除并返回最大键值对
+    /// 多重集模式下与pop_first相同，整体删除节点
+    pub fn pop_last(&mut self) -> Option<(K, V)> {
+        let node_rc = self.root.as_ref().map(Self::find_maximum)?;
+        Some(self.remove_node(node_rc))
+    }
+
+    /// 节点数量，O(1)
+    /// 多重集模式下为不同键的数量，不计重复次数，某个键的重复次数见count
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    ///同len
     pub fn size(&self) -> usize {
-        Self::count_size(&self.root)
+        self.len
     }
 
-    pub fn preorder_traversal(&self) {
-        println!("preorder_traversal");
-        if let Some(root) = &self.root {
-            Self::do_preorder_traversal(&*root.as_ref().borrow())
+    /// 树高，即最长根叶路径上的节点数，空树为0
+    /// 按需计算，O(n)，红黑树保证不超过2log2(n+1)
+    pub fn height(&self) -> usize {
+        let mut height = 0;
+        let mut stack: Vec<_> = self.root.iter().map(|root_ref| (Rc::clone(root_ref), 1)).collect();
+        while let Some((cur_rc, depth)) = stack.pop() {
+            height = height.max(depth);
+            let cur = cur_rc.borrow();
+            stack.extend(cur.left.iter().chain(cur.right.iter()).map(|son_ref| (Rc::clone(son_ref), depth + 1)));
         }
+        height
     }
 
-    pub fn inorder_traversal(&self) {
-        println!("inorder_traversal");
-        if let Some(root) = &self.root {
-            Self::do_inorder_traversal(&*root.as_ref().borrow())
+    /// 黑高，即根到叶路径上的黑色节点数(不计空叶子)，空树为0
+    /// 各路径黑高相等，沿最左路径计算，O(log n)
+    pub fn black_height(&self) -> usize {
+        Self::subtree_black_height(&self.root)
+    }
+
+    ///中序迭代
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter::new(self)
+    }
+
+    /// 范围迭代，类似BTreeMap::range
+    /// 起点大于终点时返回空迭代器
+    pub fn range<R: RangeBounds<K>>(&self, range: R) -> Iter<'_, K, V> {
+        let front = self.find_lower_bound(range.start_bound());
+        let back = self.find_upper_bound(range.end_bound());
+        Iter::between(self, front, back)
+    }
+
+    /// 按键升序原地修改范围内的值
+    /// 只进入与范围相交的子树，O(log n + m)，无需先收集键；起点大于终点时不做任何事
+    pub fn for_each_in_range<R: RangeBounds<K>, F: FnMut(&K, &mut V)>(&mut self, range: R, mut f: F) {
+        if let Some(root_ref) = &self.root {
+            Self::for_each_in_subtree(root_ref, &range, &mut f);
         }
     }
 
-    pub fn postorder_traversal(&self) {
-        println!("postorder_traversal");
-        if let Some(root) = &self.root {
-            Self::do_postorder_traversal(&*root.as_ref().borrow())
+    fn for_each_in_subtree<R: RangeBounds<K>, F: FnMut(&K, &mut V)>(node_ref: &Rc<RefCell<Node<K, V>>>, range: &R, f: &mut F) {
+        let mut node = node_ref.borrow_mut();
+        //键大于起点时左子树可能有范围内的键，小于终点时右子树可能有
+        let go_left = match range.start_bound() {
+            Bound::Included(start) | Bound::Excluded(start) => *start < node.key,
+            Bound::Unbounded => true,
+        };
+        let go_right = match range.end_bound() {
+            Bound::Included(end) | Bound::Excluded(end) => node.key < *end,
+            Bound::Unbounded => true,
+        };
+        if let Some(left_ref) = node.left.as_ref().filter(|_| go_left) {
+            Self::for_each_in_subtree(left_ref, range, f);
         }
+        if range.contains(&node.key) {
+            let node = &mut *node;
+            f(&node.key, &mut node.value);
+        }
+        if let Some(right_ref) = node.right.as_ref().filter(|_| go_right) {
+            Self::for_each_in_subtree(right_ref, range, f);
+        }
+    }
+
+    /// 从第一个大于等于key的键开始顺序迭代，O(log n)定位
+    /// 分页时以上一页最后一个键的后继为起点，无需从头扫描
+    /// 键可以是K的借用形式
+    pub fn iter_from<Q: Ord + ?Sized>(&self, key: &Q) -> Iter<'_, K, V>
+    where
+        K: core::borrow::Borrow<Q>,
+    {
+        let front = self.find_lower_bound(Bound::Included(key));
+        let back = self.root.as_ref().map(Self::find_maximum);
+        Iter::between(self, front, back)
+    }
+
+    /// 与外部有序序列同步遍历，类似归并
+    /// other必须按键升序排列，无需先把外部数据载入另一棵树
+    pub fn zip_sorted<V2, I: IntoIterator<Item = (K, V2)>>(&self, other: I) -> ZipSorted<'_, K, V, V2, I::IntoIter>
+    where
+        K: Clone,
+        V: Clone,
+    {
+        ZipSorted::new(self.iter(), other.into_iter())
     }
 
     ///左旋
-    fn rotate_left(&mut self, grand_parent_ref: &Rc<RefCell<Node>>, parent_ref: &Rc<RefCell<Node>>) {
+    fn rotate_left(&mut self, grand_parent_ref: &Rc<RefCell<Node<K, V>>>, parent_ref: &Rc<RefCell<Node<K, V>>>) {
+        self.record(|stats| stats.rotations_left += 1);
+        self.notify(grand_parent_ref, |observer, key| observer.on_rotate_left(key));
         let mut parent = parent_ref.borrow_mut();
         let mut grand_parent = grand_parent_ref.borrow_mut();
         if let Some(brother_ref) = &parent.left {
@@ -427,10 +797,15 @@ impl RedBlackTree {
         }
         parent.left = Some(Rc::clone(grand_parent_ref));
         grand_parent.parent = Some(Rc::downgrade(parent_ref));
+        //子树整体节点数量不变，原爷节点重新计算
+        parent.size = grand_parent.size;
+        grand_parent.size = Self::size_of(&grand_parent.left) + Self::size_of(&grand_parent.right) + 1;
     }
 
     ///右旋
-    fn rotate_right(&mut self, grand_parent_ref: &Rc<RefCell<Node>>, parent_ref: &Rc<RefCell<Node>>) {
+    fn rotate_right(&mut self, grand_parent_ref: &Rc<RefCell<Node<K, V>>>, parent_ref: &Rc<RefCell<Node<K, V>>>) {
+        self.record(|stats| stats.rotations_right += 1);
+        self.notify(grand_parent_ref, |observer, key| observer.on_rotate_right(key));
         let mut parent = parent_ref.borrow_mut();
         let mut grand_parent = grand_parent_ref.borrow_mut();
         if let Some(brother_ref) = &parent.right {
@@ -460,6 +835,9 @@ impl RedBlackTree {
         }
         parent.right = Some(Rc::clone(grand_parent_ref));
         grand_parent.parent = Some(Rc::downgrade(parent_ref));
+        //子树整体节点数量不变，原爷节点重新计算
+        parent.size = grand_parent.size;
+        grand_parent.size = Self::size_of(&grand_parent.left) + Self::size_of(&grand_parent.right) + 1;
     }
 
     /// 插入平衡
@@ -469,63 +847,79 @@ impl RedBlackTree {
     /// 存在LL,LR,RL,RR的情况
     /// 2.2.叔节点为红色 上溢情况
     /// 需要把父节点和叔节点染黑，爷节点染红，以爷节点为新插入的节点，递归平衡操作
-    fn insert_balance(&mut self, parent_ref: &Rc<RefCell<Node>>, son_ref: &Rc<RefCell<Node>>) {
-        let (insert_situation, grand_parent_rc, uncle_rc) = Self::judge_insert_situation(parent_ref, son_ref);
-        match insert_situation {
-            InsertSituation::LL => {
-                self.rotate_right(&grand_parent_rc, parent_ref);
-                grand_parent_rc.borrow_mut().color = Color::Red;
-                parent_ref.borrow_mut().color = Color::Black;
-            }
-            InsertSituation::RR => {
-                self.rotate_left(&grand_parent_rc, parent_ref);
-                grand_parent_rc.borrow_mut().color = Color::Red;
-                parent_ref.borrow_mut().color = Color::Black;
-            }
-            InsertSituation::LR => {
-                self.rotate_left(parent_ref, son_ref);
-                self.rotate_right(&grand_parent_rc, son_ref);
-                grand_parent_rc.borrow_mut().color = Color::Red;
-                son_ref.borrow_mut().color = Color::Black;
-            }
-            InsertSituation::RL => {
-                self.rotate_right(parent_ref, son_ref);
-                self.rotate_left(&grand_parent_rc, son_ref);
-                grand_parent_rc.borrow_mut().color = Color::Red;
-                son_ref.borrow_mut().color = Color::Black;
+    fn insert_balance(&mut self, parent_ref: &Rc<RefCell<Node<K, V>>>, son_ref: &Rc<RefCell<Node<K, V>>>) {
+        //上溢时以爷节点为新插入的节点继续循环，避免递归
+        let mut parent_rc = Rc::clone(parent_ref);
+        let mut son_rc = Rc::clone(son_ref);
+        //上溢的层数
+        let mut depth = 0;
+        loop {
+            let parent_ref = &parent_rc;
+            let son_ref = &son_rc;
+            let (insert_situation, grand_parent_rc, uncle_rc) = Self::judge_insert_situation(parent_ref, son_ref);
+            if insert_situation != InsertSituation::Stable {
+                depth += 1;
             }
-            InsertSituation::Recursion => {
-                let mut grand_parent_parent_rc = Rc::clone(&grand_parent_rc);
-                let mut grand_parent_color = Color::Red;
-                //缩小借用范围
-                {
-                    parent_ref.borrow_mut().color = Color::Black;
-                    uncle_rc.borrow_mut().color = Color::Black;
-                    match &grand_parent_rc.borrow().parent {
-                        Some(grand_parent_parent_weak) => {
-                            if let Some(grand_parent_parent_ref) = &grand_parent_parent_weak.upgrade() {
-                                grand_parent_parent_rc = Rc::clone(grand_parent_parent_ref);
+            match insert_situation {
+                InsertSituation::LL => {
+                    self.rotate_right(&grand_parent_rc, parent_ref);
+                    self.paint(&grand_parent_rc, Color::Red);
+                    self.paint(parent_ref, Color::Black);
+                }
+                InsertSituation::RR => {
+                    self.rotate_left(&grand_parent_rc, parent_ref);
+                    self.paint(&grand_parent_rc, Color::Red);
+                    self.paint(parent_ref, Color::Black);
+                }
+                InsertSituation::LR => {
+                    self.rotate_left(parent_ref, son_ref);
+                    self.rotate_right(&grand_parent_rc, son_ref);
+                    self.paint(&grand_parent_rc, Color::Red);
+                    self.paint(son_ref, Color::Black);
+                }
+                InsertSituation::RL => {
+                    self.rotate_right(parent_ref, son_ref);
+                    self.rotate_left(&grand_parent_rc, son_ref);
+                    self.paint(&grand_parent_rc, Color::Red);
+                    self.paint(son_ref, Color::Black);
+                }
+                InsertSituation::Recursion => {
+                    let mut grand_parent_parent_rc = Rc::clone(&grand_parent_rc);
+                    let mut grand_parent_color = Color::Red;
+                    //缩小借用范围
+                    {
+                        self.paint(parent_ref, Color::Black);
+                        self.paint(&uncle_rc, Color::Black);
+                        match &grand_parent_rc.borrow().parent {
+                            Some(grand_parent_parent_weak) => {
+                                if let Some(grand_parent_parent_ref) = &grand_parent_parent_weak.upgrade() {
+                                    grand_parent_parent_rc = Rc::clone(grand_parent_parent_ref);
+                                }
+                            }
+                            //爷节点已经是根节点，结束递归
+                            None => {
+                                grand_parent_color = Color::Black;
                             }
                         }
-                        //爷节点已经是根节点，结束递归
-                        None => {
-                            grand_parent_color = Color::Black;
-                        }
+                        self.paint(&grand_parent_rc, grand_parent_color);
                     }
-                    grand_parent_rc.borrow_mut().color = grand_parent_color;
-                }
-                match grand_parent_color {
-                    Color::Red => {
-                        //以爷节点为新插入的节点，递归平衡操作
-                        self.insert_balance(&grand_parent_parent_rc, &grand_parent_rc);
-                    }
-                    Color::Black => {
-                        //爷节点已经是根节点，结束递归
-                        self.root = Some(Rc::clone(&grand_parent_rc));
+                    match grand_parent_color {
+                        Color::Red => {
+                            //以爷节点为新插入的节点，继续平衡操作
+                            parent_rc = grand_parent_parent_rc;
+                            son_rc = grand_parent_rc;
+                            continue;
+                        }
+                        Color::Black => {
+                            //爷节点已经是根节点，结束递归
+                            self.root = Some(Rc::clone(&grand_parent_rc));
+                        }
                     }
                 }
+                InsertSituation::Stable => {}
             }
-            InsertSituation::Stable => {}
+            self.record_fixup_depth(depth);
+            break;
         }
     }
 
@@ -545,8 +939,12 @@ impl RedBlackTree {
     /// 2.3兄弟节点为黑色，且只有一个右子节点
     /// 2.4兄弟节点为黑色，且没有子节点
     ///删除节点为右节点时，对称以上情况即可
-    fn delete_balance(&mut self, parent_ref: &Rc<RefCell<Node>>) {
+    fn delete_balance(&mut self, parent_ref: &Rc<RefCell<Node<K, V>>>) {
         let (situation, brother_rc, brother_left_rc, brother_right_rc) = Self::judge_delete_situation(parent_ref);
+        if situation != DeleteSituation::Stable {
+            self.record_fixup_depth(1);
+            self.notify(parent_ref, |observer, key| observer.on_delete_situation(key, situation));
+        }
         match situation {
             //1.父节点是红色的
             //兄弟节点一定为黑色,其子节点存在则必为红色
@@ -554,15 +952,15 @@ impl RedBlackTree {
             //1.1兄弟节点有两个子节点，且必为红色
             DeleteSituation::RLRR => {
                 self.rotate_left(parent_ref, &brother_rc);
-                brother_rc.borrow_mut().color = Color::Red;
-                parent_ref.borrow_mut().color = Color::Black;
-                brother_right_rc.borrow_mut().color = Color::Black;
+                self.paint(&brother_rc, Color::Red);
+                self.paint(parent_ref, Color::Black);
+                self.paint(&brother_right_rc, Color::Black);
             }
             //1.2兄弟节点只有一个左子节点，且必为红色
             DeleteSituation::RLRE => {
                 self.rotate_right(&brother_rc, &brother_left_rc);
                 self.rotate_left(parent_ref, &brother_left_rc);
-                parent_ref.borrow_mut().color = Color::Black;
+                self.paint(parent_ref, Color::Black);
             }
             //1.3兄弟节点只有一个右子节点，且必为红色
             DeleteSituation::RLER => {
@@ -570,22 +968,22 @@ impl RedBlackTree {
             }
             //1.4兄弟节点没有子节点
             DeleteSituation::RLEE => {
-                parent_ref.borrow_mut().color = Color::Black;
-                brother_rc.borrow_mut().color = Color::Red;
+                self.paint(parent_ref, Color::Black);
+                self.paint(&brother_rc, Color::Red);
             }
             //删除节点是右节点,，兄弟节点为左节点
             //1.1兄弟节点有两个子节点，且必为红色
             DeleteSituation::RRRR => {
                 self.rotate_right(parent_ref, &brother_rc);
-                brother_rc.borrow_mut().color = Color::Red;
-                parent_ref.borrow_mut().color = Color::Black;
-                brother_left_rc.borrow_mut().color = Color::Black;
+                self.paint(&brother_rc, Color::Red);
+                self.paint(parent_ref, Color::Black);
+                self.paint(&brother_left_rc, Color::Black);
             }
             //1.2兄弟节点只有一个右子节点，且必为红色
             DeleteSituation::RRER => {
                 self.rotate_left(&brother_rc, &brother_right_rc);
                 self.rotate_right(parent_ref, &brother_right_rc);
-                parent_ref.borrow_mut().color = Color::Black;
+                self.paint(parent_ref, Color::Black);
             }
             //1.3兄弟节点只有一个左子节点，且必为红色
             DeleteSituation::RRRE => {
@@ -593,8 +991,8 @@ impl RedBlackTree {
             }
             //1.4没有侄子节点
             DeleteSituation::RREE => {
-                parent_ref.borrow_mut().color = Color::Black;
-                brother_rc.borrow_mut().color = Color::Red;
+                self.paint(parent_ref, Color::Black);
+                self.paint(&brother_rc, Color::Red);
             }
             //2.父节点是黑色的
             //兄弟节点一定存在
@@ -605,8 +1003,8 @@ impl RedBlackTree {
                 self.rotate_left(parent_ref, &brother_rc);
                 self.rotate_left(parent_ref, &brother_left_rc);
                 {
-                    brother_rc.borrow_mut().color = Color::Black;
-                    parent_ref.borrow_mut().color = Color::Red;
+                    self.paint(&brother_rc, Color::Black);
+                    self.paint(parent_ref, Color::Red);
                 }
                 //如果旋转前，兄弟节点的左子节点存在左子节点，
                 //即原来的父节点，旋转过后存在右子节点，则需要对其做插入调平处理
@@ -625,18 +1023,18 @@ impl RedBlackTree {
             DeleteSituation::BLBRW => {
                 self.rotate_right(&brother_rc, &brother_left_rc);
                 self.rotate_left(parent_ref, &brother_left_rc);
-                brother_left_rc.borrow_mut().color = Color::Black;
+                self.paint(&brother_left_rc, Color::Black);
             }
             //2.3兄弟节点为黑色，且只有一个右子节点
             DeleteSituation::BLBER => {
                 self.rotate_left(parent_ref, &brother_rc);
-                brother_right_rc.borrow_mut().color = Color::Black;
+                self.paint(&brother_right_rc, Color::Black);
             }
             //2.4兄弟节点为黑色，且没有子节点
             DeleteSituation::BLBEE => {
                 //先达到局部平衡
                 {
-                    brother_rc.borrow_mut().color = Color::Red;
+                    self.paint(&brother_rc, Color::Red);
                 }
                 self.delete_balance_recursion(parent_ref);
             }
@@ -647,8 +1045,8 @@ impl RedBlackTree {
                 self.rotate_right(parent_ref, &brother_rc);
                 self.rotate_right(parent_ref, &brother_right_rc);
                 {
-                    brother_rc.borrow_mut().color = Color::Black;
-                    parent_ref.borrow_mut().color = Color::Red;
+                    self.paint(&brother_rc, Color::Black);
+                    self.paint(parent_ref, Color::Red);
                 }
                 //如果旋转前，兄弟节点的右子节点存在右子节点，
                 //即原来的父节点，旋转过后存在左子节点，则需要对其做插入调平处理
@@ -667,18 +1065,18 @@ impl RedBlackTree {
             DeleteSituation::BRBWR => {
                 self.rotate_left(&brother_rc, &brother_right_rc);
                 self.rotate_right(parent_ref, &brother_right_rc);
-                brother_right_rc.borrow_mut().color = Color::Black;
+                self.paint(&brother_right_rc, Color::Black);
             }
             //2.3兄弟节点为黑色，且只有一个左子节点
             DeleteSituation::BRBRE => {
                 self.rotate_right(parent_ref, &brother_rc);
-                brother_left_rc.borrow_mut().color = Color::Black;
+                self.paint(&brother_left_rc, Color::Black);
             }
             //2.4兄弟节点为黑色，且没有子节点
             DeleteSituation::BRBEE => {
                 //先达到局部平衡
                 {
-                    brother_rc.borrow_mut().color = Color::Red;
+                    self.paint(&brother_rc, Color::Red);
                 }
                 self.delete_balance_recursion(parent_ref);
             }
@@ -689,164 +1087,246 @@ impl RedBlackTree {
     /// 处理删除平衡操作的失衡情况
     /// target_ref为失衡节点
     /// 失衡节点为局部平衡后的根节点
-    fn delete_balance_recursion(&mut self, target_ref: &Rc<RefCell<Node>>) {
-        let (situation, parent_rc, brother_rc, brother_left_rc, brother_right_rc) = Self::judge_delete_recursion_situation(target_ref);
-        match situation {
-            //失衡节点为左节点
-            //1.父节点是红色
-            //1.1兄弟的左子节点为黑色
-            DeleteRecursionSituation::LRBW => {
-                self.rotate_left(&parent_rc, &brother_rc);
+    fn delete_balance_recursion(&mut self, target_ref: &Rc<RefCell<Node<K, V>>>) {
+        //失衡向上传递时以父节点为新的失衡节点继续循环，避免递归
+        let mut target_rc = Rc::clone(target_ref);
+        //delete_balance本身算一层
+        let mut depth = 1;
+        loop {
+            let target_ref = &target_rc;
+            let (situation, parent_rc, brother_rc, brother_left_rc, brother_right_rc) = Self::judge_delete_recursion_situation(target_ref);
+            if situation != DeleteRecursionSituation::Stable {
+                depth += 1;
+                self.notify(target_ref, |observer, key| observer.on_delete_recursion_situation(key, situation));
             }
-            //1.2兄弟的左子节点为红色，兄弟的右子节点为黑色
-            DeleteRecursionSituation::LRRB => {
-                {
-                    parent_rc.borrow_mut().color = Color::Black;
-                    brother_rc.borrow_mut().color = Color::Red;
+            match situation {
+                //失衡节点为左节点
+                //1.父节点是红色
+                //1.1兄弟的左子节点为黑色
+                DeleteRecursionSituation::LRBW => {
+                    self.rotate_left(&parent_rc, &brother_rc);
                 }
-                self.insert_balance(&brother_rc, &brother_left_rc);
-            }
-            //1.3兄弟的两个子节点都为红色
-            DeleteRecursionSituation::LRRR => {
-                {
-                    parent_rc.borrow_mut().color = Color::Black;
-                    brother_rc.borrow_mut().color = Color::Red;
-                    brother_right_rc.borrow_mut().color = Color::Black;
+                //1.2兄弟的左子节点为红色，兄弟的右子节点为黑色
+                DeleteRecursionSituation::LRRB => {
+                    {
+                        self.paint(&parent_rc, Color::Black);
+                        self.paint(&brother_rc, Color::Red);
+                    }
+                    self.insert_balance(&brother_rc, &brother_left_rc);
                 }
-                self.rotate_left(&parent_rc, &brother_rc);
-            }
-            //2.父节点是黑色
-            //兄弟节点为黑色
-            //2.1兄弟的两子节点都为黑色
-            DeleteRecursionSituation::LBBBB => {
-                {
-                    brother_rc.borrow_mut().color = Color::Red;
+                //1.3兄弟的两个子节点都为红色
+                DeleteRecursionSituation::LRRR => {
+                    {
+                        self.paint(&parent_rc, Color::Black);
+                        self.paint(&brother_rc, Color::Red);
+                        self.paint(&brother_right_rc, Color::Black);
+                    }
+                    self.rotate_left(&parent_rc, &brother_rc);
                 }
-                //继续求助上级
-                self.delete_balance_recursion(&parent_rc);
-            }
-            //2.2兄弟的右子节点为红色
-            DeleteRecursionSituation::LBBWR => {
-                self.rotate_left(&parent_rc, &brother_rc);
-                brother_right_rc.borrow_mut().color = Color::Black;
-            }
-            DeleteRecursionSituation::LBBRB => {
-                {
-                    brother_left_rc.borrow_mut().color = Color::Black;
+                //2.父节点是黑色
+                //兄弟节点为黑色
+                //2.1兄弟的两子节点都为黑色
+                DeleteRecursionSituation::LBBBB => {
+                    {
+                        self.paint(&brother_rc, Color::Red);
+                    }
+                    //继续求助上级
+                    target_rc = parent_rc;
+                    continue;
                 }
-                self.rotate_right(&brother_rc, &brother_left_rc);
-                self.rotate_left(&parent_rc, &brother_left_rc);
-            }
-            DeleteRecursionSituation::LBR => {
-                {
-                    parent_rc.borrow_mut().color = Color::Red;
-                    brother_rc.borrow_mut().color = Color::Black;
+                //2.2兄弟的右子节点为红色
+                DeleteRecursionSituation::LBBWR => {
+                    self.rotate_left(&parent_rc, &brother_rc);
+                    self.paint(&brother_right_rc, Color::Black);
                 }
-                self.rotate_left(&parent_rc, &brother_rc);
-                //转为情况1，继续递归
-                self.delete_balance_recursion(target_ref);
-            }
-            //失衡节点为右节点
-            //1.父节点是红色
-            //1.1兄弟的右子节点为黑色
-            DeleteRecursionSituation::RRWB => {
-                self.rotate_right(&parent_rc, &brother_rc);
-            }
-            //1.2兄弟的右子节点为红色，兄弟的左子节点为黑色
-            DeleteRecursionSituation::RRBR => {
-                {
-                    parent_rc.borrow_mut().color = Color::Black;
-                    brother_rc.borrow_mut().color = Color::Red;
+                DeleteRecursionSituation::LBBRB => {
+                    {
+                        self.paint(&brother_left_rc, Color::Black);
+                    }
+                    self.rotate_right(&brother_rc, &brother_left_rc);
+                    self.rotate_left(&parent_rc, &brother_left_rc);
                 }
-                self.insert_balance(&brother_rc, &brother_right_rc);
-            }
-            //1.3兄弟的两个子节点都为红色
-            DeleteRecursionSituation::RRRR => {
-                {
-                    parent_rc.borrow_mut().color = Color::Black;
-                    brother_rc.borrow_mut().color = Color::Red;
-                    brother_left_rc.borrow_mut().color = Color::Black;
+                DeleteRecursionSituation::LBR => {
+                    {
+                        self.paint(&parent_rc, Color::Red);
+                        self.paint(&brother_rc, Color::Black);
+                    }
+                    self.rotate_left(&parent_rc, &brother_rc);
+                    //转为情况1，继续循环
+                    continue;
                 }
-                self.rotate_right(&parent_rc, &brother_rc);
-            }
-            //2.父节点是黑色
-            //兄弟节点为黑色
-            //2.1兄弟的两子节点都为黑色
-            DeleteRecursionSituation::RBBBB => {
-                {
-                    brother_rc.borrow_mut().color = Color::Red;
+                //失衡节点为右节点
+                //1.父节点是红色
+                //1.1兄弟的右子节点为黑色
+                DeleteRecursionSituation::RRWB => {
+                    self.rotate_right(&parent_rc, &brother_rc);
                 }
-                //继续求助上级
-                self.delete_balance_recursion(&parent_rc);
-            }
-            //2.2兄弟的左子节点为红色
-            DeleteRecursionSituation::RBBRW => {
-                self.rotate_right(&parent_rc, &brother_rc);
-                brother_left_rc.borrow_mut().color = Color::Black;
-            }
-            //2.3兄弟的右子节点为红色，兄弟的左子节点为黑色
-            DeleteRecursionSituation::RBBBR => {
-                {
-                    brother_right_rc.borrow_mut().color = Color::Black;
+                //1.2兄弟的右子节点为红色，兄弟的左子节点为黑色
+                DeleteRecursionSituation::RRBR => {
+                    {
+                        self.paint(&parent_rc, Color::Black);
+                        self.paint(&brother_rc, Color::Red);
+                    }
+                    self.insert_balance(&brother_rc, &brother_right_rc);
                 }
-                self.rotate_left(&brother_rc, &brother_right_rc);
-                self.rotate_right(&parent_rc, &brother_right_rc);
-            }
-            //兄弟节点为红色
-            //2.4兄弟节点为红色
-            DeleteRecursionSituation::RBR => {
-                {
-                    parent_rc.borrow_mut().color = Color::Red;
-                    brother_rc.borrow_mut().color = Color::Black;
+                //1.3兄弟的两个子节点都为红色
+                DeleteRecursionSituation::RRRR => {
+                    {
+                        self.paint(&parent_rc, Color::Black);
+                        self.paint(&brother_rc, Color::Red);
+                        self.paint(&brother_left_rc, Color::Black);
+                    }
+                    self.rotate_right(&parent_rc, &brother_rc);
                 }
-                self.rotate_right(&parent_rc, &brother_rc);
-                //转为情况1，继续递归
-                self.delete_balance_recursion(target_ref);
+                //2.父节点是黑色
+                //兄弟节点为黑色
+                //2.1兄弟的两子节点都为黑色
+                DeleteRecursionSituation::RBBBB => {
+                    {
+                        self.paint(&brother_rc, Color::Red);
+                    }
+                    //继续求助上级
+                    target_rc = parent_rc;
+                    continue;
+                }
+                //2.2兄弟的左子节点为红色
+                DeleteRecursionSituation::RBBRW => {
+                    self.rotate_right(&parent_rc, &brother_rc);
+                    self.paint(&brother_left_rc, Color::Black);
+                }
+                //2.3兄弟的右子节点为红色，兄弟的左子节点为黑色
+                DeleteRecursionSituation::RBBBR => {
+                    {
+                        self.paint(&brother_right_rc, Color::Black);
+                    }
+                    self.rotate_left(&brother_rc, &brother_right_rc);
+                    self.rotate_right(&parent_rc, &brother_right_rc);
+                }
+                //兄弟节点为红色
+                //2.4兄弟节点为红色
+                DeleteRecursionSituation::RBR => {
+                    {
+                        self.paint(&parent_rc, Color::Red);
+                        self.paint(&brother_rc, Color::Black);
+                    }
+                    self.rotate_right(&parent_rc, &brother_rc);
+                    //转为情况1，继续循环
+                    continue;
+                }
+                DeleteRecursionSituation::Stable => {}
             }
-            DeleteRecursionSituation::Stable => {}
+            self.record_fixup_depth(depth);
+            break;
         }
     }
 
-    ///寻找最小节点
-    fn find_minimum(node_ref: &Rc<RefCell<Node>>) -> Rc<RefCell<Node>> {
-        let mut next_rc = Rc::clone(&node_ref);
-        loop {
-            let cur_rc = Rc::clone(&next_rc);
-            let cur = cur_rc.borrow();
-            match &cur.left {
-                Some(next_ref) => {
-                    next_rc = Rc::clone(next_ref);
-                }
-                None => {
-                    return next_rc;
-                }
+    fn size_of(node_option: &Option<Rc<RefCell<Node<K, V>>>>) -> usize {
+        node_option.as_ref().map_or(0, |node_ref| node_ref.borrow().size)
+    }
+
+    ///从node_ref到根节点路径上的子树节点数量加一或减一
+    fn adjust_path_size(node_ref: &Rc<RefCell<Node<K, V>>>, increase: bool) {
+        let mut next_option = Some(Rc::clone(node_ref));
+        while let Some(cur_rc) = next_option {
+            let mut cur = cur_rc.borrow_mut();
+            if increase {
+                cur.size += 1;
+            } else {
+                cur.size -= 1;
             }
+            next_option = cur.parent.as_ref().and_then(Weak::upgrade);
         }
     }
 
-    fn find(cur_option: &Option<Rc<RefCell<Node>>>, key: i32) -> Option<Rc<RefCell<Node>>> {
-        match cur_option {
-            Some(cur_ref) => {
-                let cur = cur_ref.borrow();
-                return match key.cmp(&cur.key) {
-                    std::cmp::Ordering::Equal => {
-                        Some(Rc::clone(cur_ref))
-                    }
-                    std::cmp::Ordering::Less => {
-                        Self::find(&cur.left, key)
-                    }
-                    std::cmp::Ordering::Greater => {
-                        Self::find(&cur.right, key)
-                    }
-                };
+    ///寻找满足下界的最小节点
+    fn find_lower_bound<Q: Ord + ?Sized>(&self, bound: Bound<&Q>) -> Option<Rc<RefCell<Node<K, V>>>>
+    where
+        K: core::borrow::Borrow<Q>,
+    {
+        let mut result = None;
+        let mut next_option = self.root.clone();
+        let mut comparisons = 0;
+        while let Some(cur_rc) = next_option {
+            let cur = cur_rc.borrow();
+            comparisons += 1;
+            let cur_key: &Q = core::borrow::Borrow::borrow(&cur.key);
+            let satisfied = match bound {
+                Bound::Included(key) => cur_key >= key,
+                Bound::Excluded(key) => cur_key > key,
+                Bound::Unbounded => true,
+            };
+            //满足下界则记录，继续在左子树寻找更小的
+            if satisfied {
+                next_option = cur.left.clone();
+                drop(cur);
+                result = Some(cur_rc);
+            } else {
+                next_option = cur.right.clone();
             }
-            None => {
-                None
+        }
+        self.record(|stats| stats.comparisons += comparisons);
+        result
+    }
+
+    ///寻找满足上界的最大节点
+    fn find_upper_bound<Q: Ord + ?Sized>(&self, bound: Bound<&Q>) -> Option<Rc<RefCell<Node<K, V>>>>
+    where
+        K: core::borrow::Borrow<Q>,
+    {
+        let mut result = None;
+        let mut next_option = self.root.clone();
+        let mut comparisons = 0;
+        while let Some(cur_rc) = next_option {
+            let cur = cur_rc.borrow();
+            comparisons += 1;
+            let cur_key: &Q = core::borrow::Borrow::borrow(&cur.key);
+            let satisfied = match bound {
+                Bound::Included(key) => cur_key <= key,
+                Bound::Excluded(key) => cur_key < key,
+                Bound::Unbounded => true,
+            };
+            //满足上界则记录，继续在右子树寻找更大的
+            if satisfied {
+                next_option = cur.right.clone();
+                drop(cur);
+                result = Some(cur_rc);
+            } else {
+                next_option = cur.left.clone();
             }
         }
+        self.record(|stats| stats.comparisons += comparisons);
+        result
     }
 
-    fn judge_insert_situation(parent_ref: &Rc<RefCell<Node>>, son_ref: &Rc<RefCell<Node>>) -> (InsertSituation, Rc<RefCell<Node>>, Rc<RefCell<Node>>) {
+    ///查找节点，键可以是K的借用形式(如Vec<u8>的&[u8])，无需构造临时的K
+    fn find<Q: Ord + ?Sized>(&self, key: &Q) -> Option<Rc<RefCell<Node<K, V>>>>
+    where
+        K: core::borrow::Borrow<Q>,
+    {
+        let mut next_option = self.root.clone();
+        let mut comparisons = 0;
+        while let Some(cur_rc) = next_option {
+            let cur = cur_rc.borrow();
+            comparisons += 1;
+            next_option = match key.cmp(core::borrow::Borrow::borrow(&cur.key)) {
+                core::cmp::Ordering::Equal => {
+                    drop(cur);
+                    self.record(|stats| stats.comparisons += comparisons);
+                    return Some(cur_rc);
+                }
+                core::cmp::Ordering::Less => {
+                    cur.left.clone()
+                }
+                core::cmp::Ordering::Greater => {
+                    cur.right.clone()
+                }
+            };
+        }
+        self.record(|stats| stats.comparisons += comparisons);
+        None
+    }
+
+    fn judge_insert_situation(parent_ref: &Rc<RefCell<Node<K, V>>>, son_ref: &Rc<RefCell<Node<K, V>>>) -> InsertSituationContext<K, V> {
         let mut insert_situation = InsertSituation::Stable;
         let mut grand_parent_rc = Rc::clone(parent_ref);
         let mut uncle_rc = Rc::clone(parent_ref);
@@ -923,9 +1403,9 @@ impl RedBlackTree {
         (insert_situation, grand_parent_rc, uncle_rc)
     }
 
-    fn judge_delete_situation(parent_ref: &Rc<RefCell<Node>>) -> (DeleteSituation, Rc<RefCell<Node>>, Rc<RefCell<Node>>, Rc<RefCell<Node>>) {
+    fn judge_delete_situation(parent_ref: &Rc<RefCell<Node<K, V>>>) -> DeleteSituationContext<K, V> {
         let parent = parent_ref.borrow();
-        return match parent.color {
+        match parent.color {
             //1.父节点是红色的
             Color::Red => {
                 //兄弟节点一定为黑色,其子节点存在则必为红色
@@ -1040,10 +1520,10 @@ impl RedBlackTree {
                 }
                 (DeleteSituation::Stable, Rc::clone(parent_ref), Rc::clone(parent_ref), Rc::clone(parent_ref))
             }
-        };
+        }
     }
 
-    fn judge_delete_recursion_situation(cur_ref: &Rc<RefCell<Node>>) -> (DeleteRecursionSituation, Rc<RefCell<Node>>, Rc<RefCell<Node>>, Rc<RefCell<Node>>, Rc<RefCell<Node>>) {
+    fn judge_delete_recursion_situation(cur_ref: &Rc<RefCell<Node<K, V>>>) -> DeleteRecursionSituationContext<K, V> {
         //失衡节点的父节点不存在，即达到了全局平衡
         if let Some(parent_weak) = &cur_ref.borrow().parent {
             if let Some(parent_ref) = &parent_weak.upgrade() {
@@ -1171,53 +1651,418 @@ impl RedBlackTree {
                 }
             }
         }
-        return (DeleteRecursionSituation::Stable, Rc::clone(cur_ref), Rc::clone(cur_ref), Rc::clone(cur_ref), Rc::clone(cur_ref));
+        (DeleteRecursionSituation::Stable, Rc::clone(cur_ref), Rc::clone(cur_ref), Rc::clone(cur_ref), Rc::clone(cur_ref))
+    }
+}
+
+///诊断输出，开启log特性时以debug级别写入log，否则输出到标准输出
+#[cfg(any(feature = "std", feature = "log"))]
+macro_rules! diagnostic {
+    ($($arg:tt)*) => {{
+        #[cfg(feature = "log")]
+        log::debug!($($arg)*);
+        #[cfg(not(feature = "log"))]
+        std::println!($($arg)*);
+    }};
+}
+
+/// 打印树结构与遍历结果
+/// 开启log特性时写入log而不是标准输出，可在no_std下使用
+#[cfg(any(feature = "std", feature = "log"))]
+impl<K: Ord + fmt::Display, V> RedBlackTree<K, V> {
+    ///横向打印树结构，右子树在上，左子树在下
+    pub fn print_tree(&self) {
+        //Display以换行结尾，逐行输出时去掉
+        diagnostic!("{}", format!("{}", self).trim_end());
+    }
+
+    pub fn preorder_traversal(&self) {
+        diagnostic!("preorder_traversal");
+        self.walk(Order::Pre, &mut |node, _| diagnostic!("{}", node));
+    }
+
+    pub fn inorder_traversal(&self) {
+        diagnostic!("inorder_traversal");
+        self.walk(Order::In, &mut |node, _| diagnostic!("{}", node));
+    }
+
+    pub fn postorder_traversal(&self) {
+        diagnostic!("postorder_traversal");
+        self.walk(Order::Post, &mut |node, _| diagnostic!("{}", node));
+    }
+}
+
+///遍历顺序
+#[derive(Clone, Copy, PartialEq)]
+enum Order {
+    Pre,
+    In,
+    Post,
+}
+
+impl<K, V> RedBlackTree<K, V> {
+    ///先序访问每个节点的键、颜色与深度(根为0)
+    pub fn visit_preorder<F: FnMut(&K, Color, usize)>(&self, mut visit: F) {
+        self.walk(Order::Pre, &mut |node, depth| visit(&node.key, node.color, depth));
+    }
+
+    ///中序访问，键升序
+    pub fn visit_inorder<F: FnMut(&K, Color, usize)>(&self, mut visit: F) {
+        self.walk(Order::In, &mut |node, depth| visit(&node.key, node.color, depth));
+    }
+
+    ///后序访问
+    pub fn visit_postorder<F: FnMut(&K, Color, usize)>(&self, mut visit: F) {
+        self.walk(Order::Post, &mut |node, depth| visit(&node.key, node.color, depth));
+    }
+
+    fn walk(&self, order: Order, visit: &mut dyn FnMut(&Node<K, V>, usize)) {
+        if let Some(root) = &self.root {
+            Self::walk_node(&root.borrow(), 0, order, visit);
+        }
     }
 
-    fn do_preorder_traversal(node: &Node) {
-        println!("{}", node);
+    fn walk_node(node: &Node<K, V>, depth: usize, order: Order, visit: &mut dyn FnMut(&Node<K, V>, usize)) {
+        if order == Order::Pre {
+            visit(node, depth);
+        }
         if let Some(left) = &node.left {
-            Self::do_preorder_traversal(&*left.as_ref().borrow());
+            Self::walk_node(&left.borrow(), depth + 1, order, visit);
+        }
+        if order == Order::In {
+            visit(node, depth);
         }
         if let Some(right) = &node.right {
-            Self::do_preorder_traversal(&*right.as_ref().borrow());
+            Self::walk_node(&right.borrow(), depth + 1, order, visit);
+        }
+        if order == Order::Post {
+            visit(node, depth);
         }
     }
+}
 
-    fn do_inorder_traversal(node: &Node) {
-        if let Some(left) = &node.left {
-            Self::do_inorder_traversal(&*left.as_ref().borrow());
+impl<K, V> RedBlackTree<K, V> {
+    ///寻找最小节点
+    fn find_minimum(node_ref: &Rc<RefCell<Node<K, V>>>) -> Rc<RefCell<Node<K, V>>> {
+        let mut next_rc = Rc::clone(node_ref);
+        loop {
+            let cur_rc = Rc::clone(&next_rc);
+            let cur = cur_rc.borrow();
+            match &cur.left {
+                Some(next_ref) => {
+                    next_rc = Rc::clone(next_ref);
+                }
+                None => {
+                    return next_rc;
+                }
+            }
         }
-        println!("{}", node);
-        if let Some(right) = &node.right {
-            Self::do_inorder_traversal(&*right.as_ref().borrow());
+    }
+
+    ///寻找最大节点
+    fn find_maximum(node_ref: &Rc<RefCell<Node<K, V>>>) -> Rc<RefCell<Node<K, V>>> {
+        let mut next_rc = Rc::clone(node_ref);
+        loop {
+            let cur_rc = Rc::clone(&next_rc);
+            let cur = cur_rc.borrow();
+            match &cur.right {
+                Some(next_ref) => {
+                    next_rc = Rc::clone(next_ref);
+                }
+                None => {
+                    return next_rc;
+                }
+            }
         }
     }
 
-    fn do_postorder_traversal(node: &Node) {
-        if let Some(left) = &node.left {
-            Self::do_postorder_traversal(&*left.as_ref().borrow());
+    ///寻找后继节点
+    /// 有右子树则为右子树最小节点，否则向上寻找第一个以当前节点所在子树为左子树的祖先
+    fn successor(node_ref: &Rc<RefCell<Node<K, V>>>) -> Option<Rc<RefCell<Node<K, V>>>> {
+        if let Some(right_ref) = &node_ref.borrow().right {
+            return Some(Self::find_minimum(right_ref));
         }
-        if let Some(right) = &node.right {
-            Self::do_postorder_traversal(&*right.as_ref().borrow());
+        let mut cur_rc = Rc::clone(node_ref);
+        loop {
+            let parent_option = cur_rc.borrow().parent.as_ref().and_then(Weak::upgrade);
+            let parent_rc = parent_option?;
+            let is_left = match &parent_rc.borrow().left {
+                Some(parent_left_ref) => Rc::ptr_eq(parent_left_ref, &cur_rc),
+                None => false,
+            };
+            if is_left {
+                return Some(parent_rc);
+            }
+            cur_rc = parent_rc;
         }
-        println!("{}", node);
     }
 
-    fn count_size(cur_option: &Option<Rc<RefCell<Node>>>) -> usize {
-        return match cur_option {
-            Some(cur_ref) => {
-                let cur = cur_ref.borrow();
-                Self::count_size(&cur.left) + 1 + Self::count_size(&cur.right)
+    ///寻找前驱节点，与寻找后继节点对称
+    fn predecessor(node_ref: &Rc<RefCell<Node<K, V>>>) -> Option<Rc<RefCell<Node<K, V>>>> {
+        if let Some(left_ref) = &node_ref.borrow().left {
+            return Some(Self::find_maximum(left_ref));
+        }
+        let mut cur_rc = Rc::clone(node_ref);
+        loop {
+            let parent_option = cur_rc.borrow().parent.as_ref().and_then(Weak::upgrade);
+            let parent_rc = parent_option?;
+            let is_right = match &parent_rc.borrow().right {
+                Some(parent_right_ref) => Rc::ptr_eq(parent_right_ref, &cur_rc),
+                None => false,
+            };
+            if is_right {
+                return Some(parent_rc);
             }
-            None => {
-                0
+            cur_rc = parent_rc;
+        }
+    }
+
+    ///清空树，逐个释放节点，保留抽样校验、错误回调与随机源等设置
+    pub fn clear(&mut self) {
+        Self::teardown(self.root.take());
+        self.len = 0;
+    }
+
+    /// 逐个拆除子树
+    /// 先断开子节点连接再释放节点，避免Rc链式析构导致的深递归
+    fn teardown(root_option: Option<Rc<RefCell<Node<K, V>>>>) {
+        let mut stack: Vec<Rc<RefCell<Node<K, V>>>> = root_option.into_iter().collect();
+        while let Some(cur_rc) = stack.pop() {
+            let mut cur = cur_rc.borrow_mut();
+            stack.extend(cur.left.take());
+            stack.extend(cur.right.take());
+        }
+    }
+}
+
+impl<K, V> Drop for RedBlackTree<K, V> {
+    fn drop(&mut self) {
+        RedBlackTree::teardown(self.root.take());
+    }
+}
+
+impl<K: Ord, V> Default for RedBlackTree<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 按结构输出，每个节点含键、值、颜色与左右子树
+/// {:#?}可得到缩进的树形
+impl<K: fmt::Debug, V: fmt::Debug> fmt::Debug for RedBlackTree<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RedBlackTree")
+            .field("len", &self.len)
+            .field("root", &self.root.as_ref().map(|root_ref| DebugNode(root_ref)))
+            .finish()
+    }
+}
+
+struct DebugNode<'a, K, V>(&'a Rc<RefCell<Node<K, V>>>);
+
+impl<K: fmt::Debug, V: fmt::Debug> fmt::Debug for DebugNode<'_, K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let node = self.0.borrow();
+        f.debug_struct("Node")
+            .field("key", &node.key)
+            .field("value", &node.value)
+            .field("color", &node.color)
+            .field("count", &node.count)
+            .field("left", &node.left.as_ref().map(DebugNode))
+            .field("right", &node.right.as_ref().map(DebugNode))
+            .finish()
+    }
+}
+
+/// 按内容比较，中序键值序列(及重复次数)相同即相等，与树的形状和颜色无关
+impl<K: PartialEq, V: PartialEq> PartialEq for RedBlackTree<K, V> {
+    fn eq(&self, other: &Self) -> bool {
+        if self.len != other.len {
+            return false;
+        }
+        let mut next_option = self.root.as_ref().map(Self::find_minimum);
+        let mut other_next_option = other.root.as_ref().map(Self::find_minimum);
+        while let (Some(cur_rc), Some(other_rc)) = (next_option, other_next_option) {
+            {
+                let (cur, other) = (cur_rc.borrow(), other_rc.borrow());
+                if cur.key != other.key || cur.value != other.value || cur.count != other.count {
+                    return false;
+                }
+            }
+            next_option = Self::successor(&cur_rc);
+            other_next_option = Self::successor(&other_rc);
+        }
+        true
+    }
+}
+
+impl<K: Eq, V: Eq> Eq for RedBlackTree<K, V> {}
+
+/// 按中序键值序列的字典序比较，与BTreeMap相同
+/// 多重集模式下每个键值按重复次数展开后再比较，如{1×2, 2} > {1×3}，与相等的判断一致
+impl<K: PartialOrd, V: PartialOrd> PartialOrd for RedBlackTree<K, V> {
+    fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
+        self.compare_contents(other, |cur, other| match cur.key.partial_cmp(&other.key)? {
+            core::cmp::Ordering::Equal => cur.value.partial_cmp(&other.value),
+            ordering => Some(ordering),
+        })
+    }
+}
+
+impl<K: Ord, V: Ord> Ord for RedBlackTree<K, V> {
+    fn cmp(&self, other: &Self) -> core::cmp::Ordering {
+        self.compare_contents(other, |cur, other| Some(cur.key.cmp(&other.key).then_with(|| cur.value.cmp(&other.value))))
+            .expect("total order always compares")
+    }
+}
+
+impl<K, V> RedBlackTree<K, V> {
+    /// 同步中序遍历两棵树，以compare比较键值，遇到不相等的节点即返回
+    /// 每个节点视为连续count个相同的键值，键值相同时双方各消耗较少的重复次数，耗尽的一方前进到后继
+    fn compare_contents<F>(&self, other: &Self, mut compare: F) -> Option<core::cmp::Ordering>
+    where
+        F: FnMut(&Node<K, V>, &Node<K, V>) -> Option<core::cmp::Ordering>,
+    {
+        //(当前节点，当前节点剩余的重复次数)
+        let with_count = |node_rc: Rc<RefCell<Node<K, V>>>| {
+            let count = node_rc.borrow().count;
+            (node_rc, count)
+        };
+        let mut next_option = self.root.as_ref().map(Self::find_minimum).map(with_count);
+        let mut other_next_option = other.root.as_ref().map(Self::find_minimum).map(with_count);
+        while let (Some((cur_rc, cur_left)), Some((other_rc, other_left))) = (&mut next_option, &mut other_next_option) {
+            match compare(&cur_rc.borrow(), &other_rc.borrow())? {
+                core::cmp::Ordering::Equal => {}
+                ordering => return Some(ordering),
+            }
+            let consumed = (*cur_left).min(*other_left);
+            *cur_left -= consumed;
+            *other_left -= consumed;
+            if *cur_left == 0 {
+                next_option = Self::successor(cur_rc).map(with_count);
             }
+            if *other_left == 0 {
+                other_next_option = Self::successor(other_rc).map(with_count);
+            }
+        }
+        Some(next_option.is_some().cmp(&other_next_option.is_some()))
+    }
+}
+
+/// 按中序键值序列计算，与BTreeMap相同先写入长度
+/// 相等的树重复次数也相同，不参与计算
+impl<K: core::hash::Hash, V: core::hash::Hash> core::hash::Hash for RedBlackTree<K, V> {
+    fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+        state.write_usize(self.len);
+        let mut next_option = self.root.as_ref().map(Self::find_minimum);
+        while let Some(cur_rc) = next_option {
+            {
+                let cur = cur_rc.borrow();
+                cur.key.hash(state);
+                cur.value.hash(state);
+            }
+            next_option = Self::successor(&cur_rc);
+        }
+    }
+}
+
+/// 深拷贝，逐个复制节点的键、值、颜色与结构，不与原树共享节点
+/// 抽样校验设置与错误钩子随之复制，随机源复制当前状态(注入的随机数生成器无法复制，改用默认随机源)
+impl<K: Clone, V: Clone> Clone for RedBlackTree<K, V> {
+    fn clone(&self) -> Self {
+        let copy_node = |source: &Node<K, V>| {
+            Rc::new(RefCell::new(Node {
+                key: source.key.clone(),
+                value: source.value.clone(),
+                parent: None,
+                left: None,
+                right: None,
+                color: source.color,
+                size: source.size,
+                count: source.count,
+            }))
         };
+        let root = self.root.as_ref().map(|root_ref| copy_node(&root_ref.borrow()));
+        //(原节点, 对应的新节点)
+        let mut stack: Vec<_> = self.root.iter().cloned().zip(root.iter().cloned()).collect();
+        while let Some((source_rc, copy_rc)) = stack.pop() {
+            let source = source_rc.borrow();
+            let mut copy = copy_rc.borrow_mut();
+            if let Some(source_left_ref) = &source.left {
+                let left_rc = copy_node(&source_left_ref.borrow());
+                left_rc.borrow_mut().parent = Some(Rc::downgrade(&copy_rc));
+                copy.left = Some(Rc::clone(&left_rc));
+                stack.push((Rc::clone(source_left_ref), left_rc));
+            }
+            if let Some(source_right_ref) = &source.right {
+                let right_rc = copy_node(&source_right_ref.borrow());
+                right_rc.borrow_mut().parent = Some(Rc::downgrade(&copy_rc));
+                copy.right = Some(Rc::clone(&right_rc));
+                stack.push((Rc::clone(source_right_ref), right_rc));
+            }
+        }
+        let mut tree = self.empty_like();
+        tree.root = root;
+        tree.len = self.len;
+        tree
+    }
+}
+
+impl<K, V> RedBlackTree<K, V> {
+    /// 沿用本树设置的空树：重复键策略、抽样校验、错误钩子与观察者，随机源fork
+    /// clone、split_off、remove_range等由本树产生新树的操作共用；节点池不共享，统计从零开始
+    pub(super) fn empty_like(&self) -> Self {
+        RedBlackTree {
+            root: None,
+            len: 0,
+            validation_sampling: self.validation_sampling,
+            sampled_validator: self.sampled_validator,
+            error_hook: self.error_hook.clone(),
+            observer: self.observer.clone(),
+            random: self.random.fork(),
+            duplicate_policy: self.duplicate_policy,
+            pool: Default::default(),
+            #[cfg(feature = "stats")]
+            stats: Default::default(),
+        }
     }
 }
 
-impl fmt::Display for Node {
+/// 横向渲染树结构
+/// 每行一个节点，缩进表示深度，右子树在上，左子树在下，[R]/[B]表示颜色
+/// 例如依次插入1..=5:
+/// ```text
+///         /-- 5[R]
+///     /-- 4[B]
+///     |   \-- 3[R]
+/// --- 2[B]
+///     \-- 1[B]
+/// ```
+impl<K: fmt::Display, V> fmt::Display for RedBlackTree<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn render<K: fmt::Display, V>(f: &mut fmt::Formatter<'_>, node_ref: &Rc<RefCell<Node<K, V>>>, prefix: &str, connector: &str, child_prefix_up: &str, child_prefix_down: &str) -> fmt::Result {
+            let node = node_ref.borrow();
+            if let Some(right_ref) = &node.right {
+                render(f, right_ref, &format!("{}{}", prefix, child_prefix_up), "/-- ", "    ", "|   ")?;
+            }
+            let color = match node.color {
+                Color::Red => "R",
+                Color::Black => "B",
+            };
+            writeln!(f, "{}{}{}[{}]", prefix, connector, node.key, color)?;
+            if let Some(left_ref) = &node.left {
+                render(f, left_ref, &format!("{}{}", prefix, child_prefix_down), "\\-- ", "|   ", "    ")?;
+            }
+            Ok(())
+        }
+        match &self.root {
+            None => writeln!(f, "(empty)"),
+            Some(root_ref) => render(f, root_ref, "", "--- ", "    ", "    "),
+        }
+    }
+}
+
+impl<K: fmt::Display, V> fmt::Display for Node<K, V> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
             f,
@@ -1242,3 +2087,55 @@ impl fmt::Display for Node {
         write!(f, ")")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::ToString;
+
+    use super::{DuplicatePolicy, RedBlackTree};
+
+    #[test]
+    fn borrowed_key_lookups() {
+        let mut tree = RedBlackTree::new();
+        for word in ["pear", "apple", "fig"] {
+            tree.insert(word.to_string(), word.len());
+        }
+        //以&str查找String键
+        assert_eq!(tree.get("apple"), Some(5));
+        {
+            let fig = tree.get_key_value("fig").unwrap();
+            let (key, value) = fig.entry();
+            assert_eq!((key.as_str(), *value), ("fig", 3));
+        }
+        assert_eq!(tree.update("pear", |len| *len * 2), Some(8));
+        tree.delete("apple");
+        assert!(!tree.contains("apple") && tree.contains("pear"));
+        assert_eq!(tree.count("fig"), 1);
+        //有序查找同样接受借用形式
+        assert_eq!(tree.floor("g"), Some(("fig".to_string(), 3)));
+        assert_eq!(tree.ceiling("g"), Some(("pear".to_string(), 4)));
+        assert_eq!(tree.strictly_lower("fig"), None);
+        assert_eq!(tree.strictly_higher("fig").map(|(key, _)| key), Some("pear".to_string()));
+        assert!(tree.iter_from("g").map(|(key, _)| key).eq(["pear".to_string()]));
+        assert_eq!((tree.rank("pear"), tree.index_of("pear"), tree.index_of("kiwi")), (1, Some(1), None));
+    }
+
+    #[test]
+    fn ordering_expands_counts() {
+        let multiset = |keys: &[u32]| {
+            let mut tree = RedBlackTree::with_duplicate_policy(DuplicatePolicy::Count);
+            for key in keys {
+                tree.insert(*key, ());
+            }
+            tree
+        };
+        //与展开后序列的字典序一致
+        let cases: [&[u32]; 5] = [&[1, 1, 2], &[1, 1, 1], &[1, 1], &[1, 2], &[]];
+        for left in cases {
+            for right in cases {
+                assert_eq!(multiset(left).cmp(&multiset(right)), left.cmp(right), "{:?} {:?}", left, right);
+                assert_eq!(multiset(left) == multiset(right), left == right);
+            }
+        }
+    }
+}
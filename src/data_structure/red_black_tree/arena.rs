@@ -0,0 +1,500 @@
+//! 基于数组的红黑树
+//! 节点连续存放在Vec中，父子连接为u32下标，删除的槽位串成空闲链表供后续插入复用
+//! 没有Rc/RefCell的分配与运行时借用检查，读接口可直接返回借用
+//! 可选的类型参数A为节点附加信息，见Augment
+
+use alloc::vec::Vec;
+use core::fmt;
+use core::ops::RangeBounds;
+
+use super::super::ordered_map::{after_start, before_end};
+use super::slot_tree::{Links, Slot, SlotNode, SlotTree, NIL};
+use super::{Augment, Color, InvariantViolation};
+
+mod merkle;
+#[cfg(feature = "rayon")]
+mod parallel;
+#[cfg(feature = "sha2")]
+pub use merkle::Sha256Hash;
+pub use merkle::{CanonicalEncode, ContentHash, MembershipProof, ProofStep};
+#[cfg(feature = "rayon")]
+pub use parallel::ArenaParIter;
+
+pub struct RedBlackTreeArena<K, V, A: Augment<K, V> = ()> {
+    slots: Vec<Slot<K, V, A::Value>>,
+    links: Links,
+}
+
+///槽位存放在Vec中，按需增长
+impl<K: Ord, V, A: Augment<K, V>> SlotTree<K, V, A> for RedBlackTreeArena<K, V, A> {
+    fn slots(&self) -> &[Slot<K, V, A::Value>] {
+        &self.slots
+    }
+
+    fn slots_mut(&mut self) -> &mut [Slot<K, V, A::Value>] {
+        &mut self.slots
+    }
+
+    fn links(&self) -> &Links {
+        &self.links
+    }
+
+    fn links_mut(&mut self) -> &mut Links {
+        &mut self.links
+    }
+
+    fn push_slot(&mut self, node: SlotNode<K, V, A::Value>) -> Result<u32, SlotNode<K, V, A::Value>> {
+        if self.slots.len() >= NIL as usize {
+            return Err(node);
+        }
+        self.slots.push(Slot::Occupied(node));
+        Ok((self.slots.len() - 1) as u32)
+    }
+}
+
+impl<K: Ord, V> RedBlackTreeArena<K, V> {
+    pub fn new() -> Self {
+        Self::with_capacity(0)
+    }
+
+    ///预留capacity个节点的空间
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::empty(capacity)
+    }
+
+    /// 可变借用值
+    /// 修改值会使附加信息失效，因此只对不附加信息的树提供，附加信息的树使用update
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        let index = self.find(key);
+        (index != NIL).then(|| &mut self.node_mut(index).value)
+    }
+}
+
+impl<K: Ord, V, A: Augment<K, V>> RedBlackTreeArena<K, V, A> {
+    ///附加信息为A的空树，A由类型标注给出
+    pub fn with_augment() -> Self {
+        Self::empty(0)
+    }
+
+    fn empty(capacity: usize) -> Self {
+        RedBlackTreeArena { slots: Vec::with_capacity(capacity), links: Links::EMPTY }
+    }
+
+    /// 由严格升序的键值对构造，O(n)，与RedBlackTree::from_sorted_vec相同
+    /// 槽位按键的顺序排列，输入不是严格升序时panic
+    pub fn from_sorted_vec(entries: Vec<(K, V)>) -> Self {
+        assert!(entries.windows(2).all(|pair| pair[0].0 < pair[1].0), "input is not strictly ascending");
+        let len = entries.len();
+        let mut tree = Self::empty(len);
+        tree.slots.extend(entries.into_iter().map(|(key, value)| {
+            let augment = A::combine(None, &key, &value, None);
+            Slot::Occupied(SlotNode { key, value, parent: NIL, left: NIL, right: NIL, color: Color::Black, augment })
+        }));
+        //最深一层的深度(根为0)，单节点时根保持黑色
+        let red_depth = match len {
+            0 | 1 => usize::MAX,
+            _ => (usize::BITS - 1 - len.leading_zeros()) as usize,
+        };
+        tree.links.root = tree.build_balanced(0, len, NIL, 0, red_depth);
+        tree.links.len = len;
+        tree
+    }
+
+    ///以槽位[start, end)构造子树并返回其根，最深一层染红
+    fn build_balanced(&mut self, start: usize, end: usize, parent: u32, depth: usize, red_depth: usize) -> u32 {
+        if start == end {
+            return NIL;
+        }
+        let index = start + (end - start - 1) / 2;
+        let left = self.build_balanced(start, index, index as u32, depth + 1, red_depth);
+        let right = self.build_balanced(index + 1, end, index as u32, depth + 1, red_depth);
+        let node = self.node_mut(index as u32);
+        node.parent = parent;
+        node.left = left;
+        node.right = right;
+        node.color = if depth == red_depth { Color::Red } else { Color::Black };
+        self.pull(index as u32);
+        index as u32
+    }
+
+    pub fn len(&self) -> usize {
+        self.links.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.links.len == 0
+    }
+
+    ///清空树，槽位一并释放
+    pub fn clear(&mut self) {
+        self.slots.clear();
+        self.links = Links::EMPTY;
+    }
+
+    ///插入键值对，键已存在时忽略
+    pub fn insert(&mut self, key: K, value: V) {
+        if self.insert_entry(key, value).is_err() {
+            panic!("arena is full");
+        }
+    }
+
+    ///删除键，键不存在时忽略
+    pub fn delete(&mut self, key: &K) {
+        let target = self.find(key);
+        if target != NIL {
+            self.remove_index(target);
+        }
+    }
+
+    ///删除键并返回值
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let target = self.find(key);
+        (target != NIL).then(|| self.remove_index(target).1)
+    }
+
+    pub fn get(&self, key: &K) -> Option<&V> {
+        let index = self.find(key);
+        (index != NIL).then(|| &self.node(index).value)
+    }
+
+    /// 原地修改键对应的值，返回闭包的结果，键不存在时返回None
+    /// 修改后重新计算到根路径上的附加信息
+    pub fn update<R, F: FnOnce(&mut V) -> R>(&mut self, key: &K, f: F) -> Option<R> {
+        let index = self.find(key);
+        if index == NIL {
+            return None;
+        }
+        let result = f(&mut self.node_mut(index).value);
+        self.pull_path(index);
+        Some(result)
+    }
+
+    ///整棵树的附加信息，空树为None
+    pub fn augment(&self) -> Option<&A::Value> {
+        (self.links.root != NIL).then(|| &self.node(self.links.root).augment)
+    }
+
+    ///根节点的只读视图，用于沿附加信息自定义下降，如区间树查询
+    pub fn root(&self) -> Option<ArenaNodeRef<'_, K, V, A>> {
+        self.node_ref(self.links.root)
+    }
+
+    fn node_ref(&self, index: u32) -> Option<ArenaNodeRef<'_, K, V, A>> {
+        (index != NIL).then_some(ArenaNodeRef { tree: self, index })
+    }
+
+    pub fn contains(&self, key: &K) -> bool {
+        self.find(key) != NIL
+    }
+
+    pub fn get_key_value(&self, key: &K) -> Option<(&K, &V)> {
+        let index = self.find(key);
+        (index != NIL).then(|| self.entry(index))
+    }
+
+    pub fn first(&self) -> Option<(&K, &V)> {
+        (self.links.root != NIL).then(|| self.entry(self.minimum(self.links.root)))
+    }
+
+    pub fn last(&self) -> Option<(&K, &V)> {
+        (self.links.root != NIL).then(|| self.entry(self.maximum(self.links.root)))
+    }
+
+    pub fn pop_first(&mut self) -> Option<(K, V)> {
+        (self.links.root != NIL).then(|| self.remove_index(self.minimum(self.links.root)))
+    }
+
+    pub fn pop_last(&mut self) -> Option<(K, V)> {
+        (self.links.root != NIL).then(|| self.remove_index(self.maximum(self.links.root)))
+    }
+
+    ///中序迭代，产出借用
+    pub fn iter(&self) -> ArenaIter<'_, K, V, A> {
+        let (front, back) = if self.links.root == NIL { (NIL, NIL) } else { (self.minimum(self.links.root), self.maximum(self.links.root)) };
+        ArenaIter { tree: self, front, back }
+    }
+
+    ///按键升序迭代范围内的条目，两端各沿查找路径下降一次，O(log n)
+    pub fn range<R: RangeBounds<K>>(&self, range: R) -> ArenaIter<'_, K, V, A> {
+        let (mut front, mut back) = (NIL, NIL);
+        let mut cur = self.links.root;
+        while cur != NIL {
+            let node = self.node(cur);
+            if after_start(range.start_bound(), &node.key) {
+                front = cur;
+                cur = node.left;
+            } else {
+                cur = node.right;
+            }
+        }
+        cur = self.links.root;
+        while cur != NIL {
+            let node = self.node(cur);
+            if before_end(range.end_bound(), &node.key) {
+                back = cur;
+                cur = node.right;
+            } else {
+                cur = node.left;
+            }
+        }
+        //范围内没有节点，或起点在终点之后
+        if front == NIL || back == NIL || self.node(front).key > self.node(back).key {
+            (front, back) = (NIL, NIL);
+        }
+        ArenaIter { tree: self, front, back }
+    }
+
+    ///校验每个节点的附加信息与重新计算的结果一致
+    pub fn validate_augment(&self) -> Result<(), InvariantViolation<K>>
+    where
+        K: Clone,
+        A::Value: PartialEq,
+    {
+        let mut stack: Vec<u32> = (self.links.root != NIL).then_some(self.links.root).into_iter().collect();
+        while let Some(index) = stack.pop() {
+            let node = self.node(index);
+            let left = (node.left != NIL).then(|| &self.node(node.left).augment);
+            let right = (node.right != NIL).then(|| &self.node(node.right).augment);
+            if A::combine(left, &node.key, &node.value, right) != node.augment {
+                return Err(InvariantViolation::StaleAugment { key: node.key.clone() });
+            }
+            stack.extend([node.left, node.right].into_iter().filter(|son| *son != NIL));
+        }
+        Ok(())
+    }
+
+    /// 校验红黑树性质，与RedBlackTree::validate相同
+    /// 数组实现没有子树节点数量，不检查SubtreeSize
+    pub fn validate(&self) -> Result<(), InvariantViolation<K>>
+    where
+        K: Clone,
+    {
+        self.validate_links()
+    }
+}
+
+impl<K: Ord, V, A: Augment<K, V>> Default for RedBlackTreeArena<K, V, A> {
+    fn default() -> Self {
+        Self::empty(0)
+    }
+}
+
+///tree[&key]，键不存在时panic
+impl<K: Ord, V, A: Augment<K, V>> core::ops::Index<&K> for RedBlackTreeArena<K, V, A> {
+    type Output = V;
+
+    fn index(&self, key: &K) -> &V {
+        self.get(key).expect("key not found in arena tree")
+    }
+}
+
+impl<K: Ord + fmt::Debug, V: fmt::Debug, A: Augment<K, V>> fmt::Debug for RedBlackTreeArena<K, V, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+/// 中序迭代器，与Iter相同借助父节点下标寻找后继
+/// front为下一个产出的节点，back为最后一个产出的节点，二者相遇时迭代结束
+pub struct ArenaIter<'a, K, V, A: Augment<K, V> = ()> {
+    tree: &'a RedBlackTreeArena<K, V, A>,
+    front: u32,
+    back: u32,
+}
+
+impl<'a, K: Ord, V, A: Augment<K, V>> Iterator for ArenaIter<'a, K, V, A> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.front == NIL {
+            return None;
+        }
+        let cur = self.front;
+        if cur == self.back {
+            self.front = NIL;
+            self.back = NIL;
+        } else {
+            self.front = self.tree.successor(cur);
+        }
+        Some(self.tree.entry(cur))
+    }
+}
+
+impl<K: Ord, V, A: Augment<K, V>> DoubleEndedIterator for ArenaIter<'_, K, V, A> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.back == NIL {
+            return None;
+        }
+        let cur = self.back;
+        if cur == self.front {
+            self.front = NIL;
+            self.back = NIL;
+        } else {
+            self.back = self.tree.predecessor(cur);
+        }
+        Some(self.tree.entry(cur))
+    }
+}
+
+/// 节点的只读视图
+/// 可读取键、值、附加信息并访问左右子节点，不能修改树
+pub struct ArenaNodeRef<'a, K, V, A: Augment<K, V> = ()> {
+    tree: &'a RedBlackTreeArena<K, V, A>,
+    index: u32,
+}
+
+impl<'a, K: Ord, V, A: Augment<K, V>> ArenaNodeRef<'a, K, V, A> {
+    pub fn key(&self) -> &'a K {
+        &self.tree.node(self.index).key
+    }
+
+    pub fn value(&self) -> &'a V {
+        &self.tree.node(self.index).value
+    }
+
+    ///以该节点为根的子树的附加信息
+    pub fn augment(&self) -> &'a A::Value {
+        &self.tree.node(self.index).augment
+    }
+
+    pub fn left(&self) -> Option<Self> {
+        self.tree.node_ref(self.tree.node(self.index).left)
+    }
+
+    pub fn right(&self) -> Option<Self> {
+        self.tree.node_ref(self.tree.node(self.index).right)
+    }
+}
+
+impl<K, V, A: Augment<K, V>> Clone for ArenaNodeRef<'_, K, V, A> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<K, V, A: Augment<K, V>> Copy for ArenaNodeRef<'_, K, V, A> {}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    use super::{ArenaNodeRef, RedBlackTreeArena};
+    use crate::data_structure::red_black_tree::Augment;
+
+    ///子树值之和
+    struct Sum;
+
+    impl Augment<u32, u64> for Sum {
+        type Value = u64;
+
+        fn combine(left: Option<&u64>, _key: &u32, value: &u64, right: Option<&u64>) -> u64 {
+            left.copied().unwrap_or(0) + value + right.copied().unwrap_or(0)
+        }
+    }
+
+    ///区间树，键为起点，值为终点，附加子树中最大的终点
+    struct MaxEnd;
+
+    impl Augment<u32, u32> for MaxEnd {
+        type Value = u32;
+
+        fn combine(left: Option<&u32>, _key: &u32, value: &u32, right: Option<&u32>) -> u32 {
+            (*value).max(left.copied().unwrap_or(0)).max(right.copied().unwrap_or(0))
+        }
+    }
+
+    ///包含point的所有区间的起点，跳过最大终点小于point的子树
+    fn stabbing(node: Option<ArenaNodeRef<'_, u32, u32, MaxEnd>>, point: u32, found: &mut Vec<u32>) {
+        let Some(node) = node else { return };
+        if *node.augment() < point {
+            return;
+        }
+        stabbing(node.left(), point, found);
+        if *node.key() <= point && point <= *node.value() {
+            found.push(*node.key());
+        }
+        if *node.key() <= point {
+            stabbing(node.right(), point, found);
+        }
+    }
+
+    #[test]
+    fn matches_btree_map_model() {
+        let mut rng = StdRng::seed_from_u64(2032);
+        let mut tree = RedBlackTreeArena::new();
+        let mut model = BTreeMap::new();
+        for step in 0..20_000 {
+            let key = rng.gen_range(0..500);
+            if rng.gen_bool(0.55) {
+                tree.insert(key, step);
+                model.entry(key).or_insert(step);
+            } else {
+                assert_eq!(tree.remove(&key), model.remove(&key));
+            }
+            if step % 100 == 0 {
+                assert_eq!(tree.validate(), Ok(()));
+                assert!(tree.iter().eq(model.iter()));
+                assert!(tree.iter().rev().eq(model.iter().rev()));
+                assert!(model.iter().all(|(key, value)| tree[key] == *value));
+            }
+        }
+        //删除后的槽位被复用，槽位数不超过同时存在的最大节点数
+        assert!(tree.slots.len() <= 500);
+    }
+
+    #[test]
+    fn sorted_construction_is_valid() {
+        for n in 0..300u32 {
+            let tree: RedBlackTreeArena<u32, u64, Sum> = RedBlackTreeArena::from_sorted_vec((0..n).map(|key| (key, u64::from(key))).collect());
+            assert_eq!(tree.validate(), Ok(()), "n={}", n);
+            assert_eq!(tree.validate_augment(), Ok(()), "n={}", n);
+            assert!(tree.iter().map(|(key, value)| (*key, *value)).eq((0..n).map(|key| (key, u64::from(key)))));
+        }
+    }
+
+    #[test]
+    fn sum_augment_follows_updates() {
+        let mut rng = StdRng::seed_from_u64(2063);
+        let mut tree: RedBlackTreeArena<u32, u64, Sum> = RedBlackTreeArena::with_augment();
+        let mut model = BTreeMap::new();
+        for step in 0..5_000 {
+            let key = rng.gen_range(0..300);
+            match rng.gen_range(0..4) {
+                0 | 1 => {
+                    tree.insert(key, step);
+                    model.entry(key).or_insert(step);
+                }
+                2 => assert_eq!(tree.remove(&key), model.remove(&key)),
+                _ => assert_eq!(tree.update(&key, |value| *value += 1), model.get_mut(&key).map(|value| *value += 1)),
+            }
+            assert_eq!(tree.augment().copied(), (!model.is_empty()).then(|| model.values().sum()));
+            if step % 100 == 0 {
+                assert_eq!(tree.validate(), Ok(()));
+                assert_eq!(tree.validate_augment(), Ok(()));
+            }
+        }
+    }
+
+    #[test]
+    fn interval_tree_stabbing_query() {
+        let mut rng = StdRng::seed_from_u64(2064);
+        let mut tree: RedBlackTreeArena<u32, u32, MaxEnd> = RedBlackTreeArena::default();
+        for _ in 0..500 {
+            let start = rng.gen_range(0..1_000);
+            tree.insert(start, start + rng.gen_range(0..50));
+        }
+        for _ in 0..200 {
+            tree.delete(&rng.gen_range(0..1_000));
+        }
+        assert_eq!(tree.validate_augment(), Ok(()));
+        for point in (0..1_100).step_by(7) {
+            let mut found = Vec::new();
+            stabbing(tree.root(), point, &mut found);
+            let expected: Vec<_> = tree.iter().filter(|(start, end)| **start <= point && point <= **end).map(|(start, _)| *start).collect();
+            assert_eq!(found, expected);
+        }
+    }
+}
@@ -0,0 +1,300 @@
+//! Merkle式内容指纹
+//! 以附加信息保存子树摘要：节点摘要由左子树摘要、键、值、右子树摘要计算，旋转时随附加信息一起维护
+//! 根摘要概括整棵树的内容与形状，成员证明为从节点到根的路径上重新计算摘要所需的信息
+//! 摘要的输入为键值的规范编码(CanonicalEncode)，与平台的字节序、usize宽度无关，不同机器上的根摘要可以比对
+//! ContentHash为FNV-1a的64位摘要，只用于检测意外的改动；需要抵御伪造时用Sha256Hash(sha2特性)或以其他密码学摘要实现Augment
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use super::{Augment, RedBlackTreeArena, SlotTree, NIL};
+
+/// 摘要输入的规范编码
+/// 整数一律为小端定宽，usize、isize按64位；变长数据前置64位长度；Option前置一个字节的标记
+/// 不使用core::hash::Hash，其编码依赖平台字节序与usize宽度
+pub trait CanonicalEncode {
+    ///把编码逐段写入sink
+    fn encode(&self, sink: &mut dyn FnMut(&[u8]));
+}
+
+macro_rules! encode_integers {
+    ($($int:ty),*) => {$(
+        impl CanonicalEncode for $int {
+            fn encode(&self, sink: &mut dyn FnMut(&[u8])) {
+                sink(&self.to_le_bytes());
+            }
+        }
+    )*};
+}
+
+encode_integers!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128);
+
+impl CanonicalEncode for usize {
+    fn encode(&self, sink: &mut dyn FnMut(&[u8])) {
+        (*self as u64).encode(sink);
+    }
+}
+
+impl CanonicalEncode for isize {
+    fn encode(&self, sink: &mut dyn FnMut(&[u8])) {
+        (*self as i64).encode(sink);
+    }
+}
+
+impl CanonicalEncode for bool {
+    fn encode(&self, sink: &mut dyn FnMut(&[u8])) {
+        sink(&[u8::from(*self)]);
+    }
+}
+
+impl CanonicalEncode for () {
+    fn encode(&self, _: &mut dyn FnMut(&[u8])) {}
+}
+
+impl CanonicalEncode for str {
+    fn encode(&self, sink: &mut dyn FnMut(&[u8])) {
+        self.len().encode(sink);
+        sink(self.as_bytes());
+    }
+}
+
+impl CanonicalEncode for String {
+    fn encode(&self, sink: &mut dyn FnMut(&[u8])) {
+        self.as_str().encode(sink);
+    }
+}
+
+impl<T: CanonicalEncode> CanonicalEncode for [T] {
+    fn encode(&self, sink: &mut dyn FnMut(&[u8])) {
+        self.len().encode(sink);
+        for item in self {
+            item.encode(sink);
+        }
+    }
+}
+
+impl<T: CanonicalEncode> CanonicalEncode for Vec<T> {
+    fn encode(&self, sink: &mut dyn FnMut(&[u8])) {
+        self.as_slice().encode(sink);
+    }
+}
+
+///定长数组不写长度
+impl<T: CanonicalEncode, const N: usize> CanonicalEncode for [T; N] {
+    fn encode(&self, sink: &mut dyn FnMut(&[u8])) {
+        for item in self {
+            item.encode(sink);
+        }
+    }
+}
+
+impl<T: CanonicalEncode> CanonicalEncode for Option<T> {
+    fn encode(&self, sink: &mut dyn FnMut(&[u8])) {
+        match self {
+            None => sink(&[0]),
+            Some(item) => {
+                sink(&[1]);
+                item.encode(sink);
+            }
+        }
+    }
+}
+
+impl<T: CanonicalEncode + ?Sized> CanonicalEncode for &T {
+    fn encode(&self, sink: &mut dyn FnMut(&[u8])) {
+        (**self).encode(sink);
+    }
+}
+
+impl<A: CanonicalEncode, B: CanonicalEncode> CanonicalEncode for (A, B) {
+    fn encode(&self, sink: &mut dyn FnMut(&[u8])) {
+        self.0.encode(sink);
+        self.1.encode(sink);
+    }
+}
+
+/// 以64位非密码学摘要为附加信息
+/// RedBlackTreeArena<K, V, ContentHash>的root_hash即整棵树的指纹
+pub struct ContentHash;
+
+impl<K: CanonicalEncode, V: CanonicalEncode> Augment<K, V> for ContentHash {
+    type Value = u64;
+
+    fn combine(left: Option<&u64>, key: &K, value: &V, right: Option<&u64>) -> u64 {
+        let mut hash = FNV_OFFSET;
+        let mut sink = |bytes: &[u8]| fnv1a(&mut hash, bytes);
+        left.encode(&mut sink);
+        key.encode(&mut sink);
+        value.encode(&mut sink);
+        right.encode(&mut sink);
+        hash
+    }
+}
+
+const FNV_OFFSET: u64 = 0xCBF2_9CE4_8422_2325;
+
+///FNV-1a，把bytes并入hash
+fn fnv1a(hash: &mut u64, bytes: &[u8]) {
+    for byte in bytes {
+        *hash = (*hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01B3);
+    }
+}
+
+/// 以SHA-256摘要为附加信息，可抵御伪造的成员证明
+/// 输入与ContentHash相同，为左右子树摘要与键值的规范编码
+#[cfg(feature = "sha2")]
+pub struct Sha256Hash;
+
+#[cfg(feature = "sha2")]
+impl<K: CanonicalEncode, V: CanonicalEncode> Augment<K, V> for Sha256Hash {
+    type Value = [u8; 32];
+
+    fn combine(left: Option<&[u8; 32]>, key: &K, value: &V, right: Option<&[u8; 32]>) -> [u8; 32] {
+        use sha2::{Digest, Sha256};
+
+        let mut hasher = Sha256::new();
+        let mut sink = |bytes: &[u8]| hasher.update(bytes);
+        left.encode(&mut sink);
+        key.encode(&mut sink);
+        value.encode(&mut sink);
+        right.encode(&mut sink);
+        hasher.finalize().into()
+    }
+}
+
+/// 路径上的一个祖先
+/// from_left为true时下方的节点在其左子树，sibling为另一侧子树的摘要
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct ProofStep<K, V, D> {
+    pub key: K,
+    pub value: V,
+    pub from_left: bool,
+    pub sibling: Option<D>,
+}
+
+/// 成员证明
+/// 节点自身左右子树的摘要，以及由近到远的祖先
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct MembershipProof<K, V, D> {
+    pub left: Option<D>,
+    pub right: Option<D>,
+    pub path: Vec<ProofStep<K, V, D>>,
+}
+
+impl<K, V, D: PartialEq> MembershipProof<K, V, D> {
+    ///由键值与证明重新计算根摘要，与root一致即证明键值对在树中
+    pub fn verify<A: Augment<K, V, Value = D>>(&self, key: &K, value: &V, root: &D) -> bool {
+        let mut digest = A::combine(self.left.as_ref(), key, value, self.right.as_ref());
+        for step in &self.path {
+            digest = if step.from_left {
+                A::combine(Some(&digest), &step.key, &step.value, step.sibling.as_ref())
+            } else {
+                A::combine(step.sibling.as_ref(), &step.key, &step.value, Some(&digest))
+            };
+        }
+        digest == *root
+    }
+}
+
+impl<K: Ord, V, A: Augment<K, V>> RedBlackTreeArena<K, V, A> {
+    ///根摘要，即整棵树的附加信息，空树为None
+    pub fn root_hash(&self) -> Option<&A::Value> {
+        self.augment()
+    }
+
+    ///key的成员证明，键不存在时为None
+    pub fn prove(&self, key: &K) -> Option<MembershipProof<K, V, A::Value>>
+    where
+        K: Clone,
+        V: Clone,
+        A::Value: Clone,
+    {
+        let index = self.find(key);
+        if index == NIL {
+            return None;
+        }
+        let digest_of = |index: u32| (index != NIL).then(|| self.node(index).augment.clone());
+        let node = self.node(index);
+        let mut path = Vec::new();
+        let (mut cur, mut parent) = (index, node.parent);
+        while parent != NIL {
+            let parent_node = self.node(parent);
+            let from_left = parent_node.left == cur;
+            let sibling = if from_left { parent_node.right } else { parent_node.left };
+            path.push(ProofStep { key: parent_node.key.clone(), value: parent_node.value.clone(), from_left, sibling: digest_of(sibling) });
+            cur = parent;
+            parent = parent_node.parent;
+        }
+        Some(MembershipProof { left: digest_of(node.left), right: digest_of(node.right), path })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::String;
+
+    use super::{ContentHash, RedBlackTreeArena};
+
+    #[test]
+    fn proofs_verify_against_root_hash() {
+        let mut tree = RedBlackTreeArena::<u32, u64, ContentHash>::with_augment();
+        for key in (0..200).rev() {
+            tree.insert(key, u64::from(key) * 7);
+        }
+        for key in (0..200).step_by(3) {
+            tree.remove(&key);
+        }
+        assert_eq!(tree.validate_augment(), Ok(()));
+        let root = *tree.root_hash().unwrap();
+        for key in (1..200).filter(|key| key % 3 != 0) {
+            let proof = tree.prove(&key).unwrap();
+            assert!(proof.verify::<ContentHash>(&key, &(u64::from(key) * 7), &root));
+            assert!(!proof.verify::<ContentHash>(&key, &0, &root));
+        }
+        assert!(tree.prove(&3).is_none());
+        //值改动后根摘要随之改变
+        tree.update(&1, |value| *value += 1);
+        assert_ne!(*tree.root_hash().unwrap(), root);
+    }
+
+    #[test]
+    fn equal_history_gives_equal_root_hash() {
+        let build = || {
+            let mut tree = RedBlackTreeArena::<u32, u32, ContentHash>::with_augment();
+            for key in 0..50 {
+                tree.insert(key * 37 % 50, key);
+            }
+            tree
+        };
+        assert_eq!(build().root_hash(), build().root_hash());
+        assert!(RedBlackTreeArena::<u32, u32, ContentHash>::with_augment().root_hash().is_none());
+    }
+
+    ///规范编码与平台无关，固定内容的根摘要是常量
+    #[test]
+    fn root_hash_is_stable_across_platforms() {
+        let mut tree = RedBlackTreeArena::<usize, String, ContentHash>::with_augment();
+        for key in 0..3 {
+            tree.insert(key, String::from("v"));
+        }
+        assert_eq!(tree.root_hash(), Some(&ROOT_HASH));
+    }
+
+    ///按规范编码手工计算：根为1，左右子节点为0与2
+    const ROOT_HASH: u64 = 1_210_557_905_191_441_572;
+
+    #[cfg(feature = "sha2")]
+    #[test]
+    fn sha256_proofs() {
+        use super::Sha256Hash;
+
+        let mut tree = RedBlackTreeArena::<u32, String, Sha256Hash>::with_augment();
+        for key in 0..64 {
+            tree.insert(key, alloc::format!("value {}", key));
+        }
+        let root = *tree.root_hash().unwrap();
+        let proof = tree.prove(&17).unwrap();
+        assert!(proof.verify::<Sha256Hash>(&17, &String::from("value 17"), &root));
+        assert!(!proof.verify::<Sha256Hash>(&17, &String::from("value 18"), &root));
+    }
+}
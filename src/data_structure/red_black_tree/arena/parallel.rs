@@ -0,0 +1,195 @@
+//! 并行迭代(rayon)
+//! 把待迭代的范围表示为有序的若干片段：单个节点或整棵子树
+//! 拆分时片段多于一个则对半分，只剩一棵子树时在其根处拆为左子树加根、右子树两部分
+//! 红黑树平衡，拆分得到的各部分大小相近
+//! 并行构造先并行排序，再以from_sorted_vec在O(n)内整体构造
+
+use std::ops::{Bound, RangeBounds};
+
+use rayon::iter::plumbing::{bridge_unindexed, Folder, UnindexedConsumer, UnindexedProducer};
+use rayon::iter::{FromParallelIterator, IntoParallelIterator, ParallelIterator};
+use rayon::slice::ParallelSliceMut;
+
+use super::{Augment, RedBlackTreeArena, SlotTree, NIL};
+
+///迭代范围的一个片段，按中序排列
+#[derive(Clone, Copy)]
+enum Piece {
+    Node(u32),
+    Subtree(u32),
+}
+
+/// 并行迭代器，产出借用，顺序敏感的操作(如collect)保持键的升序
+pub struct ArenaParIter<'a, K, V, A: Augment<K, V> = ()> {
+    tree: &'a RedBlackTreeArena<K, V, A>,
+    pieces: Vec<Piece>,
+}
+
+impl<K: Ord + Sync, V: Sync, A: Augment<K, V>> RedBlackTreeArena<K, V, A>
+where
+    A::Value: Sync,
+{
+    ///并行迭代全部键值对
+    pub fn par_iter(&self) -> ArenaParIter<'_, K, V, A> {
+        self.par_range(..)
+    }
+
+    /// 并行迭代范围内的键值对
+    /// 范围被分解为O(log n)个节点与子树，起点大于终点时为空
+    pub fn par_range<R: RangeBounds<K>>(&self, range: R) -> ArenaParIter<'_, K, V, A> {
+        let mut pieces = Vec::new();
+        self.cover(self.links.root, range.start_bound(), range.end_bound(), &mut pieces);
+        ArenaParIter { tree: self, pieces }
+    }
+
+    ///把index子树中位于范围内的部分按中序分解为片段
+    fn cover(&self, index: u32, start: Bound<&K>, end: Bound<&K>, pieces: &mut Vec<Piece>) {
+        if index == NIL {
+            return;
+        }
+        if let (Bound::Unbounded, Bound::Unbounded) = (start, end) {
+            pieces.push(Piece::Subtree(index));
+            return;
+        }
+        let node = self.node(index);
+        let after_start = match start {
+            Bound::Included(start) => node.key >= *start,
+            Bound::Excluded(start) => node.key > *start,
+            Bound::Unbounded => true,
+        };
+        let before_end = match end {
+            Bound::Included(end) => node.key <= *end,
+            Bound::Excluded(end) => node.key < *end,
+            Bound::Unbounded => true,
+        };
+        if !after_start {
+            self.cover(node.right, start, end, pieces);
+        } else if !before_end {
+            self.cover(node.left, start, end, pieces);
+        } else {
+            //左子树都小于终点，右子树都大于起点
+            self.cover(node.left, start, Bound::Unbounded, pieces);
+            pieces.push(Piece::Node(index));
+            self.cover(node.right, Bound::Unbounded, end, pieces);
+        }
+    }
+}
+
+impl<'a, K: Ord + Sync, V: Sync, A: Augment<K, V>> ParallelIterator for ArenaParIter<'a, K, V, A>
+where
+    A::Value: Sync,
+{
+    type Item = (&'a K, &'a V);
+
+    fn drive_unindexed<C: UnindexedConsumer<Self::Item>>(self, consumer: C) -> C::Result {
+        bridge_unindexed(self, consumer)
+    }
+}
+
+impl<'a, K: Ord + Sync, V: Sync, A: Augment<K, V>> UnindexedProducer for ArenaParIter<'a, K, V, A>
+where
+    A::Value: Sync,
+{
+    type Item = (&'a K, &'a V);
+
+    fn split(mut self) -> (Self, Option<Self>) {
+        if let [Piece::Subtree(index)] = self.pieces[..] {
+            let node = self.tree.node(index);
+            self.pieces.clear();
+            if node.left != NIL {
+                self.pieces.push(Piece::Subtree(node.left));
+            }
+            self.pieces.push(Piece::Node(index));
+            let right = (node.right != NIL).then(|| ArenaParIter { tree: self.tree, pieces: vec![Piece::Subtree(node.right)] });
+            return (self, right);
+        }
+        if self.pieces.len() < 2 {
+            return (self, None);
+        }
+        let back = self.pieces.split_off(self.pieces.len() / 2);
+        let tree = self.tree;
+        (self, Some(ArenaParIter { tree, pieces: back }))
+    }
+
+    fn fold_with<F: Folder<Self::Item>>(self, mut folder: F) -> F {
+        let tree = self.tree;
+        let mut stack = Vec::new();
+        for piece in self.pieces {
+            match piece {
+                Piece::Node(index) => folder = folder.consume(tree.entry(index)),
+                Piece::Subtree(root) => {
+                    //中序遍历子树
+                    let mut cur = root;
+                    loop {
+                        while cur != NIL {
+                            stack.push(cur);
+                            cur = tree.node(cur).left;
+                        }
+                        let Some(index) = stack.pop() else { break };
+                        folder = folder.consume(tree.entry(index));
+                        if folder.full() {
+                            return folder;
+                        }
+                        cur = tree.node(index).right;
+                    }
+                }
+            }
+            if folder.full() {
+                break;
+            }
+        }
+        folder
+    }
+}
+
+/// 由无序的并行迭代器构造
+/// 稳定的并行排序后去除重复键，与逐个insert相同保留先出现的值
+impl<K: Ord + Send, V: Send, A: Augment<K, V>> FromParallelIterator<(K, V)> for RedBlackTreeArena<K, V, A> {
+    fn from_par_iter<I: IntoParallelIterator<Item = (K, V)>>(par_iter: I) -> Self {
+        let mut entries: Vec<(K, V)> = par_iter.into_par_iter().collect();
+        entries.par_sort_by(|left, right| left.0.cmp(&right.0));
+        entries.dedup_by(|later, earlier| later.0 == earlier.0);
+        Self::from_sorted_vec(entries)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rayon::iter::{FromParallelIterator, IntoParallelIterator, ParallelIterator};
+
+    use super::RedBlackTreeArena;
+
+    #[test]
+    fn matches_sequential_iteration() {
+        let mut tree = RedBlackTreeArena::new();
+        for key in 0..10_000u64 {
+            tree.insert(key * 7 % 10_007, key);
+        }
+        let parallel: Vec<_> = tree.par_iter().collect();
+        let sequential: Vec<_> = tree.iter().collect();
+        assert_eq!(parallel, sequential);
+        assert_eq!(tree.par_iter().map(|(_, value)| *value).sum::<u64>(), (0..10_000u64).sum::<u64>());
+        for (start, end) in [(0, 10_007), (100, 200), (5_000, 5_000), (9_000, 20_000), (300, 100)] {
+            let parallel: Vec<_> = tree.par_range(start..end).collect();
+            let sequential: Vec<_> = tree.iter().filter(|(key, _)| (start..end).contains(*key)).collect();
+            assert_eq!(parallel, sequential);
+        }
+        assert_eq!(tree.par_range(..=50).count(), tree.iter().take_while(|(key, _)| **key <= 50).count());
+        assert_eq!(RedBlackTreeArena::<u64, u64>::new().par_iter().count(), 0);
+    }
+
+    #[test]
+    fn parallel_build_keeps_first_duplicate() {
+        let entries: Vec<_> = (0..50_000u64).map(|index| (index * 7_919 % 20_011, index)).collect();
+        let tree: RedBlackTreeArena<u64, u64> = RedBlackTreeArena::from_par_iter(entries.clone().into_par_iter());
+        let mut expected = RedBlackTreeArena::new();
+        for (key, value) in entries {
+            expected.insert(key, value);
+        }
+        assert_eq!(tree.validate(), Ok(()));
+        assert_eq!(tree.len(), expected.len());
+        assert!(tree.iter().eq(expected.iter()));
+        let collected: RedBlackTreeArena<u64, ()> = (0..1_000u64).into_par_iter().map(|key| (key / 2, ())).collect();
+        assert!(collected.iter().map(|(key, _)| *key).eq(0..500));
+    }
+}
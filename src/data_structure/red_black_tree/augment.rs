@@ -0,0 +1,18 @@
+//! 节点附加信息
+//! 用户实现Augment，RedBlackTreeArena在插入、删除与旋转时沿受影响的节点重新计算
+//! 可在现有的平衡逻辑之上构建区间树、求和树、最值树等
+
+/// 由左右子树的附加信息与节点本身计算节点的附加信息
+/// 子树为空时传入None
+pub trait Augment<K, V> {
+    type Value;
+
+    fn combine(left: Option<&Self::Value>, key: &K, value: &V, right: Option<&Self::Value>) -> Self::Value;
+}
+
+///不附加信息，RedBlackTreeArena的默认参数
+impl<K, V> Augment<K, V> for () {
+    type Value = ();
+
+    fn combine(_left: Option<&()>, _key: &K, _value: &V, _right: Option<&()>) {}
+}
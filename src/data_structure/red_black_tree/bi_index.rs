@@ -0,0 +1,177 @@
+//! 双索引映射
+//! 主树按键存放(次键, 值)，次索引树按(次键, 键)排序，值只存一份
+//! 插入、删除、修改次键时同时维护两棵树；次键可以重复，次键相同时按键排序
+
+use alloc::rc::Rc;
+use core::cell::RefCell;
+use core::ops::{Bound, RangeBounds};
+
+use super::{Iter, Node, RedBlackTree};
+
+///次索引树的节点
+type IndexNodeRef<K, S> = Rc<RefCell<Node<(S, K), ()>>>;
+
+/// 可按主键或次键做范围查询的映射
+pub struct BiIndexedMap<K, S, V> {
+    primary: RedBlackTree<K, (S, V)>,
+    secondary: RedBlackTree<(S, K), ()>,
+}
+
+impl<K: Ord + Clone, S: Ord + Clone, V> BiIndexedMap<K, S, V> {
+    pub fn new() -> Self {
+        BiIndexedMap { primary: RedBlackTree::new(), secondary: RedBlackTree::new() }
+    }
+
+    ///插入条目，键已存在时替换并返回原有的次键与值
+    pub fn insert(&mut self, key: K, secondary: S, value: V) -> Option<(S, V)> {
+        let old = self.primary.insert_or_replace(key.clone(), (secondary.clone(), value));
+        if let Some((old_secondary, _)) = &old {
+            self.secondary.delete(&(old_secondary.clone(), key.clone()));
+        }
+        self.secondary.insert((secondary, key), ());
+        old
+    }
+
+    ///删除条目，返回其次键与值
+    pub fn remove(&mut self, key: &K) -> Option<(S, V)> {
+        let (key, (secondary, value)) = self.primary.take(key)?;
+        let index_key = (secondary, key);
+        self.secondary.delete(&index_key);
+        Some((index_key.0, value))
+    }
+
+    ///修改条目的次键，返回原次键，键不存在时不做任何事
+    pub fn set_secondary(&mut self, key: &K, secondary: S) -> Option<S> {
+        let old = self.primary.update(key, |(current, _)| core::mem::replace(current, secondary.clone()))?;
+        self.secondary.delete(&(old.clone(), key.clone()));
+        self.secondary.insert((secondary, key.clone()), ());
+        Some(old)
+    }
+
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.primary.contains(key)
+    }
+
+    pub fn len(&self) -> usize {
+        self.primary.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.primary.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.primary.clear();
+        self.secondary.clear();
+    }
+
+    ///满足次键下界的最小索引节点
+    fn secondary_lower_bound(&self, bound: Bound<&S>) -> Option<IndexNodeRef<K, S>> {
+        let mut result = None;
+        let mut next_option = self.secondary.root.clone();
+        while let Some(cur_rc) = next_option {
+            let cur = cur_rc.borrow();
+            let satisfied = match bound {
+                Bound::Included(secondary) => cur.key.0 >= *secondary,
+                Bound::Excluded(secondary) => cur.key.0 > *secondary,
+                Bound::Unbounded => true,
+            };
+            if satisfied {
+                next_option = cur.left.clone();
+                drop(cur);
+                result = Some(cur_rc);
+            } else {
+                next_option = cur.right.clone();
+            }
+        }
+        result
+    }
+}
+
+impl<K: Ord + Clone, S: Ord + Clone, V: Clone> BiIndexedMap<K, S, V> {
+    ///键对应的次键与值
+    pub fn get(&self, key: &K) -> Option<(S, V)> {
+        self.primary.get(key)
+    }
+
+    ///按键升序迭代键范围内的条目
+    pub fn range_by_key<R: RangeBounds<K>>(&self, range: R) -> Iter<'_, K, (S, V)> {
+        self.primary.range(range)
+    }
+
+    /// 按次键升序迭代次键范围内的条目，次键相同时按键升序
+    /// 每个条目的值需回到主树查找，O(log n + m log n)
+    pub fn range_by_secondary<R: RangeBounds<S>>(&self, range: R) -> SecondaryRange<'_, K, S, V> {
+        SecondaryRange { next: self.secondary_lower_bound(range.start_bound()), end: range.end_bound().cloned(), map: self }
+    }
+}
+
+/// 按次键顺序的范围迭代器，产出与主键迭代相同形式的(键, (次键, 值))
+pub struct SecondaryRange<'a, K, S, V> {
+    next: Option<IndexNodeRef<K, S>>,
+    end: Bound<S>,
+    map: &'a BiIndexedMap<K, S, V>,
+}
+
+impl<K: Ord + Clone, S: Ord + Clone, V: Clone> Iterator for SecondaryRange<'_, K, S, V> {
+    type Item = (K, (S, V));
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node_rc = self.next.take()?;
+        let (secondary, key) = node_rc.borrow().key.clone();
+        if !(Bound::Unbounded, self.end.as_ref()).contains(&secondary) {
+            return None;
+        }
+        self.next = RedBlackTree::successor(&node_rc);
+        let entry = self.map.primary.get(&key).expect("secondary index out of sync with primary");
+        Some((key, entry))
+    }
+}
+
+impl<K: Ord + Clone, S: Ord + Clone, V> Default for BiIndexedMap<K, S, V> {
+    fn default() -> Self {
+        BiIndexedMap::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+    use std::collections::BTreeMap;
+
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    use super::BiIndexedMap;
+
+    ///订单按编号与价格双索引
+    #[test]
+    fn both_indexes_stay_consistent() {
+        let mut rng = StdRng::seed_from_u64(5);
+        let mut orders = BiIndexedMap::new();
+        let mut model: BTreeMap<u32, (u32, u64)> = BTreeMap::new();
+        for round in 0..3000u64 {
+            let id = rng.gen_range(0..200u32);
+            let price = rng.gen_range(0..50u32);
+            match rng.gen_range(0..4) {
+                0 | 1 => assert_eq!(orders.insert(id, price, round), model.insert(id, (price, round))),
+                2 => assert_eq!(orders.remove(&id), model.remove(&id)),
+                _ => {
+                    let expected = model.get_mut(&id).map(|entry| core::mem::replace(&mut entry.0, price));
+                    assert_eq!(orders.set_secondary(&id, price), expected);
+                }
+            }
+            if round % 100 == 0 {
+                let low = rng.gen_range(0..50u32);
+                let high = rng.gen_range(low..50);
+                let mut expected: Vec<_> = model.iter().filter(|(_, (price, _))| (low..=high).contains(price)).map(|(id, entry)| (*id, *entry)).collect();
+                expected.sort_by_key(|(id, (price, _))| (*price, *id));
+                assert!(orders.range_by_secondary(low..=high).eq(expected));
+                assert!(orders.range_by_key(low * 4..high * 4).eq(model.range(low * 4..high * 4).map(|(id, entry)| (*id, *entry))));
+            }
+        }
+        assert_eq!(orders.len(), model.len());
+        assert_eq!(orders.secondary.len(), model.len());
+        assert_eq!(orders.secondary.validate(), Ok(()));
+    }
+}
@@ -0,0 +1,184 @@
+//! 紧凑的二进制快照
+//! 格式：节点数量(u64)，随后按先序每个节点一个标志字节，重复次数大于1时跟随u64的重复次数，再跟随键、值
+//! 标志字节：bit0红色，bit1有左子节点，bit2有右子节点，bit3带重复次数；整数均为小端序，键值以borsh编码
+//! 加载时按原结构直接链接节点，不做比较与旋转，只在最后校验一次红黑树性质
+//! 大量数据时应传入BufReader、BufWriter
+
+use alloc::rc::Rc;
+use core::cell::RefCell;
+use core::fmt::Debug;
+use std::io::{self, Read, Write};
+
+use borsh::{BorshDeserialize, BorshSerialize};
+
+use super::{Color, Node, RedBlackTree};
+
+const RED: u8 = 1;
+const LEFT: u8 = 1 << 1;
+const RIGHT: u8 = 1 << 2;
+const COUNTED: u8 = 1 << 3;
+
+///待填充的位置
+enum Slot<K, V> {
+    Root,
+    Left(Rc<RefCell<Node<K, V>>>),
+    Right(Rc<RefCell<Node<K, V>>>),
+}
+
+fn invalid_data(message: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+impl<K: Ord + BorshSerialize, V: BorshSerialize> RedBlackTree<K, V> {
+    ///按先序写出二进制快照
+    pub fn serialize_to<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        (self.len as u64).serialize(writer)?;
+        let mut stack: Vec<Rc<RefCell<Node<K, V>>>> = self.root.iter().cloned().collect();
+        while let Some(cur_rc) = stack.pop() {
+            let cur = cur_rc.borrow();
+            let mut flags = 0;
+            if cur.color == Color::Red {
+                flags |= RED;
+            }
+            if cur.left.is_some() {
+                flags |= LEFT;
+            }
+            if cur.right.is_some() {
+                flags |= RIGHT;
+            }
+            if cur.count > 1 {
+                flags |= COUNTED;
+            }
+            flags.serialize(writer)?;
+            if cur.count > 1 {
+                (cur.count as u64).serialize(writer)?;
+            }
+            cur.key.serialize(writer)?;
+            cur.value.serialize(writer)?;
+            //右子节点先入栈，左子节点先出栈
+            stack.extend(cur.right.iter().cloned());
+            stack.extend(cur.left.iter().cloned());
+        }
+        Ok(())
+    }
+}
+
+impl<K: Ord + Clone + Debug + BorshDeserialize, V: BorshDeserialize> RedBlackTree<K, V> {
+    /// 读取serialize_to写出的快照，原样重建树
+    /// 结构不完整、过深或违反红黑树性质时返回InvalidData
+    pub fn deserialize_from<R: Read>(reader: &mut R) -> io::Result<Self> {
+        let len = usize::try_from(u64::deserialize_reader(reader)?).map_err(invalid_data)?;
+        let max_depth = 2 * (usize::BITS - len.leading_zeros()) as usize;
+        let mut tree = RedBlackTree::new();
+        //先序创建的节点，逆序即可自底向上计算子树节点数量；数量来自输入，预留的容量设上限
+        let mut nodes = Vec::with_capacity(len.min(1 << 20));
+        let mut slots = vec![(Slot::Root, 1)];
+        for _ in 0..len {
+            let (slot, depth) = slots.pop().ok_or_else(|| invalid_data("more nodes than the structure references"))?;
+            if depth > max_depth {
+                return Err(invalid_data("structure is too deep for a red-black tree"));
+            }
+            let flags = u8::deserialize_reader(reader)?;
+            if flags & !(RED | LEFT | RIGHT | COUNTED) != 0 {
+                return Err(invalid_data("unknown node flags"));
+            }
+            let count = if flags & COUNTED != 0 { usize::try_from(u64::deserialize_reader(reader)?).map_err(invalid_data)? } else { 1 };
+            if count == 0 {
+                return Err(invalid_data("node count must be at least 1"));
+            }
+            let key = K::deserialize_reader(reader)?;
+            let value = V::deserialize_reader(reader)?;
+            let color = if flags & RED != 0 { Color::Red } else { Color::Black };
+            let node_rc = Rc::new(RefCell::new(Node { key, value, parent: None, left: None, right: None, color, size: 1, count }));
+            match slot {
+                Slot::Root => tree.root = Some(Rc::clone(&node_rc)),
+                Slot::Left(parent_rc) => {
+                    node_rc.borrow_mut().parent = Some(Rc::downgrade(&parent_rc));
+                    parent_rc.borrow_mut().left = Some(Rc::clone(&node_rc));
+                }
+                Slot::Right(parent_rc) => {
+                    node_rc.borrow_mut().parent = Some(Rc::downgrade(&parent_rc));
+                    parent_rc.borrow_mut().right = Some(Rc::clone(&node_rc));
+                }
+            }
+            if flags & RIGHT != 0 {
+                slots.push((Slot::Right(Rc::clone(&node_rc)), depth + 1));
+            }
+            if flags & LEFT != 0 {
+                slots.push((Slot::Left(Rc::clone(&node_rc)), depth + 1));
+            }
+            nodes.push(node_rc);
+        }
+        if !slots.is_empty() && len > 0 {
+            return Err(invalid_data("structure references missing nodes"));
+        }
+        tree.len = len;
+        for node_rc in nodes.iter().rev() {
+            let mut node = node_rc.borrow_mut();
+            node.size = RedBlackTree::size_of(&node.left) + RedBlackTree::size_of(&node.right) + 1;
+        }
+        tree.validate().map_err(|violation| invalid_data(violation.to_string()))?;
+        Ok(tree)
+    }
+}
+
+impl<K: Ord + BorshSerialize, V: BorshSerialize> BorshSerialize for RedBlackTree<K, V> {
+    fn serialize<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        self.serialize_to(writer)
+    }
+}
+
+impl<K: Ord + Clone + Debug + BorshDeserialize, V: BorshDeserialize> BorshDeserialize for RedBlackTree<K, V> {
+    fn deserialize_reader<R: Read>(reader: &mut R) -> io::Result<Self> {
+        Self::deserialize_from(reader)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{DuplicatePolicy, RedBlackTree};
+
+    #[test]
+    fn round_trip_keeps_structure() {
+        let mut tree = RedBlackTree::new();
+        for key in (0..500u32).map(|key| key * 7919 % 500) {
+            tree.insert(key, key.to_string());
+        }
+        for key in (0..500).step_by(3) {
+            tree.delete(&key);
+        }
+        let mut bytes = Vec::new();
+        tree.serialize_to(&mut bytes).unwrap();
+        let loaded = RedBlackTree::<u32, String>::deserialize_from(&mut bytes.as_slice()).unwrap();
+        assert!(loaded.structural_eq(&tree));
+        assert_eq!(borsh::to_vec(&loaded).unwrap(), bytes);
+
+        let mut empty = Vec::new();
+        RedBlackTree::<u32, String>::new().serialize_to(&mut empty).unwrap();
+        assert!(RedBlackTree::<u32, String>::deserialize_from(&mut empty.as_slice()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn round_trip_keeps_counts() {
+        let mut tree = RedBlackTree::with_duplicate_policy(DuplicatePolicy::Count);
+        for key in [3u8, 1, 3, 2, 3, 1] {
+            tree.insert(key, ());
+        }
+        let bytes = borsh::to_vec(&tree).unwrap();
+        let loaded: RedBlackTree<u8, ()> = borsh::from_slice(&bytes).unwrap();
+        assert!(loaded.structural_eq(&tree));
+    }
+
+    #[test]
+    fn rejects_malformed_input() {
+        //红色根节点
+        let red_root = [1, 0, 0, 0, 0, 0, 0, 0, 1, 7];
+        assert!(RedBlackTree::<u8, ()>::deserialize_from(&mut red_root.as_slice()).is_err());
+        //缺少引用的子节点
+        let missing_child = [1, 0, 0, 0, 0, 0, 0, 0, 2, 7];
+        assert!(RedBlackTree::<u8, ()>::deserialize_from(&mut missing_child.as_slice()).is_err());
+        //数据截断
+        let truncated = [2, 0, 0, 0, 0, 0, 0, 0, 2, 7];
+        assert!(RedBlackTree::<u8, ()>::deserialize_from(&mut truncated.as_slice()).is_err());
+    }
+}
@@ -0,0 +1,254 @@
+//! 由有序输入批量构造
+//! 取中点为根递归构造完全平衡的树，叶子只分布在最深两层，最深一层染红，无需任何旋转
+//! retain、extract_if等批量删除也先拆下节点，再以同样的方式整体重建
+//! 条件闭包panic时，剩余节点在迭代器析构时照常重建，树保持合法且长度正确
+
+use alloc::rc::Rc;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+
+use super::{Color, IntoIter, Node, RedBlackTree};
+
+impl<K: Ord, V> RedBlackTree<K, V> {
+    /// 由严格升序的键值对构造，O(n)
+    /// 输入不是严格升序时panic
+    pub fn from_sorted_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        Self::from_sorted_vec(iter.into_iter().collect())
+    }
+
+    ///同from_sorted_iter
+    pub fn from_sorted_vec(entries: Vec<(K, V)>) -> Self {
+        assert!(entries.windows(2).all(|pair| pair[0].0 < pair[1].0), "input is not strictly ascending");
+        let nodes = entries.into_iter().map(|(key, value)| {
+            Rc::new(RefCell::new(Node {
+                key,
+                value,
+                parent: None,
+                left: None,
+                right: None,
+                color: Color::Black,
+                size: 1,
+                count: 1,
+            }))
+        });
+        let mut tree = RedBlackTree::new();
+        tree.rebuild(nodes.collect());
+        tree
+    }
+
+    /// 只保留满足条件的键值对，条件可以修改值
+    /// 一次中序遍历拆下全部节点，过滤后整体重建，O(n)，无需逐个删除调平
+    pub fn retain<F: FnMut(&K, &mut V) -> bool>(&mut self, mut keep: F) {
+        self.extract_if(|key, value| !keep(key, value)).for_each(drop);
+    }
+
+    /// 清空树，按键升序产出全部键值对
+    /// 与Vec::drain不同，调用时即拆下全部节点并清空树，即使迭代器被mem::forget树也已为空；
+    /// 未消费的键值对随迭代器一起释放
+    pub fn drain(&mut self) -> IntoIter<K, V> {
+        self.len = 0;
+        IntoIter::new(Self::take_nodes_in_order(self.root.take()))
+    }
+
+    /// 删除满足条件的键值对并按键升序产出，条件可以修改值
+    /// 与BTreeMap::extract_if相同，惰性求值：每次next才对下一个节点调用条件，迭代器析构时未访问的节点全部保留，
+    /// 剩余节点在析构时整体重建，O(n)；条件panic时正在判断的节点也保留
+    /// 迭代期间树处于拆开的状态，迭代器被mem::forget时树为空
+    pub fn extract_if<F: FnMut(&K, &mut V) -> bool>(&mut self, extract: F) -> ExtractIf<'_, K, V, F> {
+        let rest = Self::take_nodes_in_order(self.root.take());
+        self.len = 0;
+        ExtractIf { kept: Vec::with_capacity(rest.len()), rest: rest.into_iter(), tree: self, extract }
+    }
+
+    ///以升序排列的孤立节点重建为完全平衡的树
+    fn rebuild(&mut self, nodes: Vec<Rc<RefCell<Node<K, V>>>>) {
+        let len = nodes.len();
+        //最深一层的深度(根为0)，单节点时根保持黑色
+        let red_depth = match len {
+            0 | 1 => usize::MAX,
+            _ => (usize::BITS - 1 - len.leading_zeros()) as usize,
+        };
+        let mut nodes = nodes.into_iter();
+        self.root = Self::build_balanced(&mut nodes, len, 0, red_depth);
+        self.len = len;
+    }
+
+    ///中序拆下全部节点，节点间的连接全部断开
+    pub(super) fn take_nodes_in_order(root_option: Option<Rc<RefCell<Node<K, V>>>>) -> Vec<Rc<RefCell<Node<K, V>>>> {
+        let mut nodes = Vec::new();
+        let mut stack = Vec::new();
+        let mut next_option = root_option;
+        loop {
+            //沿左边缘入栈
+            while let Some(cur_rc) = next_option {
+                next_option = cur_rc.borrow_mut().left.take();
+                stack.push(cur_rc);
+            }
+            let Some(cur_rc) = stack.pop() else {
+                break;
+            };
+            {
+                let mut cur = cur_rc.borrow_mut();
+                next_option = cur.right.take();
+                cur.parent = None;
+            }
+            nodes.push(cur_rc);
+        }
+        nodes
+    }
+
+    ///中序消费count个节点，构造子树并返回其根
+    fn build_balanced(nodes: &mut alloc::vec::IntoIter<Rc<RefCell<Node<K, V>>>>, count: usize, depth: usize, red_depth: usize) -> Option<Rc<RefCell<Node<K, V>>>> {
+        if count == 0 {
+            return None;
+        }
+        let left_count = (count - 1) / 2;
+        let left_option = Self::build_balanced(nodes, left_count, depth + 1, red_depth);
+        let node_rc = nodes.next()?;
+        let right_option = Self::build_balanced(nodes, count - 1 - left_count, depth + 1, red_depth);
+        for son_ref in [&left_option, &right_option].into_iter().flatten() {
+            son_ref.borrow_mut().parent = Some(Rc::downgrade(&node_rc));
+        }
+        {
+            let mut node = node_rc.borrow_mut();
+            node.left = left_option;
+            node.right = right_option;
+            node.color = if depth == red_depth { Color::Red } else { Color::Black };
+            node.size = count;
+        }
+        Some(node_rc)
+    }
+}
+
+/// extract_if返回的迭代器，按键升序产出被删除的键值对
+/// 析构时以保留的与未访问的节点重建树
+pub struct ExtractIf<'a, K: Ord, V, F: FnMut(&K, &mut V) -> bool> {
+    tree: &'a mut RedBlackTree<K, V>,
+    //尚未判断的节点，升序
+    rest: alloc::vec::IntoIter<Rc<RefCell<Node<K, V>>>>,
+    //判断为保留的节点，升序
+    kept: Vec<Rc<RefCell<Node<K, V>>>>,
+    extract: F,
+}
+
+impl<K: Ord, V, F: FnMut(&K, &mut V) -> bool> Iterator for ExtractIf<'_, K, V, F> {
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            //先判断再取出，条件panic时节点仍在rest中
+            let extracted = {
+                let mut node = self.rest.as_slice().first()?.borrow_mut();
+                let node = &mut *node;
+                (self.extract)(&node.key, &mut node.value)
+            };
+            let node_rc = self.rest.next()?;
+            if extracted {
+                return Some(IntoIter::entry_of(node_rc));
+            }
+            self.kept.push(node_rc);
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.rest.len()))
+    }
+}
+
+impl<K: Ord, V, F: FnMut(&K, &mut V) -> bool> Drop for ExtractIf<'_, K, V, F> {
+    fn drop(&mut self) {
+        let mut nodes = core::mem::take(&mut self.kept);
+        nodes.extend(self.rest.by_ref());
+        self.tree.rebuild(nodes);
+        self.tree.sample_validation();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+
+    use super::super::RedBlackTree;
+
+    #[test]
+    fn sorted_construction_is_valid() {
+        for n in 0..300 {
+            let tree = RedBlackTree::from_sorted_iter((0..n).map(|key| (key, key * 2)));
+            assert_eq!(tree.validate(), Ok(()), "n={}", n);
+            assert_eq!(tree.len(), n as usize);
+            assert!(tree.iter().eq((0..n).map(|key| (key, key * 2))));
+        }
+    }
+
+    #[test]
+    fn retain_rebuilds_valid_tree() {
+        for n in 0..200 {
+            let mut tree = RedBlackTree::new();
+            for key in (0..n).rev() {
+                tree.insert(key, key);
+            }
+            tree.retain(|key, value| {
+                *value += 1;
+                key % 3 != 0
+            });
+            assert_eq!(tree.validate(), Ok(()), "n={}", n);
+            assert!(tree.iter().eq((0..n).filter(|key| key % 3 != 0).map(|key| (key, key + 1))));
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "strictly ascending")]
+    fn rejects_unsorted_input() {
+        RedBlackTree::from_sorted_vec(vec![(2, ()), (1, ())]);
+    }
+
+    #[test]
+    fn extract_if_and_drain() {
+        for n in 0..100 {
+            let mut tree = RedBlackTree::from_sorted_iter((0..n).map(|key| (key, key)));
+            let extracted: Vec<_> = tree.extract_if(|key, value| {
+                *value *= 2;
+                key % 3 == 0
+            }).collect();
+            assert!(extracted.into_iter().eq((0..n).filter(|key| key % 3 == 0).map(|key| (key, key * 2))));
+            assert_eq!(tree.validate(), Ok(()), "n={}", n);
+            let drained: Vec<_> = tree.drain().collect();
+            assert!(drained.into_iter().eq((0..n).filter(|key| key % 3 != 0).map(|key| (key, key * 2))));
+            assert!(tree.is_empty());
+            assert_eq!(tree.validate(), Ok(()));
+        }
+    }
+
+    #[test]
+    fn extract_if_is_lazy() {
+        let mut tree = RedBlackTree::from_sorted_iter((0..10).map(|key| (key, key)));
+        let mut visited = 0;
+        let first: Vec<_> = tree.extract_if(|key, _| {
+            visited += 1;
+            key % 2 == 1
+        }).take(2).collect();
+        assert_eq!((first, visited), (vec![(1, 1), (3, 3)], 4));
+        //未访问的节点保留
+        assert_eq!(tree.len(), 8);
+        assert!(tree.iter().map(|(key, _)| key).eq([0, 2, 4, 5, 6, 7, 8, 9]));
+        assert_eq!(tree.validate(), Ok(()));
+    }
+
+    #[test]
+    fn panicking_predicate_keeps_remaining_nodes() {
+        let mut tree = RedBlackTree::from_sorted_iter((0..20).map(|key| (key, key)));
+        let result = catch_unwind(AssertUnwindSafe(|| {
+            tree.extract_if(|key, _| {
+                assert!(*key != 10, "predicate panicked");
+                key % 3 == 0
+            }).for_each(drop);
+        }));
+        assert!(result.is_err());
+        assert_eq!(tree.validate(), Ok(()));
+        assert!(tree.iter().map(|(key, _)| key).eq((0..20).filter(|key| *key >= 10 || key % 3 != 0)));
+        let result = catch_unwind(AssertUnwindSafe(|| tree.retain(|key, _| if *key == 15 { panic!("predicate panicked") } else { key % 2 == 0 })));
+        assert!(result.is_err());
+        assert_eq!(tree.validate(), Ok(()));
+        assert!(tree.iter().map(|(key, _)| key).eq((0..20).filter(|key| (*key >= 10 || key % 3 != 0) && (*key >= 15 || key % 2 == 0))));
+    }
+}
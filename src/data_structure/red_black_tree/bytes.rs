@@ -0,0 +1,53 @@
+//! 字节串键
+//! 以Vec<u8>为键时，get、contains、delete等方法可直接用&[u8]查找，无需分配临时的Vec
+//! 切片比较逐字节进行，遇到第一个不同字节即返回，兆字节级的键只在公共前缀很长时才会比较较多字节
+
+use alloc::vec::Vec;
+
+use super::RedBlackTree;
+
+impl<V> RedBlackTree<Vec<u8>, V> {
+    /// 所有键占用的堆内存字节数(按容量计)
+    /// 需要遍历全部节点，O(n)
+    pub fn key_heap_bytes(&self) -> usize {
+        let mut total = 0;
+        let mut next_option = self.root.as_ref().map(Self::find_minimum);
+        while let Some(cur_rc) = next_option {
+            total += cur_rc.borrow().key.capacity();
+            next_option = Self::successor(&cur_rc);
+        }
+        total
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn megabyte_keys_with_long_common_prefix() {
+        let size = 1 << 20;
+        let mut tree = RedBlackTree::new();
+        for last in 0..16u8 {
+            let mut key = vec![7u8; size];
+            key[size - 1] = last;
+            tree.insert(key, last);
+        }
+        assert!(tree.validate().is_ok());
+        assert_eq!(tree.key_heap_bytes(), 16 * size);
+
+        let mut probe = vec![7u8; size];
+        probe[size - 1] = 9;
+        assert_eq!(tree.get(probe.as_slice()), Some(9));
+        //前缀不同，第一个字节即可区分
+        probe[0] = 0;
+        assert!(!tree.contains(probe.as_slice()));
+
+        probe[0] = 7;
+        tree.delete(probe.as_slice());
+        assert!(!tree.contains(probe.as_slice()));
+        assert_eq!(tree.len(), 15);
+        assert_eq!(tree.key_heap_bytes(), 15 * size);
+        assert!(tree.validate().is_ok());
+    }
+}
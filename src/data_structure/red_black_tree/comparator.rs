@@ -0,0 +1,221 @@
+//! 自定义比较的红黑树
+//! 构造时传入比较函数(如忽略大小写、降序)，无需为键定义新类型
+//! 内部是键为OrderedBy<K>的RedBlackTree，每个键携带比较函数的共享引用
+
+use alloc::rc::Rc;
+use core::cell::RefCell;
+use core::cmp::Ordering;
+use core::fmt;
+
+use super::{InvariantViolation, Iter, Node, RawEntryMut, RedBlackTree};
+
+///比较函数
+type Compare<K> = Rc<dyn Fn(&K, &K) -> Ordering>;
+
+type NodeRef<K, V> = Rc<RefCell<Node<OrderedBy<K>, V>>>;
+
+/// 携带比较函数的键，Ord由比较函数决定
+/// 同一棵树内的键共享同一个比较函数
+pub struct OrderedBy<K> {
+    key: K,
+    compare: Compare<K>,
+}
+
+impl<K> PartialEq for OrderedBy<K> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<K> Eq for OrderedBy<K> {}
+
+impl<K> PartialOrd for OrderedBy<K> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<K> Ord for OrderedBy<K> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.compare)(&self.key, &other.key)
+    }
+}
+
+impl<K: Clone> Clone for OrderedBy<K> {
+    fn clone(&self) -> Self {
+        OrderedBy { key: self.key.clone(), compare: Rc::clone(&self.compare) }
+    }
+}
+
+/// 按自定义比较排序的红黑树
+/// 比较函数视为相等的键即为同一个键，如忽略大小写时"a"与"A"
+pub struct RedBlackTreeBy<K, V> {
+    tree: RedBlackTree<OrderedBy<K>, V>,
+    compare: Compare<K>,
+}
+
+impl<K, V> RedBlackTreeBy<K, V> {
+    ///以compare为键的顺序创建空树
+    pub fn new<F: Fn(&K, &K) -> Ordering + 'static>(compare: F) -> Self {
+        RedBlackTreeBy { tree: RedBlackTree::new(), compare: Rc::new(compare) }
+    }
+
+    fn wrap(&self, key: K) -> OrderedBy<K> {
+        OrderedBy { key, compare: Rc::clone(&self.compare) }
+    }
+
+    ///插入，键已存在时保留原值
+    pub fn insert(&mut self, key: K, value: V) {
+        let key = self.wrap(key);
+        self.tree.insert(key, value);
+    }
+
+    ///插入或替换，返回旧值
+    pub fn insert_or_replace(&mut self, key: K, value: V) -> Option<V> {
+        let key = self.wrap(key);
+        self.tree.insert_or_replace(key, value)
+    }
+
+    ///删除，返回被删除的值
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let compare = Rc::clone(&self.compare);
+        match self.tree.raw_entry_mut().search(|node_key| compare(key, &node_key.key)) {
+            RawEntryMut::Occupied(occupied) => Some(occupied.remove()),
+            RawEntryMut::Vacant(_) => None,
+        }
+    }
+
+    pub fn get(&self, key: &K) -> Option<V>
+    where
+        V: Clone,
+    {
+        self.tree.find_by(|node_key| (self.compare)(key, &node_key.key)).map(|node_rc| node_rc.borrow().value.clone())
+    }
+
+    pub fn contains(&self, key: &K) -> bool {
+        self.tree.find_by(|node_key| (self.compare)(key, &node_key.key)).is_some()
+    }
+
+    pub fn len(&self) -> usize {
+        self.tree.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tree.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.tree.clear();
+    }
+
+    pub fn pop_first(&mut self) -> Option<(K, V)> {
+        self.tree.pop_first().map(|(key, value)| (key.key, value))
+    }
+
+    pub fn pop_last(&mut self) -> Option<(K, V)> {
+        self.tree.pop_last().map(|(key, value)| (key.key, value))
+    }
+
+    ///校验红黑树性质
+    pub fn validate(&self) -> Result<(), InvariantViolation<K>>
+    where
+        K: Clone,
+    {
+        self.tree.validate().map_err(|violation| violation.map_key(|key| key.key))
+    }
+}
+
+impl<K: Clone, V: Clone> RedBlackTreeBy<K, V> {
+    pub fn first(&self) -> Option<(K, V)> {
+        self.tree.first().map(|(key, value)| (key.key, value))
+    }
+
+    pub fn last(&self) -> Option<(K, V)> {
+        self.tree.last().map(|(key, value)| (key.key, value))
+    }
+
+    ///按比较函数的顺序迭代
+    pub fn iter(&self) -> IterBy<'_, K, V> {
+        IterBy { iter: self.tree.iter() }
+    }
+}
+
+impl<K, V> RedBlackTree<OrderedBy<K>, V> {
+    ///按比较查找节点，compare返回目标相对节点键的顺序
+    fn find_by<F: FnMut(&OrderedBy<K>) -> Ordering>(&self, mut compare: F) -> Option<NodeRef<K, V>> {
+        let mut next_option = self.root.clone();
+        let mut comparisons = 0;
+        while let Some(cur_rc) = next_option {
+            let cur = cur_rc.borrow();
+            comparisons += 1;
+            next_option = match compare(&cur.key) {
+                Ordering::Equal => {
+                    drop(cur);
+                    self.record(|stats| stats.comparisons += comparisons);
+                    return Some(cur_rc);
+                }
+                Ordering::Less => cur.left.clone(),
+                Ordering::Greater => cur.right.clone(),
+            };
+        }
+        self.record(|stats| stats.comparisons += comparisons);
+        None
+    }
+}
+
+/// 自定义比较树的迭代器
+pub struct IterBy<'a, K, V> {
+    iter: Iter<'a, OrderedBy<K>, V>,
+}
+
+impl<K: Clone, V: Clone> Iterator for IterBy<'_, K, V> {
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<(K, V)> {
+        self.iter.next().map(|(key, value)| (key.key, value))
+    }
+}
+
+impl<K: Clone, V: Clone> DoubleEndedIterator for IterBy<'_, K, V> {
+    fn next_back(&mut self) -> Option<(K, V)> {
+        self.iter.next_back().map(|(key, value)| (key.key, value))
+    }
+}
+
+impl<K: Clone, V: Clone> Clone for RedBlackTreeBy<K, V> {
+    fn clone(&self) -> Self {
+        RedBlackTreeBy { tree: self.tree.clone(), compare: Rc::clone(&self.compare) }
+    }
+}
+
+///按映射格式输出键值对
+impl<K: Clone + fmt::Debug, V: Clone + fmt::Debug> fmt::Debug for RedBlackTreeBy<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RedBlackTreeBy;
+
+    #[test]
+    fn custom_orderings() {
+        let mut descending = RedBlackTreeBy::new(|a: &i32, b: &i32| b.cmp(a));
+        for key in [3, 1, 4, 1, 5, 9, 2, 6] {
+            descending.insert(key, key * 10);
+        }
+        assert_eq!(descending.validate(), Ok(()));
+        assert!(descending.iter().map(|(key, _)| key).eq([9, 6, 5, 4, 3, 2, 1]));
+        assert_eq!((descending.first(), descending.pop_last()), (Some((9, 90)), Some((1, 10))));
+
+        let mut names = RedBlackTreeBy::new(|a: &String, b: &String| a.to_lowercase().cmp(&b.to_lowercase()));
+        names.insert("bob".to_string(), 1);
+        names.insert("Alice".to_string(), 2);
+        assert_eq!(names.insert_or_replace("BOB".to_string(), 3), Some(1));
+        assert_eq!(names.get(&"alice".to_string()), Some(2));
+        assert_eq!(format!("{:?}", names), r#"{"Alice": 2, "bob": 3}"#);
+        assert_eq!(names.remove(&"ALICE".to_string()), Some(2));
+        assert!(!names.contains(&"alice".to_string()) && names.len() == 1);
+    }
+}
@@ -0,0 +1,295 @@
+//! 分片并发映射
+//! 按键的哈希把键空间分到N棵独立的红黑树上，每棵树各有一把读写锁
+//! 不同分片上的写操作互不阻塞，避免一把全局锁串行化所有写线程
+//! 各分片内有序，有序迭代经snapshot().iter()在持有全部读锁的快照上做多路归并，产出借用而不复制
+//! 涉及全部分片的读操作经snapshot按分片下标顺序加全部读锁，得到同一时刻的一致视图
+
+use std::cmp::Reverse;
+use std::collections::hash_map::RandomState;
+use std::collections::BinaryHeap;
+use std::fmt;
+use std::hash::{BuildHasher, Hash};
+use std::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use super::{ArenaIter, InvariantViolation, RedBlackTreeArena};
+
+///每个核心的默认分片数，分片越多冲突越少
+const SHARDS_PER_CORE: usize = 4;
+
+/// 分片并发映射，所有方法只需&self，以Arc在线程间共享
+/// len等涉及全部分片的读操作都作用于一致的快照，有序迭代见snapshot().iter()
+pub struct ConcurrentRbMap<K, V, S = RandomState> {
+    shards: Vec<RwLock<RedBlackTreeArena<K, V>>>,
+    hasher: S,
+}
+
+impl<K: Ord + Hash, V> ConcurrentRbMap<K, V> {
+    ///分片数为可用核心数的SHARDS_PER_CORE倍
+    pub fn new() -> Self {
+        let cores = std::thread::available_parallelism().map_or(1, |cores| cores.get());
+        Self::with_shards(cores * SHARDS_PER_CORE)
+    }
+
+    ///指定分片数，至少为1
+    pub fn with_shards(shard_count: usize) -> Self {
+        Self::with_shards_and_hasher(shard_count, RandomState::new())
+    }
+}
+
+impl<K: Ord + Hash, V, S: BuildHasher> ConcurrentRbMap<K, V, S> {
+    pub fn with_shards_and_hasher(shard_count: usize, hasher: S) -> Self {
+        let shards = (0..shard_count.max(1)).map(|_| RwLock::new(RedBlackTreeArena::new())).collect();
+        ConcurrentRbMap { shards, hasher }
+    }
+
+    pub fn shard_count(&self) -> usize {
+        self.shards.len()
+    }
+
+    fn shard_of(&self, key: &K) -> &RwLock<RedBlackTreeArena<K, V>> {
+        let index = self.hasher.hash_one(key) % self.shards.len() as u64;
+        &self.shards[index as usize]
+    }
+
+    ///持锁的线程panic时分片可能处于修改中途，不再继续使用
+    fn read_lock(shard: &RwLock<RedBlackTreeArena<K, V>>) -> RwLockReadGuard<'_, RedBlackTreeArena<K, V>> {
+        shard.read().expect("shard lock poisoned")
+    }
+
+    fn write_lock(shard: &RwLock<RedBlackTreeArena<K, V>>) -> RwLockWriteGuard<'_, RedBlackTreeArena<K, V>> {
+        shard.write().expect("shard lock poisoned")
+    }
+
+    ///插入键值对，键已存在时忽略
+    pub fn insert(&self, key: K, value: V) {
+        Self::write_lock(self.shard_of(&key)).insert(key, value);
+    }
+
+    ///插入或替换，返回旧值
+    pub fn insert_or_replace(&self, key: K, value: V) -> Option<V> {
+        let mut tree = Self::write_lock(self.shard_of(&key));
+        match tree.get_mut(&key) {
+            Some(old) => Some(std::mem::replace(old, value)),
+            None => {
+                tree.insert(key, value);
+                None
+            }
+        }
+    }
+
+    ///删除键并返回值
+    pub fn remove(&self, key: &K) -> Option<V> {
+        Self::write_lock(self.shard_of(key)).remove(key)
+    }
+
+    ///锁释放后无法返回借用，返回值的拷贝
+    pub fn get(&self, key: &K) -> Option<V>
+    where
+        V: Clone,
+    {
+        Self::read_lock(self.shard_of(key)).get(key).cloned()
+    }
+
+    pub fn contains(&self, key: &K) -> bool {
+        Self::read_lock(self.shard_of(key)).contains(key)
+    }
+
+    ///原地修改键对应的值，只锁住键所在的分片
+    pub fn update<R, F: FnOnce(&mut V) -> R>(&self, key: &K, f: F) -> Option<R> {
+        Self::write_lock(self.shard_of(key)).get_mut(key).map(f)
+    }
+
+    /// 一致的只读快照
+    /// 按分片下标顺序加全部读锁，加锁顺序固定，多个快照之间不会死锁
+    /// 快照存在期间所有写操作阻塞，用完尽快释放
+    pub fn snapshot(&self) -> ShardSnapshot<'_, K, V, S> {
+        ShardSnapshot { shards: self.shards.iter().map(Self::read_lock).collect(), hasher: &self.hasher }
+    }
+
+    ///键值对数量，同时持有全部读锁累加，不会计入修改到一半的状态
+    pub fn len(&self) -> usize {
+        self.snapshot().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.snapshot().is_empty()
+    }
+
+    pub fn clear(&self) {
+        for shard in &self.shards {
+            Self::write_lock(shard).clear();
+        }
+    }
+}
+
+/// 持有全部分片读锁的只读视图，各项查询看到的是同一时刻的映射
+pub struct ShardSnapshot<'a, K, V, S = RandomState> {
+    shards: Vec<RwLockReadGuard<'a, RedBlackTreeArena<K, V>>>,
+    hasher: &'a S,
+}
+
+impl<K: Ord + Hash, V, S: BuildHasher> ShardSnapshot<'_, K, V, S> {
+    fn shard_of(&self, key: &K) -> &RedBlackTreeArena<K, V> {
+        let index = self.hasher.hash_one(key) % self.shards.len() as u64;
+        &self.shards[index as usize]
+    }
+
+    pub fn len(&self) -> usize {
+        self.shards.iter().map(|tree| tree.len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.shards.iter().all(|tree| tree.is_empty())
+    }
+
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.shard_of(key).get(key)
+    }
+
+    pub fn contains(&self, key: &K) -> bool {
+        self.shard_of(key).contains(key)
+    }
+
+    ///校验全部分片的红黑树性质
+    pub fn validate(&self) -> Result<(), InvariantViolation<K>>
+    where
+        K: Clone,
+    {
+        self.shards.iter().try_for_each(|tree| tree.validate())
+    }
+
+    ///各分片的中序迭代做多路归并，按键升序产出借用
+    pub fn iter(&self) -> SnapshotIter<'_, K, V> {
+        let mut iters: Vec<_> = self.shards.iter().map(|tree| tree.iter()).collect();
+        //堆中为各分片的下一个键，取最小者
+        let mut heap = BinaryHeap::with_capacity(iters.len());
+        let mut pending = Vec::with_capacity(iters.len());
+        for (index, iter) in iters.iter_mut().enumerate() {
+            if let Some((key, value)) = iter.next() {
+                heap.push(Reverse((key, index)));
+                pending.push(Some(value));
+            } else {
+                pending.push(None);
+            }
+        }
+        SnapshotIter { iters, heap, pending }
+    }
+}
+
+/// 快照的有序迭代器
+pub struct SnapshotIter<'a, K, V> {
+    iters: Vec<ArenaIter<'a, K, V>>,
+    heap: BinaryHeap<Reverse<(&'a K, usize)>>,
+    //各分片已取出、键在堆中的值
+    pending: Vec<Option<&'a V>>,
+}
+
+impl<'a, K: Ord, V> Iterator for SnapshotIter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let Reverse((key, index)) = self.heap.pop()?;
+        let value = self.pending[index].take().expect("pending value for a queued key");
+        if let Some((next_key, next_value)) = self.iters[index].next() {
+            self.heap.push(Reverse((next_key, index)));
+            self.pending[index] = Some(next_value);
+        }
+        Some((key, value))
+    }
+}
+
+impl<K: Ord + Hash, V> Default for ConcurrentRbMap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+///在快照上按键升序输出，输出期间写操作阻塞
+impl<K: Ord + Hash + fmt::Debug, V: fmt::Debug, S: BuildHasher> fmt::Debug for ConcurrentRbMap<K, V, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.snapshot().iter()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::thread;
+
+    use super::ConcurrentRbMap;
+
+    #[test]
+    fn concurrent_writers_then_ordered_iteration() {
+        let map = Arc::new(ConcurrentRbMap::with_shards(8));
+        let handles: Vec<_> = (0..8u32)
+            .map(|thread_index| {
+                let map = Arc::clone(&map);
+                thread::spawn(move || {
+                    for key in (thread_index..8_000).step_by(8) {
+                        map.insert(key, key);
+                        map.update(&key, |value| *value += 1);
+                    }
+                    for key in (thread_index..8_000).step_by(16) {
+                        assert_eq!(map.remove(&key), Some(key + 1));
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        assert_eq!(map.len(), 4_000);
+        let expected: Vec<_> = (0..8_000).filter(|key| key % 16 >= 8).map(|key| (key, key + 1)).collect();
+        assert!(map.snapshot().iter().map(|(key, value)| (*key, *value)).eq(expected));
+        assert_eq!(map.insert_or_replace(8, 0), Some(9));
+        assert_eq!(map.get(&8), Some(0));
+        assert!(!map.contains(&0));
+    }
+
+    #[test]
+    fn single_shard_degenerates_to_one_tree() {
+        let map = ConcurrentRbMap::with_shards(0);
+        assert_eq!(map.shard_count(), 1);
+        for key in (0..100).rev() {
+            map.insert(key, ());
+        }
+        assert!(map.snapshot().iter().map(|(key, _)| *key).eq(0..100));
+        map.clear();
+        assert!(map.is_empty());
+    }
+
+    #[test]
+    fn debug_prints_snapshot_in_key_order() {
+        let map = ConcurrentRbMap::with_shards(3);
+        map.insert(2, 'b');
+        map.insert(1, 'a');
+        assert_eq!(format!("{:?}", map), "{1: 'a', 2: 'b'}");
+    }
+
+    ///写线程不断插入时，每个快照内len、迭代与校验互相一致
+    #[test]
+    fn snapshot_is_consistent_during_writes() {
+        let map = Arc::new(ConcurrentRbMap::with_shards(4));
+        let writer = {
+            let map = Arc::clone(&map);
+            thread::spawn(move || {
+                for key in 0..4_000u32 {
+                    map.insert(key, key + 1);
+                }
+            })
+        };
+        let mut previous = 0;
+        while !writer.is_finished() {
+            let snapshot = map.snapshot();
+            assert_eq!(snapshot.len(), snapshot.iter().count());
+            assert!(snapshot.len() >= previous);
+            assert_eq!(snapshot.validate(), Ok(()));
+            previous = snapshot.len();
+        }
+        writer.join().unwrap();
+        let snapshot = map.snapshot();
+        assert_eq!(snapshot.len(), 4_000);
+        assert_eq!(snapshot.get(&1_999), Some(&2_000));
+        assert!(!snapshot.contains(&4_000));
+    }
+}
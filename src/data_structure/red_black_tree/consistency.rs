@@ -0,0 +1,96 @@
+//! 关联树的一致性检查
+//! 用于主索引与二级索引、树与TTL截止时间树等成对维护的结构
+
+use alloc::vec::Vec;
+
+use super::RedBlackTree;
+
+/// 成对检查的结果
+/// left_orphans为左树中对应键在右树不存在(或已被其他条目占用)的键
+/// right_orphans为右树中没有任何左树条目对应的键
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct PairReport<K1, K2> {
+    pub left_orphans: Vec<K1>,
+    pub right_orphans: Vec<K2>,
+}
+
+impl<K1, K2> PairReport<K1, K2> {
+    ///两棵树一一对应
+    pub fn is_consistent(&self) -> bool {
+        self.left_orphans.is_empty() && self.right_orphans.is_empty()
+    }
+}
+
+/// 检查左树每个条目经relation映射后的键与右树的键一一对应
+/// 映射结果排序后与右树中序同步遍历一次，O(n log n + m)
+pub fn check_pair<K1, V1, K2, V2, F>(left: &RedBlackTree<K1, V1>, right: &RedBlackTree<K2, V2>, mut relation: F) -> PairReport<K1, K2>
+where
+    K1: Ord + Clone,
+    K2: Ord + Clone,
+    F: FnMut(&K1, &V1) -> K2,
+{
+    let mut expected = Vec::with_capacity(left.len);
+    let mut next_option = left.root.as_ref().map(RedBlackTree::find_minimum);
+    while let Some(cur_rc) = next_option {
+        {
+            let cur = cur_rc.borrow();
+            expected.push((relation(&cur.key, &cur.value), cur.key.clone()));
+        }
+        next_option = RedBlackTree::successor(&cur_rc);
+    }
+    //稳定排序，同一右键被多个条目映射时，先出现的条目视为匹配
+    expected.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    let mut report = PairReport { left_orphans: Vec::new(), right_orphans: Vec::new() };
+    let mut expected_iter = expected.into_iter().peekable();
+    let mut right_option = right.root.as_ref().map(RedBlackTree::find_minimum);
+    while let Some(right_rc) = right_option {
+        {
+            let right_key = &right_rc.borrow().key;
+            while let Some((_, left_key)) = expected_iter.next_if(|(key, _)| key < right_key) {
+                report.left_orphans.push(left_key);
+            }
+            if expected_iter.next_if(|(key, _)| key == right_key).is_some() {
+                while let Some((_, left_key)) = expected_iter.next_if(|(key, _)| key == right_key) {
+                    report.left_orphans.push(left_key);
+                }
+            } else {
+                report.right_orphans.push(right_key.clone());
+            }
+        }
+        right_option = RedBlackTree::successor(&right_rc);
+    }
+    report.left_orphans.extend(expected_iter.map(|(_, left_key)| left_key));
+    report.left_orphans.sort();
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::RedBlackTree;
+    use super::check_pair;
+
+    #[test]
+    fn reports_orphans_on_both_sides() {
+        //主树：id -> 截止时间，截止时间树：(截止时间, id)
+        let mut primary = RedBlackTree::new();
+        let mut deadlines = RedBlackTree::new();
+        for (id, deadline) in [(1, 30), (2, 10), (3, 20), (4, 10)] {
+            primary.insert(id, deadline);
+            deadlines.insert((deadline, id), ());
+        }
+        assert!(check_pair(&primary, &deadlines, |id, deadline| (*deadline, *id)).is_consistent());
+
+        primary.delete(&3);
+        deadlines.delete(&(10, 4));
+        deadlines.insert((99, 9), ());
+        let report = check_pair(&primary, &deadlines, |id, deadline| (*deadline, *id));
+        assert_eq!(report.left_orphans, vec![4]);
+        assert_eq!(report.right_orphans, vec![(20, 3), (99, 9)]);
+
+        //多个条目映射到同一右键
+        let report = check_pair(&primary, &deadlines, |_, _| (10, 2));
+        assert_eq!(report.left_orphans, vec![2, 4]);
+        assert_eq!(report.right_orphans, vec![(20, 3), (30, 1), (99, 9)]);
+    }
+}
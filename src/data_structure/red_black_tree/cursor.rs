@@ -0,0 +1,75 @@
+//! 只读游标
+//! 游标指向树中某个节点，或指向首尾之间的空位置(None)
+//! 从空位置向后移动到最小节点，向前移动到最大节点
+
+use alloc::rc::Rc;
+use core::cell::RefCell;
+use core::ops::Bound;
+
+use super::{Iter, Node, RedBlackTree};
+
+pub struct Cursor<'a, K, V> {
+    tree: &'a RedBlackTree<K, V>,
+    node: Option<Rc<RefCell<Node<K, V>>>>,
+}
+
+impl<K: Ord, V> RedBlackTree<K, V> {
+    ///指向满足下界的最小节点的游标，不存在则指向空位置
+    pub fn lower_bound(&self, bound: Bound<&K>) -> Cursor<'_, K, V> {
+        Cursor { tree: self, node: self.find_lower_bound(bound) }
+    }
+
+    ///指向满足上界的最大节点的游标，不存在则指向空位置
+    pub fn upper_bound(&self, bound: Bound<&K>) -> Cursor<'_, K, V> {
+        Cursor { tree: self, node: self.find_upper_bound(bound) }
+    }
+}
+
+impl<'a, K: Ord, V> Cursor<'a, K, V> {
+    pub fn key(&self) -> Option<K>
+    where
+        K: Clone,
+    {
+        self.node.as_ref().map(|node_ref| node_ref.borrow().key.clone())
+    }
+
+    pub fn value(&self) -> Option<V>
+    where
+        V: Clone,
+    {
+        self.node.as_ref().map(|node_ref| node_ref.borrow().value.clone())
+    }
+
+    ///移动到后继节点
+    pub fn move_next(&mut self) {
+        self.node = match &self.node {
+            Some(node_ref) => RedBlackTree::successor(node_ref),
+            None => self.tree.root.as_ref().map(RedBlackTree::find_minimum),
+        };
+    }
+
+    ///移动到前驱节点
+    pub fn move_prev(&mut self) {
+        self.node = match &self.node {
+            Some(node_ref) => RedBlackTree::predecessor(node_ref),
+            None => self.tree.root.as_ref().map(RedBlackTree::find_maximum),
+        };
+    }
+
+    /// 从当前游标(含)到other(不含)的迭代器
+    /// 直接使用两个游标的位置，无需再次查找
+    /// other指向空位置时迭代到最大节点；当前游标指向空位置或在other之后时为空迭代器
+    pub fn until(&self, other: &Cursor<'a, K, V>) -> Iter<'a, K, V> {
+        let back = match &other.node {
+            Some(other_ref) => RedBlackTree::predecessor(other_ref),
+            None => self.tree.root.as_ref().map(RedBlackTree::find_maximum),
+        };
+        Iter::between(self.tree, self.node.clone(), back)
+    }
+}
+
+impl<K, V> Clone for Cursor<'_, K, V> {
+    fn clone(&self) -> Self {
+        Cursor { tree: self.tree, node: self.node.clone() }
+    }
+}
@@ -0,0 +1,115 @@
+//! 结构比较与差异
+//! structural_eq要求形状、颜色与内容都相同，==只比较内容
+//! diff同步中序遍历两棵树，只产出不同之处，用于副本间同步
+
+use alloc::rc::Rc;
+use core::cell::RefCell;
+
+use super::{EitherOrBoth, Iter, Node, RedBlackTree, ZipSorted};
+
+/// 两棵树之间的一处差异
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum Delta<K, V> {
+    ///只在本树中存在
+    OnlyInSelf { key: K, value: V },
+    ///只在另一棵树中存在
+    OnlyInOther { key: K, value: V },
+    ///两者都有，值不同
+    Changed { key: K, value: V, other_value: V },
+}
+
+/// 差异迭代器，按键升序产出
+pub struct Diff<'a, K: Ord + Clone, V: Clone> {
+    zip: ZipSorted<'a, K, V, V, Iter<'a, K, V>>,
+}
+
+impl<K: Ord + Clone, V: Clone + PartialEq> Iterator for Diff<'_, K, V> {
+    type Item = Delta<K, V>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            return Some(match self.zip.next()? {
+                EitherOrBoth::Left((key, value)) => Delta::OnlyInSelf { key, value },
+                EitherOrBoth::Right((key, value)) => Delta::OnlyInOther { key, value },
+                EitherOrBoth::Both((_, value), (_, other_value)) if value == other_value => continue,
+                EitherOrBoth::Both((key, value), (_, other_value)) => Delta::Changed { key, value, other_value },
+            });
+        }
+    }
+}
+
+impl<K: Ord, V> RedBlackTree<K, V> {
+    /// 结构相等，形状、颜色、键值与重复次数逐节点相同
+    /// 相同的插入删除序列得到结构相等的树，内容相等的树结构未必相等
+    pub fn structural_eq(&self, other: &Self) -> bool
+    where
+        V: PartialEq,
+    {
+        self.len == other.len && Self::subtree_structural_eq(&self.root, &other.root)
+    }
+
+    fn subtree_structural_eq(node_option: &Option<Rc<RefCell<Node<K, V>>>>, other_option: &Option<Rc<RefCell<Node<K, V>>>>) -> bool
+    where
+        V: PartialEq,
+    {
+        match (node_option, other_option) {
+            (None, None) => true,
+            (Some(node_ref), Some(other_ref)) => {
+                let (node, other) = (node_ref.borrow(), other_ref.borrow());
+                node.key == other.key
+                    && node.value == other.value
+                    && node.color == other.color
+                    && node.count == other.count
+                    && Self::subtree_structural_eq(&node.left, &other.left)
+                    && Self::subtree_structural_eq(&node.right, &other.right)
+            }
+            _ => false,
+        }
+    }
+
+    /// 与other的差异，按键升序产出只在一方存在或值不同的键
+    /// 同步中序遍历，O(n + m)
+    pub fn diff<'a>(&'a self, other: &'a Self) -> Diff<'a, K, V>
+    where
+        K: Clone,
+        V: Clone + PartialEq,
+    {
+        Diff { zip: self.zip_sorted(other.iter()) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::RedBlackTree;
+    use super::Delta;
+
+    #[test]
+    fn diff_reports_changes_in_key_order() {
+        let mut tree = RedBlackTree::from_sorted_iter((0..10).map(|key| (key, key)));
+        let mut replica = tree.clone();
+        replica.delete(&2);
+        replica.insert_or_replace(5, 50);
+        replica.insert(12, 12);
+        tree.delete(&9);
+        let delta: Vec<_> = tree.diff(&replica).collect();
+        assert_eq!(delta, vec![
+            Delta::OnlyInSelf { key: 2, value: 2 },
+            Delta::Changed { key: 5, value: 5, other_value: 50 },
+            Delta::OnlyInOther { key: 9, value: 9 },
+            Delta::OnlyInOther { key: 12, value: 12 },
+        ]);
+        assert_eq!(replica.diff(&replica).next(), None);
+    }
+
+    #[test]
+    fn structural_eq_sees_shape() {
+        let ascending = RedBlackTree::from_sorted_iter((0..10).map(|key| (key, ())));
+        let mut inserted = RedBlackTree::new();
+        for key in 0..10 {
+            inserted.insert(key, ());
+        }
+        assert!(ascending == inserted);
+        assert!(!ascending.structural_eq(&inserted));
+        assert!(inserted.structural_eq(&inserted.clone()));
+    }
+}
@@ -0,0 +1,196 @@
+//! 有序文档模型
+//! 类似serde_json的Value，对象以红黑树为map，键始终按升序排列
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use super::{RawEntryMut, RedBlackTree};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Document {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Document>),
+    Object(RedBlackTree<String, Document>),
+}
+
+impl Document {
+    pub fn new_object() -> Self {
+        Document::Object(RedBlackTree::new())
+    }
+
+    pub fn as_object(&self) -> Option<&RedBlackTree<String, Document>> {
+        match self {
+            Document::Object(object) => Some(object),
+            _ => None,
+        }
+    }
+
+    /// 取得对象，Null视为空对象
+    /// 与serde_json的索引赋值一致，其他类型无法作为对象时panic
+    fn object_mut(&mut self) -> &mut RedBlackTree<String, Document> {
+        if let Document::Null = self {
+            *self = Document::new_object();
+        }
+        match self {
+            Document::Object(object) => object,
+            _ => panic!("document is not an object"),
+        }
+    }
+
+    ///插入字段，返回被替换的旧值
+    pub fn insert(&mut self, key: String, value: Document) -> Option<Document> {
+        match self.object_mut().raw_entry_mut().from_key(&key) {
+            RawEntryMut::Occupied(mut occupied) => Some(occupied.insert(value)),
+            RawEntryMut::Vacant(vacant) => {
+                vacant.insert(key, value);
+                None
+            }
+        }
+    }
+
+    /// 按路径插入，路径上缺少的对象自动创建
+    /// 路径为空时替换自身
+    pub fn insert_path(&mut self, path: &[&str], value: Document) {
+        let (first, rest) = match path.split_first() {
+            None => {
+                *self = value;
+                return;
+            }
+            Some(split) => split,
+        };
+        match self.object_mut().raw_entry_mut().from_key(&first.to_string()) {
+            RawEntryMut::Occupied(mut occupied) => {
+                occupied.with_value_mut(|child| child.insert_path(rest, value));
+            }
+            RawEntryMut::Vacant(vacant) => {
+                let mut child = Document::Null;
+                child.insert_path(rest, value);
+                vacant.insert(first.to_string(), child);
+            }
+        }
+    }
+
+    ///按路径读取，f接收目标节点的借用
+    pub fn visit_path<R, F: FnOnce(&Document) -> R>(&self, path: &[&str], f: F) -> Option<R> {
+        let (first, rest) = match path.split_first() {
+            None => return Some(f(self)),
+            Some(split) => split,
+        };
+        let object = self.as_object()?;
+        let node_rc = object.find(*first)?;
+        let node = node_rc.borrow();
+        node.value.visit_path(rest, f)
+    }
+
+    /// 深度合并
+    /// 双方都是对象时逐字段递归合并，否则other覆盖self
+    pub fn merge(&mut self, other: Document) {
+        match (self, other) {
+            (Document::Object(object), Document::Object(mut other_object)) => {
+                while let Some((key, other_value)) = other_object.pop_first() {
+                    match object.raw_entry_mut().from_key(&key) {
+                        RawEntryMut::Occupied(mut occupied) => {
+                            occupied.with_value_mut(|value| value.merge(other_value));
+                        }
+                        RawEntryMut::Vacant(vacant) => vacant.insert(key, other_value),
+                    }
+                }
+            }
+            (this, other) => *this = other,
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+mod serde_impl {
+    use core::fmt;
+
+    use serde::de::{MapAccess, SeqAccess, Visitor};
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use super::{Document, RedBlackTree};
+
+    impl Serialize for Document {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            match self {
+                Document::Null => serializer.serialize_unit(),
+                Document::Bool(value) => serializer.serialize_bool(*value),
+                Document::Number(value) => serializer.serialize_f64(*value),
+                Document::String(value) => serializer.serialize_str(value),
+                Document::Array(values) => values.serialize(serializer),
+                Document::Object(object) => object.serialize(serializer),
+            }
+        }
+    }
+
+    struct DocumentVisitor;
+
+    impl<'de> Visitor<'de> for DocumentVisitor {
+        type Value = Document;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("a document value")
+        }
+
+        fn visit_unit<E>(self) -> Result<Document, E> {
+            Ok(Document::Null)
+        }
+
+        fn visit_none<E>(self) -> Result<Document, E> {
+            Ok(Document::Null)
+        }
+
+        fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Document, D::Error> {
+            Document::deserialize(deserializer)
+        }
+
+        fn visit_bool<E>(self, value: bool) -> Result<Document, E> {
+            Ok(Document::Bool(value))
+        }
+
+        fn visit_i64<E>(self, value: i64) -> Result<Document, E> {
+            Ok(Document::Number(value as f64))
+        }
+
+        fn visit_u64<E>(self, value: u64) -> Result<Document, E> {
+            Ok(Document::Number(value as f64))
+        }
+
+        fn visit_f64<E>(self, value: f64) -> Result<Document, E> {
+            Ok(Document::Number(value))
+        }
+
+        fn visit_str<E>(self, value: &str) -> Result<Document, E> {
+            Ok(Document::String(value.to_string()))
+        }
+
+        fn visit_string<E>(self, value: String) -> Result<Document, E> {
+            Ok(Document::String(value))
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, mut access: A) -> Result<Document, A::Error> {
+            let mut values = Vec::new();
+            while let Some(value) = access.next_element()? {
+                values.push(value);
+            }
+            Ok(Document::Array(values))
+        }
+
+        fn visit_map<A: MapAccess<'de>>(self, mut access: A) -> Result<Document, A::Error> {
+            let mut document = Document::Object(RedBlackTree::new());
+            while let Some((key, value)) = access.next_entry::<String, Document>()? {
+                document.insert(key, value);
+            }
+            Ok(document)
+        }
+    }
+
+    impl<'de> Deserialize<'de> for Document {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            deserializer.deserialize_any(DocumentVisitor)
+        }
+    }
+}
@@ -0,0 +1,88 @@
+//! Graphviz结构导出
+//! 节点以填充色表示颜色，缺少的子节点画为NIL叶子，左右子节点的先后顺序即输出顺序
+
+use alloc::rc::Rc;
+use alloc::string::String;
+use core::cell::RefCell;
+use core::fmt::{self, Write};
+
+use super::{Color, Node, RedBlackTree};
+
+impl<K: fmt::Display, V> RedBlackTree<K, V> {
+    ///以Graphviz的dot格式导出树结构，dot -Tsvg即可渲染
+    pub fn to_dot(&self) -> String {
+        let mut out = String::from("digraph RedBlackTree {\n    node [style=filled, fontcolor=white, shape=circle];\n");
+        if let Some(root_ref) = &self.root {
+            let mut next_id = 0;
+            Self::write_dot(&mut out, root_ref, &mut next_id);
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    ///写出子树，返回子树根的编号
+    fn write_dot(out: &mut String, node_ref: &Rc<RefCell<Node<K, V>>>, next_id: &mut usize) -> usize {
+        let node = node_ref.borrow();
+        let id = *next_id;
+        *next_id += 1;
+        let color = match node.color {
+            Color::Red => "red",
+            Color::Black => "black",
+        };
+        write!(out, "    n{} [label=\"", id).expect("writing to a String cannot fail");
+        write!(Escape(out), "{}", node.key).expect("writing to a String cannot fail");
+        writeln!(out, "\", fillcolor={}];", color).expect("writing to a String cannot fail");
+        for child in [&node.left, &node.right] {
+            let child_id = match child {
+                Some(child_ref) => Self::write_dot(out, child_ref, next_id),
+                None => {
+                    let nil_id = *next_id;
+                    *next_id += 1;
+                    writeln!(out, "    n{} [label=\"\", shape=point];", nil_id).expect("writing to a String cannot fail");
+                    nil_id
+                }
+            };
+            writeln!(out, "    n{} -> n{};", id, child_id).expect("writing to a String cannot fail");
+        }
+        id
+    }
+}
+
+///转义dot字符串中的引号与反斜杠
+struct Escape<'a>(&'a mut String);
+
+impl Write for Escape<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for c in s.chars() {
+            match c {
+                '"' => self.0.push_str("\\\""),
+                '\\' => self.0.push_str("\\\\"),
+                '\n' => self.0.push_str("\\n"),
+                c => self.0.push(c),
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::RedBlackTree;
+
+    #[test]
+    fn dot_lists_nodes_and_nil_leaves() {
+        let mut tree = RedBlackTree::new();
+        tree.insert(2, ());
+        tree.insert(1, ());
+        assert_eq!(
+            tree.to_dot(),
+            "digraph RedBlackTree {\n    node [style=filled, fontcolor=white, shape=circle];\n    \
+             n0 [label=\"2\", fillcolor=black];\n    \
+             n1 [label=\"1\", fillcolor=red];\n    \
+             n2 [label=\"\", shape=point];\n    n1 -> n2;\n    \
+             n3 [label=\"\", shape=point];\n    n1 -> n3;\n    n0 -> n1;\n    \
+             n4 [label=\"\", shape=point];\n    n0 -> n4;\n}\n"
+        );
+        assert_eq!(RedBlackTree::<u32, ()>::new().to_dot(), "digraph RedBlackTree {\n    node [style=filled, fontcolor=white, shape=circle];\n}\n");
+    }
+}
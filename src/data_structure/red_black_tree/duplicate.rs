@@ -0,0 +1,111 @@
+//! 重复键策略
+//! 默认忽略重复插入；可在构造时选择覆盖或计数(多重集)，也可直接调用对应的插入方法
+
+use super::{RawEntryMut, RedBlackTree};
+
+/// 插入已存在的键时的处理方式
+/// Count模式下每个键记录重复次数，delete、try_delete每次减少一次计数，计数归零时删除节点
+/// 返回被删除键值对的方法(pop_first、pop_last、take、remove_by_index、entry与raw entry的remove)只有一份键值可还，
+/// 因此按节点整体删除；len、rank、select等也按节点计算。需要按重复次数计数的集合用RedBlackMultiSet
+#[derive(PartialEq, Eq, Copy, Clone, Debug, Default)]
+pub enum DuplicatePolicy {
+    ///保留原值
+    #[default]
+    Ignore,
+    ///以新值覆盖
+    Overwrite,
+    ///保留原值，重复次数加一
+    Count,
+}
+
+impl<K: Ord, V> RedBlackTree<K, V> {
+    pub fn with_duplicate_policy(policy: DuplicatePolicy) -> Self {
+        let mut tree = RedBlackTree::new();
+        tree.duplicate_policy = policy;
+        tree
+    }
+
+    pub fn duplicate_policy(&self) -> DuplicatePolicy {
+        self.duplicate_policy
+    }
+
+    ///插入键值对，键已存在时覆盖并返回旧值，与策略无关
+    pub fn insert_or_replace(&mut self, key: K, value: V) -> Option<V> {
+        match self.raw_entry_mut().from_key(&key) {
+            RawEntryMut::Occupied(mut occupied) => Some(occupied.insert(value)),
+            RawEntryMut::Vacant(vacant) => {
+                vacant.insert(key, value);
+                None
+            }
+        }
+    }
+
+    /// 插入键值对，键已存在时连同键一起替换，返回原有的键值对，与策略无关
+    /// 用于Ord相等并不意味着完全相同的键，如驻留表中换入新的键
+    pub fn replace(&mut self, key: K, value: V) -> Option<(K, V)> {
+        match self.raw_entry_mut().from_key(&key) {
+            RawEntryMut::Occupied(occupied) => {
+                let mut node = occupied.node.borrow_mut();
+                Some((core::mem::replace(&mut node.key, key), core::mem::replace(&mut node.value, value)))
+            }
+            RawEntryMut::Vacant(vacant) => {
+                vacant.insert(key, value);
+                None
+            }
+        }
+    }
+
+    /// 删除键并返回树中存储的键值对，键可以是K的借用形式
+    /// 多重集模式下与pop_first相同，整体删除而不是减少计数
+    pub fn take<Q: Ord + ?Sized>(&mut self, key: &Q) -> Option<(K, V)>
+    where
+        K: core::borrow::Borrow<Q>,
+    {
+        let node_rc = self.find(key)?;
+        Some(self.remove_node(node_rc))
+    }
+
+    ///按多重集插入，键已存在时保留原值并将重复次数加一，与策略无关
+    pub fn insert_multi(&mut self, key: K, value: V) {
+        match self.raw_entry_mut().from_key(&key) {
+            RawEntryMut::Occupied(occupied) => occupied.node.borrow_mut().count += 1,
+            RawEntryMut::Vacant(vacant) => vacant.insert(key, value),
+        }
+    }
+
+    ///键的重复次数，不存在时为0
+    pub fn count<Q: Ord + ?Sized>(&self, key: &Q) -> usize
+    where
+        K: core::borrow::Borrow<Q>,
+    {
+        self.find(key).map_or(0, |node_rc| node_rc.borrow().count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{Entry, RedBlackTree};
+    use super::DuplicatePolicy;
+
+    #[test]
+    fn removal_granularity_with_counts() {
+        let mut tree = RedBlackTree::with_duplicate_policy(DuplicatePolicy::Count);
+        for (key, value) in [(1, 10), (1, 11), (2, 20), (3, 30), (3, 31)] {
+            tree.insert(key, value);
+        }
+        assert_eq!((tree.len(), tree.count(&1), tree.count(&3)), (3, 2, 2));
+        //delete逐个减少计数
+        tree.delete(&3);
+        assert_eq!((tree.len(), tree.count(&3)), (3, 1));
+        //返回键值对的删除按节点整体删除
+        assert_eq!(tree.pop_first(), Some((1, 10)));
+        assert_eq!((tree.len(), tree.count(&1)), (2, 0));
+        tree.insert_multi(2, 21);
+        let Entry::Occupied(entry) = tree.entry(2) else { panic!() };
+        assert_eq!(entry.remove(), 20);
+        assert_eq!(tree.count(&2), 0);
+        assert_eq!(tree.pop_last(), Some((3, 30)));
+        assert!(tree.is_empty());
+        assert_eq!(tree.validate(), Ok(()));
+    }
+}
@@ -0,0 +1,169 @@
+//! 入口API，类似BTreeMap::entry
+//! 只下降一次，读改写无需先get再insert
+//! 值位于RefCell内，无法返回&mut V，插入后返回占用句柄，通过with_value_mut原地修改
+
+use core::error::Error;
+use core::fmt;
+
+use super::raw_entry::{RawOccupiedEntryMut, RawVacantEntryMut};
+use super::{RawEntryMut, RedBlackTree};
+
+pub enum Entry<'a, K, V> {
+    Occupied(OccupiedEntry<'a, K, V>),
+    Vacant(VacantEntry<'a, K, V>),
+}
+
+///已存在节点的句柄
+pub struct OccupiedEntry<'a, K, V> {
+    raw: RawOccupiedEntryMut<'a, K, V>,
+}
+
+///空位句柄，持有待插入的键
+pub struct VacantEntry<'a, K, V> {
+    raw: RawVacantEntryMut<'a, K, V>,
+    key: K,
+}
+
+/// try_insert的键已存在
+/// entry为已存在节点的句柄，value为未插入的值
+pub struct OccupiedError<'a, K, V> {
+    pub entry: OccupiedEntry<'a, K, V>,
+    pub value: V,
+}
+
+impl<K: Ord, V> RedBlackTree<K, V> {
+    pub fn entry(&mut self, key: K) -> Entry<'_, K, V> {
+        match self.raw_entry_mut().from_key(&key) {
+            RawEntryMut::Occupied(raw) => Entry::Occupied(OccupiedEntry { raw }),
+            RawEntryMut::Vacant(raw) => Entry::Vacant(VacantEntry { raw, key }),
+        }
+    }
+
+    ///最小键的占用句柄，空树为None
+    pub fn first_entry(&mut self) -> Option<OccupiedEntry<'_, K, V>> {
+        let node = self.root.as_ref().map(Self::find_minimum)?;
+        Some(OccupiedEntry { raw: RawOccupiedEntryMut::new(self, node) })
+    }
+
+    ///最大键的占用句柄，空树为None
+    pub fn last_entry(&mut self) -> Option<OccupiedEntry<'_, K, V>> {
+        let node = self.root.as_ref().map(Self::find_maximum)?;
+        Some(OccupiedEntry { raw: RawOccupiedEntryMut::new(self, node) })
+    }
+
+    /// 插入键值对，返回新节点的句柄
+    /// 键已存在时不修改树，返回带有已存在节点句柄的错误，与BTreeMap::try_insert相同
+    pub fn try_insert(&mut self, key: K, value: V) -> Result<OccupiedEntry<'_, K, V>, OccupiedError<'_, K, V>> {
+        match self.entry(key) {
+            Entry::Occupied(entry) => Err(OccupiedError { entry, value }),
+            Entry::Vacant(vacant) => Ok(vacant.insert(value)),
+        }
+    }
+}
+
+impl<'a, K: Ord, V> Entry<'a, K, V> {
+    ///不存在时插入default，返回占用句柄
+    pub fn or_insert(self, default: V) -> OccupiedEntry<'a, K, V> {
+        self.or_insert_with(|| default)
+    }
+
+    pub fn or_insert_with<F: FnOnce() -> V>(self, default: F) -> OccupiedEntry<'a, K, V> {
+        match self {
+            Entry::Occupied(occupied) => occupied,
+            Entry::Vacant(vacant) => vacant.insert(default()),
+        }
+    }
+
+    ///default可以使用键
+    pub fn or_insert_with_key<F: FnOnce(&K) -> V>(self, default: F) -> OccupiedEntry<'a, K, V> {
+        match self {
+            Entry::Occupied(occupied) => occupied,
+            Entry::Vacant(vacant) => {
+                let value = default(&vacant.key);
+                vacant.insert(value)
+            }
+        }
+    }
+
+    pub fn or_default(self) -> OccupiedEntry<'a, K, V>
+    where
+        V: Default,
+    {
+        self.or_insert_with(V::default)
+    }
+
+    ///存在时原地修改值
+    pub fn and_modify<F: FnOnce(&mut V)>(mut self, f: F) -> Self {
+        if let Entry::Occupied(occupied) = &mut self {
+            occupied.with_value_mut(f);
+        }
+        self
+    }
+}
+
+impl<K: Ord, V> OccupiedEntry<'_, K, V> {
+    pub fn key(&self) -> K
+    where
+        K: Clone,
+    {
+        self.raw.key()
+    }
+
+    pub fn get(&self) -> V
+    where
+        V: Clone,
+    {
+        self.raw.get()
+    }
+
+    ///原地修改值
+    pub fn with_value_mut<R, F: FnOnce(&mut V) -> R>(&mut self, f: F) -> R {
+        self.raw.with_value_mut(f)
+    }
+
+    ///替换值，返回旧值
+    pub fn insert(&mut self, value: V) -> V {
+        self.raw.insert(value)
+    }
+
+    /// 删除节点，返回键值对
+    /// 多重集模式下连同全部重复整体删除，逐个删除用delete
+    pub fn remove_entry(self) -> (K, V) {
+        self.raw.remove_entry()
+    }
+
+    pub fn remove(self) -> V {
+        self.raw.remove()
+    }
+}
+
+impl<'a, K: Ord, V> VacantEntry<'a, K, V> {
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    pub fn into_key(self) -> K {
+        self.key
+    }
+
+    ///插入值，返回新节点的句柄
+    pub fn insert(self, value: V) -> OccupiedEntry<'a, K, V> {
+        OccupiedEntry { raw: self.raw.insert_entry(self.key, value) }
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug> fmt::Debug for OccupiedError<'_, K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let node = self.entry.raw.node.borrow();
+        f.debug_struct("OccupiedError").field("key", &node.key).field("old_value", &node.value).field("new_value", &self.value).finish()
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug> fmt::Display for OccupiedError<'_, K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let node = self.entry.raw.node.borrow();
+        write!(f, "failed to insert {:?}, key {:?} already exists with value {:?}", self.value, node.key, node.value)
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug> Error for OccupiedError<'_, K, V> {}
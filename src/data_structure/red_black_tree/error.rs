@@ -0,0 +1,95 @@
+//! 统一的错误类型
+//! try_insert的OccupiedError、StaticRbTree的Full与validate的InvariantViolation都可以经?转换为TreeError
+//! 专用错误带回的值与条目句柄在转换时丢弃，只保留出问题的键
+
+use core::error::Error;
+use core::fmt;
+
+use super::{Full, InvariantViolation, OccupiedError, RedBlackTree};
+
+/// 修改操作失败的原因
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum TreeError<K> {
+    ///键已存在
+    DuplicateKey { key: K },
+    ///键不存在
+    NotFound,
+    ///容量已满，key为未能插入的键
+    CapacityExceeded { key: K },
+    ///红黑树性质被破坏
+    InvariantViolation(InvariantViolation<K>),
+}
+
+impl<K: fmt::Debug> fmt::Display for TreeError<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TreeError::DuplicateKey { key } => write!(f, "key {:?} already exists", key),
+            TreeError::NotFound => write!(f, "key not found"),
+            TreeError::CapacityExceeded { key } => write!(f, "no capacity left to insert key {:?}", key),
+            TreeError::InvariantViolation(violation) => write!(f, "invariant violated: {}", violation),
+        }
+    }
+}
+
+impl<K: fmt::Debug> Error for TreeError<K> {}
+
+impl<K: Ord + Clone, V> From<OccupiedError<'_, K, V>> for TreeError<K> {
+    fn from(error: OccupiedError<'_, K, V>) -> Self {
+        TreeError::DuplicateKey {
+            key: error.entry.key(),
+        }
+    }
+}
+
+impl<K, V> From<Full<K, V>> for TreeError<K> {
+    fn from(error: Full<K, V>) -> Self {
+        TreeError::CapacityExceeded { key: error.key }
+    }
+}
+
+impl<K> From<InvariantViolation<K>> for TreeError<K> {
+    fn from(violation: InvariantViolation<K>) -> Self {
+        TreeError::InvariantViolation(violation)
+    }
+}
+
+impl<K: Ord, V> RedBlackTree<K, V> {
+    /// 同delete，键不存在时返回NotFound
+    /// 插入的对应版本为try_insert，其错误可经?转换为TreeError::DuplicateKey
+    pub fn try_delete<Q: Ord + ?Sized>(&mut self, key: &Q) -> Result<(), TreeError<K>>
+    where
+        K: core::borrow::Borrow<Q>,
+    {
+        let target_rc = self.find(key).ok_or(TreeError::NotFound)?;
+        self.delete_found(target_rc);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{RedBlackTree, StaticRbTree};
+    use super::TreeError;
+
+    fn pipeline(tree: &mut RedBlackTree<u32, u32>, key: u32) -> Result<(), TreeError<u32>> {
+        tree.try_insert(key, key)?;
+        tree.validate()?;
+        tree.try_delete(&(key + 1))?;
+        Ok(())
+    }
+
+    #[test]
+    fn specific_errors_convert() {
+        let mut tree = RedBlackTree::new();
+        assert_eq!(pipeline(&mut tree, 1), Err(TreeError::NotFound));
+        assert_eq!(pipeline(&mut tree, 1), Err(TreeError::DuplicateKey { key: 1 }));
+        assert_eq!(pipeline(&mut tree, 0), Ok(()));
+        assert_eq!(tree.try_delete(&0), Ok(()));
+        assert_eq!(tree.try_delete(&0), Err(TreeError::NotFound));
+
+        let mut full = StaticRbTree::<u32, (), 1>::new();
+        let mut fill = |key| -> Result<(), TreeError<u32>> { Ok(full.insert(key, ())?) };
+        assert_eq!(fill(1), Ok(()));
+        assert_eq!(fill(2), Err(TreeError::CapacityExceeded { key: 2 }));
+    }
+}
@@ -0,0 +1,109 @@
+//! 穷举形状的借用安全测试，仅用于测试
+//! 验证RedBlackTree类型文档中的借用安全保证：合法的树上任何公开操作都不会出现BorrowMutError
+//! 调平各情况只取决于附近几层的形状与颜色，这里枚举一定规模内全部合法的红黑树，
+//! 在每棵树上删除每个键、在每个空隙插入、弹出首尾，覆盖所有同时借用的组合
+
+use std::rc::Rc;
+
+use super::{Color, Node, RedBlackTree};
+use std::cell::RefCell;
+
+///最大节点数量
+const MAX_NODES: usize = 12;
+
+///只有形状与颜色的红黑树
+enum Shape {
+    Nil,
+    Node(Color, Rc<Shape>, Rc<Shape>),
+}
+
+/// 全部合法的子树
+/// black_height不计空节点，red_allowed为false时子树根不能为红色(父节点为红色)
+fn shapes(nodes: usize, black_height: usize, red_allowed: bool) -> Vec<Rc<Shape>> {
+    if nodes == 0 {
+        return if black_height == 0 { vec![Rc::new(Shape::Nil)] } else { Vec::new() };
+    }
+    let mut result = Vec::new();
+    for color in [Color::Black, Color::Red] {
+        let child_black_height = match color {
+            Color::Black if black_height == 0 => continue,
+            Color::Black => black_height - 1,
+            Color::Red if !red_allowed => continue,
+            Color::Red => black_height,
+        };
+        for left_nodes in 0..nodes {
+            let rights = shapes(nodes - 1 - left_nodes, child_black_height, color == Color::Black);
+            for left in shapes(left_nodes, child_black_height, color == Color::Black) {
+                for right in &rights {
+                    result.push(Rc::new(Shape::Node(color, Rc::clone(&left), Rc::clone(right))));
+                }
+            }
+        }
+    }
+    result
+}
+
+///按形状构造树，中序的键依次为2、4、6……，奇数键为插入的空隙
+fn build(shape: &Shape) -> RedBlackTree<u32, u32> {
+    fn build_node(shape: &Shape, next_key: &mut u32, parent_option: Option<&Rc<RefCell<Node<u32, u32>>>>) -> Option<Rc<RefCell<Node<u32, u32>>>> {
+        let Shape::Node(color, left, right) = shape else {
+            return None;
+        };
+        let node_rc = Rc::new(RefCell::new(Node { key: 0, value: 0, parent: parent_option.map(Rc::downgrade), left: None, right: None, color: *color, size: 1, count: 1 }));
+        let left_option = build_node(left, next_key, Some(&node_rc));
+        *next_key += 2;
+        let key = *next_key;
+        let right_option = build_node(right, next_key, Some(&node_rc));
+        {
+            let mut node = node_rc.borrow_mut();
+            node.key = key;
+            node.value = key;
+            node.size = RedBlackTree::size_of(&left_option) + RedBlackTree::size_of(&right_option) + 1;
+            node.left = left_option;
+            node.right = right_option;
+        }
+        Some(node_rc)
+    }
+    let mut tree = RedBlackTree::new();
+    tree.root = build_node(shape, &mut 0, None);
+    tree.len = RedBlackTree::size_of(&tree.root);
+    tree
+}
+
+fn assert_keys(tree: &RedBlackTree<u32, u32>, keys: impl Iterator<Item = u32>) {
+    assert_eq!(tree.validate(), Ok(()));
+    assert_eq!(tree.debug_refcounts(), []);
+    assert!(tree.keys().eq(keys));
+}
+
+#[test]
+fn every_operation_on_every_small_tree() {
+    let mut trees = 0;
+    for nodes in 1..=MAX_NODES {
+        let keys = || (1..=nodes as u32).map(|index| index * 2);
+        for black_height in 1..=4 {
+            for shape in shapes(nodes, black_height, false) {
+                trees += 1;
+                assert_keys(&build(&shape), keys());
+                for key in keys() {
+                    let mut tree = build(&shape);
+                    tree.delete(&key);
+                    assert_keys(&tree, keys().filter(|other| *other != key));
+                }
+                for gap in (0..=nodes as u32).map(|index| index * 2 + 1) {
+                    let mut tree = build(&shape);
+                    tree.insert(gap, gap);
+                    let mut expected: Vec<u32> = keys().collect();
+                    expected.insert(gap as usize / 2, gap);
+                    assert_keys(&tree, expected.into_iter());
+                }
+                let mut tree = build(&shape);
+                tree.pop_first();
+                tree.pop_last();
+                assert_keys(&tree, keys().skip(1).take(nodes.saturating_sub(2)));
+            }
+        }
+    }
+    //确认枚举本身没有遗漏，12个节点以内根为黑色的合法红黑树
+    assert_eq!(trees, 1394);
+}
@@ -0,0 +1,630 @@
+//! 基于裸指针的红黑树
+//! 节点由Box分配，连接为NonNull指针，与标准库内部的树结构相同，没有Rc计数与RefCell借用检查
+//! 算法与RedBlackTreeArena相同，对外只提供安全接口
+//!
+//! 安全性约定：树中出现的每个指针都来自Box::leak，且只被本树持有
+//! 节点只在删除(remove_node)与析构(Drop)时通过Box::from_raw释放，释放后不再被任何连接指向
+//! 字段的读写均通过裸指针的位置表达式进行，不长期持有节点的&mut，避免别名冲突
+//! miri校验：cargo +nightly miri test --lib --no-default-features --features fast fast::
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::fmt;
+use core::marker::PhantomData;
+use core::ops::RangeBounds;
+use core::ptr::NonNull;
+
+use super::super::ordered_map::{after_start, before_end};
+use super::{Color, InvariantViolation};
+
+type Link<K, V> = Option<NonNull<FastNode<K, V>>>;
+
+struct FastNode<K, V> {
+    key: K,
+    value: V,
+    parent: Link<K, V>,
+    left: Link<K, V>,
+    right: Link<K, V>,
+    color: Color,
+}
+
+pub struct RedBlackTreeFast<K, V> {
+    root: Link<K, V>,
+    len: usize,
+    //表明树拥有节点，供drop检查使用
+    marker: PhantomData<Box<FastNode<K, V>>>,
+}
+
+//与Box<FastNode>相同，节点只被本树持有
+unsafe impl<K: Send, V: Send> Send for RedBlackTreeFast<K, V> {}
+unsafe impl<K: Sync, V: Sync> Sync for RedBlackTreeFast<K, V> {}
+
+impl<K, V> RedBlackTreeFast<K, V> {
+    //以下访问器要求node为本树中存活的节点
+
+    fn left(&self, node: NonNull<FastNode<K, V>>) -> Link<K, V> {
+        unsafe { (*node.as_ptr()).left }
+    }
+
+    fn right(&self, node: NonNull<FastNode<K, V>>) -> Link<K, V> {
+        unsafe { (*node.as_ptr()).right }
+    }
+
+    fn parent(&self, node: NonNull<FastNode<K, V>>) -> Link<K, V> {
+        unsafe { (*node.as_ptr()).parent }
+    }
+
+    fn set_left(&mut self, node: NonNull<FastNode<K, V>>, link: Link<K, V>) {
+        unsafe { (*node.as_ptr()).left = link }
+    }
+
+    fn set_right(&mut self, node: NonNull<FastNode<K, V>>, link: Link<K, V>) {
+        unsafe { (*node.as_ptr()).right = link }
+    }
+
+    fn set_parent(&mut self, node: NonNull<FastNode<K, V>>, link: Link<K, V>) {
+        unsafe { (*node.as_ptr()).parent = link }
+    }
+
+    ///空指针视为黑色
+    fn color(&self, link: Link<K, V>) -> Color {
+        match link {
+            Some(node) => unsafe { (*node.as_ptr()).color },
+            None => Color::Black,
+        }
+    }
+
+    fn set_color(&mut self, node: NonNull<FastNode<K, V>>, color: Color) {
+        unsafe { (*node.as_ptr()).color = color }
+    }
+
+    fn key(&self, node: NonNull<FastNode<K, V>>) -> &K {
+        unsafe { &(*node.as_ptr()).key }
+    }
+
+    fn entry(&self, node: NonNull<FastNode<K, V>>) -> (&K, &V) {
+        unsafe { (&(*node.as_ptr()).key, &(*node.as_ptr()).value) }
+    }
+
+    ///清空树，逐个释放节点
+    pub fn clear(&mut self) {
+        let mut stack: Vec<NonNull<FastNode<K, V>>> = self.root.take().into_iter().collect();
+        while let Some(node) = stack.pop() {
+            //节点只出现一次，释放后不再访问
+            let boxed = unsafe { Box::from_raw(node.as_ptr()) };
+            stack.extend(boxed.left);
+            stack.extend(boxed.right);
+        }
+        self.len = 0;
+    }
+}
+
+impl<K: Ord, V> RedBlackTreeFast<K, V> {
+    pub fn new() -> Self {
+        RedBlackTreeFast { root: None, len: 0, marker: PhantomData }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn find(&self, key: &K) -> Link<K, V> {
+        let mut cur = self.root;
+        while let Some(node) = cur {
+            cur = match key.cmp(self.key(node)) {
+                Ordering::Equal => return cur,
+                Ordering::Less => self.left(node),
+                Ordering::Greater => self.right(node),
+            };
+        }
+        None
+    }
+
+    fn minimum(&self, mut node: NonNull<FastNode<K, V>>) -> NonNull<FastNode<K, V>> {
+        while let Some(left) = self.left(node) {
+            node = left;
+        }
+        node
+    }
+
+    fn maximum(&self, mut node: NonNull<FastNode<K, V>>) -> NonNull<FastNode<K, V>> {
+        while let Some(right) = self.right(node) {
+            node = right;
+        }
+        node
+    }
+
+    ///后继，有右子树则为右子树最小节点，否则向上寻找第一个从左侧到达的祖先
+    fn successor(&self, node: NonNull<FastNode<K, V>>) -> Link<K, V> {
+        if let Some(right) = self.right(node) {
+            return Some(self.minimum(right));
+        }
+        let mut cur = node;
+        let mut parent = self.parent(node);
+        while let Some(parent_node) = parent {
+            if self.right(parent_node) != Some(cur) {
+                break;
+            }
+            cur = parent_node;
+            parent = self.parent(parent_node);
+        }
+        parent
+    }
+
+    ///前驱，与后继对称
+    fn predecessor(&self, node: NonNull<FastNode<K, V>>) -> Link<K, V> {
+        if let Some(left) = self.left(node) {
+            return Some(self.maximum(left));
+        }
+        let mut cur = node;
+        let mut parent = self.parent(node);
+        while let Some(parent_node) = parent {
+            if self.left(parent_node) != Some(cur) {
+                break;
+            }
+            cur = parent_node;
+            parent = self.parent(parent_node);
+        }
+        parent
+    }
+
+    ///在parent中以new取代子节点old，parent为空时取代根
+    fn replace_child(&mut self, parent: Link<K, V>, old: NonNull<FastNode<K, V>>, new: Link<K, V>) {
+        match parent {
+            None => self.root = new,
+            Some(parent_node) if self.left(parent_node) == Some(old) => self.set_left(parent_node, new),
+            Some(parent_node) => self.set_right(parent_node, new),
+        }
+    }
+
+    ///左旋，x的右子节点上升
+    fn rotate_left(&mut self, x: NonNull<FastNode<K, V>>) {
+        let y = self.right(x).expect("rotate_left without right child");
+        let y_left = self.left(y);
+        self.set_right(x, y_left);
+        if let Some(y_left) = y_left {
+            self.set_parent(y_left, Some(x));
+        }
+        let x_parent = self.parent(x);
+        self.set_parent(y, x_parent);
+        self.replace_child(x_parent, x, Some(y));
+        self.set_left(y, Some(x));
+        self.set_parent(x, Some(y));
+    }
+
+    ///右旋，x的左子节点上升
+    fn rotate_right(&mut self, x: NonNull<FastNode<K, V>>) {
+        let y = self.left(x).expect("rotate_right without left child");
+        let y_right = self.right(y);
+        self.set_left(x, y_right);
+        if let Some(y_right) = y_right {
+            self.set_parent(y_right, Some(x));
+        }
+        let x_parent = self.parent(x);
+        self.set_parent(y, x_parent);
+        self.replace_child(x_parent, x, Some(y));
+        self.set_right(y, Some(x));
+        self.set_parent(x, Some(y));
+    }
+
+    ///插入键值对，键已存在时忽略
+    pub fn insert(&mut self, key: K, value: V) {
+        let mut parent = None;
+        let mut ordering = Ordering::Equal;
+        let mut cur = self.root;
+        while let Some(node) = cur {
+            ordering = key.cmp(self.key(node));
+            parent = cur;
+            cur = match ordering {
+                Ordering::Equal => return,
+                Ordering::Less => self.left(node),
+                Ordering::Greater => self.right(node),
+            };
+        }
+        let boxed = Box::new(FastNode { key, value, parent, left: None, right: None, color: Color::Red });
+        let node = NonNull::from(Box::leak(boxed));
+        match parent {
+            None => self.root = Some(node),
+            Some(parent_node) if ordering == Ordering::Less => self.set_left(parent_node, Some(node)),
+            Some(parent_node) => self.set_right(parent_node, Some(node)),
+        }
+        self.len += 1;
+        self.insert_balance(node);
+    }
+
+    /// 插入平衡
+    /// 叔节点为红色时父、叔染黑，爷节点染红后以爷节点继续向上
+    /// 叔节点为黑色时经一到两次旋转结束
+    fn insert_balance(&mut self, mut son: NonNull<FastNode<K, V>>) {
+        while let Some(parent) = self.parent(son).filter(|parent| self.color(Some(*parent)) == Color::Red) {
+            //父节点为红色，必不是根节点，爷节点存在
+            let grand_parent = self.parent(parent).expect("red node without parent");
+            let parent_is_left = self.left(grand_parent) == Some(parent);
+            let uncle = if parent_is_left { self.right(grand_parent) } else { self.left(grand_parent) };
+            if let Some(uncle) = uncle.filter(|uncle| self.color(Some(*uncle)) == Color::Red) {
+                self.set_color(parent, Color::Black);
+                self.set_color(uncle, Color::Black);
+                self.set_color(grand_parent, Color::Red);
+                son = grand_parent;
+                continue;
+            }
+            let mut top = parent;
+            if parent_is_left {
+                //LR先转为LL
+                if self.right(parent) == Some(son) {
+                    self.rotate_left(parent);
+                    top = son;
+                }
+                self.rotate_right(grand_parent);
+            } else {
+                //RL先转为RR
+                if self.left(parent) == Some(son) {
+                    self.rotate_right(parent);
+                    top = son;
+                }
+                self.rotate_left(grand_parent);
+            }
+            self.set_color(top, Color::Black);
+            self.set_color(grand_parent, Color::Red);
+            break;
+        }
+        if let Some(root) = self.root {
+            self.set_color(root, Color::Black);
+        }
+    }
+
+    ///删除键，键不存在时忽略
+    pub fn delete(&mut self, key: &K) {
+        if let Some(target) = self.find(key) {
+            self.remove_node(target);
+        }
+    }
+
+    ///删除键并返回值
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        self.find(key).map(|target| self.remove_node(target).1)
+    }
+
+    /// 删除节点
+    /// 有两个子节点时以后继节点取代其位置与颜色，实际离开的是后继节点
+    /// 离开的节点为黑色时，从补位节点(可能为空)与其父节点开始调平
+    fn remove_node(&mut self, target: NonNull<FastNode<K, V>>) -> (K, V) {
+        let (left, right) = (self.left(target), self.right(target));
+        let (fix, fix_parent, removed_color);
+        match (left, right) {
+            (Some(left), Some(right)) => {
+                let successor = self.minimum(right);
+                removed_color = self.color(Some(successor));
+                fix = self.right(successor);
+                if self.parent(successor) == Some(target) {
+                    fix_parent = Some(successor);
+                } else {
+                    fix_parent = self.parent(successor);
+                    if let Some(fix) = fix {
+                        self.set_parent(fix, fix_parent);
+                    }
+                    self.set_left(fix_parent.expect("successor without parent"), fix);
+                    self.set_right(successor, Some(right));
+                    self.set_parent(right, Some(successor));
+                }
+                let target_parent = self.parent(target);
+                self.replace_child(target_parent, target, Some(successor));
+                self.set_parent(successor, target_parent);
+                self.set_left(successor, Some(left));
+                let target_color = self.color(Some(target));
+                self.set_color(successor, target_color);
+                self.set_parent(left, Some(successor));
+            }
+            _ => {
+                fix = left.or(right);
+                fix_parent = self.parent(target);
+                removed_color = self.color(Some(target));
+                if let Some(fix) = fix {
+                    self.set_parent(fix, fix_parent);
+                }
+                self.replace_child(fix_parent, target, fix);
+            }
+        }
+        if removed_color == Color::Black {
+            self.delete_balance(fix, fix_parent);
+        }
+        self.len -= 1;
+        //节点已脱离树，不再被任何连接指向
+        let boxed = unsafe { Box::from_raw(target.as_ptr()) };
+        (boxed.key, boxed.value)
+    }
+
+    /// 删除平衡，son所在一侧黑高少一
+    /// 兄弟为红色时旋转转为兄弟为黑色
+    /// 兄弟的子节点都为黑色时兄弟染红，失衡上移
+    /// 否则经一到两次旋转结束
+    fn delete_balance(&mut self, mut son: Link<K, V>, mut parent: Link<K, V>) {
+        while son != self.root && self.color(son) == Color::Black {
+            //son不是根节点，父节点存在，黑高少一的一侧兄弟必存在
+            let parent_node = parent.expect("unbalanced node without parent");
+            let son_is_left = self.left(parent_node) == son;
+            let sibling = |tree: &Self| if son_is_left { tree.right(parent_node) } else { tree.left(parent_node) };
+            let mut brother = sibling(self).expect("black-height deficit without brother");
+            if self.color(Some(brother)) == Color::Red {
+                self.set_color(brother, Color::Black);
+                self.set_color(parent_node, Color::Red);
+                if son_is_left {
+                    self.rotate_left(parent_node);
+                } else {
+                    self.rotate_right(parent_node);
+                }
+                brother = sibling(self).expect("black-height deficit without brother");
+            }
+            let (near, far) = if son_is_left {
+                (self.left(brother), self.right(brother))
+            } else {
+                (self.right(brother), self.left(brother))
+            };
+            if self.color(near) == Color::Black && self.color(far) == Color::Black {
+                self.set_color(brother, Color::Red);
+                son = parent;
+                parent = self.parent(parent_node);
+                continue;
+            }
+            if self.color(far) == Color::Black {
+                //近侄子为红，先转到远侧
+                self.set_color(near.expect("red nephew"), Color::Black);
+                self.set_color(brother, Color::Red);
+                if son_is_left {
+                    self.rotate_right(brother);
+                } else {
+                    self.rotate_left(brother);
+                }
+                brother = sibling(self).expect("black-height deficit without brother");
+            }
+            let far = if son_is_left { self.right(brother) } else { self.left(brother) };
+            let parent_color = self.color(parent);
+            self.set_color(brother, parent_color);
+            self.set_color(parent_node, Color::Black);
+            self.set_color(far.expect("red nephew"), Color::Black);
+            if son_is_left {
+                self.rotate_left(parent_node);
+            } else {
+                self.rotate_right(parent_node);
+            }
+            son = self.root;
+        }
+        if let Some(son) = son {
+            self.set_color(son, Color::Black);
+        }
+    }
+
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.find(key).map(|node| self.entry(node).1)
+    }
+
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        //&mut self保证没有其他借用
+        self.find(key).map(|node| unsafe { &mut (*node.as_ptr()).value })
+    }
+
+    pub fn contains(&self, key: &K) -> bool {
+        self.find(key).is_some()
+    }
+
+    pub fn get_key_value(&self, key: &K) -> Option<(&K, &V)> {
+        self.find(key).map(|node| self.entry(node))
+    }
+
+    pub fn first(&self) -> Option<(&K, &V)> {
+        self.root.map(|root| self.entry(self.minimum(root)))
+    }
+
+    pub fn last(&self) -> Option<(&K, &V)> {
+        self.root.map(|root| self.entry(self.maximum(root)))
+    }
+
+    pub fn pop_first(&mut self) -> Option<(K, V)> {
+        self.root.map(|root| self.remove_node(self.minimum(root)))
+    }
+
+    pub fn pop_last(&mut self) -> Option<(K, V)> {
+        self.root.map(|root| self.remove_node(self.maximum(root)))
+    }
+
+    ///中序迭代，产出借用
+    pub fn iter(&self) -> FastIter<'_, K, V> {
+        FastIter {
+            tree: self,
+            front: self.root.map(|root| self.minimum(root)),
+            back: self.root.map(|root| self.maximum(root)),
+        }
+    }
+
+    ///按键升序迭代范围内的条目，两端各沿查找路径下降一次，O(log n)
+    pub fn range<R: RangeBounds<K>>(&self, range: R) -> FastIter<'_, K, V> {
+        let (mut front, mut back) = (None, None);
+        let mut cur = self.root;
+        while let Some(node) = cur {
+            if after_start(range.start_bound(), self.key(node)) {
+                front = cur;
+                cur = self.left(node);
+            } else {
+                cur = self.right(node);
+            }
+        }
+        cur = self.root;
+        while let Some(node) = cur {
+            if before_end(range.end_bound(), self.key(node)) {
+                back = cur;
+                cur = self.right(node);
+            } else {
+                cur = self.left(node);
+            }
+        }
+        //范围内没有节点，或起点在终点之后
+        match (front, back) {
+            (Some(first), Some(last)) if self.key(first) <= self.key(last) => FastIter { tree: self, front, back },
+            _ => FastIter { tree: self, front: None, back: None },
+        }
+    }
+
+    ///校验红黑树性质，与RedBlackTreeArena::validate相同
+    pub fn validate(&self) -> Result<(), InvariantViolation<K>>
+    where
+        K: Clone,
+    {
+        if let Some(root) = self.root {
+            if self.color(Some(root)) == Color::Red {
+                return Err(InvariantViolation::RedRoot);
+            }
+            if self.parent(root).is_some() {
+                return Err(InvariantViolation::ParentPointer { key: self.key(root).clone() });
+            }
+        }
+        let mut count = 0;
+        self.validate_node(self.root, None, None, &mut count)?;
+        if count != self.len {
+            return Err(InvariantViolation::LengthMismatch { expected: self.len, actual: count });
+        }
+        Ok(())
+    }
+
+    ///递归校验子树，返回子树黑高(空节点黑高为1)
+    fn validate_node(&self, link: Link<K, V>, lower: Option<&K>, upper: Option<&K>, count: &mut usize) -> Result<usize, InvariantViolation<K>>
+    where
+        K: Clone,
+    {
+        let Some(node) = link else {
+            return Ok(1);
+        };
+        *count += 1;
+        let key = self.key(node);
+        if lower.is_some_and(|lower| key <= lower) || upper.is_some_and(|upper| key >= upper) {
+            return Err(InvariantViolation::OutOfOrder { key: key.clone() });
+        }
+        for son in [self.left(node), self.right(node)].into_iter().flatten() {
+            if self.parent(son) != Some(node) {
+                return Err(InvariantViolation::ParentPointer { key: self.key(son).clone() });
+            }
+            if self.color(link) == Color::Red && self.color(Some(son)) == Color::Red {
+                return Err(InvariantViolation::RedRed { key: key.clone() });
+            }
+        }
+        let left = self.validate_node(self.left(node), lower, Some(key), count)?;
+        let right = self.validate_node(self.right(node), Some(key), upper, count)?;
+        if left != right {
+            return Err(InvariantViolation::BlackHeight { key: key.clone(), left, right });
+        }
+        Ok(left + usize::from(self.color(link) == Color::Black))
+    }
+}
+
+impl<K, V> Drop for RedBlackTreeFast<K, V> {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+impl<K: Ord, V> Default for RedBlackTreeFast<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Ord + fmt::Debug, V: fmt::Debug> fmt::Debug for RedBlackTreeFast<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+/// 中序迭代器
+/// front为下一个产出的节点，back为最后一个产出的节点，二者相遇时迭代结束
+pub struct FastIter<'a, K, V> {
+    tree: &'a RedBlackTreeFast<K, V>,
+    front: Link<K, V>,
+    back: Link<K, V>,
+}
+
+impl<'a, K: Ord, V> Iterator for FastIter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let cur = self.front?;
+        if self.back == Some(cur) {
+            self.front = None;
+            self.back = None;
+        } else {
+            self.front = self.tree.successor(cur);
+        }
+        Some(self.tree.entry(cur))
+    }
+}
+
+impl<K: Ord, V> DoubleEndedIterator for FastIter<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let cur = self.back?;
+        if self.front == Some(cur) {
+            self.front = None;
+            self.back = None;
+        } else {
+            self.back = self.tree.predecessor(cur);
+        }
+        Some(self.tree.entry(cur))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    use super::RedBlackTreeFast;
+
+    //miri下运行较慢，规模保持较小
+    #[test]
+    fn matches_btree_map_model() {
+        let mut rng = StdRng::seed_from_u64(2033);
+        let mut tree = RedBlackTreeFast::new();
+        let mut model = BTreeMap::new();
+        for step in 0..2_000 {
+            let key = rng.gen_range(0..100);
+            match rng.gen_range(0..10) {
+                0..=5 => {
+                    tree.insert(key, step);
+                    model.entry(key).or_insert(step);
+                }
+                6..=8 => assert_eq!(tree.remove(&key), model.remove(&key)),
+                _ => {
+                    if let Some(value) = tree.get_mut(&key) {
+                        *value += 1;
+                    }
+                    if let Some(value) = model.get_mut(&key) {
+                        *value += 1;
+                    }
+                }
+            }
+            if step % 50 == 0 {
+                assert_eq!(tree.validate(), Ok(()));
+                assert!(tree.iter().eq(model.iter()));
+                assert!(tree.iter().rev().eq(model.iter().rev()));
+            }
+        }
+        assert_eq!(tree.pop_first(), model.pop_first());
+        assert_eq!(tree.pop_last(), model.pop_last());
+    }
+
+    #[test]
+    fn drops_owned_values() {
+        let mut tree = RedBlackTreeFast::new();
+        for key in 0..64 {
+            tree.insert(key, key.to_string());
+        }
+        tree.delete(&10);
+        tree.clear();
+        assert!(tree.is_empty());
+        tree.insert(1, String::from("again"));
+    }
+}
@@ -0,0 +1,109 @@
+//! 空隙迭代器
+//! 范围内相邻两个已有键之间、范围端点与最近的已有键之间，都是不含任何键的极大区间
+//! 只知道键的顺序，无法判断整数等离散键的(3, 4)这类区间是否为空，需要时由调用者过滤
+
+use alloc::rc::Rc;
+use core::cell::RefCell;
+use core::marker::PhantomData;
+use core::ops::{Bound, RangeBounds};
+
+use super::{Node, RedBlackTree};
+
+pub struct Gaps<'a, K, V> {
+    //下一个已有键的节点
+    next: Option<Rc<RefCell<Node<K, V>>>>,
+    //下一个空隙的下界，迭代结束时为None
+    lower: Option<Bound<K>>,
+    upper: Bound<K>,
+    tree: PhantomData<&'a RedBlackTree<K, V>>,
+}
+
+impl<K: Ord + Clone, V> RedBlackTree<K, V> {
+    /// 按升序返回范围内不含任何键的极大区间
+    /// 区间以已有键为开端点，两端受范围本身的端点限制；明显为空的区间(如范围起点恰为已有键)不会返回
+    pub fn gaps<R: RangeBounds<K>>(&self, range: R) -> Gaps<'_, K, V> {
+        Gaps { next: self.find_lower_bound(range.start_bound()), lower: Some(range.start_bound().cloned()), upper: range.end_bound().cloned(), tree: PhantomData }
+    }
+}
+
+///区间是否一定为空
+fn is_empty<K: Ord>(lower: &Bound<K>, upper: &Bound<K>) -> bool {
+    match (lower, upper) {
+        (Bound::Included(start), Bound::Included(end)) => start > end,
+        (Bound::Included(start) | Bound::Excluded(start), Bound::Excluded(end)) | (Bound::Excluded(start), Bound::Included(end)) => start >= end,
+        _ => false,
+    }
+}
+
+impl<K: Ord + Clone, V> Iterator for Gaps<'_, K, V> {
+    type Item = (Bound<K>, Bound<K>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let lower = self.lower.take()?;
+            match self.next.take() {
+                Some(node_rc) if (Bound::Unbounded, self.upper.as_ref()).contains(&node_rc.borrow().key) => {
+                    let key = node_rc.borrow().key.clone();
+                    self.next = RedBlackTree::successor(&node_rc);
+                    self.lower = Some(Bound::Excluded(key.clone()));
+                    let upper = Bound::Excluded(key);
+                    if !is_empty(&lower, &upper) {
+                        return Some((lower, upper));
+                    }
+                }
+                //范围内已没有键，最后一个空隙延伸到范围终点
+                _ => {
+                    let upper = self.upper.clone();
+                    return (!is_empty(&lower, &upper)).then_some((lower, upper));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+    use core::ops::Bound::{Excluded, Included, Unbounded};
+    use core::ops::RangeInclusive;
+
+    use super::super::RedBlackTree;
+
+    #[test]
+    fn gaps_between_keys() {
+        let mut tree = RedBlackTree::new();
+        assert_eq!(tree.gaps(..).collect::<Vec<_>>(), [(Unbounded, Unbounded)]);
+        for key in [3u32, 4, 7, 10] {
+            tree.insert(key, ());
+        }
+        assert_eq!(
+            tree.gaps(..).collect::<Vec<_>>(),
+            [(Unbounded, Excluded(3)), (Excluded(3), Excluded(4)), (Excluded(4), Excluded(7)), (Excluded(7), Excluded(10)), (Excluded(10), Unbounded)]
+        );
+        assert_eq!(tree.gaps(4..=10).collect::<Vec<_>>(), [(Excluded(4), Excluded(7)), (Excluded(7), Excluded(10))]);
+        assert_eq!(tree.gaps(5..12).collect::<Vec<_>>(), [(Included(5), Excluded(7)), (Excluded(7), Excluded(10)), (Excluded(10), Excluded(12))]);
+        assert_eq!(tree.gaps(8..9).collect::<Vec<_>>(), [(Included(8), Excluded(9))]);
+        assert_eq!(tree.gaps(3..=4).count(), 1);
+        assert_eq!(tree.gaps((Included(9), Excluded(5))).count(), 0);
+
+        //分配编号：换成闭区间并去掉空区间
+        let free: Vec<RangeInclusive<u32>> = tree
+            .gaps(1..=12)
+            .map(|(lower, upper)| {
+                let start = match lower {
+                    Included(start) => start,
+                    Excluded(start) => start + 1,
+                    Unbounded => 0,
+                };
+                let end = match upper {
+                    Included(end) => end,
+                    Excluded(end) => end - 1,
+                    Unbounded => u32::MAX,
+                };
+                start..=end
+            })
+            .filter(|range| !range.is_empty())
+            .collect();
+        assert_eq!(free, [1..=2, 5..=6, 8..=9, 11..=12]);
+    }
+}
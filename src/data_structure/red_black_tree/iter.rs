@@ -0,0 +1,335 @@
+use alloc::rc::Rc;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+use core::marker::PhantomData;
+use core::ptr::NonNull;
+
+use super::{Node, RedBlackTree};
+
+/// 中序迭代器
+/// 借助父节点指针寻找后继节点，无需额外的栈
+/// front为下一个产出的节点，back为最后一个产出的节点，二者相遇时迭代结束
+/// 支持rev()从大到小迭代，两端可交替推进
+/// 节点位于RefCell内，迭代产出键值对的拷贝
+pub struct Iter<'a, K, V> {
+    //借用树，防止迭代过程中修改树
+    _tree: &'a RedBlackTree<K, V>,
+    front: Option<Rc<RefCell<Node<K, V>>>>,
+    back: Option<Rc<RefCell<Node<K, V>>>>,
+}
+
+impl<'a, K: Ord, V> Iter<'a, K, V> {
+    pub(super) fn new(tree: &'a RedBlackTree<K, V>) -> Self {
+        Iter {
+            _tree: tree,
+            front: tree.root.as_ref().map(RedBlackTree::find_minimum),
+            back: tree.root.as_ref().map(RedBlackTree::find_maximum),
+        }
+    }
+
+    /// 以front、back为首尾的迭代器
+    /// front的键大于back的键时为空迭代器
+    pub(super) fn between(tree: &'a RedBlackTree<K, V>, front: Option<Rc<RefCell<Node<K, V>>>>, back: Option<Rc<RefCell<Node<K, V>>>>) -> Self {
+        match (front, back) {
+            (Some(front_rc), Some(back_rc)) if front_rc.borrow().key <= back_rc.borrow().key => {
+                Iter { _tree: tree, front: Some(front_rc), back: Some(back_rc) }
+            }
+            _ => Iter { _tree: tree, front: None, back: None },
+        }
+    }
+
+    ///产出下一个节点，不要求K、V可拷贝
+    fn next_node(&mut self) -> Option<Rc<RefCell<Node<K, V>>>> {
+        let cur_rc = self.front.take()?;
+        //与back相遇，迭代结束
+        match &self.back {
+            Some(back_ref) if Rc::ptr_eq(back_ref, &cur_rc) => {
+                self.back = None;
+            }
+            _ => {
+                self.front = RedBlackTree::successor(&cur_rc);
+            }
+        }
+        Some(cur_rc)
+    }
+
+    ///从大到小产出节点，与next_node对称，借助前驱节点
+    fn next_back_node(&mut self) -> Option<Rc<RefCell<Node<K, V>>>> {
+        let cur_rc = self.back.take()?;
+        match &self.front {
+            Some(front_ref) if Rc::ptr_eq(front_ref, &cur_rc) => {
+                self.front = None;
+            }
+            _ => {
+                self.back = RedBlackTree::predecessor(&cur_rc);
+            }
+        }
+        Some(cur_rc)
+    }
+}
+
+impl<K: Ord + Clone, V: Clone> Iterator for Iter<'_, K, V> {
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_node().map(|node_rc| RedBlackTree::clone_entry(&node_rc))
+    }
+}
+
+impl<K: Ord + Clone, V: Clone> DoubleEndedIterator for Iter<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.next_back_node().map(|node_rc| RedBlackTree::clone_entry(&node_rc))
+    }
+}
+
+///键的迭代器，产出键的拷贝
+pub struct Keys<'a, K, V> {
+    iter: Iter<'a, K, V>,
+}
+
+impl<K: Ord + Clone, V> Iterator for Keys<'_, K, V> {
+    type Item = K;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next_node().map(|node_rc| node_rc.borrow().key.clone())
+    }
+}
+
+impl<K: Ord + Clone, V> DoubleEndedIterator for Keys<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.iter.next_back_node().map(|node_rc| node_rc.borrow().key.clone())
+    }
+}
+
+///值的迭代器，产出值的拷贝
+pub struct Values<'a, K, V> {
+    iter: Iter<'a, K, V>,
+}
+
+impl<K: Ord, V: Clone> Iterator for Values<'_, K, V> {
+    type Item = V;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next_node().map(|node_rc| node_rc.borrow().value.clone())
+    }
+}
+
+impl<K: Ord, V: Clone> DoubleEndedIterator for Values<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.iter.next_back_node().map(|node_rc| node_rc.borrow().value.clone())
+    }
+}
+
+/// 值的可变迭代器
+/// 树被独占借用期间节点不会被释放，也没有其他借用
+/// 开始时按中序记下全部值的地址，迭代中不再访问节点的连接，已借出的值与后续遍历互不重叠
+pub struct ValuesMut<'a, K, V> {
+    values: alloc::vec::IntoIter<NonNull<V>>,
+    _tree: PhantomData<&'a mut RedBlackTree<K, V>>,
+}
+
+impl<'a, K, V> ValuesMut<'a, K, V> {
+    fn value_of(value: NonNull<V>) -> &'a mut V {
+        //SAFETY: 节点由独占借用'a的树持有，'a内存活；每个值只产出一次，RefCell没有未结束的借用
+        unsafe { &mut *value.as_ptr() }
+    }
+}
+
+impl<'a, K, V> Iterator for ValuesMut<'a, K, V> {
+    type Item = &'a mut V;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.values.next().map(Self::value_of)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.values.size_hint()
+    }
+}
+
+impl<K, V> DoubleEndedIterator for ValuesMut<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.values.next_back().map(Self::value_of)
+    }
+}
+
+impl<K, V> ExactSizeIterator for ValuesMut<'_, K, V> {}
+
+/// 按键升序取出全部键值对
+/// 创建时一次拆下全部节点，节点间不再相连，逐个取出无需调平
+pub struct IntoIter<K, V> {
+    nodes: alloc::vec::IntoIter<Rc<RefCell<Node<K, V>>>>,
+}
+
+impl<K, V> IntoIter<K, V> {
+    ///nodes为升序排列、彼此不再相连的节点
+    pub(super) fn new(nodes: Vec<Rc<RefCell<Node<K, V>>>>) -> Self {
+        IntoIter { nodes: nodes.into_iter() }
+    }
+
+    pub(super) fn entry_of(node_rc: Rc<RefCell<Node<K, V>>>) -> (K, V) {
+        let node = match Rc::try_unwrap(node_rc) {
+            Ok(node_cell) => node_cell.into_inner(),
+            Err(_) => unreachable!("detached node is still referenced"),
+        };
+        (node.key, node.value)
+    }
+}
+
+impl<K, V> Iterator for IntoIter<K, V> {
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.nodes.next().map(Self::entry_of)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.nodes.size_hint()
+    }
+}
+
+impl<K, V> DoubleEndedIterator for IntoIter<K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.nodes.next_back().map(Self::entry_of)
+    }
+}
+
+impl<K, V> ExactSizeIterator for IntoIter<K, V> {}
+
+///按键升序取出全部键
+pub struct IntoKeys<K, V> {
+    inner: IntoIter<K, V>,
+}
+
+impl<K, V> Iterator for IntoKeys<K, V> {
+    type Item = K;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(key, _)| key)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K, V> DoubleEndedIterator for IntoKeys<K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back().map(|(key, _)| key)
+    }
+}
+
+impl<K, V> ExactSizeIterator for IntoKeys<K, V> {}
+
+///按键升序取出全部值
+pub struct IntoValues<K, V> {
+    inner: IntoIter<K, V>,
+}
+
+impl<K, V> Iterator for IntoValues<K, V> {
+    type Item = V;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(_, value)| value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K, V> DoubleEndedIterator for IntoValues<K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back().map(|(_, value)| value)
+    }
+}
+
+impl<K, V> ExactSizeIterator for IntoValues<K, V> {}
+
+impl<K: Ord, V> RedBlackTree<K, V> {
+    pub fn keys(&self) -> Keys<'_, K, V> {
+        Keys { iter: self.iter() }
+    }
+
+    pub fn values(&self) -> Values<'_, K, V> {
+        Values { iter: self.iter() }
+    }
+
+    /// 按键升序可变借用每个值
+    /// 节点位于RefCell内，借出前先收集全部值的地址，O(n)额外空间
+    pub fn values_mut(&mut self) -> ValuesMut<'_, K, V> {
+        let mut iter = self.iter();
+        let values: Vec<_> = core::iter::from_fn(|| iter.next_node())
+            .map(|node_rc| {
+                //只取地址，不创建引用，也不持有节点的计数
+                //SAFETY: as_ptr指向存活节点的内容，非空
+                unsafe { NonNull::new_unchecked(&raw mut (*node_rc.as_ptr()).value) }
+            })
+            .collect();
+        ValuesMut { values: values.into_iter(), _tree: PhantomData }
+    }
+
+    pub fn into_keys(self) -> IntoKeys<K, V> {
+        IntoKeys { inner: self.into_iter() }
+    }
+
+    pub fn into_values(self) -> IntoValues<K, V> {
+        IntoValues { inner: self.into_iter() }
+    }
+}
+
+/// 多重集模式下每个键只产出一次
+impl<K: Ord, V> IntoIterator for RedBlackTree<K, V> {
+    type Item = (K, V);
+    type IntoIter = IntoIter<K, V>;
+
+    fn into_iter(mut self) -> IntoIter<K, V> {
+        self.drain()
+    }
+}
+
+impl<'a, K: Ord + Clone, V: Clone> IntoIterator for &'a RedBlackTree<K, V> {
+    type Item = (K, V);
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Iter<'a, K, V> {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::super::RedBlackTree;
+
+    #[test]
+    fn iterator_family_matches_btree_map() {
+        let mut tree = RedBlackTree::new();
+        let mut model = BTreeMap::new();
+        for key in [5u32, 3, 9, 1, 4, 7, 8, 2, 6, 0] {
+            tree.insert(key, key.to_string());
+            model.insert(key, key.to_string());
+        }
+        assert!(tree.keys().eq(model.keys().copied()));
+        assert!(tree.values().rev().eq(model.values().rev().cloned()));
+        for (value, model_value) in tree.values_mut().zip(model.values_mut()) {
+            value.push('!');
+            model_value.push('!');
+        }
+        assert!((&tree).into_iter().eq(model.clone()));
+        let mut values = tree.values_mut();
+        let (first, last) = (values.next().unwrap(), values.next_back().unwrap());
+        std::mem::swap(first, last);
+        assert_eq!(values.len(), 8);
+        model.insert(0, "9!".to_string());
+        model.insert(9, "0!".to_string());
+        let mut into_iter = tree.into_iter();
+        assert_eq!(into_iter.next_back(), model.pop_last());
+        assert!(into_iter.eq(model.clone()));
+        let tree = RedBlackTree::from_sorted_iter(model.clone());
+        assert!(tree.into_keys().eq(model.keys().copied()));
+        let tree = RedBlackTree::from_sorted_iter(model.clone());
+        assert!(tree.into_values().rev().eq(model.into_values().rev()));
+    }
+}
@@ -0,0 +1,398 @@
+//! 红黑树的合并(join)与分割(split)
+//! join: 左树所有键小于中间节点，右树所有键大于中间节点，沿较高一侧的边缘下降到黑高相同处挂接，O(log n)
+//! split: 沿查找路径拆开，路径两侧的子树自底向上依次join；每次join重新计算两侧黑高，共O(log² n)
+//! 在此基础上实现split_off、append与remove_range
+//! 挂接后的红红冲突复用插入平衡处理
+
+use alloc::rc::Rc;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+use core::ops::{Bound, RangeBounds};
+
+use super::{Color, IntoIter, Node, RawEntryMut, RedBlackTree};
+
+///分割结果：小于键的部分，等于键的节点，大于键的部分
+type SplitParts<K, V> = (Option<Rc<RefCell<Node<K, V>>>>, Option<Rc<RefCell<Node<K, V>>>>, Option<Rc<RefCell<Node<K, V>>>>);
+
+///断开后的左右子树
+type Children<K, V> = (Option<Rc<RefCell<Node<K, V>>>>, Option<Rc<RefCell<Node<K, V>>>>);
+
+impl<K: Ord, V> RedBlackTree<K, V> {
+    /// 分割，返回所有键大于等于key的新树，小于key的部分留在原树
+    /// 新树沿用本树的设置，见empty_like
+    /// 通过split/join完成，O(log² n)
+    pub fn split_off(&mut self, key: &K) -> Self {
+        let (left_option, equal_option, right_option) = Self::split_subtree(self.take_root(), key);
+        let right_option = match equal_option {
+            Some(equal_rc) => Self::join_subtrees(None, equal_rc, right_option),
+            None => right_option,
+        };
+        self.set_root(left_option);
+        let mut right = self.empty_like();
+        right.set_root(right_option);
+        self.sample_validation();
+        right.sample_validation();
+        right
+    }
+
+    /// 将other的全部键值对移入本树，other变为空树
+    /// 两树键范围不重叠时以other或本树的边界节点为中间节点join，O(log n)
+    /// 范围重叠时逐个插入，键已存在时以other的值为准，重复次数相加
+    pub fn append(&mut self, other: &mut Self) {
+        let (self_root_ref, other_root_ref) = match (&self.root, &other.root) {
+            (_, None) => return,
+            (None, Some(_)) => {
+                self.set_root(other.take_root());
+                return;
+            }
+            (Some(self_root_ref), Some(other_root_ref)) => (self_root_ref, other_root_ref),
+        };
+        let other_min_rc = Self::find_minimum(other_root_ref);
+        let self_min_rc = Self::find_minimum(self_root_ref);
+        let self_before_other = Self::find_maximum(self_root_ref).borrow().key < other_min_rc.borrow().key;
+        let other_before_self = Self::find_maximum(other_root_ref).borrow().key < self_min_rc.borrow().key;
+        let root_option = if self_before_other {
+            //本树全部小于other，取other的最小节点为中间节点
+            let middle_rc = other.detach_node(&other_min_rc);
+            Self::join_subtrees(self.take_root(), middle_rc, other.take_root())
+        } else if other_before_self {
+            //other全部小于本树，取本树的最小节点为中间节点
+            let middle_rc = self.detach_node(&self_min_rc);
+            Self::join_subtrees(other.take_root(), middle_rc, self.take_root())
+        } else {
+            //逐个取出时节点不能被其他引用持有
+            drop((other_min_rc, self_min_rc));
+            for node_rc in Self::take_nodes_in_order(other.take_root()) {
+                let count = node_rc.borrow().count;
+                let (key, value) = IntoIter::entry_of(node_rc);
+                match self.raw_entry_mut().from_key(&key) {
+                    RawEntryMut::Occupied(mut occupied) => {
+                        occupied.insert(value);
+                        occupied.node.borrow_mut().count += count;
+                    }
+                    RawEntryMut::Vacant(vacant) => vacant.insert_entry(key, value).node.borrow_mut().count = count,
+                }
+            }
+            self.sample_validation();
+            return;
+        };
+        self.set_root(root_option);
+        self.sample_validation();
+    }
+
+    /// 删除范围内的全部键，以新树返回被删除的部分，丢弃返回值即释放
+    /// 新树沿用本树的设置，见empty_like
+    /// 两次split取出范围内的部分，剩余两部分再join，O(log² n)(不含释放节点)
+    pub fn remove_range<R: RangeBounds<K>>(&mut self, range: R) -> Self {
+        let (below_option, rest_option) = match range.start_bound() {
+            Bound::Included(key) => Self::split_keep(self.take_root(), key, false),
+            Bound::Excluded(key) => Self::split_keep(self.take_root(), key, true),
+            Bound::Unbounded => (None, self.take_root()),
+        };
+        let (inside_option, above_option) = match range.end_bound() {
+            Bound::Included(key) => Self::split_keep(rest_option, key, true),
+            Bound::Excluded(key) => Self::split_keep(rest_option, key, false),
+            Bound::Unbounded => (rest_option, None),
+        };
+        let root_option = Self::concat_subtrees(below_option, above_option);
+        self.set_root(root_option);
+        let mut removed = self.empty_like();
+        removed.set_root(inside_option);
+        self.sample_validation();
+        removed
+    }
+
+    ///按键分割为两部分，等于键的节点按equal_to_left归入左侧或右侧
+    fn split_keep(root_option: Option<Rc<RefCell<Node<K, V>>>>, key: &K, equal_to_left: bool) -> Children<K, V> {
+        let (left_option, equal_option, right_option) = Self::split_subtree(root_option, key);
+        match equal_option {
+            Some(equal_rc) if equal_to_left => (Self::join_subtrees(left_option, equal_rc, None), right_option),
+            Some(equal_rc) => (left_option, Self::join_subtrees(None, equal_rc, right_option)),
+            None => (left_option, right_option),
+        }
+    }
+
+    ///连接左右两部分，取右侧最小节点为中间节点join
+    fn concat_subtrees(left_option: Option<Rc<RefCell<Node<K, V>>>>, right_option: Option<Rc<RefCell<Node<K, V>>>>) -> Option<Rc<RefCell<Node<K, V>>>> {
+        let right_root_ref = match (&left_option, &right_option) {
+            (None, _) => return right_option,
+            (_, None) => return left_option,
+            (Some(_), Some(right_root_ref)) => right_root_ref,
+        };
+        let minimum_rc = Self::find_minimum(right_root_ref);
+        let mut right = RedBlackTree::new();
+        right.set_root(right_option);
+        let middle_rc = right.detach_node(&minimum_rc);
+        Self::join_subtrees(left_option, middle_rc, right.take_root())
+    }
+
+    ///取出根节点，树变为空树
+    fn take_root(&mut self) -> Option<Rc<RefCell<Node<K, V>>>> {
+        self.len = 0;
+        self.root.take()
+    }
+
+    ///以独立的子树为根，根染黑，节点数量取自子树节点数量
+    fn set_root(&mut self, root_option: Option<Rc<RefCell<Node<K, V>>>>) {
+        if let Some(root_ref) = &root_option {
+            root_ref.borrow_mut().color = Color::Black;
+        }
+        self.len = Self::size_of(&root_option);
+        self.root = root_option;
+    }
+
+    ///从树中删除节点，重置为可挂接的孤立节点
+    fn detach_node(&mut self, node_rc: &Rc<RefCell<Node<K, V>>>) -> Rc<RefCell<Node<K, V>>> {
+        self.delete_node(node_rc);
+        //删除后节点仍可能保留旧的子节点引用，直接覆盖，不能修改这些子节点
+        {
+            let mut node = node_rc.borrow_mut();
+            node.parent = None;
+            node.left = None;
+            node.right = None;
+            node.size = 1;
+        }
+        Rc::clone(node_rc)
+    }
+
+    ///断开节点与左右子树的连接，返回左右子树，节点重置为孤立节点
+    fn split_children(node_rc: &Rc<RefCell<Node<K, V>>>) -> Children<K, V> {
+        let mut node = node_rc.borrow_mut();
+        let left_option = node.left.take();
+        let right_option = node.right.take();
+        for son_ref in [&left_option, &right_option].into_iter().flatten() {
+            son_ref.borrow_mut().parent = None;
+        }
+        node.parent = None;
+        node.size = 1;
+        (left_option, right_option)
+    }
+
+    ///黑高，空树为0
+    pub(super) fn subtree_black_height(root_option: &Option<Rc<RefCell<Node<K, V>>>>) -> usize {
+        let mut height = 0;
+        let mut next_option = root_option.clone();
+        while let Some(cur_rc) = next_option {
+            let cur = cur_rc.borrow();
+            if cur.color == Color::Black {
+                height += 1;
+            }
+            next_option = cur.left.clone();
+        }
+        height
+    }
+
+    /// 合并左子树、中间节点、右子树
+    /// 两侧的根先染黑，沿黑高较大一侧的边缘下降到黑高相同的黑色节点(或空位)
+    /// 中间节点染红后取代该位置，以该节点和另一侧为左右子树，再按插入平衡处理红红冲突
+    fn join_subtrees(left_option: Option<Rc<RefCell<Node<K, V>>>>, middle_rc: Rc<RefCell<Node<K, V>>>, right_option: Option<Rc<RefCell<Node<K, V>>>>) -> Option<Rc<RefCell<Node<K, V>>>> {
+        for root_ref in [&left_option, &right_option].into_iter().flatten() {
+            root_ref.borrow_mut().color = Color::Black;
+        }
+        let left_height = Self::subtree_black_height(&left_option);
+        let right_height = Self::subtree_black_height(&right_option);
+        //descend_right为真时沿左树右边缘下降，否则沿右树左边缘下降
+        let descend_right = left_height >= right_height;
+        let (tall_option, short_option, mut height, target_height) = if descend_right {
+            (left_option, right_option, left_height, right_height)
+        } else {
+            (right_option, left_option, right_height, left_height)
+        };
+        //借用插入平衡，临时树以较高一侧为根
+        let mut tree = RedBlackTree::new();
+        tree.root = tall_option;
+        let mut parent_option: Option<Rc<RefCell<Node<K, V>>>> = None;
+        let mut cur_option = tree.root.clone();
+        while let Some(cur_rc) = cur_option.clone() {
+            let cur = cur_rc.borrow();
+            if cur.color == Color::Black {
+                if height == target_height {
+                    break;
+                }
+                height -= 1;
+            }
+            cur_option = if descend_right { cur.right.clone() } else { cur.left.clone() };
+            drop(cur);
+            parent_option = Some(cur_rc);
+        }
+        let short_size = Self::size_of(&short_option);
+        {
+            let mut middle = middle_rc.borrow_mut();
+            for son_ref in [&cur_option, &short_option].into_iter().flatten() {
+                son_ref.borrow_mut().parent = Some(Rc::downgrade(&middle_rc));
+            }
+            middle.size = Self::size_of(&cur_option) + short_size + 1;
+            middle.color = Color::Red;
+            if descend_right {
+                middle.left = cur_option;
+                middle.right = short_option;
+            } else {
+                middle.left = short_option;
+                middle.right = cur_option;
+            }
+        }
+        match &parent_option {
+            None => {
+                middle_rc.borrow_mut().color = Color::Black;
+                tree.root = Some(middle_rc);
+            }
+            Some(parent_ref) => {
+                middle_rc.borrow_mut().parent = Some(Rc::downgrade(parent_ref));
+                {
+                    let mut parent = parent_ref.borrow_mut();
+                    if descend_right {
+                        parent.right = Some(Rc::clone(&middle_rc));
+                    } else {
+                        parent.left = Some(Rc::clone(&middle_rc));
+                    }
+                }
+                //边缘上的祖先都多出了另一侧的子树与中间节点
+                let mut next_option = Some(Rc::clone(parent_ref));
+                while let Some(cur_rc) = next_option {
+                    let mut cur = cur_rc.borrow_mut();
+                    cur.size += short_size + 1;
+                    next_option = cur.parent.as_ref().and_then(alloc::rc::Weak::upgrade);
+                }
+                if parent_ref.borrow().color == Color::Red {
+                    tree.insert_balance(parent_ref, &middle_rc);
+                }
+            }
+        }
+        tree.root.take()
+    }
+
+    /// 按键分割子树
+    /// 沿查找路径下降时拆开每个节点，路径左侧(小于键)与右侧(大于键)的部分各自入栈，再自底向上join
+    fn split_subtree(root_option: Option<Rc<RefCell<Node<K, V>>>>, key: &K) -> SplitParts<K, V> {
+        let mut left_pieces = Vec::new();
+        let mut right_pieces = Vec::new();
+        let mut left_option = None;
+        let mut equal_option = None;
+        let mut right_option = None;
+        let mut next_option = root_option;
+        while let Some(cur_rc) = next_option {
+            let (cur_left_option, cur_right_option) = Self::split_children(&cur_rc);
+            let ordering = key.cmp(&cur_rc.borrow().key);
+            match ordering {
+                core::cmp::Ordering::Equal => {
+                    left_option = cur_left_option;
+                    right_option = cur_right_option;
+                    equal_option = Some(cur_rc);
+                    break;
+                }
+                core::cmp::Ordering::Less => {
+                    right_pieces.push((cur_rc, cur_right_option));
+                    next_option = cur_left_option;
+                }
+                core::cmp::Ordering::Greater => {
+                    left_pieces.push((cur_left_option, cur_rc));
+                    next_option = cur_right_option;
+                }
+            }
+        }
+        while let Some((piece_left_option, middle_rc)) = left_pieces.pop() {
+            left_option = Self::join_subtrees(piece_left_option, middle_rc, left_option);
+        }
+        while let Some((middle_rc, piece_right_option)) = right_pieces.pop() {
+            right_option = Self::join_subtrees(right_option, middle_rc, piece_right_option);
+        }
+        (left_option, equal_option, right_option)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ops::{Bound, RangeBounds};
+
+    use super::super::{DuplicatePolicy, RedBlackTree};
+
+    #[test]
+    fn split_off_at_every_key() {
+        let n = 200;
+        for pivot in 0..=n + 1 {
+            let mut left = RedBlackTree::new();
+            for key in (0..n).map(|i| i * 37 % n) {
+                left.insert(key, ());
+            }
+            let right = left.split_off(&pivot);
+            assert_eq!(left.validate(), Ok(()), "pivot={}", pivot);
+            assert_eq!(right.validate(), Ok(()), "pivot={}", pivot);
+            assert!(left.iter().map(|(key, _)| key).eq(0..pivot.min(n)));
+            assert!(right.iter().map(|(key, _)| key).eq(pivot.min(n)..n));
+        }
+    }
+
+    #[test]
+    fn append_disjoint_and_overlapping() {
+        for (low, high) in [(0..50, 50..300), (100..400, 0..100), (0..10, 5..20), (0..0, 3..9)] {
+            let mut tree = RedBlackTree::new();
+            let mut other = RedBlackTree::new();
+            for key in low.clone() {
+                tree.insert(key, 0);
+            }
+            for key in high.clone() {
+                other.insert(key, 1);
+            }
+            tree.append(&mut other);
+            assert_eq!(tree.validate(), Ok(()));
+            assert!(other.is_empty());
+            let mut expected: Vec<_> = low.filter(|key| !high.contains(key)).map(|key| (key, 0)).chain(high.clone().map(|key| (key, 1))).collect();
+            expected.sort();
+            assert!(tree.iter().eq(expected));
+        }
+    }
+
+    #[test]
+    fn remove_range_bounds() {
+        let ranges: [(Bound<i32>, Bound<i32>); 6] = [
+            (Bound::Included(10), Bound::Excluded(20)),
+            (Bound::Excluded(10), Bound::Included(20)),
+            (Bound::Unbounded, Bound::Excluded(50)),
+            (Bound::Included(150), Bound::Unbounded),
+            (Bound::Unbounded, Bound::Unbounded),
+            (Bound::Included(30), Bound::Excluded(10)),
+        ];
+        for range in ranges {
+            let mut tree = RedBlackTree::from_sorted_iter((0..100).map(|key| (key, ())));
+            let removed = tree.remove_range(range);
+            assert_eq!(tree.validate(), Ok(()));
+            assert_eq!(removed.validate(), Ok(()));
+            assert!(tree.iter().map(|(key, _)| key).eq((0..100).filter(|key| !range.contains(key))));
+            assert!(removed.iter().map(|(key, _)| key).eq((0..100).filter(|key| range.contains(key))));
+        }
+    }
+
+    #[test]
+    fn split_parts_keep_settings() {
+        let mut tree = RedBlackTree::with_duplicate_policy(DuplicatePolicy::Count);
+        tree.set_validation_sampling(1);
+        for key in 0..100 {
+            tree.insert(key, ());
+        }
+        let mut right = tree.split_off(&50);
+        let mut removed = tree.remove_range(10..20);
+        for part in [&mut tree, &mut right, &mut removed] {
+            let key = part.first().unwrap().0;
+            part.insert(key, ());
+            assert_eq!((part.count(&key), part.validation_sampling), (2, 1));
+        }
+    }
+
+    #[test]
+    fn overlapping_append_keeps_counts() {
+        let mut tree = RedBlackTree::with_duplicate_policy(DuplicatePolicy::Count);
+        let mut other = RedBlackTree::with_duplicate_policy(DuplicatePolicy::Count);
+        for key in [1, 3, 3, 5] {
+            tree.insert(key, 0);
+        }
+        for key in [2, 3, 5, 5, 5, 6] {
+            other.insert(key, 1);
+        }
+        tree.append(&mut other);
+        assert_eq!(tree.validate(), Ok(()));
+        assert!(other.is_empty());
+        let counts: Vec<_> = (1..=6).map(|key| tree.count(&key)).collect();
+        assert_eq!(counts, [1, 1, 3, 0, 4, 1]);
+        assert!(tree.iter().eq([(1, 0), (2, 1), (3, 1), (5, 1), (6, 1)]));
+    }
+}
@@ -0,0 +1,90 @@
+//! JSON结构导出
+//! 每个节点为 {"key": ..., "color": "red" | "black", "left": ..., "right": ...}，缺少的子节点与空树为null
+//! 键以Display输出为JSON字符串，供外部可视化工具与notebook还原树的形状
+
+use alloc::rc::Rc;
+use alloc::string::String;
+use core::cell::RefCell;
+use core::fmt::{self, Write};
+
+use super::{Color, Node, RedBlackTree};
+
+impl<K: fmt::Display, V> RedBlackTree<K, V> {
+    ///以嵌套的JSON对象导出树结构
+    pub fn to_json(&self) -> String {
+        let mut out = String::new();
+        Self::write_json(&mut out, &self.root);
+        out
+    }
+
+    fn write_json(out: &mut String, node_option: &Option<Rc<RefCell<Node<K, V>>>>) {
+        let node_ref = match node_option {
+            None => return out.push_str("null"),
+            Some(node_ref) => node_ref,
+        };
+        let node = node_ref.borrow();
+        out.push_str("{\"key\":");
+        write_json_string(out, &node.key);
+        out.push_str(match node.color {
+            Color::Red => ",\"color\":\"red\",\"left\":",
+            Color::Black => ",\"color\":\"black\",\"left\":",
+        });
+        Self::write_json(out, &node.left);
+        out.push_str(",\"right\":");
+        Self::write_json(out, &node.right);
+        out.push('}');
+    }
+}
+
+///写出转义后的JSON字符串
+fn write_json_string<T: fmt::Display>(out: &mut String, value: &T) {
+    struct Escape<'a>(&'a mut String);
+
+    impl Write for Escape<'_> {
+        fn write_str(&mut self, s: &str) -> fmt::Result {
+            for c in s.chars() {
+                match c {
+                    '"' => self.0.push_str("\\\""),
+                    '\\' => self.0.push_str("\\\\"),
+                    '\n' => self.0.push_str("\\n"),
+                    '\r' => self.0.push_str("\\r"),
+                    '\t' => self.0.push_str("\\t"),
+                    c if (c as u32) < 0x20 => write!(self.0, "\\u{:04x}", c as u32)?,
+                    c => self.0.push(c),
+                }
+            }
+            Ok(())
+        }
+    }
+
+    out.push('"');
+    write!(Escape(out), "{}", value).expect("writing to a String cannot fail");
+    out.push('"');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::RedBlackTree;
+
+    #[test]
+    fn dump_matches_shape() {
+        let mut tree = RedBlackTree::new();
+        for key in 1..=3 {
+            tree.insert(key, ());
+        }
+        assert_eq!(
+            tree.to_json(),
+            r#"{"key":"2","color":"black","left":{"key":"1","color":"red","left":null,"right":null},"right":{"key":"3","color":"red","left":null,"right":null}}"#
+        );
+        assert_eq!(RedBlackTree::<u32, ()>::new().to_json(), "null");
+    }
+
+    #[test]
+    fn keys_are_escaped() {
+        let mut tree = RedBlackTree::new();
+        tree.insert(String::from("a\"b\\c\n\u{1}"), ());
+        let json: serde_json::Value = serde_json::from_str(&tree.to_json()).unwrap();
+        assert_eq!(json["key"], "a\"b\\c\n\u{1}");
+        assert!(json["left"].is_null());
+    }
+}
@@ -0,0 +1,191 @@
+//! 一键多值的有序映射
+//! 内部是值为VecDeque<V>的RedBlackTree，同一个键的值按插入顺序排列
+//! 与树一样，查询与迭代产出键值的拷贝
+
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+use core::fmt;
+
+use super::{Iter, RedBlackTree};
+
+/// 一键多值的有序映射
+pub struct RedBlackMultiMap<K, V> {
+    map: RedBlackTree<K, VecDeque<V>>,
+    //键值对总数
+    len: usize,
+}
+
+impl<K: Ord, V> RedBlackMultiMap<K, V> {
+    pub fn new() -> Self {
+        RedBlackMultiMap { map: RedBlackTree::new(), len: 0 }
+    }
+
+    ///插入键值对，键已存在时追加在该键已有的值之后
+    pub fn insert(&mut self, key: K, value: V) {
+        let mut value = Some(value);
+        self.map.update(&key, |values| values.push_back(value.take().unwrap()));
+        if let Some(value) = value {
+            self.map.insert(key, VecDeque::from([value]));
+        }
+        self.len += 1;
+    }
+
+    ///删除键最早插入的一个值，最后一个值删除后键也被删除
+    pub fn remove_one<Q: Ord + ?Sized>(&mut self, key: &Q) -> Option<V>
+    where
+        K: core::borrow::Borrow<Q>,
+    {
+        let (value, emptied) = self.map.update(key, |values| (values.pop_front(), values.is_empty()))?;
+        if emptied {
+            self.map.delete(key);
+        }
+        self.len -= 1;
+        value
+    }
+
+    ///删除键及其全部值，按插入顺序返回
+    pub fn remove_all<Q: Ord + ?Sized>(&mut self, key: &Q) -> Vec<V>
+    where
+        K: core::borrow::Borrow<Q>,
+    {
+        let values: Vec<V> = self.map.take(key).map(|(_, values)| values.into()).unwrap_or_default();
+        self.len -= values.len();
+        values
+    }
+
+    ///键的值数量，不存在时为0
+    pub fn count<Q: Ord + ?Sized>(&self, key: &Q) -> usize
+    where
+        K: core::borrow::Borrow<Q>,
+    {
+        self.map.find(key).map_or(0, |node_rc| node_rc.borrow().value.len())
+    }
+
+    pub fn contains_key<Q: Ord + ?Sized>(&self, key: &Q) -> bool
+    where
+        K: core::borrow::Borrow<Q>,
+    {
+        self.map.contains(key)
+    }
+
+    ///键值对总数
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    ///不同键的数量
+    pub fn keys_len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn clear(&mut self) {
+        self.map.clear();
+        self.len = 0;
+    }
+}
+
+impl<K: Ord + Clone, V: Clone> RedBlackMultiMap<K, V> {
+    ///键的全部值，按插入顺序，不存在时为空
+    pub fn get_all<Q: Ord + ?Sized>(&self, key: &Q) -> Vec<V>
+    where
+        K: core::borrow::Borrow<Q>,
+    {
+        self.map.get(key).map(Vec::from).unwrap_or_default()
+    }
+
+    ///按键升序迭代全部键值对，同一个键的值按插入顺序
+    pub fn iter(&self) -> MultiMapIter<'_, K, V> {
+        MultiMapIter { iter: self.map.iter(), current: None }
+    }
+}
+
+/// 一键多值映射的迭代器，每个键值对产出一次
+pub struct MultiMapIter<'a, K, V> {
+    iter: Iter<'a, K, VecDeque<V>>,
+    current: Option<(K, alloc::collections::vec_deque::IntoIter<V>)>,
+}
+
+impl<K: Ord + Clone, V: Clone> Iterator for MultiMapIter<'_, K, V> {
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<(K, V)> {
+        loop {
+            if let Some((key, values)) = &mut self.current {
+                if let Some(value) = values.next() {
+                    return Some((key.clone(), value));
+                }
+            }
+            let (key, values) = self.iter.next()?;
+            self.current = Some((key, values.into_iter()));
+        }
+    }
+}
+
+impl<'a, K: Ord + Clone, V: Clone> IntoIterator for &'a RedBlackMultiMap<K, V> {
+    type Item = (K, V);
+    type IntoIter = MultiMapIter<'a, K, V>;
+
+    fn into_iter(self) -> MultiMapIter<'a, K, V> {
+        self.iter()
+    }
+}
+
+impl<K: Ord, V> FromIterator<(K, V)> for RedBlackMultiMap<K, V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut map = RedBlackMultiMap::new();
+        map.extend(iter);
+        map
+    }
+}
+
+impl<K: Ord, V> Extend<(K, V)> for RedBlackMultiMap<K, V> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        for (key, value) in iter {
+            self.insert(key, value);
+        }
+    }
+}
+
+impl<K: Ord, V> Default for RedBlackMultiMap<K, V> {
+    fn default() -> Self {
+        RedBlackMultiMap::new()
+    }
+}
+
+///按映射格式输出，重复的键各占一项
+impl<K: Ord + Clone + fmt::Debug, V: Clone + fmt::Debug> fmt::Debug for RedBlackMultiMap<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::format;
+    use alloc::vec::Vec;
+
+    use super::RedBlackMultiMap;
+
+    #[test]
+    fn values_per_key_in_insertion_order() {
+        let mut events: RedBlackMultiMap<u64, &str> = [(20, "b"), (10, "a"), (20, "c"), (30, "d"), (20, "e")].into_iter().collect();
+        assert_eq!((events.len(), events.keys_len()), (5, 3));
+        assert_eq!(events.get_all(&20), ["b", "c", "e"]);
+        assert_eq!(events.count(&20), 3);
+        assert!(events.get_all(&15).is_empty());
+        assert_eq!(events.iter().collect::<Vec<_>>(), [(10, "a"), (20, "b"), (20, "c"), (20, "e"), (30, "d")]);
+
+        assert_eq!(events.remove_one(&20), Some("b"));
+        assert_eq!(events.remove_one(&10), Some("a"));
+        assert!(!events.contains_key(&10));
+        assert_eq!(events.remove_one(&10), None);
+        assert_eq!(events.remove_all(&20), ["c", "e"]);
+        assert_eq!(events.remove_all(&20), Vec::<&str>::new());
+        assert_eq!((events.len(), events.keys_len()), (1, 1));
+        assert_eq!(format!("{:?}", events), r#"{30: "d"}"#);
+    }
+}
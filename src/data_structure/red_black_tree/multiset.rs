@@ -0,0 +1,189 @@
+//! 计数的有序多重集
+//! 内部是值为()的RedBlackTree，重复的元素只占一个节点，由节点的重复次数记录
+//! len按重复次数累计，迭代时每个元素按重复次数展开
+
+use alloc::rc::Rc;
+use core::cell::RefCell;
+use core::fmt;
+use core::marker::PhantomData;
+
+use super::{Node, RedBlackTree};
+
+/// 计数的有序多重集
+pub struct RedBlackMultiSet<T> {
+    map: RedBlackTree<T, ()>,
+    //计入重复次数的元素总数
+    len: usize,
+}
+
+impl<T: Ord> RedBlackMultiSet<T> {
+    pub fn new() -> Self {
+        RedBlackMultiSet { map: RedBlackTree::new(), len: 0 }
+    }
+
+    ///插入元素，已存在时重复次数加一
+    pub fn insert(&mut self, value: T) {
+        self.map.insert_multi(value, ());
+        self.len += 1;
+    }
+
+    ///元素的重复次数减一，归零时删除，元素不存在时返回false
+    pub fn remove_one<Q: Ord + ?Sized>(&mut self, value: &Q) -> bool
+    where
+        T: core::borrow::Borrow<Q>,
+    {
+        let removed = self.map.try_delete(value).is_ok();
+        self.len -= usize::from(removed);
+        removed
+    }
+
+    ///删除元素的全部重复，返回删除的数量
+    pub fn remove_all<Q: Ord + ?Sized>(&mut self, value: &Q) -> usize
+    where
+        T: core::borrow::Borrow<Q>,
+    {
+        let count = self.map.count(value);
+        self.map.take(value);
+        self.len -= count;
+        count
+    }
+
+    ///元素的重复次数，不存在时为0
+    pub fn count<Q: Ord + ?Sized>(&self, value: &Q) -> usize
+    where
+        T: core::borrow::Borrow<Q>,
+    {
+        self.map.count(value)
+    }
+
+    pub fn contains<Q: Ord + ?Sized>(&self, value: &Q) -> bool
+    where
+        T: core::borrow::Borrow<Q>,
+    {
+        self.map.contains(value)
+    }
+
+    ///计入重复次数的元素总数
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    ///不同元素的数量
+    pub fn distinct_len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn clear(&mut self) {
+        self.map.clear();
+        self.len = 0;
+    }
+
+    ///按升序迭代，每个元素按重复次数重复产出
+    pub fn iter(&self) -> MultiSetIter<'_, T> {
+        let next = self.map.root.as_ref().map(RedBlackTree::find_minimum);
+        let remaining = next.as_ref().map_or(0, |node_rc| node_rc.borrow().count);
+        MultiSetIter { next, remaining, set: PhantomData }
+    }
+}
+
+/// 多重集的中序迭代器，沿后继节点推进
+pub struct MultiSetIter<'a, T> {
+    next: Option<Rc<RefCell<Node<T, ()>>>>,
+    //当前节点还需产出的次数
+    remaining: usize,
+    set: PhantomData<&'a RedBlackMultiSet<T>>,
+}
+
+impl<T: Ord + Clone> Iterator for MultiSetIter<'_, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        let node_rc = self.next.as_ref()?;
+        let value = node_rc.borrow().key.clone();
+        self.remaining -= 1;
+        if self.remaining == 0 {
+            let next = RedBlackTree::successor(node_rc);
+            self.remaining = next.as_ref().map_or(0, |next_rc| next_rc.borrow().count);
+            self.next = next;
+        }
+        Some(value)
+    }
+}
+
+impl<'a, T: Ord + Clone> IntoIterator for &'a RedBlackMultiSet<T> {
+    type Item = T;
+    type IntoIter = MultiSetIter<'a, T>;
+
+    fn into_iter(self) -> MultiSetIter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<T: Ord> FromIterator<T> for RedBlackMultiSet<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut set = RedBlackMultiSet::new();
+        set.extend(iter);
+        set
+    }
+}
+
+impl<T: Ord> Extend<T> for RedBlackMultiSet<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for value in iter {
+            self.insert(value);
+        }
+    }
+}
+
+impl<T: Ord> Default for RedBlackMultiSet<T> {
+    fn default() -> Self {
+        RedBlackMultiSet::new()
+    }
+}
+
+///按列表格式输出，重复的元素各占一项，如[1, 1, 2]
+impl<T: Ord + Clone + fmt::Debug> fmt::Debug for RedBlackMultiSet<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    use super::RedBlackMultiSet;
+
+    #[test]
+    fn matches_counted_btree_map() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut set = RedBlackMultiSet::new();
+        let mut model: BTreeMap<u8, usize> = BTreeMap::new();
+        for _ in 0..5000 {
+            let value = rng.gen_range(0..32u8);
+            match rng.gen_range(0..4) {
+                0 | 1 => {
+                    set.insert(value);
+                    *model.entry(value).or_default() += 1;
+                }
+                2 => {
+                    let expected = model.get_mut(&value).map(|count| *count -= 1).is_some();
+                    model.retain(|_, count| *count > 0);
+                    assert_eq!(set.remove_one(&value), expected);
+                }
+                _ => assert_eq!(set.remove_all(&value), model.remove(&value).unwrap_or(0)),
+            }
+            assert_eq!(set.count(&value), model.get(&value).copied().unwrap_or(0));
+        }
+        assert_eq!((set.len(), set.distinct_len()), (model.values().sum(), model.len()));
+        assert!(set.iter().eq(model.iter().flat_map(|(value, count)| std::iter::repeat_n(*value, *count))));
+        assert_eq!(format!("{:?}", [3, 1, 3].into_iter().collect::<RedBlackMultiSet<_>>()), "[1, 3, 3]");
+    }
+}
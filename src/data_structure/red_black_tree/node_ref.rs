@@ -0,0 +1,111 @@
+//! 节点句柄
+//! 由查找得到，借助父指针直接走到相邻节点，无需从根重新查找
+//! 沿句柄遍历全部节点时每条边至多经过两次，单步均摊O(1)
+
+use alloc::rc::Rc;
+use core::cell::{Ref, RefCell};
+use core::marker::PhantomData;
+
+use super::{Node, RedBlackTree};
+
+/// 指向树中某个节点的句柄，存活期间树不可修改
+pub struct NodeRef<'a, K, V> {
+    node: Rc<RefCell<Node<K, V>>>,
+    tree: PhantomData<&'a RedBlackTree<K, V>>,
+}
+
+impl<K: Ord, V> RedBlackTree<K, V> {
+    ///键所在节点的句柄
+    pub fn get_node<Q: Ord + ?Sized>(&self, key: &Q) -> Option<NodeRef<'_, K, V>>
+    where
+        K: core::borrow::Borrow<Q>,
+    {
+        self.find(key).map(NodeRef::new)
+    }
+
+    ///最小节点的句柄
+    pub fn first_node(&self) -> Option<NodeRef<'_, K, V>> {
+        self.root.as_ref().map(|root_ref| NodeRef::new(Self::find_minimum(root_ref)))
+    }
+
+    ///最大节点的句柄
+    pub fn last_node(&self) -> Option<NodeRef<'_, K, V>> {
+        self.root.as_ref().map(|root_ref| NodeRef::new(Self::find_maximum(root_ref)))
+    }
+}
+
+impl<'a, K: Ord, V> NodeRef<'a, K, V> {
+    fn new(node: Rc<RefCell<Node<K, V>>>) -> Self {
+        NodeRef { node, tree: PhantomData }
+    }
+
+    pub fn key(&self) -> K
+    where
+        K: Clone,
+    {
+        self.node.borrow().key.clone()
+    }
+
+    pub fn value(&self) -> V
+    where
+        V: Clone,
+    {
+        self.node.borrow().value.clone()
+    }
+
+    /// 借出键与值，不要求K、V可拷贝
+    /// 句柄存活期间树不可修改，借用不会与可变借用冲突
+    pub fn entry(&self) -> (Ref<'_, K>, Ref<'_, V>) {
+        Ref::map_split(self.node.borrow(), |node| (&node.key, &node.value))
+    }
+
+    ///后继节点的句柄
+    pub fn next(&self) -> Option<NodeRef<'a, K, V>> {
+        RedBlackTree::successor(&self.node).map(NodeRef::new)
+    }
+
+    ///前驱节点的句柄
+    pub fn prev(&self) -> Option<NodeRef<'a, K, V>> {
+        RedBlackTree::predecessor(&self.node).map(NodeRef::new)
+    }
+}
+
+impl<K, V> Clone for NodeRef<'_, K, V> {
+    fn clone(&self) -> Self {
+        NodeRef { node: Rc::clone(&self.node), tree: PhantomData }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use super::super::RedBlackTree;
+
+    #[test]
+    fn merge_join_by_stepping() {
+        let left = RedBlackTree::from_sorted_iter((0..1000u32).step_by(2).map(|key| (key, key)));
+        let right = RedBlackTree::from_sorted_iter((0..1000u32).step_by(3).map(|key| (key, key * 10)));
+        let mut joined = Vec::new();
+        let (mut left_node, mut right_node) = (left.first_node(), right.first_node());
+        while let (Some(left_ref), Some(right_ref)) = (&left_node, &right_node) {
+            let (left_key, right_key) = (left_ref.key(), right_ref.key());
+            if left_key <= right_key {
+                left_node = left_ref.next();
+            }
+            if right_key <= left_key {
+                right_node = right_ref.next();
+            }
+            if left_key == right_key {
+                joined.push(right_key);
+            }
+        }
+        assert_eq!(joined, (0..1000).step_by(6).collect::<Vec<u32>>());
+
+        let node = left.get_node(&500).unwrap();
+        assert_eq!((node.prev().unwrap().key(), node.value(), node.next().unwrap().value()), (498, 500, 502));
+        assert!(left.get_node(&501).is_none());
+        assert!(left.first_node().unwrap().prev().is_none());
+        assert!(left.last_node().unwrap().next().is_none());
+    }
+}
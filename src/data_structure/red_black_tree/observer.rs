@@ -0,0 +1,109 @@
+//! 结构操作的观察者
+//! 插入、删除、旋转、变色时按发生顺序通知注册的观察者，用于逐步展示调平过程
+//! 只有逐个节点的插入删除会通知插入、删除事件；from_sorted_iter、retain等批量重建不逐节点通知
+
+use alloc::rc::Rc;
+use core::cell::RefCell;
+
+use super::{Color, DeleteRecursionSituation, DeleteSituation, Node, RedBlackTree};
+
+/// 观察者，方法默认为空操作，按需实现
+/// 通知时树正在修改中，观察者只能看到事件本身
+pub trait Observer<K> {
+    /// 新节点以红色挂到叶子位置之后、调平之前
+    /// 插入空树时新节点直接成为黑色的根，不另行通知变色
+    fn on_insert(&self, _key: &K) {}
+
+    /// 节点脱离树之前
+    /// 有两个子节点时由后继节点取代其位置与颜色，后继节点的右子节点取代后继节点的位置与颜色，均不另行通知变色
+    fn on_delete(&self, _key: &K) {}
+
+    ///以key所在节点为轴左旋之前，该节点下降为其右子节点的左子节点
+    fn on_rotate_left(&self, _key: &K) {}
+
+    ///以key所在节点为轴右旋之前，该节点下降为其左子节点的右子节点
+    fn on_rotate_right(&self, _key: &K) {}
+
+    ///节点颜色实际变化之后
+    fn on_recolor(&self, _key: &K, _color: Color) {}
+
+    ///删除黑色叶子后、按situation调平之前，key为删除节点的父节点
+    fn on_delete_situation(&self, _key: &K, _situation: DeleteSituation) {}
+
+    ///黑高减少向上传递、按situation调平之前，key为失衡节点
+    fn on_delete_recursion_situation(&self, _key: &K, _situation: DeleteRecursionSituation) {}
+}
+
+impl<K, V> RedBlackTree<K, V> {
+    /// 注册观察者，替换已有的观察者
+    /// 调用方保留Rc即可在操作后读取观察者记录的内容；clone得到的树共享同一观察者
+    pub fn set_observer<O: Observer<K> + 'static>(&mut self, observer: Rc<O>) {
+        self.observer = Some(observer);
+    }
+
+    ///移除并返回观察者
+    pub fn take_observer(&mut self) -> Option<Rc<dyn Observer<K>>> {
+        self.observer.take()
+    }
+
+    ///通知观察者，未注册时为空操作
+    #[inline]
+    pub(super) fn notify<F: FnOnce(&dyn Observer<K>, &K)>(&self, node_ref: &Rc<RefCell<Node<K, V>>>, event: F) {
+        if let Some(observer) = &self.observer {
+            event(observer.as_ref(), &node_ref.borrow().key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::rc::Rc;
+    use alloc::vec::Vec;
+    use core::cell::RefCell;
+
+    use super::super::{Color, RedBlackTree};
+    use super::Observer;
+
+    #[derive(Default)]
+    struct Log(RefCell<Vec<String>>);
+
+    impl Observer<u32> for Log {
+        fn on_insert(&self, key: &u32) {
+            self.0.borrow_mut().push(format!("insert {}", key));
+        }
+
+        fn on_delete(&self, key: &u32) {
+            self.0.borrow_mut().push(format!("delete {}", key));
+        }
+
+        fn on_rotate_left(&self, key: &u32) {
+            self.0.borrow_mut().push(format!("rotate_left {}", key));
+        }
+
+        fn on_rotate_right(&self, key: &u32) {
+            self.0.borrow_mut().push(format!("rotate_right {}", key));
+        }
+
+        fn on_recolor(&self, key: &u32, color: Color) {
+            self.0.borrow_mut().push(format!("recolor {} {:?}", key, color));
+        }
+    }
+
+    #[test]
+    fn reports_rebalancing_steps() {
+        let log = Rc::new(Log::default());
+        let mut tree = RedBlackTree::new();
+        tree.set_observer(Rc::clone(&log));
+        for key in 1..=3 {
+            tree.insert(key, ());
+        }
+        //插入3时RR情况，以1为轴左旋，爷节点染红、父节点染黑
+        assert_eq!(*log.0.borrow(), ["insert 1", "insert 2", "insert 3", "rotate_left 1", "recolor 1 Red", "recolor 2 Black"]);
+        log.0.borrow_mut().clear();
+        tree.delete(&2);
+        assert_eq!(*log.0.borrow(), ["delete 2"]);
+        assert!(tree.take_observer().is_some());
+        tree.insert(2, ());
+        assert_eq!(log.0.borrow().len(), 1);
+    }
+}
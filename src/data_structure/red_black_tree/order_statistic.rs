@@ -0,0 +1,166 @@
+//! 顺序统计
+//! 每个节点记录子树节点数量，排名、按序号访问与删除、范围计数均为O(log n)
+
+use alloc::rc::Rc;
+use core::cell::RefCell;
+use core::cmp::Ordering;
+use core::ops::{Bound, RangeBounds};
+
+use super::{Node, RedBlackTree};
+
+impl<K: Ord, V> RedBlackTree<K, V> {
+    ///小于key的键的数量，键可以是K的借用形式
+    pub fn rank<Q: Ord + ?Sized>(&self, key: &Q) -> usize
+    where
+        K: core::borrow::Borrow<Q>,
+    {
+        self.count_below(key, false)
+    }
+
+    /// 范围内键的数量，O(log n)，无需迭代
+    /// 为上界以下与下界以下的数量之差，起点大于终点时为0
+    pub fn count_range<R: RangeBounds<K>>(&self, range: R) -> usize {
+        let (below_start, below_end) = self.index_range(&range);
+        below_end.saturating_sub(below_start)
+    }
+
+    ///范围两端以下的键的数量，即范围内键的序号区间
+    fn index_range<R: RangeBounds<K>>(&self, range: &R) -> (usize, usize) {
+        let below_start = match range.start_bound() {
+            Bound::Included(key) => self.count_below(key, false),
+            Bound::Excluded(key) => self.count_below(key, true),
+            Bound::Unbounded => 0,
+        };
+        let below_end = match range.end_bound() {
+            Bound::Included(key) => self.count_below(key, true),
+            Bound::Excluded(key) => self.count_below(key, false),
+            Bound::Unbounded => self.len,
+        };
+        (below_start, below_end)
+    }
+
+    ///小于key(inclusive时为小于等于)的键的数量
+    fn count_below<Q: Ord + ?Sized>(&self, key: &Q, inclusive: bool) -> usize
+    where
+        K: core::borrow::Borrow<Q>,
+    {
+        let mut count = 0;
+        let mut next_option = self.root.clone();
+        while let Some(cur_rc) = next_option {
+            let cur = cur_rc.borrow();
+            let cur_key: &Q = core::borrow::Borrow::borrow(&cur.key);
+            next_option = if key < cur_key || (key == cur_key && !inclusive) {
+                cur.left.clone()
+            } else {
+                //左子树与当前节点都在key之前
+                count += Self::size_of(&cur.left) + 1;
+                cur.right.clone()
+            };
+        }
+        count
+    }
+
+    ///第index小的键值对(从0开始)
+    pub fn select(&self, index: usize) -> Option<(K, V)>
+    where
+        K: Clone,
+        V: Clone,
+    {
+        self.find_by_index(index).map(|node_rc| Self::clone_entry(&node_rc))
+    }
+
+    ///按序号访问，同select
+    pub fn get_by_index(&self, index: usize) -> Option<(K, V)>
+    where
+        K: Clone,
+        V: Clone,
+    {
+        self.select(index)
+    }
+
+    ///键的序号(从0开始)，键不存在时返回None，键可以是K的借用形式
+    pub fn index_of<Q: Ord + ?Sized>(&self, key: &Q) -> Option<usize>
+    where
+        K: core::borrow::Borrow<Q>,
+    {
+        self.find(key).map(|_| self.rank(key))
+    }
+
+    /// 删除并返回第index小的键值对，O(log n)
+    /// 序号按节点计算，多重集模式下整体删除节点，不减少计数
+    pub fn remove_by_index(&mut self, index: usize) -> Option<(K, V)> {
+        let node_rc = self.find_by_index(index)?;
+        Some(self.remove_node(node_rc))
+    }
+
+    ///均匀随机抽取一个键，按随机序号select，O(log n)
+    #[cfg(feature = "rand")]
+    pub fn sample<R: rand::Rng + ?Sized>(&self, rng: &mut R) -> Option<K>
+    where
+        K: Clone,
+    {
+        self.sample_range(.., rng)
+    }
+
+    ///在范围内均匀随机抽取一个键，范围内没有键时返回None，O(log n)
+    #[cfg(feature = "rand")]
+    pub fn sample_range<B: RangeBounds<K>, R: rand::Rng + ?Sized>(&self, range: B, rng: &mut R) -> Option<K>
+    where
+        K: Clone,
+    {
+        let (below_start, below_end) = self.index_range(&range);
+        if below_start >= below_end {
+            return None;
+        }
+        let node_rc = self.find_by_index(rng.gen_range(below_start..below_end))?;
+        let key = node_rc.borrow().key.clone();
+        Some(key)
+    }
+
+    ///第index小的节点，沿子树节点数量下降
+    fn find_by_index(&self, index: usize) -> Option<Rc<RefCell<Node<K, V>>>> {
+        let mut index = index;
+        let mut next_option = self.root.clone();
+        while let Some(cur_rc) = next_option {
+            let cur = cur_rc.borrow();
+            let left_size = Self::size_of(&cur.left);
+            next_option = match index.cmp(&left_size) {
+                Ordering::Less => cur.left.clone(),
+                Ordering::Equal => {
+                    drop(cur);
+                    return Some(cur_rc);
+                }
+                Ordering::Greater => {
+                    index -= left_size + 1;
+                    cur.right.clone()
+                }
+            };
+        }
+        None
+    }
+}
+
+#[cfg(all(test, feature = "rand"))]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use super::super::RedBlackTree;
+
+    #[test]
+    fn sampling_is_uniform_within_range() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let tree = RedBlackTree::from_sorted_iter((0..100u32).map(|key| (key * 2, ())));
+        assert_eq!(RedBlackTree::<u32, ()>::new().sample(&mut rng), None);
+        assert_eq!(tree.sample_range(199..250, &mut rng), None);
+        let mut hits = [0u32; 10];
+        for _ in 0..20_000 {
+            let key = tree.sample_range(20..40, &mut rng).unwrap();
+            assert!((20..40).contains(&key) && key % 2 == 0);
+            hits[(key as usize - 20) / 2] += 1;
+        }
+        //每个键期望2000次，允许约5个标准差的偏差
+        assert!(hits.iter().all(|hit| (1800..=2200).contains(hit)), "{:?}", hits);
+        assert!(tree.sample(&mut rng).is_some_and(|key| key < 200));
+    }
+}
@@ -0,0 +1,584 @@
+//! 写时复制的红黑树
+//! RedBlackTree有父节点指针，节点无法在多棵树间共享，clone只能深拷贝
+//! 此处以Arc共享子树、不设父节点指针，clone为O(1)，修改时只复制从根到修改位置的路径(Arc::make_mut)
+//! 平衡采用左倾红黑树，递归下降后沿返回路径修正，与LlrbTree相同
+//! 克隆之间互不影响，相当于快照；K、V满足Send + Sync时树也可跨线程共享
+
+use alloc::sync::Arc;
+use core::cmp::Ordering;
+use core::fmt;
+use core::ops::RangeBounds;
+
+use super::super::inorder::{BinaryNode, InorderIter};
+use super::{Color, InvariantViolation};
+
+type Link<K, V> = Option<Arc<PersistentNode<K, V>>>;
+
+#[derive(Clone)]
+struct PersistentNode<K, V> {
+    key: K,
+    value: V,
+    left: Link<K, V>,
+    right: Link<K, V>,
+    //指向该节点的链接的颜色
+    color: Color,
+}
+
+impl<K, V> BinaryNode for PersistentNode<K, V> {
+    type Key = K;
+    type Value = V;
+
+    fn entry(&self) -> (&K, &V) {
+        (&self.key, &self.value)
+    }
+
+    fn left(&self) -> Option<&Self> {
+        self.left.as_deref()
+    }
+
+    fn right(&self) -> Option<&Self> {
+        self.right.as_deref()
+    }
+}
+
+pub struct PersistentRedBlackTree<K, V> {
+    root: Link<K, V>,
+    len: usize,
+}
+
+fn is_red<K, V>(link: &Link<K, V>) -> bool {
+    link.as_ref().is_some_and(|node| node.color == Color::Red)
+}
+
+///左子节点的左子节点是否为红色
+fn is_left_left_red<K, V>(link: &Link<K, V>) -> bool {
+    link.as_ref().is_some_and(|node| is_red(&node.left))
+}
+
+fn flip(color: Color) -> Color {
+    match color {
+        Color::Red => Color::Black,
+        Color::Black => Color::Red,
+    }
+}
+
+///取出节点的键值对，节点仍被其他树共享时拷贝
+fn into_entry<K: Clone, V: Clone>(node: Arc<PersistentNode<K, V>>) -> (K, V) {
+    match Arc::try_unwrap(node) {
+        Ok(node) => (node.key, node.value),
+        Err(shared) => (shared.key.clone(), shared.value.clone()),
+    }
+}
+
+///左旋，右侧的红色链接转到左侧
+fn rotate_left<K: Clone, V: Clone>(mut node: Arc<PersistentNode<K, V>>) -> Arc<PersistentNode<K, V>> {
+    let parent = Arc::make_mut(&mut node);
+    let mut right = parent.right.take().expect("rotate left without right child");
+    let son = Arc::make_mut(&mut right);
+    parent.right = son.left.take();
+    son.color = parent.color;
+    parent.color = Color::Red;
+    son.left = Some(node);
+    right
+}
+
+///右旋，左侧的红色链接转到右侧
+fn rotate_right<K: Clone, V: Clone>(mut node: Arc<PersistentNode<K, V>>) -> Arc<PersistentNode<K, V>> {
+    let parent = Arc::make_mut(&mut node);
+    let mut left = parent.left.take().expect("rotate right without left child");
+    let son = Arc::make_mut(&mut left);
+    parent.left = son.right.take();
+    son.color = parent.color;
+    parent.color = Color::Red;
+    son.right = Some(node);
+    left
+}
+
+///翻转节点与两个子节点的颜色，子节点被共享时先复制
+fn flip_colors<K: Clone, V: Clone>(node: &mut Arc<PersistentNode<K, V>>) {
+    let node = Arc::make_mut(node);
+    node.color = flip(node.color);
+    for son in [&mut node.left, &mut node.right].into_iter().flatten() {
+        let son = Arc::make_mut(son);
+        son.color = flip(son.color);
+    }
+}
+
+///沿返回路径恢复左倾性质
+fn fix_up<K: Clone, V: Clone>(mut node: Arc<PersistentNode<K, V>>) -> Arc<PersistentNode<K, V>> {
+    if is_red(&node.right) && !is_red(&node.left) {
+        node = rotate_left(node);
+    }
+    if is_red(&node.left) && is_left_left_red(&node.left) {
+        node = rotate_right(node);
+    }
+    if is_red(&node.left) && is_red(&node.right) {
+        flip_colors(&mut node);
+    }
+    node
+}
+
+///保证左子节点或其左子节点为红色，以便向左下降删除
+fn move_red_left<K: Clone, V: Clone>(mut node: Arc<PersistentNode<K, V>>) -> Arc<PersistentNode<K, V>> {
+    flip_colors(&mut node);
+    if node.right.as_ref().is_some_and(|right| is_red(&right.left)) {
+        let parent = Arc::make_mut(&mut node);
+        parent.right = parent.right.take().map(rotate_right);
+        node = rotate_left(node);
+        flip_colors(&mut node);
+    }
+    node
+}
+
+///保证右子节点或其左子节点为红色，以便向右下降删除
+fn move_red_right<K: Clone, V: Clone>(mut node: Arc<PersistentNode<K, V>>) -> Arc<PersistentNode<K, V>> {
+    flip_colors(&mut node);
+    if is_left_left_red(&node.left) {
+        node = rotate_right(node);
+        flip_colors(&mut node);
+    }
+    node
+}
+
+/// 插入，返回子树新的根与被替换的旧值
+/// 调用方保证不替换时键不存在，避免无谓地复制路径
+fn insert_node<K: Ord + Clone, V: Clone>(link: Link<K, V>, key: K, value: V) -> (Arc<PersistentNode<K, V>>, Option<V>) {
+    let Some(mut node) = link else {
+        return (Arc::new(PersistentNode { key, value, left: None, right: None, color: Color::Red }), None);
+    };
+    let parent = Arc::make_mut(&mut node);
+    let displaced = match key.cmp(&parent.key) {
+        Ordering::Less => {
+            let (left, displaced) = insert_node(parent.left.take(), key, value);
+            parent.left = Some(left);
+            displaced
+        }
+        Ordering::Greater => {
+            let (right, displaced) = insert_node(parent.right.take(), key, value);
+            parent.right = Some(right);
+            displaced
+        }
+        Ordering::Equal => Some(core::mem::replace(&mut parent.value, value)),
+    };
+    (fix_up(node), displaced)
+}
+
+///删除子树最小节点，返回子树新的根与被删除的节点
+fn delete_min<K: Clone, V: Clone>(mut node: Arc<PersistentNode<K, V>>) -> (Link<K, V>, Arc<PersistentNode<K, V>>) {
+    if node.left.is_none() {
+        //左倾，没有左子节点时也没有右子节点
+        return (None, node);
+    }
+    if !is_red(&node.left) && !is_left_left_red(&node.left) {
+        node = move_red_left(node);
+    }
+    let parent = Arc::make_mut(&mut node);
+    let (left, min) = delete_min(parent.left.take().expect("left child checked above"));
+    parent.left = left;
+    (Some(fix_up(node)), min)
+}
+
+fn delete_max<K: Clone, V: Clone>(mut node: Arc<PersistentNode<K, V>>) -> (Link<K, V>, Arc<PersistentNode<K, V>>) {
+    if is_red(&node.left) {
+        node = rotate_right(node);
+    }
+    if node.right.is_none() {
+        let left = Arc::make_mut(&mut node).left.take();
+        return (left, node);
+    }
+    if !is_red(&node.right) && !is_left_left_red(&node.right) {
+        node = move_red_right(node);
+    }
+    let parent = Arc::make_mut(&mut node);
+    let (right, max) = delete_max(parent.right.take().expect("right child checked above"));
+    parent.right = right;
+    (Some(fix_up(node)), max)
+}
+
+/// 删除key，调用方需保证key存在
+/// 下降时保证当前节点或其左子节点为红色，到达底部时被删除的节点不是2-节点
+fn delete_node<K: Ord + Clone, V: Clone>(mut node: Arc<PersistentNode<K, V>>, key: &K) -> (Link<K, V>, (K, V)) {
+    let removed;
+    if *key < node.key {
+        if !is_red(&node.left) && !is_left_left_red(&node.left) {
+            node = move_red_left(node);
+        }
+        let parent = Arc::make_mut(&mut node);
+        let (left, entry) = delete_node(parent.left.take().expect("key exists in left subtree"), key);
+        parent.left = left;
+        removed = entry;
+    } else {
+        if is_red(&node.left) {
+            node = rotate_right(node);
+        }
+        if *key == node.key && node.right.is_none() {
+            return (None, into_entry(node));
+        }
+        if !is_red(&node.right) && !is_left_left_red(&node.right) {
+            node = move_red_right(node);
+        }
+        let parent = Arc::make_mut(&mut node);
+        if *key == parent.key {
+            //以右子树最小节点取代当前节点
+            let (right, min) = delete_min(parent.right.take().expect("right child exists"));
+            parent.right = right;
+            let (min_key, min_value) = into_entry(min);
+            removed = (core::mem::replace(&mut parent.key, min_key), core::mem::replace(&mut parent.value, min_value));
+        } else {
+            let (right, entry) = delete_node(parent.right.take().expect("key exists in right subtree"), key);
+            parent.right = right;
+            removed = entry;
+        }
+    }
+    (Some(fix_up(node)), removed)
+}
+
+impl<K: Ord, V> PersistentRedBlackTree<K, V> {
+    pub fn new() -> Self {
+        PersistentRedBlackTree { root: None, len: 0 }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn clear(&mut self) {
+        self.root = None;
+        self.len = 0;
+    }
+
+    ///两棵树是否共享同一个根，即clone之后都还没有修改
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        match (&self.root, &other.root) {
+            (Some(root), Some(other_root)) => Arc::ptr_eq(root, other_root),
+            (None, None) => true,
+            _ => false,
+        }
+    }
+
+    fn find(&self, key: &K) -> Option<&PersistentNode<K, V>> {
+        let mut cur = self.root.as_deref();
+        while let Some(node) = cur {
+            cur = match key.cmp(&node.key) {
+                Ordering::Equal => return Some(node),
+                Ordering::Less => node.left.as_deref(),
+                Ordering::Greater => node.right.as_deref(),
+            };
+        }
+        None
+    }
+
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.find(key).map(|node| &node.value)
+    }
+
+    pub fn contains(&self, key: &K) -> bool {
+        self.find(key).is_some()
+    }
+
+    pub fn get_key_value(&self, key: &K) -> Option<(&K, &V)> {
+        self.find(key).map(|node| (&node.key, &node.value))
+    }
+
+    pub fn first(&self) -> Option<(&K, &V)> {
+        self.iter().next()
+    }
+
+    pub fn last(&self) -> Option<(&K, &V)> {
+        self.iter().next_back()
+    }
+
+    ///中序迭代，产出借用
+    pub fn iter(&self) -> PersistentIter<'_, K, V> {
+        PersistentIter { iter: InorderIter::new(self.root.as_deref(), self.len) }
+    }
+
+    ///按键升序迭代范围内的条目，两端沿查找路径定位，O(log n)
+    pub fn range<R: RangeBounds<K>>(&self, range: R) -> PersistentIter<'_, K, V> {
+        PersistentIter { iter: InorderIter::range(self.root.as_deref(), range) }
+    }
+
+    /// 树高，即最长根叶路径上的节点数，空树为0
+    /// 按需计算，O(n)
+    pub fn height(&self) -> usize {
+        fn height_of<K, V>(link: &Link<K, V>) -> usize {
+            link.as_ref().map_or(0, |node| 1 + height_of(&node.left).max(height_of(&node.right)))
+        }
+        height_of(&self.root)
+    }
+
+    /// 黑高，即根到叶路径上的黑色节点数(不计空叶子)，空树为0
+    /// 各路径黑高相等，沿最左路径计算，O(log n)
+    pub fn black_height(&self) -> usize {
+        let mut black_height = 0;
+        let mut cur = self.root.as_deref();
+        while let Some(node) = cur {
+            black_height += usize::from(node.color == Color::Black);
+            cur = node.left.as_deref();
+        }
+        black_height
+    }
+
+    /// 校验左倾红黑树性质，与LlrbTree::validate相同
+    pub fn validate(&self) -> Result<(), InvariantViolation<K>>
+    where
+        K: Clone,
+    {
+        if is_red(&self.root) {
+            return Err(InvariantViolation::RedRoot);
+        }
+        let mut count = 0;
+        Self::validate_node(&self.root, None, None, &mut count)?;
+        if count != self.len {
+            return Err(InvariantViolation::LengthMismatch { expected: self.len, actual: count });
+        }
+        Ok(())
+    }
+
+    ///递归校验子树，返回子树黑高(空节点黑高为1)
+    fn validate_node(link: &Link<K, V>, lower: Option<&K>, upper: Option<&K>, count: &mut usize) -> Result<usize, InvariantViolation<K>>
+    where
+        K: Clone,
+    {
+        let Some(node) = link else {
+            return Ok(1);
+        };
+        *count += 1;
+        if lower.is_some_and(|lower| node.key <= *lower) || upper.is_some_and(|upper| node.key >= *upper) {
+            return Err(InvariantViolation::OutOfOrder { key: node.key.clone() });
+        }
+        if is_red(&node.right) {
+            return Err(InvariantViolation::RightLeaningRed { key: node.key.clone() });
+        }
+        if node.color == Color::Red && is_red(&node.left) {
+            return Err(InvariantViolation::RedRed { key: node.key.clone() });
+        }
+        let left = Self::validate_node(&node.left, lower, Some(&node.key), count)?;
+        let right = Self::validate_node(&node.right, Some(&node.key), upper, count)?;
+        if left != right {
+            return Err(InvariantViolation::BlackHeight { key: node.key.clone(), left, right });
+        }
+        Ok(left + usize::from(node.color == Color::Black))
+    }
+}
+
+/// 修改操作
+/// 路径上被其他克隆共享的节点需要复制，因此要求K: Clone、V: Clone
+impl<K: Ord + Clone, V: Clone> PersistentRedBlackTree<K, V> {
+    ///插入键值对，键已存在时保留原值
+    pub fn insert(&mut self, key: K, value: V) {
+        if !self.contains(&key) {
+            self.insert_or_replace(key, value);
+        }
+    }
+
+    ///插入或替换，返回旧值
+    pub fn insert_or_replace(&mut self, key: K, value: V) -> Option<V> {
+        let (mut root, displaced) = insert_node(self.root.take(), key, value);
+        Arc::make_mut(&mut root).color = Color::Black;
+        self.root = Some(root);
+        if displaced.is_none() {
+            self.len += 1;
+        }
+        displaced
+    }
+
+    ///删除键，键不存在时忽略
+    pub fn delete(&mut self, key: &K) {
+        self.remove(key);
+    }
+
+    ///删除键并返回值
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        if !self.contains(key) {
+            return None;
+        }
+        let (_, value) = self.remove_with(|root| delete_node(root, key));
+        Some(value)
+    }
+
+    pub fn pop_first(&mut self) -> Option<(K, V)> {
+        (!self.is_empty()).then(|| {
+            self.remove_with(|root| {
+                let (root, min) = delete_min(root);
+                (root, into_entry(min))
+            })
+        })
+    }
+
+    pub fn pop_last(&mut self) -> Option<(K, V)> {
+        (!self.is_empty()).then(|| {
+            self.remove_with(|root| {
+                let (root, max) = delete_max(root);
+                (root, into_entry(max))
+            })
+        })
+    }
+
+    /// 在非空树上执行删除
+    /// 根的两个子节点都为黑色时先把根染红，使下降的起点不是2-节点
+    fn remove_with<F: FnOnce(Arc<PersistentNode<K, V>>) -> (Link<K, V>, (K, V))>(&mut self, delete: F) -> (K, V) {
+        let mut root = self.root.take().expect("remove from an empty tree");
+        if !is_red(&root.left) && !is_red(&root.right) {
+            Arc::make_mut(&mut root).color = Color::Red;
+        }
+        let (root, entry) = delete(root);
+        self.root = root;
+        if let Some(root) = &mut self.root {
+            Arc::make_mut(root).color = Color::Black;
+        }
+        self.len -= 1;
+        entry
+    }
+
+    ///可变借用值，复制从根到该节点路径上被共享的节点
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        if !self.contains(key) {
+            return None;
+        }
+        let mut cur = self.root.as_mut();
+        while let Some(link) = cur {
+            let node = Arc::make_mut(link);
+            cur = match key.cmp(&node.key) {
+                Ordering::Equal => return Some(&mut node.value),
+                Ordering::Less => node.left.as_mut(),
+                Ordering::Greater => node.right.as_mut(),
+            };
+        }
+        None
+    }
+}
+
+///O(1)，与原树共享全部节点，之后各自修改时按需复制
+impl<K, V> Clone for PersistentRedBlackTree<K, V> {
+    fn clone(&self) -> Self {
+        PersistentRedBlackTree { root: self.root.clone(), len: self.len }
+    }
+}
+
+impl<K: Ord, V> Default for PersistentRedBlackTree<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Ord + fmt::Debug, V: fmt::Debug> fmt::Debug for PersistentRedBlackTree<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+/// 中序迭代器
+pub struct PersistentIter<'a, K, V> {
+    iter: InorderIter<'a, PersistentNode<K, V>>,
+}
+
+impl<'a, K, V> Iterator for PersistentIter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<K, V> DoubleEndedIterator for PersistentIter<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.iter.next_back()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+
+    use super::PersistentRedBlackTree;
+
+    #[derive(Debug, Clone)]
+    enum Op {
+        Insert(u8, u32),
+        InsertOrReplace(u8, u32),
+        Delete(u8),
+        PopFirst,
+        PopLast,
+        Snapshot,
+    }
+
+    fn op() -> impl Strategy<Value = Op> {
+        prop_oneof![
+            4 => (any::<u8>(), any::<u32>()).prop_map(|(key, value)| Op::Insert(key % 64, value)),
+            2 => (any::<u8>(), any::<u32>()).prop_map(|(key, value)| Op::InsertOrReplace(key % 64, value)),
+            4 => any::<u8>().prop_map(|key| Op::Delete(key % 64)),
+            1 => Just(Op::PopFirst),
+            1 => Just(Op::PopLast),
+            1 => Just(Op::Snapshot),
+        ]
+    }
+
+    proptest! {
+        #[test]
+        fn snapshots_stay_unchanged(ops in vec(op(), 0..300)) {
+            let mut tree = PersistentRedBlackTree::new();
+            let mut model = BTreeMap::new();
+            let mut snapshots = Vec::new();
+            for op in ops {
+                match op {
+                    Op::Insert(key, value) => {
+                        tree.insert(key, value);
+                        model.entry(key).or_insert(value);
+                    }
+                    Op::InsertOrReplace(key, value) => prop_assert_eq!(tree.insert_or_replace(key, value), model.insert(key, value)),
+                    Op::Delete(key) => prop_assert_eq!(tree.remove(&key), model.remove(&key)),
+                    Op::PopFirst => prop_assert_eq!(tree.pop_first(), model.pop_first()),
+                    Op::PopLast => prop_assert_eq!(tree.pop_last(), model.pop_last()),
+                    Op::Snapshot => {
+                        let snapshot = tree.clone();
+                        prop_assert!(snapshot.ptr_eq(&tree));
+                        snapshots.push((snapshot, model.clone()));
+                    }
+                }
+                prop_assert_eq!(tree.validate(), Ok(()));
+                prop_assert_eq!(tree.len(), model.len());
+                prop_assert!(tree.iter().eq(model.iter()));
+                prop_assert!(tree.iter().rev().eq(model.iter().rev()));
+            }
+            //之后的修改不影响先前的快照
+            for (snapshot, model) in &snapshots {
+                prop_assert_eq!(snapshot.validate(), Ok(()));
+                prop_assert!(snapshot.iter().eq(model.iter()));
+            }
+        }
+    }
+
+    #[test]
+    fn get_mut_copies_only_the_path() {
+        let mut tree = PersistentRedBlackTree::new();
+        for key in 0..1_000 {
+            tree.insert(key, key);
+        }
+        let snapshot = tree.clone();
+        *tree.get_mut(&500).unwrap() = 0;
+        assert_eq!(tree.get(&500), Some(&0));
+        assert_eq!(snapshot.get(&500), Some(&500));
+        assert!(!tree.ptr_eq(&snapshot));
+        //不在路径上的子树仍然共享
+        let shared = tree.iter().zip(snapshot.iter()).filter(|((key, value), (other_key, other_value))| std::ptr::eq(*key, *other_key) && std::ptr::eq(*value, *other_value)).count();
+        assert!(shared >= 1_000 - 2 * 11);
+    }
+
+    #[test]
+    fn is_send_and_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<PersistentRedBlackTree<u32, String>>();
+    }
+}
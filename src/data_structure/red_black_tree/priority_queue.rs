@@ -0,0 +1,182 @@
+//! 基于红黑树的优先队列
+//! 一棵树按(优先级, 键)排序供取最小值与按优先级迭代，另一棵树记录每个键当前的优先级
+//! 按键修改优先级只需在两棵树中各删除、插入一次，O(log n)，BinaryHeap做不到
+
+use super::{Iter, RedBlackTree};
+
+/// 以键标识元素的最小优先队列，同一个键只出现一次，优先级相同时键小者优先
+pub struct TreePriorityQueue<K, P> {
+    queue: RedBlackTree<(P, K), ()>,
+    priorities: RedBlackTree<K, P>,
+}
+
+impl<K: Ord + Clone, P: Ord + Clone> TreePriorityQueue<K, P> {
+    pub fn new() -> Self {
+        TreePriorityQueue { queue: RedBlackTree::new(), priorities: RedBlackTree::new() }
+    }
+
+    ///加入元素，键已存在时改为新的优先级并返回原优先级
+    pub fn push(&mut self, key: K, priority: P) -> Option<P> {
+        let old = self.priorities.insert_or_replace(key.clone(), priority.clone());
+        if let Some(old_priority) = &old {
+            self.queue.delete(&(old_priority.clone(), key.clone()));
+        }
+        self.queue.insert((priority, key), ());
+        old
+    }
+
+    ///取出优先级最小的元素
+    pub fn pop_min(&mut self) -> Option<(K, P)> {
+        let ((priority, key), _) = self.queue.pop_first()?;
+        self.priorities.delete(&key);
+        Some((key, priority))
+    }
+
+    ///优先级最小的元素
+    pub fn peek_min(&self) -> Option<(K, P)> {
+        self.queue.first().map(|((priority, key), _)| (key, priority))
+    }
+
+    ///修改已有元素的优先级，返回原优先级，键不存在时不做任何事
+    pub fn change_priority(&mut self, key: &K, priority: P) -> Option<P> {
+        if !self.priorities.contains(key) {
+            return None;
+        }
+        self.push(key.clone(), priority)
+    }
+
+    ///删除元素，返回其优先级
+    pub fn remove(&mut self, key: &K) -> Option<P> {
+        let (key, priority) = self.priorities.take(key)?;
+        self.queue.delete(&(priority.clone(), key));
+        Some(priority)
+    }
+
+    ///键当前的优先级
+    pub fn priority(&self, key: &K) -> Option<P> {
+        self.priorities.get(key)
+    }
+
+    pub fn contains(&self, key: &K) -> bool {
+        self.priorities.contains(key)
+    }
+
+    ///并入other的全部元素，键重复时以other中的优先级为准，other被清空
+    pub fn append(&mut self, other: &mut Self) {
+        while let Some((key, priority)) = other.pop_min() {
+            self.push(key, priority);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.priorities.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.priorities.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.queue.clear();
+        self.priorities.clear();
+    }
+
+    ///按优先级升序迭代，不取出元素
+    pub fn iter(&self) -> PriorityIter<'_, K, P> {
+        PriorityIter { iter: self.queue.iter() }
+    }
+}
+
+/// 优先队列按优先级升序的迭代器，产出(键, 优先级)
+pub struct PriorityIter<'a, K, P> {
+    iter: Iter<'a, (P, K), ()>,
+}
+
+impl<K: Ord + Clone, P: Ord + Clone> Iterator for PriorityIter<'_, K, P> {
+    type Item = (K, P);
+
+    fn next(&mut self) -> Option<(K, P)> {
+        self.iter.next().map(|((priority, key), _)| (key, priority))
+    }
+}
+
+impl<K: Ord + Clone, P: Ord + Clone> DoubleEndedIterator for PriorityIter<'_, K, P> {
+    fn next_back(&mut self) -> Option<(K, P)> {
+        self.iter.next_back().map(|((priority, key), _)| (key, priority))
+    }
+}
+
+impl<'a, K: Ord + Clone, P: Ord + Clone> IntoIterator for &'a TreePriorityQueue<K, P> {
+    type Item = (K, P);
+    type IntoIter = PriorityIter<'a, K, P>;
+
+    fn into_iter(self) -> PriorityIter<'a, K, P> {
+        self.iter()
+    }
+}
+
+impl<K: Ord + Clone, P: Ord + Clone> FromIterator<(K, P)> for TreePriorityQueue<K, P> {
+    fn from_iter<I: IntoIterator<Item = (K, P)>>(iter: I) -> Self {
+        let mut queue = TreePriorityQueue::new();
+        queue.extend(iter);
+        queue
+    }
+}
+
+impl<K: Ord + Clone, P: Ord + Clone> Extend<(K, P)> for TreePriorityQueue<K, P> {
+    fn extend<I: IntoIterator<Item = (K, P)>>(&mut self, iter: I) {
+        for (key, priority) in iter {
+            self.push(key, priority);
+        }
+    }
+}
+
+impl<K: Ord + Clone, P: Ord + Clone> Default for TreePriorityQueue<K, P> {
+    fn default() -> Self {
+        TreePriorityQueue::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    use super::TreePriorityQueue;
+
+    ///以优先队列实现的Dijkstra，change_priority即减小键
+    fn shortest_paths(edges: &[(usize, usize, u32)], nodes: usize) -> Vec<Option<u32>> {
+        let mut distances = vec![None; nodes];
+        let mut queue: TreePriorityQueue<usize, u32> = [(0, 0)].into_iter().collect();
+        while let Some((node, distance)) = queue.pop_min() {
+            distances[node] = Some(distance);
+            for &(_, to, weight) in edges.iter().filter(|(from, ..)| *from == node) {
+                if distances[to].is_some() {
+                    continue;
+                }
+                match queue.priority(&to) {
+                    Some(current) if current <= distance + weight => {}
+                    Some(_) => assert!(queue.change_priority(&to, distance + weight).is_some()),
+                    None => assert_eq!(queue.push(to, distance + weight), None),
+                }
+            }
+        }
+        distances
+    }
+
+    #[test]
+    fn decrease_key_and_merge() {
+        let edges = [(0, 1, 7), (0, 2, 2), (2, 1, 3), (1, 3, 1), (2, 3, 9)];
+        assert_eq!(shortest_paths(&edges, 5), [Some(0), Some(5), Some(2), Some(6), None]);
+
+        let mut left: TreePriorityQueue<&str, u32> = [("a", 5), ("b", 1), ("c", 3)].into_iter().collect();
+        let mut right: TreePriorityQueue<&str, u32> = [("c", 0), ("d", 4)].into_iter().collect();
+        assert_eq!(left.change_priority(&"x", 1), None);
+        assert_eq!(left.remove(&"a"), Some(5));
+        left.append(&mut right);
+        assert!(right.is_empty());
+        assert_eq!(left.peek_min(), Some(("c", 0)));
+        assert_eq!(left.iter().rev().collect::<Vec<_>>(), [("d", 4), ("b", 1), ("c", 0)]);
+        assert_eq!(left.len(), 3);
+    }
+}
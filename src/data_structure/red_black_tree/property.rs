@@ -0,0 +1,265 @@
+//! 基于proptest的性质测试，仅用于测试
+//! 随机交错的插入、删除、查找序列同时作用于红黑树和BTreeMap模型，每次修改后校验红黑树性质并比对结果
+
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet};
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::ops::Bound;
+use std::rc::Rc;
+
+use proptest::collection::vec;
+use proptest::prelude::*;
+
+use super::{Observer, RedBlackTree, TreeError};
+
+#[derive(Debug, Clone)]
+enum Op {
+    Insert(u8, u32),
+    InsertOrReplace(u8, u32),
+    TryInsert(u8, u32),
+    Replace(u8, u32),
+    Take(u8),
+    Delete(u8),
+    TryDelete(u8),
+    Get(u8),
+    Update(u8, u32),
+    UpdateRange(u8, u8, u32),
+    PopFirst,
+    PopLast,
+    FirstEntry,
+    LastEntry,
+    SplitAppend(u8),
+    RemoveByIndex(u8),
+}
+
+///键取值范围较小，保证序列中有足够多的重复键和命中删除
+fn op() -> impl Strategy<Value = Op> {
+    prop_oneof![
+        4 => (any::<u8>(), any::<u32>()).prop_map(|(key, value)| Op::Insert(key % 64, value)),
+        2 => (any::<u8>(), any::<u32>()).prop_map(|(key, value)| Op::InsertOrReplace(key % 64, value)),
+        1 => (any::<u8>(), any::<u32>()).prop_map(|(key, value)| Op::TryInsert(key % 64, value)),
+        1 => (any::<u8>(), any::<u32>()).prop_map(|(key, value)| Op::Replace(key % 64, value)),
+        1 => any::<u8>().prop_map(|key| Op::Take(key % 64)),
+        4 => any::<u8>().prop_map(|key| Op::Delete(key % 64)),
+        1 => any::<u8>().prop_map(|key| Op::TryDelete(key % 64)),
+        2 => any::<u8>().prop_map(|key| Op::Get(key % 64)),
+        2 => (any::<u8>(), any::<u32>()).prop_map(|(key, delta)| Op::Update(key % 64, delta)),
+        1 => (any::<u8>(), any::<u8>(), any::<u32>()).prop_map(|(start, end, delta)| Op::UpdateRange(start % 64, end % 64, delta)),
+        1 => Just(Op::PopFirst),
+        1 => Just(Op::PopLast),
+        1 => Just(Op::FirstEntry),
+        1 => Just(Op::LastEntry),
+        1 => any::<u8>().prop_map(|key| Op::SplitAppend(key % 64)),
+        1 => any::<u8>().prop_map(|index| Op::RemoveByIndex(index % 64)),
+    ]
+}
+
+///逐项比对内容与顺序统计
+fn assert_same(tree: &RedBlackTree<u8, u32>, model: &BTreeMap<u8, u32>) {
+    assert_eq!(tree.validate(), Ok(()));
+    assert_eq!(tree.debug_refcounts(), []);
+    assert_eq!(tree.len(), model.len());
+    assert!(tree.iter().eq(model.iter().map(|(key, value)| (*key, *value))));
+    assert_eq!(tree.first(), model.first_key_value().map(|(key, value)| (*key, *value)));
+    assert_eq!(tree.last(), model.last_key_value().map(|(key, value)| (*key, *value)));
+    //高度上界2log2(n+1)，且黑高不超过高度、高度不超过两倍黑高
+    let (height, black_height) = (tree.height(), tree.black_height());
+    assert!(height as f64 <= 2.0 * ((tree.len() + 1) as f64).log2());
+    assert!(black_height <= height && height <= 2 * black_height);
+}
+
+proptest! {
+    #[test]
+    fn matches_btree_map(ops in vec(op(), 0..300)) {
+        let mut tree = RedBlackTree::new();
+        let mut model = BTreeMap::new();
+        for op in ops {
+            match op {
+                Op::Insert(key, value) => {
+                    //默认策略下重复键保留原值
+                    tree.insert(key, value);
+                    model.entry(key).or_insert(value);
+                }
+                Op::InsertOrReplace(key, value) => {
+                    prop_assert_eq!(tree.insert_or_replace(key, value), model.insert(key, value));
+                }
+                Op::TryInsert(key, value) => match tree.try_insert(key, value) {
+                    Ok(entry) => {
+                        prop_assert_eq!(entry.get(), value);
+                        prop_assert!(model.insert(key, value).is_none());
+                    }
+                    Err(error) => {
+                        prop_assert_eq!(error.value, value);
+                        prop_assert_eq!(Some(error.entry.get()), model.get(&key).copied());
+                    }
+                },
+                Op::Replace(key, value) => {
+                    let expected = model.remove_entry(&key);
+                    model.insert(key, value);
+                    prop_assert_eq!(tree.replace(key, value), expected);
+                }
+                Op::Take(key) => prop_assert_eq!(tree.take(&key), model.remove_entry(&key)),
+                Op::Delete(key) => {
+                    tree.delete(&key);
+                    model.remove(&key);
+                }
+                Op::TryDelete(key) => {
+                    let expected = if model.remove(&key).is_some() { Ok(()) } else { Err(TreeError::NotFound) };
+                    prop_assert_eq!(tree.try_delete(&key), expected);
+                }
+                Op::Get(key) => {
+                    prop_assert_eq!(tree.get(&key), model.get(&key).copied());
+                    prop_assert_eq!(tree.rank(&key), model.range(..key).count());
+                    prop_assert_eq!(tree.index_of(&key), model.keys().position(|other| *other == key));
+                    prop_assert_eq!(tree.count_range(key / 2..=key), model.range(key / 2..=key).count());
+                    prop_assert_eq!(tree.count_range((Bound::Excluded(key / 2), Bound::Unbounded)), model.range((Bound::Excluded(key / 2), Bound::Unbounded)).count());
+                    continue;
+                }
+                Op::Update(key, delta) => {
+                    let expected = model.get_mut(&key).map(|value| {
+                        *value = value.wrapping_add(delta);
+                        *value
+                    });
+                    prop_assert_eq!(tree.update(&key, |value| {
+                        *value = value.wrapping_add(delta);
+                        *value
+                    }), expected);
+                }
+                Op::UpdateRange(start, end, delta) => {
+                    //起点大于终点时为空范围，BTreeMap会panic
+                    let mut visited = Vec::new();
+                    tree.for_each_in_range(start..=end, |key, value| {
+                        visited.push(*key);
+                        *value = value.wrapping_add(delta);
+                    });
+                    let mut expected = Vec::new();
+                    if start <= end {
+                        for (key, value) in model.range_mut(start..=end) {
+                            expected.push(*key);
+                            *value = value.wrapping_add(delta);
+                        }
+                    }
+                    prop_assert_eq!(visited, expected);
+                }
+                Op::PopFirst => prop_assert_eq!(tree.pop_first(), model.pop_first()),
+                Op::PopLast => prop_assert_eq!(tree.pop_last(), model.pop_last()),
+                //值为偶数时删除，否则加一
+                Op::FirstEntry => match (tree.first_entry(), model.first_entry()) {
+                    (Some(entry), Some(model_entry)) if model_entry.get() % 2 == 0 => prop_assert_eq!(entry.remove_entry(), model_entry.remove_entry()),
+                    (Some(mut entry), Some(mut model_entry)) => {
+                        *model_entry.get_mut() = model_entry.get().wrapping_add(1);
+                        entry.with_value_mut(|value| *value = value.wrapping_add(1));
+                        prop_assert_eq!((entry.key(), entry.get()), (*model_entry.key(), *model_entry.get()));
+                    }
+                    (entry, model_entry) => prop_assert_eq!(entry.is_none(), model_entry.is_none()),
+                },
+                Op::LastEntry => match (tree.last_entry(), model.last_entry()) {
+                    (Some(entry), Some(model_entry)) if model_entry.get() % 2 == 0 => prop_assert_eq!(entry.remove_entry(), model_entry.remove_entry()),
+                    (Some(mut entry), Some(mut model_entry)) => {
+                        *model_entry.get_mut() = model_entry.get().wrapping_add(1);
+                        entry.with_value_mut(|value| *value = value.wrapping_add(1));
+                        prop_assert_eq!((entry.key(), entry.get()), (*model_entry.key(), *model_entry.get()));
+                    }
+                    (entry, model_entry) => prop_assert_eq!(entry.is_none(), model_entry.is_none()),
+                },
+                Op::SplitAppend(key) => {
+                    let mut right = tree.split_off(&key);
+                    let model_right = model.split_off(&key);
+                    assert_same(&tree, &model);
+                    assert_same(&right, &model_right);
+                    tree.append(&mut right);
+                    model.extend(model_right);
+                    prop_assert!(right.is_empty());
+                }
+                Op::RemoveByIndex(index) => {
+                    let expected = model.keys().nth(index as usize).copied().map(|key| (key, model.remove(&key).unwrap()));
+                    prop_assert_eq!(tree.remove_by_index(index as usize), expected);
+                }
+            }
+            assert_same(&tree, &model);
+        }
+    }
+
+    #[test]
+    fn select_inverts_rank(keys in vec(any::<u16>(), 0..200)) {
+        let mut tree = RedBlackTree::new();
+        for key in &keys {
+            tree.insert(*key, ());
+        }
+        prop_assert_eq!(tree.validate(), Ok(()));
+        for (index, (key, _)) in tree.iter().enumerate() {
+            prop_assert_eq!(tree.rank(&key), index);
+            prop_assert_eq!(tree.select(index), Some((key, ())));
+        }
+        prop_assert_eq!(tree.select(tree.len()), None);
+    }
+
+    #[test]
+    fn hash_follows_contents(keys in vec(any::<u8>(), 0..100), other_keys in vec(any::<u8>(), 0..100)) {
+        let build = |keys: &[u8]| {
+            let mut tree = RedBlackTree::new();
+            for key in keys {
+                tree.insert(*key, u32::from(*key) * 3);
+            }
+            tree
+        };
+        let (tree, other) = (build(&keys), build(&other_keys));
+        //逆序插入得到形状不同而内容相同的树
+        let reversed = build(&keys.iter().rev().copied().collect::<Vec<_>>());
+        prop_assert_eq!(hash_of(&tree), hash_of(&reversed));
+        if tree != other {
+            prop_assert_ne!(hash_of(&tree), hash_of(&other));
+        }
+    }
+
+    #[test]
+    fn order_matches_btree_map(keys in vec(any::<u8>(), 0..20), other_keys in vec(any::<u8>(), 0..20)) {
+        let build = |keys: &[u8]| keys.iter().map(|key| (key % 8, u32::from(*key))).collect::<BTreeMap<_, _>>();
+        let (model, other_model) = (build(&keys), build(&other_keys));
+        let (tree, other) = (RedBlackTree::from_sorted_iter(model.clone()), RedBlackTree::from_sorted_iter(other_model.clone()));
+        prop_assert_eq!(tree.cmp(&other), model.cmp(&other_model));
+        prop_assert_eq!(tree.partial_cmp(&other), model.partial_cmp(&other_model));
+        prop_assert_eq!(tree == other, model == other_model);
+    }
+
+    #[test]
+    fn observer_tracks_membership(keys in vec(any::<u8>(), 0..200)) {
+        //观察者只凭插入删除事件维护键集合
+        struct Members(RefCell<BTreeSet<u8>>);
+        impl Observer<u8> for Members {
+            fn on_insert(&self, key: &u8) {
+                assert!(self.0.borrow_mut().insert(*key));
+            }
+            fn on_delete(&self, key: &u8) {
+                assert!(self.0.borrow_mut().remove(key));
+            }
+        }
+        let members = Rc::new(Members(RefCell::new(BTreeSet::new())));
+        let mut tree = RedBlackTree::new();
+        tree.set_observer(Rc::clone(&members));
+        for key in keys {
+            match key % 4 {
+                0 => tree.delete(&(key / 4)),
+                1 => drop(tree.pop_first()),
+                _ => tree.insert(key / 4, ()),
+            }
+            prop_assert!(members.0.borrow().iter().copied().eq(tree.keys()));
+        }
+    }
+
+    #[test]
+    fn trace_replays_to_final_shape(keys in vec(any::<u8>(), 0..200)) {
+        let mut tree = RedBlackTree::new();
+        for key in keys {
+            let trace = if key % 3 == 0 { tree.trace_delete(&(key / 3)) } else { tree.trace_insert(key / 3, ()) };
+            let expected = trace.replay().pop().unwrap_or(trace.initial);
+            prop_assert_eq!(expected, tree.trace_shape());
+        }
+    }
+}
+
+fn hash_of<T: Hash>(value: &T) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
@@ -0,0 +1,104 @@
+//! 滑动窗口分位数
+//! 窗口内的值按(值, 序号)存入树，序号区分相等的值，分位数由select按排名取出，O(log n)
+//! 另以队列记录插入顺序，窗口满时淘汰最早的值
+
+use alloc::collections::VecDeque;
+
+use super::RedBlackTree;
+
+/// 固定容量的滑动窗口，支持任意分位数查询
+pub struct WindowedQuantile<T> {
+    tree: RedBlackTree<(T, u64), ()>,
+    window: VecDeque<(T, u64)>,
+    capacity: usize,
+    //下一个插入的序号
+    sequence: u64,
+}
+
+impl<T: Ord + Clone> WindowedQuantile<T> {
+    ///容量为capacity的窗口，容量为0时按1处理
+    pub fn new(capacity: usize) -> Self {
+        WindowedQuantile { tree: RedBlackTree::new(), window: VecDeque::new(), capacity: capacity.max(1), sequence: 0 }
+    }
+
+    ///插入值，窗口已满时淘汰并返回最早的值
+    pub fn insert(&mut self, value: T) -> Option<T> {
+        let evicted = if self.window.len() == self.capacity { self.evict() } else { None };
+        let entry = (value, self.sequence);
+        self.sequence += 1;
+        self.tree.insert(entry.clone(), ());
+        self.window.push_back(entry);
+        evicted
+    }
+
+    ///淘汰并返回最早的值
+    pub fn evict(&mut self) -> Option<T> {
+        let entry = self.window.pop_front()?;
+        self.tree.delete(&entry);
+        Some(entry.0)
+    }
+
+    /// p分位数，按最近排名法取第ceil(p * n)小的值，p为0时为最小值
+    /// p不在[0, 1]内时panic，窗口为空时返回None
+    pub fn quantile(&self, p: f64) -> Option<T> {
+        assert!((0.0..=1.0).contains(&p), "quantile {} out of [0, 1]", p);
+        //无std时没有ceil，截断后补上小数部分
+        let scaled = p * self.len() as f64;
+        let rank = scaled as usize + usize::from((scaled as usize as f64) < scaled);
+        self.tree.select(rank.max(1) - 1).map(|((value, _), _)| value)
+    }
+
+    ///中位数，偶数个值时取较小的一个
+    pub fn median(&self) -> Option<T> {
+        self.quantile(0.5)
+    }
+
+    pub fn len(&self) -> usize {
+        self.window.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.window.is_empty()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::collections::VecDeque;
+    use alloc::vec::Vec;
+
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    use super::WindowedQuantile;
+
+    #[test]
+    fn matches_sorted_window() {
+        let mut rng = StdRng::seed_from_u64(11);
+        let mut quantile = WindowedQuantile::new(25);
+        let mut window = VecDeque::new();
+        assert_eq!(quantile.median(), None);
+        for _ in 0..2000 {
+            let value = rng.gen_range(0..50u32);
+            if window.len() == 25 {
+                assert_eq!(quantile.insert(value), window.pop_front());
+            } else {
+                assert_eq!(quantile.insert(value), None);
+            }
+            window.push_back(value);
+            if rng.gen_bool(0.1) {
+                assert_eq!(quantile.evict(), window.pop_front());
+            }
+            let mut sorted: Vec<u32> = window.iter().copied().collect();
+            sorted.sort_unstable();
+            for p in [0.0, 0.1, 0.5, 0.9, 0.99, 1.0] {
+                let expected = sorted.get(((p * sorted.len() as f64).ceil() as usize).max(1) - 1).copied();
+                assert_eq!(quantile.quantile(p), expected);
+            }
+        }
+    }
+}
@@ -0,0 +1,139 @@
+//! 原始结构API(不稳定)
+//! 供算法研究与可视化使用，可查看子树结构、节点颜色与子树大小，并施加受控的旋转
+//! 需开启raw特性，接口随时可能变化
+
+use alloc::rc::{Rc, Weak};
+use core::cell::RefCell;
+use core::fmt;
+
+use super::{Color, Node, RedBlackTree};
+
+/// 子树视图
+/// 借用整棵树，存在期间树不可修改
+pub struct SubtreeRef<'a, K, V> {
+    tree: &'a RedBlackTree<K, V>,
+    node: Rc<RefCell<Node<K, V>>>,
+}
+
+/// 受控旋转失败的原因
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum RotateError {
+    ///键不存在
+    NotFound,
+    ///缺少上升的子节点(左旋需要右子节点，右旋需要左子节点)
+    MissingChild,
+    ///上升的子节点为黑色，旋转会破坏黑高
+    BlackChild,
+}
+
+impl fmt::Display for RotateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RotateError::NotFound => write!(f, "key not found"),
+            RotateError::MissingChild => write!(f, "no child to rotate up"),
+            RotateError::BlackChild => write!(f, "rotating a black child up would change black height"),
+        }
+    }
+}
+
+impl core::error::Error for RotateError {}
+
+impl<'a, K: Ord, V> SubtreeRef<'a, K, V> {
+    fn wrap(&self, node_option: Option<Rc<RefCell<Node<K, V>>>>) -> Option<SubtreeRef<'a, K, V>> {
+        node_option.map(|node| SubtreeRef { tree: self.tree, node })
+    }
+
+    pub fn key(&self) -> K
+    where
+        K: Clone,
+    {
+        self.node.borrow().key.clone()
+    }
+
+    pub fn value(&self) -> V
+    where
+        V: Clone,
+    {
+        self.node.borrow().value.clone()
+    }
+
+    pub fn color(&self) -> Color {
+        self.node.borrow().color
+    }
+
+    ///子树节点数量
+    pub fn size(&self) -> usize {
+        self.node.borrow().size
+    }
+
+    ///子树黑高(空节点记为1)，沿最左路径计算，假定子树满足红黑性质
+    pub fn black_height(&self) -> usize {
+        let mut height = 1;
+        let mut next_option = Some(Rc::clone(&self.node));
+        while let Some(cur_rc) = next_option {
+            let cur = cur_rc.borrow();
+            height += usize::from(cur.color == Color::Black);
+            next_option = cur.left.clone();
+        }
+        height
+    }
+
+    pub fn left(&self) -> Option<SubtreeRef<'a, K, V>> {
+        self.wrap(self.node.borrow().left.clone())
+    }
+
+    pub fn right(&self) -> Option<SubtreeRef<'a, K, V>> {
+        self.wrap(self.node.borrow().right.clone())
+    }
+
+    pub fn parent(&self) -> Option<SubtreeRef<'a, K, V>> {
+        self.wrap(self.node.borrow().parent.as_ref().and_then(Weak::upgrade))
+    }
+}
+
+impl<K: Ord, V> RedBlackTree<K, V> {
+    ///根节点的子树视图
+    pub fn raw_root(&self) -> Option<SubtreeRef<'_, K, V>> {
+        self.root.clone().map(|node| SubtreeRef { tree: self, node })
+    }
+
+    ///键所在节点的子树视图
+    pub fn raw_subtree(&self, key: &K) -> Option<SubtreeRef<'_, K, V>> {
+        self.find(key).map(|node| SubtreeRef { tree: self, node })
+    }
+
+    /// 以key所在节点为轴左旋，并交换其与右子节点的颜色
+    /// 仅当右子节点为红色时允许，此时所有路径黑高不变，但可能产生连续红色节点
+    pub fn raw_rotate_left(&mut self, key: &K) -> Result<(), RotateError> {
+        let node_rc = self.find(key).ok_or(RotateError::NotFound)?;
+        let son_rc = node_rc.borrow().right.clone().ok_or(RotateError::MissingChild)?;
+        Self::check_rising_child(&son_rc)?;
+        self.rotate_left(&node_rc, &son_rc);
+        Self::swap_color(&node_rc, &son_rc);
+        Ok(())
+    }
+
+    /// 以key所在节点为轴右旋，并交换其与左子节点的颜色
+    /// 仅当左子节点为红色时允许
+    pub fn raw_rotate_right(&mut self, key: &K) -> Result<(), RotateError> {
+        let node_rc = self.find(key).ok_or(RotateError::NotFound)?;
+        let son_rc = node_rc.borrow().left.clone().ok_or(RotateError::MissingChild)?;
+        Self::check_rising_child(&son_rc)?;
+        self.rotate_right(&node_rc, &son_rc);
+        Self::swap_color(&node_rc, &son_rc);
+        Ok(())
+    }
+
+    fn check_rising_child(son_ref: &Rc<RefCell<Node<K, V>>>) -> Result<(), RotateError> {
+        match son_ref.borrow().color {
+            Color::Red => Ok(()),
+            Color::Black => Err(RotateError::BlackChild),
+        }
+    }
+
+    fn swap_color(a_ref: &Rc<RefCell<Node<K, V>>>, b_ref: &Rc<RefCell<Node<K, V>>>) {
+        let mut a = a_ref.borrow_mut();
+        let mut b = b_ref.borrow_mut();
+        core::mem::swap(&mut a.color, &mut b.color);
+    }
+}
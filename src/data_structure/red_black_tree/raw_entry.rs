@@ -0,0 +1,156 @@
+//! 原始入口API
+//! 调用方只查找一次(可使用自定义比较而非严格的键相等)，得到占用或空位的句柄
+//! 空位句柄记录了查找终点(父节点及方向)，插入时无需再次下降
+
+use alloc::rc::Rc;
+use core::cell::RefCell;
+use core::cmp::Ordering;
+
+use super::{Color, Node, RedBlackTree};
+
+pub struct RawEntryBuilderMut<'a, K, V> {
+    tree: &'a mut RedBlackTree<K, V>,
+}
+
+pub enum RawEntryMut<'a, K, V> {
+    Occupied(RawOccupiedEntryMut<'a, K, V>),
+    Vacant(RawVacantEntryMut<'a, K, V>),
+}
+
+///已存在节点的句柄
+pub struct RawOccupiedEntryMut<'a, K, V> {
+    tree: &'a mut RedBlackTree<K, V>,
+    pub(super) node: Rc<RefCell<Node<K, V>>>,
+}
+
+/// 空位句柄
+/// parent为查找终点，ordering为新节点相对parent的方向，parent为空时树为空
+pub struct RawVacantEntryMut<'a, K, V> {
+    tree: &'a mut RedBlackTree<K, V>,
+    parent: Option<Rc<RefCell<Node<K, V>>>>,
+    ordering: Ordering,
+}
+
+impl<K: Ord, V> RedBlackTree<K, V> {
+    pub fn raw_entry_mut(&mut self) -> RawEntryBuilderMut<'_, K, V> {
+        RawEntryBuilderMut { tree: self }
+    }
+}
+
+impl<'a, K: Ord, V> RawEntryBuilderMut<'a, K, V> {
+    ///按键查找
+    pub fn from_key(self, key: &K) -> RawEntryMut<'a, K, V> {
+        self.search(|node_key| key.cmp(node_key))
+    }
+
+    /// 按自定义比较查找
+    /// compare返回目标相对节点键的顺序，与key.cmp(node_key)含义相同
+    /// 返回Equal即视为命中
+    pub fn search<F: FnMut(&K) -> Ordering>(self, mut compare: F) -> RawEntryMut<'a, K, V> {
+        let mut parent = None;
+        let mut ordering = Ordering::Equal;
+        let mut next_option = self.tree.root.clone();
+        while let Some(cur_rc) = next_option {
+            let cur = cur_rc.borrow();
+            ordering = compare(&cur.key);
+            next_option = match ordering {
+                Ordering::Equal => {
+                    drop(cur);
+                    return RawEntryMut::Occupied(RawOccupiedEntryMut { tree: self.tree, node: cur_rc });
+                }
+                Ordering::Less => cur.left.clone(),
+                Ordering::Greater => cur.right.clone(),
+            };
+            drop(cur);
+            parent = Some(cur_rc);
+        }
+        RawEntryMut::Vacant(RawVacantEntryMut { tree: self.tree, parent, ordering })
+    }
+}
+
+impl<'a, K: Ord, V> RawOccupiedEntryMut<'a, K, V> {
+    ///node必须是tree中的节点
+    pub(super) fn new(tree: &'a mut RedBlackTree<K, V>, node: Rc<RefCell<Node<K, V>>>) -> Self {
+        RawOccupiedEntryMut { tree, node }
+    }
+
+    pub fn key(&self) -> K
+    where
+        K: Clone,
+    {
+        self.node.borrow().key.clone()
+    }
+
+    pub fn get(&self) -> V
+    where
+        V: Clone,
+    {
+        self.node.borrow().value.clone()
+    }
+
+    ///原地修改值
+    pub fn with_value_mut<R, F: FnOnce(&mut V) -> R>(&mut self, f: F) -> R {
+        f(&mut self.node.borrow_mut().value)
+    }
+
+    ///替换值，返回旧值
+    pub fn insert(&mut self, value: V) -> V {
+        core::mem::replace(&mut self.node.borrow_mut().value, value)
+    }
+
+    /// 删除节点，返回键值对
+    /// 多重集模式下连同全部重复整体删除，逐个删除用delete
+    pub fn remove_entry(self) -> (K, V) {
+        self.tree.remove_node(self.node)
+    }
+
+    pub fn remove(self) -> V {
+        self.remove_entry().1
+    }
+}
+
+impl<'a, K: Ord, V> RawVacantEntryMut<'a, K, V> {
+    /// 在空位插入
+    /// 调用方需保证key与查找时的比较结果一致，否则会破坏树的顺序
+    pub fn insert(self, key: K, value: V) {
+        self.insert_entry(key, value);
+    }
+
+    ///在空位插入，返回新节点的句柄
+    pub(super) fn insert_entry(self, key: K, value: V) -> RawOccupiedEntryMut<'a, K, V> {
+        let node_rc = self.tree.pool.allocate(Node {
+            key,
+            value,
+            parent: None,
+            left: None,
+            right: None,
+            color: Color::Red,
+            size: 1,
+            count: 1,
+        });
+        match &self.parent {
+            None => {
+                node_rc.borrow_mut().color = Color::Black;
+                self.tree.notify(&node_rc, |observer, key| observer.on_insert(key));
+                self.tree.root = Some(Rc::clone(&node_rc));
+            }
+            Some(parent_ref) => {
+                node_rc.borrow_mut().parent = Some(Rc::downgrade(parent_ref));
+                RedBlackTree::adjust_path_size(parent_ref, true);
+                {
+                    let mut parent = parent_ref.borrow_mut();
+                    if self.ordering == Ordering::Less {
+                        parent.left = Some(Rc::clone(&node_rc));
+                    } else {
+                        parent.right = Some(Rc::clone(&node_rc));
+                    }
+                }
+                self.tree.notify(&node_rc, |observer, key| observer.on_insert(key));
+                self.tree.insert_balance(parent_ref, &node_rc);
+            }
+        }
+        self.tree.len += 1;
+        self.tree.sample_validation();
+        RawOccupiedEntryMut { tree: self.tree, node: node_rc }
+    }
+}
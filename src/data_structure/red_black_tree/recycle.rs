@@ -0,0 +1,143 @@
+//! 节点回收
+//! 删除的节点取出键值对后保留其分配，后续插入直接写入，插入删除交替频繁时减少分配器调用
+//! 回收的分配以MaybeUninit保存，不持有键值对，也不会被析构
+//! 只有不再被任何强、弱引用指向的节点才会回收，其他持有者无法观察到被复用的分配
+//! 仍被其他强引用持有的节点不回收也不取出，丢弃这一份引用后由其余持有者负责析构
+//! miri校验：cargo +nightly miri test --lib --no-default-features recycle::，CI中的miri任务同样执行
+
+use alloc::rc::Rc;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+use core::mem::MaybeUninit;
+
+use super::{Node, RedBlackTree};
+
+///默认最多保留的空闲节点数量
+const DEFAULT_LIMIT: usize = 64;
+
+type Vacant<K, V> = Rc<MaybeUninit<RefCell<Node<K, V>>>>;
+
+///空闲节点池
+pub(super) struct NodePool<K, V> {
+    free: Vec<Vacant<K, V>>,
+    limit: usize,
+}
+
+impl<K, V> NodePool<K, V> {
+    ///分配节点，优先复用空闲节点
+    pub(super) fn allocate(&mut self, node: Node<K, V>) -> Rc<RefCell<Node<K, V>>> {
+        match self.free.pop() {
+            Some(mut vacant) => {
+                Rc::get_mut(&mut vacant).expect("pooled node is unique").write(RefCell::new(node));
+                //SAFETY: 上一行已写入完整的值
+                unsafe { vacant.assume_init() }
+            }
+            None => Rc::new(RefCell::new(node)),
+        }
+    }
+
+    /// 取出已脱离树的节点的内容，池未满时保留其分配
+    /// 还有其他弱引用时无法回收，取出内容后释放；还有其他强引用时无法取出，丢弃这一份引用并返回None
+    pub(super) fn recycle(&mut self, mut node_rc: Rc<RefCell<Node<K, V>>>) -> Option<Node<K, V>> {
+        if Rc::get_mut(&mut node_rc).is_none() || self.free.len() >= self.limit {
+            return Rc::try_unwrap(node_rc).ok().map(RefCell::into_inner);
+        }
+        //SAFETY: MaybeUninit<T>与T的大小、对齐相同，from_raw的指针来自同一个Rc的into_raw
+        let mut vacant: Vacant<K, V> = unsafe { Rc::from_raw(Rc::into_raw(node_rc).cast()) };
+        //SAFETY: 节点在转换前已初始化，读出后分配只以MaybeUninit存在，不会再次析构或读取
+        let node = unsafe { Rc::get_mut(&mut vacant).expect("checked unique above").assume_init_read() };
+        self.free.push(vacant);
+        Some(node.into_inner())
+    }
+
+    pub(super) fn len(&self) -> usize {
+        self.free.len()
+    }
+
+    pub(super) fn set_limit(&mut self, limit: usize) {
+        self.limit = limit;
+        self.free.truncate(limit);
+        self.free.shrink_to(limit);
+    }
+}
+
+impl<K, V> Default for NodePool<K, V> {
+    fn default() -> Self {
+        NodePool { free: Vec::new(), limit: DEFAULT_LIMIT }
+    }
+}
+
+impl<K: Ord, V> RedBlackTree<K, V> {
+    /// 设置最多保留的空闲节点数量，默认64
+    /// 为0时关闭回收，多余的空闲节点立即释放
+    pub fn set_node_pool_limit(&mut self, limit: usize) {
+        self.pool.set_limit(limit);
+    }
+
+    ///当前保留的空闲节点数量
+    pub fn pooled_nodes(&self) -> usize {
+        self.pool.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::rc::Rc;
+
+    use super::NodePool;
+    use crate::data_structure::red_black_tree::{Color, DuplicatePolicy, Node, RedBlackTree};
+
+    #[test]
+    fn deleted_nodes_are_reused() {
+        let mut tree = RedBlackTree::new();
+        for key in 0..100 {
+            tree.insert(key, Rc::new(key));
+        }
+        let values: Vec<_> = (0..100).map(|key| tree.get(&key).unwrap()).collect();
+        for key in (0..100).step_by(2) {
+            tree.delete(&key);
+        }
+        while tree.pop_first().is_some() {}
+        assert_eq!(tree.pooled_nodes(), 64);
+        //值随节点内容取出或析构，不被池持有
+        assert!(values.iter().all(|value| Rc::strong_count(value) == 1));
+        for key in 0..80 {
+            tree.insert(key, Rc::new(key));
+        }
+        assert_eq!(tree.pooled_nodes(), 0);
+        assert_eq!(tree.validate(), Ok(()));
+        assert!(tree.iter().map(|(key, value)| (key, *value)).eq((0..80).map(|key| (key, key))));
+        tree.set_node_pool_limit(0);
+        tree.pop_first();
+        assert_eq!(tree.pooled_nodes(), 0);
+    }
+
+    ///插入已存在的键不占用空闲节点
+    #[test]
+    fn duplicate_insert_keeps_pool() {
+        for policy in [DuplicatePolicy::Ignore, DuplicatePolicy::Overwrite, DuplicatePolicy::Count] {
+            let mut tree = RedBlackTree::with_duplicate_policy(policy);
+            for key in 0..3 {
+                tree.insert(key, key);
+            }
+            tree.delete(&0);
+            tree.insert(1, 10);
+            assert_eq!(tree.pooled_nodes(), 1);
+            assert_eq!(tree.get(&1), Some(if policy == DuplicatePolicy::Overwrite { 10 } else { 1 }));
+        }
+    }
+
+    ///仍有其他强引用的节点不进入池，也不取出内容
+    #[test]
+    fn shared_node_is_released_not_pooled() {
+        let mut pool = NodePool::default();
+        let node_rc = pool.allocate(Node { key: 1, value: Rc::new(1), parent: None, left: None, right: None, color: Color::Red, size: 1, count: 1 });
+        let holder = Rc::clone(&node_rc);
+        assert!(pool.recycle(node_rc).is_none());
+        assert_eq!((pool.len(), Rc::strong_count(&holder)), (0, 1));
+        assert_eq!(*holder.borrow().value, 1);
+        let weak = Rc::downgrade(&holder);
+        let node = pool.recycle(holder).expect("only a weak reference remains");
+        assert_eq!((node.key, pool.len(), weak.upgrade().is_none()), (1, 0, true));
+    }
+}
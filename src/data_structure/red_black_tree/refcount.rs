@@ -0,0 +1,105 @@
+//! 引用计数诊断
+//! 树中每个节点恰好被父节点或根持有一个强引用，每个子节点的父指针各贡献一个弱引用
+//! 多出的强引用意味着环或泄漏(迭代器、游标等存活期间会临时多持有一个)，父指针悬空或指错意味着旋转、删除时没有正确更新
+
+use alloc::rc::Rc;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+use core::fmt;
+
+use super::{Node, RedBlackTree};
+
+/// 引用计数问题，key为出问题的节点的键
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum RefcountIssue<K> {
+    ///强引用数量不为1
+    StrongCount { key: K, count: usize },
+    ///弱引用数量与子节点数量不符
+    WeakCount { key: K, expected: usize, actual: usize },
+    ///父指针指向的节点已释放
+    DanglingParent { key: K },
+    ///父指针没有指向实际的父节点，根节点有父指针也属此类
+    WrongParent { key: K },
+}
+
+impl<K: fmt::Debug> fmt::Display for RefcountIssue<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RefcountIssue::StrongCount { key, count } => write!(f, "node {:?} has {} strong references", key, count),
+            RefcountIssue::WeakCount { key, expected, actual } => {
+                write!(f, "node {:?} has {} weak references but {} children", key, actual, expected)
+            }
+            RefcountIssue::DanglingParent { key } => write!(f, "node {:?} has a dangling parent pointer", key),
+            RefcountIssue::WrongParent { key } => write!(f, "node {:?} has a parent pointer to another node", key),
+        }
+    }
+}
+
+impl<K: Clone, V> RedBlackTree<K, V> {
+    /// 遍历全部节点，检查强、弱引用数量与父指针，O(n)
+    /// 没有问题时返回空；迭代器、游标存活时其所在节点会报告多出的强引用
+    pub fn debug_refcounts(&self) -> Vec<RefcountIssue<K>> {
+        let mut issues = Vec::new();
+        if let Some(root_ref) = &self.root {
+            Self::check_refcounts(root_ref, None, &mut issues);
+        }
+        issues
+    }
+
+    fn check_refcounts(node_ref: &Rc<RefCell<Node<K, V>>>, parent_option: Option<&Rc<RefCell<Node<K, V>>>>, issues: &mut Vec<RefcountIssue<K>>) {
+        let node = node_ref.borrow();
+        let strong = Rc::strong_count(node_ref);
+        if strong != 1 {
+            issues.push(RefcountIssue::StrongCount { key: node.key.clone(), count: strong });
+        }
+        let children = usize::from(node.left.is_some()) + usize::from(node.right.is_some());
+        let weak = Rc::weak_count(node_ref);
+        if weak != children {
+            issues.push(RefcountIssue::WeakCount { key: node.key.clone(), expected: children, actual: weak });
+        }
+        match (node.parent.as_ref().map(|parent_weak| parent_weak.upgrade()), parent_option) {
+            (None, None) => {}
+            (Some(None), _) => issues.push(RefcountIssue::DanglingParent { key: node.key.clone() }),
+            (Some(Some(parent_rc)), Some(parent_ref)) if Rc::ptr_eq(&parent_rc, parent_ref) => {}
+            _ => issues.push(RefcountIssue::WrongParent { key: node.key.clone() }),
+        }
+        for child_ref in node.left.iter().chain(node.right.iter()) {
+            Self::check_refcounts(child_ref, Some(node_ref), issues);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::rc::Rc;
+
+    use super::super::RedBlackTree;
+    use super::RefcountIssue;
+
+    #[test]
+    fn healthy_after_heavy_deletes() {
+        let mut tree = RedBlackTree::new();
+        for key in 0..2000u32 {
+            tree.insert(key * 7919 % 2000, ());
+        }
+        for key in (0..2000).filter(|key| key % 3 != 0) {
+            tree.delete(&key);
+        }
+        assert_eq!(tree.debug_refcounts(), []);
+    }
+
+    #[test]
+    fn reports_extra_references() {
+        let tree = RedBlackTree::from_sorted_iter((0..3u32).map(|key| (key, ())));
+        //迭代器持有两端的节点
+        let iter = tree.iter();
+        let issues = tree.debug_refcounts();
+        assert!(!issues.is_empty() && issues.iter().all(|issue| matches!(issue, RefcountIssue::StrongCount { count: 2, .. })));
+        drop(iter);
+        //父节点的额外强引用，如同子节点以Rc而非Weak指向父节点
+        let root = Rc::clone(tree.root.as_ref().unwrap());
+        assert_eq!(tree.debug_refcounts(), [RefcountIssue::StrongCount { key: 1, count: 2 }]);
+        drop(root);
+        assert_eq!(tree.debug_refcounts(), []);
+    }
+}
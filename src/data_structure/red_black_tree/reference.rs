@@ -0,0 +1,456 @@
+//! 朴素的参考红黑树，仅用于测试
+//! 递归、不可变、无父节点指针，与优化实现采用相同的插入平衡规则(叔节点红色时变色上溢，否则旋转)
+//! 删除按Kahrs、Germane-Might的函数式写法，子树返回黑高是否减一，由父节点逐层补偿，
+//! 补偿的各情况与优化实现的delete_balance、delete_balance_recursion一一对应
+//! 因此相同插入删除序列下两者的结构与颜色应完全一致
+
+use std::rc::Rc;
+
+use super::{Color, Node, RedBlackTree};
+use std::cell::RefCell;
+
+#[derive(PartialEq, Debug)]
+enum Tree {
+    Leaf,
+    Node(Color, Rc<Tree>, i32, Rc<Tree>),
+}
+
+use Tree::Leaf;
+
+fn node(color: Color, left: &Rc<Tree>, key: i32, right: &Rc<Tree>) -> Rc<Tree> {
+    Rc::new(Tree::Node(color, Rc::clone(left), key, Rc::clone(right)))
+}
+
+fn is_red(tree: &Tree) -> bool {
+    matches!(tree, Tree::Node(Color::Red, ..))
+}
+
+fn paint(tree: &Rc<Tree>, color: Color) -> Rc<Tree> {
+    match tree.as_ref() {
+        Leaf => Rc::clone(tree),
+        Tree::Node(_, left, key, right) => node(color, left, *key, right),
+    }
+}
+
+///插入，返回新树，根节点染黑
+fn insert(tree: &Rc<Tree>, key: i32) -> Rc<Tree> {
+    paint(&insert_into(tree, key), Color::Black)
+}
+
+fn insert_into(tree: &Rc<Tree>, key: i32) -> Rc<Tree> {
+    match tree.as_ref() {
+        Leaf => node(Color::Red, &Rc::new(Leaf), key, &Rc::new(Leaf)),
+        Tree::Node(color, left, cur, right) => {
+            if key < *cur {
+                fix_left(*color, &insert_into(left, key), *cur, right)
+            } else if key > *cur {
+                fix_right(*color, left, *cur, &insert_into(right, key))
+            } else {
+                Rc::clone(tree)
+            }
+        }
+    }
+}
+
+///以当前节点为爷节点，修复左子树中的父子连续红
+fn fix_left(color: Color, left: &Rc<Tree>, key: i32, right: &Rc<Tree>) -> Rc<Tree> {
+    if let Tree::Node(Color::Red, left_left, left_key, left_right) = left.as_ref() {
+        if is_red(left_left) || is_red(left_right) {
+            //叔节点为红色，变色上溢
+            if is_red(right) {
+                return node(Color::Red, &paint(left, Color::Black), key, &paint(right, Color::Black));
+            }
+            //LL
+            if is_red(left_left) {
+                return node(Color::Black, left_left, *left_key, &node(Color::Red, left_right, key, right));
+            }
+            //LR
+            if let Tree::Node(_, lr_left, lr_key, lr_right) = left_right.as_ref() {
+                return node(
+                    Color::Black,
+                    &node(Color::Red, left_left, *left_key, lr_left),
+                    *lr_key,
+                    &node(Color::Red, lr_right, key, right),
+                );
+            }
+        }
+    }
+    node(color, left, key, right)
+}
+
+///以当前节点为爷节点，修复右子树中的父子连续红
+fn fix_right(color: Color, left: &Rc<Tree>, key: i32, right: &Rc<Tree>) -> Rc<Tree> {
+    if let Tree::Node(Color::Red, right_left, right_key, right_right) = right.as_ref() {
+        if is_red(right_left) || is_red(right_right) {
+            //叔节点为红色，变色上溢
+            if is_red(left) {
+                return node(Color::Red, &paint(left, Color::Black), key, &paint(right, Color::Black));
+            }
+            //RR
+            if is_red(right_right) {
+                return node(Color::Black, &node(Color::Red, left, key, right_left), *right_key, right_right);
+            }
+            //RL
+            if let Tree::Node(_, rl_left, rl_key, rl_right) = right_left.as_ref() {
+                return node(
+                    Color::Black,
+                    &node(Color::Red, left, key, rl_left),
+                    *rl_key,
+                    &node(Color::Red, rl_right, *right_key, right_right),
+                );
+            }
+        }
+    }
+    node(color, left, key, right)
+}
+
+///删除，返回新树
+fn delete(tree: &Rc<Tree>, key: i32) -> Rc<Tree> {
+    delete_from(tree, key).0
+}
+
+/// 删除，返回新子树及其黑高是否减一
+/// 有两个子节点时以后继取代，后继沿用删除节点的颜色
+fn delete_from(tree: &Rc<Tree>, key: i32) -> (Rc<Tree>, bool) {
+    match tree.as_ref() {
+        Leaf => (Rc::clone(tree), false),
+        Tree::Node(color, left, cur, right) => {
+            if key < *cur {
+                let (left, short) = delete_from(left, key);
+                if short { balance_left(*color, &left, *cur, right) } else { (node(*color, &left, *cur, right), false) }
+            } else if key > *cur {
+                let (right, short) = delete_from(right, key);
+                if short { balance_right(*color, left, *cur, &right) } else { (node(*color, left, *cur, &right), false) }
+            } else {
+                match (left.as_ref(), right.as_ref()) {
+                    (Leaf, Leaf) => (Rc::new(Leaf), *color == Color::Black),
+                    //只有一个子节点时必为红色，染黑后代替删除节点
+                    (_, Leaf) => (paint(left, Color::Black), false),
+                    (Leaf, _) => (paint(right, Color::Black), false),
+                    _ => {
+                        let successor = minimum(right);
+                        let (right, short) = delete_from(right, successor);
+                        if short { balance_right(*color, left, successor, &right) } else { (node(*color, left, successor, &right), false) }
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn minimum(tree: &Tree) -> i32 {
+    match tree {
+        Tree::Node(_, left, key, _) if matches!(left.as_ref(), Leaf) => *key,
+        Tree::Node(_, left, ..) => minimum(left),
+        Leaf => unreachable!("minimum of empty tree"),
+    }
+}
+
+fn parts(tree: &Tree) -> (Color, &Rc<Tree>, i32, &Rc<Tree>) {
+    match tree {
+        Tree::Node(color, left, key, right) => (*color, left, *key, right),
+        Leaf => unreachable!("sibling of a shortened subtree is never empty"),
+    }
+}
+
+/// 左子树黑高减一，以当前节点为父节点补偿
+/// 左子树为空即刚删除了黑色叶子，对应delete_balance，否则对应delete_balance_recursion
+fn balance_left(color: Color, left: &Rc<Tree>, key: i32, right: &Rc<Tree>) -> (Rc<Tree>, bool) {
+    let leaf = Rc::new(Leaf);
+    let (brother_color, brother_left, brother_key, brother_right) = parts(right);
+    if matches!(left.as_ref(), Leaf) {
+        let result = match (color, brother_color, brother_left.as_ref(), brother_right.as_ref()) {
+            //RLRR
+            (Color::Red, _, Tree::Node(..), Tree::Node(..)) => node(Color::Red, &node(Color::Black, left, key, brother_left), brother_key, &paint(brother_right, Color::Black)),
+            //RLRE
+            (Color::Red, _, Tree::Node(near_color, near_left, near_key, near_right), Leaf) => {
+                node(*near_color, &node(Color::Black, left, key, near_left), *near_key, &node(brother_color, near_right, brother_key, brother_right))
+            }
+            //RLER
+            (Color::Red, _, Leaf, Tree::Node(..)) => node(brother_color, &node(color, left, key, brother_left), brother_key, brother_right),
+            //RLEE
+            (Color::Red, _, Leaf, Leaf) => node(Color::Black, left, key, &paint(right, Color::Red)),
+            //BLR，原父节点若有右子节点，按插入调平
+            (Color::Black, Color::Red, Tree::Node(near_color, near_left, near_key, near_right), _) => {
+                node(Color::Black, &fix_left(*near_color, &node(Color::Red, left, key, near_left), *near_key, near_right), brother_key, brother_right)
+            }
+            //BLBRW
+            (Color::Black, Color::Black, Tree::Node(_, near_left, near_key, near_right), _) => {
+                node(Color::Black, &node(Color::Black, left, key, near_left), *near_key, &node(brother_color, near_right, brother_key, brother_right))
+            }
+            //BLBER
+            (Color::Black, Color::Black, Leaf, Tree::Node(..)) => node(brother_color, &node(Color::Black, left, key, &leaf), brother_key, &paint(brother_right, Color::Black)),
+            //BLBEE，黑高减一向上传递
+            (Color::Black, Color::Black, Leaf, Leaf) => return (node(Color::Black, left, key, &paint(right, Color::Red)), true),
+            _ => unreachable!("red brother without two children"),
+        };
+        return (result, false);
+    }
+    let result = match (color, brother_color, is_red(brother_left), is_red(brother_right)) {
+        //LRBW
+        (Color::Red, _, false, _) => node(brother_color, &node(Color::Red, left, key, brother_left), brother_key, brother_right),
+        //LRRB，兄弟与其左子节点连续红，按插入调平
+        (Color::Red, _, true, false) => fix_right(Color::Black, left, key, &paint(right, Color::Red)),
+        //LRRR
+        (Color::Red, _, true, true) => node(Color::Red, &node(Color::Black, left, key, brother_left), brother_key, &paint(brother_right, Color::Black)),
+        //LBBBB，黑高减一向上传递
+        (Color::Black, Color::Black, false, false) => return (node(Color::Black, left, key, &paint(right, Color::Red)), true),
+        //LBBWR
+        (Color::Black, Color::Black, _, true) => node(brother_color, &node(Color::Black, left, key, brother_left), brother_key, &paint(brother_right, Color::Black)),
+        //LBBRB
+        (Color::Black, Color::Black, true, false) => {
+            let (_, near_left, near_key, near_right) = parts(brother_left);
+            node(Color::Black, &node(Color::Black, left, key, near_left), near_key, &node(brother_color, near_right, brother_key, brother_right))
+        }
+        //LBR，旋转后父节点为红色，转为父节点红色的情况
+        (Color::Black, Color::Red, ..) => {
+            let (lower, _) = balance_left(Color::Red, left, key, brother_left);
+            node(Color::Black, &lower, brother_key, brother_right)
+        }
+    };
+    (result, false)
+}
+
+///与balance_left对称
+fn balance_right(color: Color, left: &Rc<Tree>, key: i32, right: &Rc<Tree>) -> (Rc<Tree>, bool) {
+    let leaf = Rc::new(Leaf);
+    let (brother_color, brother_left, brother_key, brother_right) = parts(left);
+    if matches!(right.as_ref(), Leaf) {
+        let result = match (color, brother_color, brother_left.as_ref(), brother_right.as_ref()) {
+            //RRRR
+            (Color::Red, _, Tree::Node(..), Tree::Node(..)) => node(Color::Red, &paint(brother_left, Color::Black), brother_key, &node(Color::Black, brother_right, key, right)),
+            //RRER
+            (Color::Red, _, Leaf, Tree::Node(near_color, near_left, near_key, near_right)) => {
+                node(*near_color, &node(brother_color, brother_left, brother_key, near_left), *near_key, &node(Color::Black, near_right, key, right))
+            }
+            //RRRE
+            (Color::Red, _, Tree::Node(..), Leaf) => node(brother_color, brother_left, brother_key, &node(color, brother_right, key, right)),
+            //RREE
+            (Color::Red, _, Leaf, Leaf) => node(Color::Black, &paint(left, Color::Red), key, right),
+            //BRR，原父节点若有左子节点，按插入调平
+            (Color::Black, Color::Red, _, Tree::Node(near_color, near_left, near_key, near_right)) => {
+                node(Color::Black, brother_left, brother_key, &fix_right(*near_color, near_left, *near_key, &node(Color::Red, near_right, key, right)))
+            }
+            //BRBWR
+            (Color::Black, Color::Black, _, Tree::Node(_, near_left, near_key, near_right)) => {
+                node(Color::Black, &node(brother_color, brother_left, brother_key, near_left), *near_key, &node(Color::Black, near_right, key, right))
+            }
+            //BRBRE
+            (Color::Black, Color::Black, Tree::Node(..), Leaf) => node(brother_color, &paint(brother_left, Color::Black), brother_key, &node(Color::Black, &leaf, key, right)),
+            //BRBEE，黑高减一向上传递
+            (Color::Black, Color::Black, Leaf, Leaf) => return (node(Color::Black, &paint(left, Color::Red), key, right), true),
+            _ => unreachable!("red brother without two children"),
+        };
+        return (result, false);
+    }
+    let result = match (color, brother_color, is_red(brother_left), is_red(brother_right)) {
+        //RRWB
+        (Color::Red, _, _, false) => node(brother_color, brother_left, brother_key, &node(Color::Red, brother_right, key, right)),
+        //RRBR，兄弟与其右子节点连续红，按插入调平
+        (Color::Red, _, false, true) => fix_left(Color::Black, &paint(left, Color::Red), key, right),
+        //RRRR
+        (Color::Red, _, true, true) => node(Color::Red, &paint(brother_left, Color::Black), brother_key, &node(Color::Black, brother_right, key, right)),
+        //RBBBB，黑高减一向上传递
+        (Color::Black, Color::Black, false, false) => return (node(Color::Black, &paint(left, Color::Red), key, right), true),
+        //RBBRW
+        (Color::Black, Color::Black, true, _) => node(brother_color, &paint(brother_left, Color::Black), brother_key, &node(Color::Black, brother_right, key, right)),
+        //RBBBR
+        (Color::Black, Color::Black, false, true) => {
+            let (_, near_left, near_key, near_right) = parts(brother_right);
+            node(Color::Black, &node(brother_color, brother_left, brother_key, near_left), near_key, &node(Color::Black, near_right, key, right))
+        }
+        //RBR，旋转后父节点为红色，转为父节点红色的情况
+        (Color::Black, Color::Red, ..) => {
+            let (lower, _) = balance_right(Color::Red, brother_right, key, right);
+            node(Color::Black, brother_left, brother_key, &lower)
+        }
+    };
+    (result, false)
+}
+
+/// 校验红黑性质，返回黑高
+/// 根为黑色由调用方保证
+fn black_height(tree: &Tree, lower: Option<i32>, upper: Option<i32>) -> Result<usize, String> {
+    match tree {
+        Leaf => Ok(1),
+        Tree::Node(color, left, key, right) => {
+            if lower.is_some_and(|lower| *key <= lower) || upper.is_some_and(|upper| *key >= upper) {
+                return Err(format!("key {} out of order", key));
+            }
+            if *color == Color::Red && (is_red(left) || is_red(right)) {
+                return Err(format!("red node {} has red child", key));
+            }
+            let left_height = black_height(left, lower, Some(*key))?;
+            let right_height = black_height(right, Some(*key), upper)?;
+            if left_height != right_height {
+                return Err(format!("black height mismatch at {}", key));
+            }
+            Ok(left_height + usize::from(*color == Color::Black))
+        }
+    }
+}
+
+fn keys(tree: &Tree, out: &mut Vec<i32>) {
+    if let Tree::Node(_, left, key, right) = tree {
+        keys(left, out);
+        out.push(*key);
+        keys(right, out);
+    }
+}
+
+///把优化实现的树导出为参考树结构，同时校验父节点指针
+fn export<V>(tree: &RedBlackTree<i32, V>) -> Rc<Tree> {
+    fn export_node<V>(node_option: &Option<Rc<RefCell<Node<i32, V>>>>, parent: Option<&Rc<RefCell<Node<i32, V>>>>) -> Rc<Tree> {
+        match node_option {
+            None => Rc::new(Leaf),
+            Some(node_ref) => {
+                let node = node_ref.borrow();
+                let actual_parent = node.parent.as_ref().and_then(|parent_weak| parent_weak.upgrade());
+                match (&actual_parent, parent) {
+                    (None, None) => {}
+                    (Some(actual_ref), Some(expected_ref)) if Rc::ptr_eq(actual_ref, expected_ref) => {}
+                    _ => panic!("wrong parent pointer at {}", node.key),
+                }
+                Rc::new(Tree::Node(node.color, export_node(&node.left, Some(node_ref)), node.key, export_node(&node.right, Some(node_ref))))
+            }
+        }
+    }
+    export_node(&tree.root, None)
+}
+
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+    use std::collections::BTreeSet;
+
+    fn assert_valid(tree: &Rc<Tree>) {
+        assert!(!is_red(tree), "root is red");
+        if let Err(message) = black_height(tree, None, None) {
+            panic!("{}: {:?}", message, tree);
+        }
+    }
+
+    fn permutations(n: i32) -> Vec<Vec<i32>> {
+        if n == 0 {
+            return vec![vec![]];
+        }
+        let mut result = Vec::new();
+        for smaller in permutations(n - 1) {
+            for position in 0..=smaller.len() {
+                let mut permutation = smaller.clone();
+                permutation.insert(position, n);
+                result.push(permutation);
+            }
+        }
+        result
+    }
+
+    #[test]
+    fn insert_matches_reference_for_all_small_permutations() {
+        for n in 1..=7 {
+            for permutation in permutations(n) {
+                let mut expected = Rc::new(Leaf);
+                let mut actual = RedBlackTree::new();
+                for key in &permutation {
+                    expected = insert(&expected, *key);
+                    actual.insert(*key, ());
+                    assert_eq!(export(&actual), expected, "insert sequence {:?}", permutation);
+                }
+                assert_valid(&expected);
+            }
+        }
+    }
+
+    #[test]
+    fn insert_matches_reference_for_random_sequences() {
+        let mut rng = StdRng::seed_from_u64(2009);
+        for _ in 0..50 {
+            let mut expected = Rc::new(Leaf);
+            let mut actual = RedBlackTree::new();
+            for _ in 0..300 {
+                let key = rng.gen_range(0..500);
+                expected = insert(&expected, key);
+                actual.insert(key, ());
+            }
+            assert_eq!(export(&actual), expected);
+        }
+    }
+
+    #[test]
+    fn delete_matches_reference_for_all_small_permutations() {
+        for n in 1..=7 {
+            for permutation in permutations(n) {
+                let mut expected = Rc::new(Leaf);
+                let mut actual = RedBlackTree::new();
+                for key in &permutation {
+                    expected = insert(&expected, *key);
+                    actual.insert(*key, ());
+                }
+                //每棵树上分别删除每个键
+                for key in 1..=n {
+                    let expected = delete(&expected, key);
+                    let mut actual = actual.clone();
+                    actual.delete(&key);
+                    assert_eq!(export(&actual), expected, "insert sequence {:?}, delete {}", permutation, key);
+                    assert_valid(&expected);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn delete_matches_reference_for_random_sequences() {
+        let mut rng = StdRng::seed_from_u64(2009);
+        for _ in 0..50 {
+            let mut expected = Rc::new(Leaf);
+            let mut actual = RedBlackTree::new();
+            let mut model = BTreeSet::new();
+            for _ in 0..200 {
+                let key = rng.gen_range(0..100);
+                if rng.gen_bool(0.6) {
+                    expected = insert(&expected, key);
+                    actual.insert(key, ());
+                    model.insert(key);
+                } else {
+                    expected = delete(&expected, key);
+                    actual.delete(&key);
+                    model.remove(&key);
+                }
+                let exported = export(&actual);
+                assert_eq!(exported, expected);
+                assert_valid(&exported);
+                let mut exported_keys = Vec::new();
+                keys(&exported, &mut exported_keys);
+                assert_eq!(exported_keys, model.iter().copied().collect::<Vec<_>>());
+            }
+        }
+    }
+
+    ///参考树的先序、中序、后序访问序列
+    fn orders(tree: &Tree, depth: usize, out: &mut [Vec<(i32, Color, usize)>; 3]) {
+        if let Tree::Node(color, left, key, right) = tree {
+            out[0].push((*key, *color, depth));
+            orders(left, depth + 1, out);
+            out[1].push((*key, *color, depth));
+            orders(right, depth + 1, out);
+            out[2].push((*key, *color, depth));
+        }
+    }
+
+    #[test]
+    fn visitors_match_reference_traversal() {
+        let mut rng = StdRng::seed_from_u64(2040);
+        let mut actual = RedBlackTree::new();
+        for _ in 0..200 {
+            actual.insert(rng.gen_range(0..500), ());
+        }
+        let mut expected = [Vec::new(), Vec::new(), Vec::new()];
+        orders(&export(&actual), 0, &mut expected);
+        let mut visited = [Vec::new(), Vec::new(), Vec::new()];
+        actual.visit_preorder(|key, color, depth| visited[0].push((*key, color, depth)));
+        actual.visit_inorder(|key, color, depth| visited[1].push((*key, color, depth)));
+        actual.visit_postorder(|key, color, depth| visited[2].push((*key, color, depth)));
+        assert_eq!(visited, expected);
+    }
+}
@@ -0,0 +1,122 @@
+//! 抽样校验
+//! 生产环境中以一定概率在插入删除后做全量校验，发现问题时通过错误钩子记录而不是panic
+//! 随机数来自每棵树自己的随机源，默认为固定种子的SplitMix64，结果可复现
+
+use alloc::rc::Rc;
+use core::error::Error;
+use core::fmt;
+
+use super::RedBlackTree;
+
+///SplitMix64的默认种子
+const DEFAULT_SEED: u64 = 0x5EED_2B1A_C4B7_7EE5;
+
+/// 每棵树的随机源，RedBlackTree、Treap与SkipList共用
+/// 核心实现不依赖rand，开启rand特性时可注入任意RngCore
+pub(crate) enum RandomSource {
+    SplitMix64(u64),
+    #[cfg(feature = "rand")]
+    Injected(Box<dyn rand::RngCore>),
+}
+
+impl RandomSource {
+    pub(crate) fn seeded(seed: u64) -> Self {
+        RandomSource::SplitMix64(seed)
+    }
+
+    pub(crate) fn next_u64(&mut self) -> u64 {
+        match self {
+            RandomSource::SplitMix64(state) => {
+                *state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+                let mut z = *state;
+                z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+                z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+                z ^ (z >> 31)
+            }
+            #[cfg(feature = "rand")]
+            RandomSource::Injected(rng) => rng.next_u64(),
+        }
+    }
+
+    ///复制当前状态，注入的随机数生成器无法复制，返回默认随机源
+    pub(super) fn fork(&self) -> Self {
+        match self {
+            RandomSource::SplitMix64(state) => RandomSource::SplitMix64(*state),
+            #[cfg(feature = "rand")]
+            RandomSource::Injected(_) => RandomSource::default(),
+        }
+    }
+
+    ///以1/one_in的概率返回true
+    fn one_in(&mut self, one_in: u32) -> bool {
+        self.next_u64().is_multiple_of(u64::from(one_in))
+    }
+}
+
+impl Default for RandomSource {
+    fn default() -> Self {
+        RandomSource::seeded(DEFAULT_SEED)
+    }
+}
+
+impl<K: Ord, V> RedBlackTree<K, V> {
+    /// 设置抽样校验，每次插入删除后以1/one_in的概率做全量校验
+    /// one_in为0时关闭
+    pub fn set_validation_sampling(&mut self, one_in: u32)
+    where
+        K: Clone + fmt::Debug,
+    {
+        self.validation_sampling = one_in;
+        //插入删除不要求K: Clone + Debug，在此处记下满足约束的校验函数
+        self.sampled_validator = if one_in == 0 { None } else { Some(Self::report_violation) };
+    }
+
+    ///以种子重置本树的随机源，相同种子得到相同的抽样结果
+    pub fn set_random_seed(&mut self, seed: u64) {
+        self.random = RandomSource::seeded(seed);
+    }
+
+    ///注入外部随机数生成器
+    #[cfg(feature = "rand")]
+    pub fn set_rng<R: rand::RngCore + 'static>(&mut self, rng: R) {
+        self.random = RandomSource::Injected(Box::new(rng));
+    }
+
+    /// 设置错误钩子，抽样校验发现的问题交给钩子处理
+    /// 未设置时开启log特性则以error级别写入log，否则输出到标准错误，no_std下忽略
+    pub fn set_error_hook<F: Fn(&dyn Error) + 'static>(&mut self, hook: F) {
+        self.error_hook = Some(Rc::new(hook));
+    }
+
+    ///修改操作完成后调用，按抽样概率校验，严格模式下每次都校验
+    pub(super) fn sample_validation(&mut self) {
+        #[cfg(all(feature = "strict", debug_assertions))]
+        self.assert_invariants();
+        if let Some(validator) = self.sampled_validator {
+            if self.random.one_in(self.validation_sampling) {
+                validator(self);
+            }
+        }
+    }
+
+    fn report_violation(&self)
+    where
+        K: Clone + fmt::Debug,
+    {
+        if let Err(violation) = self.validate() {
+            self.report_error(&violation);
+        }
+    }
+
+    pub(super) fn report_error(&self, error: &dyn Error) {
+        match &self.error_hook {
+            Some(hook) => hook(error),
+            #[cfg(feature = "log")]
+            None => log::error!("red black tree error: {}", error),
+            #[cfg(all(feature = "std", not(feature = "log")))]
+            None => eprintln!("red black tree error: {}", error),
+            #[cfg(not(any(feature = "std", feature = "log")))]
+            None => {}
+        }
+    }
+}
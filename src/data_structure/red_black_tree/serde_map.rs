@@ -0,0 +1,57 @@
+//! serde的Map模型
+//! 树按键升序序列化为map，反序列化时重复的键以后出现的为准
+
+use core::fmt;
+use core::marker::PhantomData;
+
+use serde::de::{MapAccess, Visitor};
+use serde::ser::SerializeMap;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use super::{RawEntryMut, RedBlackTree};
+
+impl<K: Ord + Serialize, V: Serialize> Serialize for RedBlackTree<K, V> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.len))?;
+        let mut next_option = self.root.as_ref().map(Self::find_minimum);
+        while let Some(cur_rc) = next_option {
+            {
+                let cur = cur_rc.borrow();
+                map.serialize_entry(&cur.key, &cur.value)?;
+            }
+            next_option = Self::successor(&cur_rc);
+        }
+        map.end()
+    }
+}
+
+struct TreeVisitor<K, V> {
+    marker: PhantomData<fn() -> RedBlackTree<K, V>>,
+}
+
+impl<'de, K: Ord + Deserialize<'de>, V: Deserialize<'de>> Visitor<'de> for TreeVisitor<K, V> {
+    type Value = RedBlackTree<K, V>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a map")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut access: A) -> Result<Self::Value, A::Error> {
+        let mut tree = RedBlackTree::new();
+        while let Some((key, value)) = access.next_entry::<K, V>()? {
+            match tree.raw_entry_mut().from_key(&key) {
+                RawEntryMut::Occupied(mut occupied) => {
+                    occupied.insert(value);
+                }
+                RawEntryMut::Vacant(vacant) => vacant.insert(key, value),
+            }
+        }
+        Ok(tree)
+    }
+}
+
+impl<'de, K: Ord + Deserialize<'de>, V: Deserialize<'de>> Deserialize<'de> for RedBlackTree<K, V> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_map(TreeVisitor { marker: PhantomData })
+    }
+}
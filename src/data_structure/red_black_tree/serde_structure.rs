@@ -0,0 +1,170 @@
+//! 保留结构的序列化
+//! 按先序序列化每个节点的键、值、颜色、重复次数和左右子节点是否存在，反序列化时原样重建并校验红黑树性质
+//! 配合 `#[serde(with = "red_black_tree::data_structure::red_black_tree::serde_structure")]` 使用
+
+use alloc::rc::Rc;
+use core::cell::RefCell;
+use core::fmt::Debug;
+
+use serde::de::Error;
+use serde::ser::SerializeSeq;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use super::{Color, Node, RedBlackTree};
+
+#[derive(Serialize)]
+struct NodeRecordRef<'a, K, V> {
+    key: &'a K,
+    value: &'a V,
+    color: Color,
+    count: usize,
+    left: bool,
+    right: bool,
+}
+
+#[derive(Deserialize)]
+struct NodeRecord<K, V> {
+    key: K,
+    value: V,
+    color: Color,
+    //多重集模式下的重复次数，缺省为1
+    #[serde(default = "single")]
+    count: usize,
+    left: bool,
+    right: bool,
+}
+
+fn single() -> usize {
+    1
+}
+
+///待填充的位置
+enum Slot<K, V> {
+    Root,
+    Left(Rc<RefCell<Node<K, V>>>),
+    Right(Rc<RefCell<Node<K, V>>>),
+}
+
+///先序序列化节点
+pub fn serialize<K, V, S>(tree: &RedBlackTree<K, V>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    K: Serialize,
+    V: Serialize,
+    S: Serializer,
+{
+    let mut seq = serializer.serialize_seq(Some(tree.len))?;
+    let mut stack: Vec<Rc<RefCell<Node<K, V>>>> = tree.root.iter().cloned().collect();
+    while let Some(cur_rc) = stack.pop() {
+        let cur = cur_rc.borrow();
+        seq.serialize_element(&NodeRecordRef {
+            key: &cur.key,
+            value: &cur.value,
+            color: cur.color,
+            count: cur.count,
+            left: cur.left.is_some(),
+            right: cur.right.is_some(),
+        })?;
+        //右子节点先入栈，左子节点先出栈
+        stack.extend(cur.right.iter().cloned());
+        stack.extend(cur.left.iter().cloned());
+    }
+    seq.end()
+}
+
+/// 按先序重建树，并校验红黑树性质
+/// 红黑树高度不超过2log2(n+1)，超出时直接拒绝，避免畸形输入导致校验时栈溢出
+pub fn deserialize<'de, K, V, D>(deserializer: D) -> Result<RedBlackTree<K, V>, D::Error>
+where
+    K: Ord + Clone + Debug + Deserialize<'de>,
+    V: Deserialize<'de>,
+    D: Deserializer<'de>,
+{
+    let records = Vec::<NodeRecord<K, V>>::deserialize(deserializer)?;
+    let max_depth = 2 * (usize::BITS - records.len().leading_zeros()) as usize;
+    let mut tree = RedBlackTree::new();
+    //先序创建的节点，逆序即可自底向上计算子树节点数量
+    let mut nodes = Vec::with_capacity(records.len());
+    let mut slots = vec![(Slot::Root, 1)];
+    for record in records {
+        let (slot, depth) = slots.pop().ok_or_else(|| D::Error::custom("more nodes than the structure references"))?;
+        if depth > max_depth {
+            return Err(D::Error::custom("structure is too deep for a red-black tree"));
+        }
+        if record.count == 0 {
+            return Err(D::Error::custom("node count must be at least 1"));
+        }
+        let node_rc = Rc::new(RefCell::new(Node {
+            key: record.key,
+            value: record.value,
+            parent: None,
+            left: None,
+            right: None,
+            color: record.color,
+            size: 1,
+            count: record.count,
+        }));
+        match slot {
+            Slot::Root => tree.root = Some(Rc::clone(&node_rc)),
+            Slot::Left(parent_rc) => {
+                node_rc.borrow_mut().parent = Some(Rc::downgrade(&parent_rc));
+                parent_rc.borrow_mut().left = Some(Rc::clone(&node_rc));
+            }
+            Slot::Right(parent_rc) => {
+                node_rc.borrow_mut().parent = Some(Rc::downgrade(&parent_rc));
+                parent_rc.borrow_mut().right = Some(Rc::clone(&node_rc));
+            }
+        }
+        if record.right {
+            slots.push((Slot::Right(Rc::clone(&node_rc)), depth + 1));
+        }
+        if record.left {
+            slots.push((Slot::Left(Rc::clone(&node_rc)), depth + 1));
+        }
+        nodes.push(node_rc);
+    }
+    if !slots.is_empty() {
+        return Err(D::Error::custom("structure references missing nodes"));
+    }
+    tree.len = nodes.len();
+    for node_rc in nodes.iter().rev() {
+        let mut node = node_rc.borrow_mut();
+        node.size = RedBlackTree::size_of(&node.left) + RedBlackTree::size_of(&node.right) + 1;
+    }
+    tree.validate().map_err(D::Error::custom)?;
+    Ok(tree)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::{Deserialize, Serialize};
+
+    use super::super::RedBlackTree;
+
+    #[derive(Serialize, Deserialize)]
+    struct Index {
+        #[serde(with = "super")]
+        tree: RedBlackTree<u32, String>,
+    }
+
+    #[test]
+    fn round_trip_keeps_structure() {
+        let mut tree = RedBlackTree::new();
+        for key in [50, 20, 80, 10, 30, 70, 90, 25, 27, 26] {
+            tree.insert(key, key.to_string());
+        }
+        let json = serde_json::to_string(&Index { tree }).unwrap();
+        let index: Index = serde_json::from_str(&json).unwrap();
+        assert!(index.tree.validate().is_ok());
+        assert_eq!(serde_json::to_string(&index).unwrap(), json);
+    }
+
+    #[test]
+    fn rejects_broken_invariants() {
+        //红色根节点
+        let json = r#"{"tree":[{"key":1,"value":"1","color":"Red","left":false,"right":false}]}"#;
+        assert!(serde_json::from_str::<Index>(json).is_err());
+        //缺少引用的子节点
+        let json = r#"{"tree":[{"key":1,"value":"1","color":"Black","left":true,"right":false}]}"#;
+        assert!(serde_json::from_str::<Index>(json).is_err());
+    }
+}
@@ -0,0 +1,300 @@
+//! 基于红黑树的有序集合
+//! 接口对照BTreeSet，内部是值为()的RedBlackTree
+//! 与树一样，迭代产出元素的拷贝
+
+use core::borrow::Borrow;
+use core::cmp::Ordering;
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::ops::RangeBounds;
+
+use super::{EitherOrBoth, Iter, RedBlackTree, ZipSorted};
+
+/// 有序集合
+pub struct RedBlackTreeSet<T> {
+    map: RedBlackTree<T, ()>,
+}
+
+impl<T: Ord> RedBlackTreeSet<T> {
+    pub fn new() -> Self {
+        RedBlackTreeSet { map: RedBlackTree::new() }
+    }
+
+    ///插入元素，元素已存在时返回false
+    pub fn insert(&mut self, value: T) -> bool {
+        self.map.insert_or_replace(value, ()).is_none()
+    }
+
+    ///删除元素，元素不存在时返回false，元素可以是T的借用形式
+    pub fn remove<Q: Ord + ?Sized>(&mut self, value: &Q) -> bool
+    where
+        T: Borrow<Q>,
+    {
+        let present = self.map.contains(value);
+        self.map.delete(value);
+        present
+    }
+
+    ///插入元素，已存在相等的元素时以value替换并返回原元素
+    pub fn replace(&mut self, value: T) -> Option<T> {
+        self.map.replace(value, ()).map(|(old, _)| old)
+    }
+
+    ///删除并返回集合中与value相等的元素
+    pub fn take<Q: Ord + ?Sized>(&mut self, value: &Q) -> Option<T>
+    where
+        T: Borrow<Q>,
+    {
+        self.map.take(value).map(|(old, _)| old)
+    }
+
+    pub fn contains<Q: Ord + ?Sized>(&self, value: &Q) -> bool
+    where
+        T: Borrow<Q>,
+    {
+        self.map.contains(value)
+    }
+
+    pub fn pop_first(&mut self) -> Option<T> {
+        self.map.pop_first().map(|(value, _)| value)
+    }
+
+    pub fn pop_last(&mut self) -> Option<T> {
+        self.map.pop_last().map(|(value, _)| value)
+    }
+
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.map.clear();
+    }
+
+    ///只保留满足条件的元素
+    pub fn retain<F: FnMut(&T) -> bool>(&mut self, mut keep: F) {
+        self.map.retain(|value, _| keep(value));
+    }
+
+    ///拆分，本集合保留小于value的元素，返回其余元素
+    pub fn split_off(&mut self, value: &T) -> Self {
+        RedBlackTreeSet { map: self.map.split_off(value) }
+    }
+
+    ///移入other的全部元素，other变为空集合
+    pub fn append(&mut self, other: &mut Self) {
+        self.map.append(&mut other.map);
+    }
+
+    pub fn is_subset(&self, other: &Self) -> bool {
+        self.map.is_subset(&other.map)
+    }
+
+    pub fn is_superset(&self, other: &Self) -> bool {
+        self.map.is_superset(&other.map)
+    }
+
+    pub fn is_disjoint(&self, other: &Self) -> bool {
+        self.map.is_disjoint(&other.map)
+    }
+}
+
+impl<T: Ord + Clone> RedBlackTreeSet<T> {
+    pub fn first(&self) -> Option<T> {
+        self.map.first().map(|(value, _)| value)
+    }
+
+    pub fn last(&self) -> Option<T> {
+        self.map.last().map(|(value, _)| value)
+    }
+
+    ///升序迭代
+    pub fn iter(&self) -> SetIter<'_, T> {
+        SetIter { iter: self.map.iter() }
+    }
+
+    ///范围内升序迭代
+    pub fn range<R: RangeBounds<T>>(&self, range: R) -> SetIter<'_, T> {
+        SetIter { iter: self.map.range(range) }
+    }
+
+    ///并集，升序
+    pub fn union<'a>(&'a self, other: &'a Self) -> SetOperation<'a, T> {
+        SetOperation::new(self, other, Operation::Union)
+    }
+
+    ///交集
+    pub fn intersection<'a>(&'a self, other: &'a Self) -> SetOperation<'a, T> {
+        SetOperation::new(self, other, Operation::Intersection)
+    }
+
+    ///差集，在本集合而不在other中的元素
+    pub fn difference<'a>(&'a self, other: &'a Self) -> SetOperation<'a, T> {
+        SetOperation::new(self, other, Operation::Difference)
+    }
+
+    ///对称差，只在其中一个集合中的元素
+    pub fn symmetric_difference<'a>(&'a self, other: &'a Self) -> SetOperation<'a, T> {
+        SetOperation::new(self, other, Operation::SymmetricDifference)
+    }
+}
+
+/// 集合的中序迭代器
+pub struct SetIter<'a, T> {
+    iter: Iter<'a, T, ()>,
+}
+
+impl<T: Ord + Clone> Iterator for SetIter<'_, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.iter.next().map(|(value, _)| value)
+    }
+}
+
+impl<T: Ord + Clone> DoubleEndedIterator for SetIter<'_, T> {
+    fn next_back(&mut self) -> Option<T> {
+        self.iter.next_back().map(|(value, _)| value)
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Operation {
+    Union,
+    Intersection,
+    Difference,
+    SymmetricDifference,
+}
+
+/// 集合运算的迭代器，两个集合同步中序推进，O(n + m)
+pub struct SetOperation<'a, T: Ord + Clone> {
+    zip: ZipSorted<'a, T, (), (), Iter<'a, T, ()>>,
+    operation: Operation,
+}
+
+impl<'a, T: Ord + Clone> SetOperation<'a, T> {
+    fn new(set: &'a RedBlackTreeSet<T>, other: &'a RedBlackTreeSet<T>, operation: Operation) -> Self {
+        SetOperation { zip: set.map.zip_sorted(other.map.iter()), operation }
+    }
+}
+
+impl<T: Ord + Clone> Iterator for SetOperation<'_, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        for item in self.zip.by_ref() {
+            let value = match (self.operation, item) {
+                (Operation::Union, EitherOrBoth::Left((value, _)) | EitherOrBoth::Right((value, _)) | EitherOrBoth::Both((value, _), _)) => value,
+                (Operation::Intersection, EitherOrBoth::Both((value, _), _)) => value,
+                (Operation::Difference, EitherOrBoth::Left((value, _))) => value,
+                (Operation::SymmetricDifference, EitherOrBoth::Left((value, _)) | EitherOrBoth::Right((value, _))) => value,
+                _ => continue,
+            };
+            return Some(value);
+        }
+        None
+    }
+}
+
+impl<'a, T: Ord + Clone> IntoIterator for &'a RedBlackTreeSet<T> {
+    type Item = T;
+    type IntoIter = SetIter<'a, T>;
+
+    fn into_iter(self) -> SetIter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<T: Ord> FromIterator<T> for RedBlackTreeSet<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut set = RedBlackTreeSet::new();
+        set.extend(iter);
+        set
+    }
+}
+
+impl<T: Ord> Extend<T> for RedBlackTreeSet<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for value in iter {
+            self.insert(value);
+        }
+    }
+}
+
+impl<T: Ord> Default for RedBlackTreeSet<T> {
+    fn default() -> Self {
+        RedBlackTreeSet::new()
+    }
+}
+
+impl<T: Clone> Clone for RedBlackTreeSet<T> {
+    fn clone(&self) -> Self {
+        RedBlackTreeSet { map: self.map.clone() }
+    }
+}
+
+impl<T: PartialEq> PartialEq for RedBlackTreeSet<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.map == other.map
+    }
+}
+
+impl<T: Eq> Eq for RedBlackTreeSet<T> {}
+
+///按元素的字典序比较，与BTreeSet相同
+impl<T: PartialOrd> PartialOrd for RedBlackTreeSet<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.map.partial_cmp(&other.map)
+    }
+}
+
+impl<T: Ord> Ord for RedBlackTreeSet<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.map.cmp(&other.map)
+    }
+}
+
+impl<T: Hash> Hash for RedBlackTreeSet<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.map.hash(state);
+    }
+}
+
+///按集合格式输出元素，如{1, 2, 3}
+impl<T: Ord + Clone + fmt::Debug> fmt::Debug for RedBlackTreeSet<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use super::RedBlackTreeSet;
+
+    #[test]
+    fn matches_btree_set() {
+        let evens: RedBlackTreeSet<i32> = (0..30).step_by(2).collect();
+        let triples: RedBlackTreeSet<i32> = (0..30).step_by(3).collect();
+        let expected_evens: BTreeSet<i32> = (0..30).step_by(2).collect();
+        let expected_triples: BTreeSet<i32> = (0..30).step_by(3).collect();
+        assert!(evens.union(&triples).eq(expected_evens.union(&expected_triples).copied()));
+        assert!(evens.intersection(&triples).eq(expected_evens.intersection(&expected_triples).copied()));
+        assert!(evens.difference(&triples).eq(expected_evens.difference(&expected_triples).copied()));
+        assert!(evens.symmetric_difference(&triples).eq(expected_evens.symmetric_difference(&expected_triples).copied()));
+        assert!(evens.range(5..=20).rev().eq(expected_evens.range(5..=20).rev().copied()));
+
+        let mut set = evens.clone();
+        assert!(!set.insert(4));
+        assert!(set.insert(5));
+        assert!(set.remove(&5) && !set.remove(&5));
+        assert_eq!(set, evens);
+        assert_eq!(format!("{:?}", set.split_off(&26)), "{26, 28}");
+        assert_eq!((set.first(), set.last(), set.len()), (Some(0), Some(24), 13));
+    }
+}
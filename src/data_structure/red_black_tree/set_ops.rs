@@ -0,0 +1,87 @@
+//! 集合关系判断
+//! 只比较键，两棵树同时中序推进，发现反例即提前结束，O(n + m)
+
+use alloc::rc::Rc;
+use core::cell::RefCell;
+use core::cmp::Ordering;
+
+use super::{Node, RedBlackTree};
+
+impl<K: Ord, V> RedBlackTree<K, V> {
+    ///本树的键是否都在other中
+    pub fn is_subset<V2>(&self, other: &RedBlackTree<K, V2>) -> bool {
+        if self.len > other.len {
+            return false;
+        }
+        let mut next_option = self.root.as_ref().map(Self::find_minimum);
+        let mut other_option = other.root.as_ref().map(RedBlackTree::find_minimum);
+        while let Some(cur_rc) = next_option {
+            //跳过other中更小的键
+            loop {
+                let Some(other_rc) = &other_option else {
+                    return false;
+                };
+                match compare(&cur_rc, other_rc) {
+                    Ordering::Less => return false,
+                    Ordering::Equal => break,
+                    Ordering::Greater => other_option = RedBlackTree::successor(other_rc),
+                }
+            }
+            next_option = Self::successor(&cur_rc);
+            other_option = other_option.as_ref().and_then(RedBlackTree::successor);
+        }
+        true
+    }
+
+    ///other的键是否都在本树中
+    pub fn is_superset<V2>(&self, other: &RedBlackTree<K, V2>) -> bool {
+        other.is_subset(self)
+    }
+
+    ///两棵树没有相同的键
+    pub fn is_disjoint<V2>(&self, other: &RedBlackTree<K, V2>) -> bool {
+        let mut next_option = self.root.as_ref().map(Self::find_minimum);
+        let mut other_option = other.root.as_ref().map(RedBlackTree::find_minimum);
+        while let (Some(cur_rc), Some(other_rc)) = (&next_option, &other_option) {
+            match compare(cur_rc, other_rc) {
+                Ordering::Less => next_option = Self::successor(cur_rc),
+                Ordering::Equal => return false,
+                Ordering::Greater => other_option = RedBlackTree::successor(other_rc),
+            }
+        }
+        true
+    }
+}
+
+fn compare<K: Ord, V, V2>(node_ref: &Rc<RefCell<Node<K, V>>>, other_ref: &Rc<RefCell<Node<K, V2>>>) -> Ordering {
+    node_ref.borrow().key.cmp(&other_ref.borrow().key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::RedBlackTree;
+
+    fn tree_of(keys: &[i32]) -> RedBlackTree<i32, ()> {
+        let mut tree = RedBlackTree::new();
+        for key in keys {
+            tree.insert(*key, ());
+        }
+        tree
+    }
+
+    #[test]
+    fn set_relations() {
+        let small = tree_of(&[3, 5, 9]);
+        let large = tree_of(&[1, 3, 4, 5, 8, 9]);
+        let other = tree_of(&[2, 6, 10]);
+        let empty = tree_of(&[]);
+        assert!(small.is_subset(&large) && large.is_superset(&small));
+        assert!(!large.is_subset(&small) && !small.is_superset(&large));
+        assert!(!tree_of(&[3, 7]).is_subset(&large));
+        assert!(!tree_of(&[10]).is_subset(&large));
+        assert!(empty.is_subset(&small) && small.is_superset(&empty));
+        assert!(small.is_disjoint(&other) && other.is_disjoint(&large));
+        assert!(!small.is_disjoint(&large));
+        assert!(empty.is_disjoint(&empty));
+    }
+}
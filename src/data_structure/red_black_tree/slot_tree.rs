@@ -0,0 +1,476 @@
+//! 以下标连接的红黑树算法
+//! RedBlackTreeArena与StaticRbTree的节点都存放在槽位中，父子连接为u32下标，二者只有槽位的存储不同(Vec或定长数组)
+//! SlotTree描述槽位存储，旋转、插入删除与调平、前驱后继及校验在此只实现一次
+
+use core::cmp::Ordering;
+
+use super::{Augment, Color, InvariantViolation};
+
+///空下标，相当于空指针
+pub(super) const NIL: u32 = u32::MAX;
+
+pub(super) struct SlotNode<K, V, G> {
+    pub(super) key: K,
+    pub(super) value: V,
+    pub(super) parent: u32,
+    pub(super) left: u32,
+    pub(super) right: u32,
+    pub(super) color: Color,
+    //附加信息，由左右子树与节点本身计算
+    pub(super) augment: G,
+}
+
+///槽位，空闲槽位记录下一个空闲槽位
+pub(super) enum Slot<K, V, G> {
+    Occupied(SlotNode<K, V, G>),
+    Vacant { next_free: u32 },
+}
+
+/// 树头
+/// free为空闲链表头，只包含删除后释放的槽位
+#[derive(Clone, Copy)]
+pub(super) struct Links {
+    pub(super) root: u32,
+    pub(super) free: u32,
+    pub(super) len: usize,
+}
+
+impl Links {
+    pub(super) const EMPTY: Links = Links { root: NIL, free: NIL, len: 0 };
+}
+
+/// 槽位存储，实现者只需给出槽位、树头与追加新槽位的方式
+/// A为节点附加信息，不附加信息时为()
+pub(super) trait SlotTree<K: Ord, V, A: Augment<K, V>> {
+    fn slots(&self) -> &[Slot<K, V, A::Value>];
+
+    fn slots_mut(&mut self) -> &mut [Slot<K, V, A::Value>];
+
+    fn links(&self) -> &Links;
+
+    fn links_mut(&mut self) -> &mut Links;
+
+    ///追加一个从未使用过的槽位，容量用尽时带回节点
+    fn push_slot(&mut self, node: SlotNode<K, V, A::Value>) -> Result<u32, SlotNode<K, V, A::Value>>;
+
+    fn node(&self, index: u32) -> &SlotNode<K, V, A::Value> {
+        match &self.slots()[index as usize] {
+            Slot::Occupied(node) => node,
+            Slot::Vacant { .. } => unreachable!("link to a free slot"),
+        }
+    }
+
+    fn node_mut(&mut self, index: u32) -> &mut SlotNode<K, V, A::Value> {
+        match &mut self.slots_mut()[index as usize] {
+            Slot::Occupied(node) => node,
+            Slot::Vacant { .. } => unreachable!("link to a free slot"),
+        }
+    }
+
+    ///空下标视为黑色
+    fn color(&self, index: u32) -> Color {
+        if index == NIL {
+            Color::Black
+        } else {
+            self.node(index).color
+        }
+    }
+
+    ///分配槽位，优先复用空闲槽位，容量用尽时带回节点
+    fn allocate(&mut self, node: SlotNode<K, V, A::Value>) -> Result<u32, SlotNode<K, V, A::Value>> {
+        let index = self.links().free;
+        if index == NIL {
+            return self.push_slot(node);
+        }
+        self.links_mut().free = match self.slots()[index as usize] {
+            Slot::Vacant { next_free } => next_free,
+            Slot::Occupied(_) => unreachable!("free list points to an occupied slot"),
+        };
+        self.slots_mut()[index as usize] = Slot::Occupied(node);
+        Ok(index)
+    }
+
+    ///释放槽位，取出节点
+    fn release(&mut self, index: u32) -> SlotNode<K, V, A::Value> {
+        let next_free = self.links().free;
+        let slot = core::mem::replace(&mut self.slots_mut()[index as usize], Slot::Vacant { next_free });
+        self.links_mut().free = index;
+        match slot {
+            Slot::Occupied(node) => node,
+            Slot::Vacant { .. } => unreachable!("release a free slot"),
+        }
+    }
+
+    fn find(&self, key: &K) -> u32 {
+        let mut cur = self.links().root;
+        while cur != NIL {
+            let node = self.node(cur);
+            cur = match key.cmp(&node.key) {
+                Ordering::Equal => return cur,
+                Ordering::Less => node.left,
+                Ordering::Greater => node.right,
+            };
+        }
+        NIL
+    }
+
+    fn minimum(&self, mut index: u32) -> u32 {
+        while self.node(index).left != NIL {
+            index = self.node(index).left;
+        }
+        index
+    }
+
+    fn maximum(&self, mut index: u32) -> u32 {
+        while self.node(index).right != NIL {
+            index = self.node(index).right;
+        }
+        index
+    }
+
+    ///后继，有右子树则为右子树最小节点，否则向上寻找第一个从左侧到达的祖先
+    fn successor(&self, index: u32) -> u32 {
+        let node = self.node(index);
+        if node.right != NIL {
+            return self.minimum(node.right);
+        }
+        let mut cur = index;
+        let mut parent = node.parent;
+        while parent != NIL && self.node(parent).right == cur {
+            cur = parent;
+            parent = self.node(parent).parent;
+        }
+        parent
+    }
+
+    ///前驱，与后继对称
+    fn predecessor(&self, index: u32) -> u32 {
+        let node = self.node(index);
+        if node.left != NIL {
+            return self.maximum(node.left);
+        }
+        let mut cur = index;
+        let mut parent = node.parent;
+        while parent != NIL && self.node(parent).left == cur {
+            cur = parent;
+            parent = self.node(parent).parent;
+        }
+        parent
+    }
+
+    fn entry<'a>(&'a self, index: u32) -> (&'a K, &'a V)
+    where
+        A::Value: 'a,
+    {
+        let node = self.node(index);
+        (&node.key, &node.value)
+    }
+
+    ///在parent中以new取代子节点old，parent为空时取代根
+    fn replace_child(&mut self, parent: u32, old: u32, new: u32) {
+        if parent == NIL {
+            self.links_mut().root = new;
+        } else if self.node(parent).left == old {
+            self.node_mut(parent).left = new;
+        } else {
+            self.node_mut(parent).right = new;
+        }
+    }
+
+    ///左旋，x的右子节点上升
+    fn rotate_left(&mut self, x: u32) {
+        let y = self.node(x).right;
+        let y_left = self.node(y).left;
+        self.node_mut(x).right = y_left;
+        if y_left != NIL {
+            self.node_mut(y_left).parent = x;
+        }
+        let x_parent = self.node(x).parent;
+        self.node_mut(y).parent = x_parent;
+        self.replace_child(x_parent, x, y);
+        self.node_mut(y).left = x;
+        self.node_mut(x).parent = y;
+        //x成为y的子节点，先算x再算y
+        self.pull(x);
+        self.pull(y);
+    }
+
+    ///右旋，x的左子节点上升
+    fn rotate_right(&mut self, x: u32) {
+        let y = self.node(x).left;
+        let y_right = self.node(y).right;
+        self.node_mut(x).left = y_right;
+        if y_right != NIL {
+            self.node_mut(y_right).parent = x;
+        }
+        let x_parent = self.node(x).parent;
+        self.node_mut(y).parent = x_parent;
+        self.replace_child(x_parent, x, y);
+        self.node_mut(y).right = x;
+        self.node_mut(x).parent = y;
+        self.pull(x);
+        self.pull(y);
+    }
+
+    /// 由子节点重新计算index的附加信息
+    /// 零大小的附加信息(包括默认的())不携带内容，无需计算
+    fn pull(&mut self, index: u32) {
+        if core::mem::size_of::<A::Value>() == 0 {
+            return;
+        }
+        let node = self.node(index);
+        let left = (node.left != NIL).then(|| &self.node(node.left).augment);
+        let right = (node.right != NIL).then(|| &self.node(node.right).augment);
+        let augment = A::combine(left, &node.key, &node.value, right);
+        self.node_mut(index).augment = augment;
+    }
+
+    ///从index到根的路径上逐个重新计算附加信息
+    fn pull_path(&mut self, mut index: u32) {
+        if core::mem::size_of::<A::Value>() == 0 {
+            return;
+        }
+        while index != NIL {
+            self.pull(index);
+            index = self.node(index).parent;
+        }
+    }
+
+    /// 插入键值对，键已存在时树不变并返回Ok(false)
+    /// 需要新槽位而容量用尽时带回键值对，树不变
+    fn insert_entry(&mut self, key: K, value: V) -> Result<bool, (K, V)> {
+        let mut parent = NIL;
+        let mut ordering = Ordering::Equal;
+        let mut cur = self.links().root;
+        while cur != NIL {
+            let node = self.node(cur);
+            ordering = key.cmp(&node.key);
+            parent = cur;
+            cur = match ordering {
+                Ordering::Equal => return Ok(false),
+                Ordering::Less => node.left,
+                Ordering::Greater => node.right,
+            };
+        }
+        let augment = A::combine(None, &key, &value, None);
+        let index = self
+            .allocate(SlotNode { key, value, parent, left: NIL, right: NIL, color: Color::Red, augment })
+            .map_err(|node| (node.key, node.value))?;
+        if parent == NIL {
+            self.links_mut().root = index;
+        } else if ordering == Ordering::Less {
+            self.node_mut(parent).left = index;
+        } else {
+            self.node_mut(parent).right = index;
+        }
+        self.links_mut().len += 1;
+        //先修正路径上的附加信息，之后的旋转只需重新计算旋转的两个节点
+        self.pull_path(parent);
+        self.insert_balance(index);
+        Ok(true)
+    }
+
+    /// 插入平衡
+    /// 叔节点为红色时父、叔染黑，爷节点染红后以爷节点继续向上
+    /// 叔节点为黑色时经一到两次旋转结束
+    fn insert_balance(&mut self, mut son: u32) {
+        while self.color(self.node(son).parent) == Color::Red {
+            let parent = self.node(son).parent;
+            //父节点为红色，必不是根节点，爷节点存在
+            let grand_parent = self.node(parent).parent;
+            let parent_is_left = self.node(grand_parent).left == parent;
+            let uncle = if parent_is_left { self.node(grand_parent).right } else { self.node(grand_parent).left };
+            if self.color(uncle) == Color::Red {
+                self.node_mut(parent).color = Color::Black;
+                self.node_mut(uncle).color = Color::Black;
+                self.node_mut(grand_parent).color = Color::Red;
+                son = grand_parent;
+                continue;
+            }
+            let mut top = parent;
+            if parent_is_left {
+                //LR先转为LL
+                if self.node(parent).right == son {
+                    self.rotate_left(parent);
+                    top = son;
+                }
+                self.rotate_right(grand_parent);
+            } else {
+                //RL先转为RR
+                if self.node(parent).left == son {
+                    self.rotate_right(parent);
+                    top = son;
+                }
+                self.rotate_left(grand_parent);
+            }
+            self.node_mut(top).color = Color::Black;
+            self.node_mut(grand_parent).color = Color::Red;
+            break;
+        }
+        let root = self.links().root;
+        self.node_mut(root).color = Color::Black;
+    }
+
+    /// 删除节点，释放的槽位供后续插入复用
+    /// 有两个子节点时以后继节点取代其位置与颜色，实际离开的是后继节点
+    /// 离开的节点为黑色时，从补位节点(可能为空)与其父节点开始调平
+    fn remove_index(&mut self, target: u32) -> (K, V) {
+        let (left, right) = (self.node(target).left, self.node(target).right);
+        let (fix, fix_parent, removed_color);
+        if left == NIL || right == NIL {
+            fix = if left == NIL { right } else { left };
+            fix_parent = self.node(target).parent;
+            removed_color = self.node(target).color;
+            if fix != NIL {
+                self.node_mut(fix).parent = fix_parent;
+            }
+            self.replace_child(fix_parent, target, fix);
+        } else {
+            let successor = self.minimum(right);
+            removed_color = self.node(successor).color;
+            fix = self.node(successor).right;
+            if self.node(successor).parent == target {
+                fix_parent = successor;
+            } else {
+                fix_parent = self.node(successor).parent;
+                if fix != NIL {
+                    self.node_mut(fix).parent = fix_parent;
+                }
+                self.node_mut(fix_parent).left = fix;
+                self.node_mut(successor).right = right;
+                self.node_mut(right).parent = successor;
+            }
+            let target_parent = self.node(target).parent;
+            self.replace_child(target_parent, target, successor);
+            let target_color = self.node(target).color;
+            let successor_node = self.node_mut(successor);
+            successor_node.parent = target_parent;
+            successor_node.left = left;
+            successor_node.color = target_color;
+            self.node_mut(left).parent = successor;
+        }
+        //fix_parent以上的路径子树内容发生变化，后继取代目标时也在此路径上
+        self.pull_path(fix_parent);
+        if removed_color == Color::Black {
+            self.delete_balance(fix, fix_parent);
+        }
+        self.links_mut().len -= 1;
+        let node = self.release(target);
+        (node.key, node.value)
+    }
+
+    /// 删除平衡，son所在一侧黑高少一
+    /// 兄弟为红色时旋转转为兄弟为黑色
+    /// 兄弟的子节点都为黑色时兄弟染红，失衡上移
+    /// 否则经一到两次旋转结束
+    fn delete_balance(&mut self, mut son: u32, mut parent: u32) {
+        while son != self.links().root && self.color(son) == Color::Black {
+            let son_is_left = self.node(parent).left == son;
+            let mut brother = if son_is_left { self.node(parent).right } else { self.node(parent).left };
+            if self.color(brother) == Color::Red {
+                self.node_mut(brother).color = Color::Black;
+                self.node_mut(parent).color = Color::Red;
+                if son_is_left {
+                    self.rotate_left(parent);
+                    brother = self.node(parent).right;
+                } else {
+                    self.rotate_right(parent);
+                    brother = self.node(parent).left;
+                }
+            }
+            let (near, far) = if son_is_left {
+                (self.node(brother).left, self.node(brother).right)
+            } else {
+                (self.node(brother).right, self.node(brother).left)
+            };
+            if self.color(near) == Color::Black && self.color(far) == Color::Black {
+                self.node_mut(brother).color = Color::Red;
+                son = parent;
+                parent = self.node(parent).parent;
+                continue;
+            }
+            if self.color(far) == Color::Black {
+                //近侄子为红，先转到远侧
+                self.node_mut(near).color = Color::Black;
+                self.node_mut(brother).color = Color::Red;
+                if son_is_left {
+                    self.rotate_right(brother);
+                    brother = self.node(parent).right;
+                } else {
+                    self.rotate_left(brother);
+                    brother = self.node(parent).left;
+                }
+            }
+            let far = if son_is_left { self.node(brother).right } else { self.node(brother).left };
+            self.node_mut(brother).color = self.node(parent).color;
+            self.node_mut(parent).color = Color::Black;
+            self.node_mut(far).color = Color::Black;
+            if son_is_left {
+                self.rotate_left(parent);
+            } else {
+                self.rotate_right(parent);
+            }
+            son = self.links().root;
+        }
+        if son != NIL {
+            self.node_mut(son).color = Color::Black;
+        }
+    }
+
+    /// 校验红黑树性质，与RedBlackTree::validate相同
+    /// 槽位实现没有子树节点数量，不检查SubtreeSize
+    fn validate_links(&self) -> Result<(), InvariantViolation<K>>
+    where
+        K: Clone,
+    {
+        let links = self.links();
+        if links.root != NIL {
+            let root = self.node(links.root);
+            if root.color == Color::Red {
+                return Err(InvariantViolation::RedRoot);
+            }
+            if root.parent != NIL {
+                return Err(InvariantViolation::ParentPointer { key: root.key.clone() });
+            }
+        }
+        let mut count = 0;
+        self.validate_node(links.root, None, None, &mut count)?;
+        if count != links.len {
+            return Err(InvariantViolation::LengthMismatch { expected: links.len, actual: count });
+        }
+        Ok(())
+    }
+
+    ///递归校验子树，返回子树黑高(空节点黑高为1)
+    fn validate_node(&self, index: u32, lower: Option<&K>, upper: Option<&K>, count: &mut usize) -> Result<usize, InvariantViolation<K>>
+    where
+        K: Clone,
+    {
+        if index == NIL {
+            return Ok(1);
+        }
+        *count += 1;
+        let node = self.node(index);
+        if lower.is_some_and(|lower| node.key <= *lower) || upper.is_some_and(|upper| node.key >= *upper) {
+            return Err(InvariantViolation::OutOfOrder { key: node.key.clone() });
+        }
+        for son in [node.left, node.right] {
+            if son == NIL {
+                continue;
+            }
+            let son_node = self.node(son);
+            if son_node.parent != index {
+                return Err(InvariantViolation::ParentPointer { key: son_node.key.clone() });
+            }
+            if node.color == Color::Red && son_node.color == Color::Red {
+                return Err(InvariantViolation::RedRed { key: node.key.clone() });
+            }
+        }
+        let left = self.validate_node(node.left, lower, Some(&node.key), count)?;
+        let right = self.validate_node(node.right, Some(&node.key), upper, count)?;
+        if left != right {
+            return Err(InvariantViolation::BlackHeight { key: node.key.clone(), left, right });
+        }
+        Ok(left + usize::from(node.color == Color::Black))
+    }
+}
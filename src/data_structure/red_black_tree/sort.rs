@@ -0,0 +1,69 @@
+use alloc::vec::Vec;
+use core::iter;
+
+use super::RedBlackTree;
+
+/// 树排序
+/// 每组相等元素中首个元素为键，其余按出现顺序存入值Vec，相等元素保持原有顺序(稳定排序)
+/// 插入完成后消耗树，按组逐个取出，结果是惰性的
+pub fn tree_sort<T: Ord, I: IntoIterator<Item = T>>(iter: I) -> impl Iterator<Item = T> {
+    let mut tree: RedBlackTree<T, Vec<T>> = RedBlackTree::new();
+    for item in iter {
+        match tree.find(&item) {
+            Some(node_rc) => node_rc.borrow_mut().value.push(item),
+            None => tree.insert(item, Vec::new()),
+        }
+    }
+    tree.into_iter()
+        .flat_map(|(first, rest)| iter::once(first).chain(rest))
+}
+
+///借助红黑树对Vec原地稳定排序
+pub fn sort_vec_via_tree<T: Ord>(vec: &mut Vec<T>) {
+    let items = core::mem::take(vec);
+    vec.extend(tree_sort(items));
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    use super::*;
+
+    //只按key比较，tag用于检查稳定性
+    #[derive(Debug)]
+    struct Tagged {
+        key: u8,
+        tag: char,
+    }
+
+    impl PartialEq for Tagged {
+        fn eq(&self, other: &Self) -> bool {
+            self.key == other.key
+        }
+    }
+
+    impl Eq for Tagged {}
+
+    impl PartialOrd for Tagged {
+        fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
+            Some(self.cmp(other))
+        }
+    }
+
+    impl Ord for Tagged {
+        fn cmp(&self, other: &Self) -> core::cmp::Ordering {
+            self.key.cmp(&other.key)
+        }
+    }
+
+    #[test]
+    fn equal_items_keep_input_order_without_clone() {
+        let items = vec![(2, 'a'), (1, 'b'), (2, 'c'), (1, 'd'), (2, 'e')];
+        let mut tagged: Vec<Tagged> = items.into_iter().map(|(key, tag)| Tagged { key, tag }).collect();
+        sort_vec_via_tree(&mut tagged);
+        let tags: Vec<char> = tagged.iter().map(|item| item.tag).collect();
+        assert_eq!(tags, ['b', 'd', 'a', 'c', 'e']);
+    }
+}
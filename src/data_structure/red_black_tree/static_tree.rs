@@ -0,0 +1,289 @@
+//! 固定容量的红黑树
+//! 节点存放在树内长度为N的数组中，连接为u32下标，与RedBlackTreeArena共用SlotTree的算法
+//! 全程不分配内存，容量用尽时插入返回Err(Full)，适合不能在运行时分配的嵌入式环境
+//! new为const fn，可直接放在static中
+
+use core::error::Error;
+use core::fmt;
+
+use super::slot_tree::{Links, Slot, SlotNode, SlotTree, NIL};
+use super::InvariantViolation;
+
+/// 容量已满，插入失败
+/// 带回未能插入的键值对
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct Full<K, V> {
+    pub key: K,
+    pub value: V,
+}
+
+impl<K, V> fmt::Display for Full<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "static tree is full")
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug> Error for Full<K, V> {}
+
+pub struct StaticRbTree<K, V, const N: usize> {
+    slots: [Slot<K, V, ()>; N],
+    links: Links,
+    //从未使用过的槽位从此开始，避免构造时串起全部槽位
+    unused: u32,
+}
+
+///槽位存放在定长数组中，从未使用过的槽位用尽后不再增长
+impl<K: Ord, V, const N: usize> SlotTree<K, V, ()> for StaticRbTree<K, V, N> {
+    fn slots(&self) -> &[Slot<K, V, ()>] {
+        &self.slots
+    }
+
+    fn slots_mut(&mut self) -> &mut [Slot<K, V, ()>] {
+        &mut self.slots
+    }
+
+    fn links(&self) -> &Links {
+        &self.links
+    }
+
+    fn links_mut(&mut self) -> &mut Links {
+        &mut self.links
+    }
+
+    fn push_slot(&mut self, node: SlotNode<K, V, ()>) -> Result<u32, SlotNode<K, V, ()>> {
+        if self.unused as usize == N {
+            return Err(node);
+        }
+        self.slots[self.unused as usize] = Slot::Occupied(node);
+        self.unused += 1;
+        Ok(self.unused - 1)
+    }
+}
+
+impl<K: Ord, V, const N: usize> StaticRbTree<K, V, N> {
+    pub const fn new() -> Self {
+        const { assert!(N < NIL as usize, "capacity must be less than u32::MAX") };
+        StaticRbTree {
+            slots: [const { Slot::Vacant { next_free: NIL } }; N],
+            links: Links::EMPTY,
+            unused: 0,
+        }
+    }
+
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    pub fn len(&self) -> usize {
+        self.links.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.links.len == 0
+    }
+
+    pub fn is_full(&self) -> bool {
+        !self.has_vacancy()
+    }
+
+    ///清空树，键值对逐个析构
+    pub fn clear(&mut self) {
+        for slot in &mut self.slots[..self.unused as usize] {
+            *slot = Slot::Vacant { next_free: NIL };
+        }
+        self.links = Links::EMPTY;
+        self.unused = 0;
+    }
+
+    ///还有可用的槽位
+    fn has_vacancy(&self) -> bool {
+        self.links.free != NIL || (self.unused as usize) < N
+    }
+
+    /// 插入键值对，键已存在时忽略
+    /// 需要新节点而容量已满时返回Err(Full)，树不变
+    pub fn insert(&mut self, key: K, value: V) -> Result<(), Full<K, V>> {
+        self.insert_entry(key, value).map(|_| ()).map_err(|(key, value)| Full { key, value })
+    }
+
+    /// 插入或替换，返回旧值
+    /// 键不存在且容量已满时返回Err(Full)
+    pub fn insert_or_replace(&mut self, key: K, value: V) -> Result<Option<V>, Full<K, V>> {
+        match self.get_mut(&key) {
+            Some(old) => Ok(Some(core::mem::replace(old, value))),
+            None => self.insert(key, value).map(|_| None),
+        }
+    }
+
+    ///删除键，键不存在时忽略
+    pub fn delete(&mut self, key: &K) {
+        let target = self.find(key);
+        if target != NIL {
+            self.remove_index(target);
+        }
+    }
+
+    ///删除键并返回值，释放的槽位供后续插入复用
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let target = self.find(key);
+        (target != NIL).then(|| self.remove_index(target).1)
+    }
+
+    pub fn get(&self, key: &K) -> Option<&V> {
+        let index = self.find(key);
+        (index != NIL).then(|| &self.node(index).value)
+    }
+
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        let index = self.find(key);
+        (index != NIL).then(|| &mut self.node_mut(index).value)
+    }
+
+    pub fn contains(&self, key: &K) -> bool {
+        self.find(key) != NIL
+    }
+
+    pub fn first(&self) -> Option<(&K, &V)> {
+        (self.links.root != NIL).then(|| self.entry(self.minimum(self.links.root)))
+    }
+
+    pub fn last(&self) -> Option<(&K, &V)> {
+        (self.links.root != NIL).then(|| self.entry(self.maximum(self.links.root)))
+    }
+
+    pub fn pop_first(&mut self) -> Option<(K, V)> {
+        (self.links.root != NIL).then(|| self.remove_index(self.minimum(self.links.root)))
+    }
+
+    pub fn pop_last(&mut self) -> Option<(K, V)> {
+        (self.links.root != NIL).then(|| self.remove_index(self.maximum(self.links.root)))
+    }
+
+    ///中序迭代，产出借用
+    pub fn iter(&self) -> StaticIter<'_, K, V, N> {
+        let (front, back) = if self.links.root == NIL { (NIL, NIL) } else { (self.minimum(self.links.root), self.maximum(self.links.root)) };
+        StaticIter { tree: self, front, back }
+    }
+
+    ///校验红黑树性质，与RedBlackTreeArena::validate相同
+    pub fn validate(&self) -> Result<(), InvariantViolation<K>>
+    where
+        K: Clone,
+    {
+        self.validate_links()
+    }
+}
+
+impl<K: Ord, V, const N: usize> Default for StaticRbTree<K, V, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Ord + fmt::Debug, V: fmt::Debug, const N: usize> fmt::Debug for StaticRbTree<K, V, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+///中序迭代器，与ArenaIter相同
+pub struct StaticIter<'a, K, V, const N: usize> {
+    tree: &'a StaticRbTree<K, V, N>,
+    front: u32,
+    back: u32,
+}
+
+impl<'a, K: Ord, V, const N: usize> Iterator for StaticIter<'a, K, V, N> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.front == NIL {
+            return None;
+        }
+        let cur = self.front;
+        if cur == self.back {
+            self.front = NIL;
+            self.back = NIL;
+        } else {
+            self.front = self.tree.successor(cur);
+        }
+        Some(self.tree.entry(cur))
+    }
+}
+
+impl<K: Ord, V, const N: usize> DoubleEndedIterator for StaticIter<'_, K, V, N> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.back == NIL {
+            return None;
+        }
+        let cur = self.back;
+        if cur == self.front {
+            self.front = NIL;
+            self.back = NIL;
+        } else {
+            self.back = self.tree.predecessor(cur);
+        }
+        Some(self.tree.entry(cur))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::{Full, StaticRbTree};
+
+    #[test]
+    fn usable_in_static() {
+        static EMPTY: StaticRbTree<u32, u32, 8> = StaticRbTree::new();
+        assert!(EMPTY.is_empty());
+        assert_eq!(EMPTY.capacity(), 8);
+    }
+
+    #[test]
+    fn full_until_a_slot_is_freed() {
+        let mut tree = StaticRbTree::<u32, &str, 3>::new();
+        assert_eq!(tree.insert(2, "b"), Ok(()));
+        assert_eq!(tree.insert(1, "a"), Ok(()));
+        assert_eq!(tree.insert(3, "c"), Ok(()));
+        assert!(tree.is_full());
+        //已有的键不需要新槽位
+        assert_eq!(tree.insert(2, "x"), Ok(()));
+        assert_eq!(tree.insert_or_replace(2, "B"), Ok(Some("b")));
+        assert_eq!(tree.insert(4, "d"), Err(Full { key: 4, value: "d" }));
+        assert_eq!(tree.remove(&1), Some("a"));
+        assert_eq!(tree.insert(4, "d"), Ok(()));
+        assert!(tree.iter().eq([(&2, &"B"), (&3, &"c"), (&4, &"d")]));
+        assert_eq!(tree.validate(), Ok(()));
+    }
+
+    #[test]
+    fn matches_btree_map_model() {
+        let mut tree = StaticRbTree::<u32, u32, 64>::new();
+        let mut model = BTreeMap::new();
+        let mut state = 7u32;
+        for step in 0..5_000u32 {
+            state = state.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+            let key = (state >> 16) % 100;
+            if state.is_multiple_of(3) {
+                assert_eq!(tree.remove(&key), model.remove(&key));
+            } else {
+                let inserted = tree.insert(key, step).is_ok();
+                if model.len() < 64 || model.contains_key(&key) {
+                    assert!(inserted);
+                    model.entry(key).or_insert(step);
+                } else {
+                    assert!(!inserted);
+                }
+            }
+            assert_eq!(tree.len(), model.len());
+        }
+        assert_eq!(tree.validate(), Ok(()));
+        assert!(tree.iter().eq(model.iter()));
+        assert!(tree.iter().rev().eq(model.iter().rev()));
+        assert_eq!(tree.pop_first(), model.pop_first());
+        assert_eq!(tree.pop_last(), model.pop_last());
+        tree.clear();
+        assert!(tree.is_empty());
+    }
+}
@@ -0,0 +1,91 @@
+//! 调平统计
+//! 记录左旋、右旋、变色、键比较次数，以及单次插入删除中调平向上传递的最大层数
+//! 需开启stats特性，未开启时记录为空操作，不占用空间
+
+use alloc::rc::Rc;
+use core::cell::RefCell;
+
+use super::{Color, Node, RedBlackTree};
+
+/// 调平统计，自创建或上次reset_stats以来累计
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(not(feature = "stats"), allow(dead_code))]
+pub struct Stats {
+    pub rotations_left: u64,
+    pub rotations_right: u64,
+    ///颜色实际发生变化的次数
+    pub recolors: u64,
+    ///查找、插入、删除中键的比较次数
+    pub comparisons: u64,
+    ///单次插入删除中调平向上传递的最大层数，不需要调平为0
+    pub max_fixup_depth: usize,
+}
+
+impl<K, V> RedBlackTree<K, V> {
+    ///当前统计
+    #[cfg(feature = "stats")]
+    pub fn stats(&self) -> Stats {
+        self.stats.get()
+    }
+
+    ///清零统计
+    #[cfg(feature = "stats")]
+    pub fn reset_stats(&mut self) {
+        self.stats.set(Stats::default());
+    }
+
+    ///记录统计，读操作也需要记录比较次数，因此只需&self
+    #[inline]
+    pub(super) fn record<F: FnOnce(&mut Stats)>(&self, update: F) {
+        #[cfg(feature = "stats")]
+        {
+            let mut stats = self.stats.get();
+            update(&mut stats);
+            self.stats.set(stats);
+        }
+        #[cfg(not(feature = "stats"))]
+        let _ = update;
+    }
+
+    ///变色，颜色实际变化时计数
+    pub(super) fn paint(&self, node_ref: &Rc<RefCell<Node<K, V>>>, color: Color) {
+        let mut node = node_ref.borrow_mut();
+        if node.color != color {
+            node.color = color;
+            drop(node);
+            self.record(|stats| stats.recolors += 1);
+            self.notify(node_ref, |observer, key| observer.on_recolor(key, color));
+        }
+    }
+
+    ///记录一次调平向上传递的层数
+    pub(super) fn record_fixup_depth(&self, depth: usize) {
+        self.record(|stats| stats.max_fixup_depth = stats.max_fixup_depth.max(depth));
+    }
+}
+
+#[cfg(all(test, feature = "stats"))]
+mod tests {
+    use super::super::RedBlackTree;
+    use super::Stats;
+
+    #[test]
+    fn counts_rebalancing_work() {
+        let mut tree = RedBlackTree::new();
+        for key in 1..=3 {
+            tree.insert(key, ());
+        }
+        //插入3时RR情况，左旋一次，爷节点染红、父节点染黑
+        assert_eq!(tree.stats(), Stats { rotations_left: 1, rotations_right: 0, recolors: 2, comparisons: 3, max_fixup_depth: 1 });
+        tree.reset_stats();
+        assert!(tree.contains(&3));
+        assert_eq!(tree.stats(), Stats { comparisons: 2, ..Stats::default() });
+        for key in 4..=1000 {
+            tree.insert(key, ());
+        }
+        let stats = tree.stats();
+        assert!(stats.rotations_left > 0 && stats.rotations_right == 0);
+        //上溢的层数不超过树高
+        assert!(stats.max_fixup_depth <= 20);
+    }
+}
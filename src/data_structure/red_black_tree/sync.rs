@@ -0,0 +1,286 @@
+//! 线程安全的红黑树
+//! RedBlackTree以Rc<RefCell<..>>连接节点，既不是Send也不是Sync，无法移动到其他线程
+//! 此处持有写时复制的PersistentRedBlackTree，K、V满足Send + Sync时即可跨线程共享
+//! 写操作在当前树的克隆上修改(只复制修改路径)，完成后换入新根；写操作之间以互斥锁串行
+//! snapshot只在换根的瞬间短暂加锁，取得的快照与之后的写操作互不影响，读线程不会阻塞写线程
+
+use std::fmt;
+use std::ops::Deref;
+use std::sync::{Arc, Mutex, PoisonError, RwLock};
+
+use super::{InvariantViolation, PersistentRedBlackTree};
+
+struct Shared<K, V> {
+    ///当前的树，读写锁只保护换根
+    current: RwLock<PersistentRedBlackTree<K, V>>,
+    ///串行化写操作，保证每次修改都基于上一次写入的结果
+    writer: Mutex<()>,
+}
+
+/// 可跨线程共享的红黑树句柄
+/// clone得到指向同一棵树的新句柄
+pub struct SyncRedBlackTree<K, V> {
+    inner: Arc<Shared<K, V>>,
+}
+
+/// 某一时刻的只读快照
+/// 持有该时刻的根，之后的写操作不影响快照，快照也不阻塞写操作
+/// 经Deref提供PersistentRedBlackTree的全部查询接口
+pub struct TreeSnapshot<K, V> {
+    tree: PersistentRedBlackTree<K, V>,
+}
+
+/// 快照上一次遍历得到的形状统计
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct SnapshotStats {
+    pub len: usize,
+    ///树高，空树为0
+    pub height: usize,
+    ///黑高，空树为0
+    pub black_height: usize,
+}
+
+impl<K: Ord, V> TreeSnapshot<K, V> {
+    ///长度、树高、黑高都取自同一个根
+    pub fn stats(&self) -> SnapshotStats {
+        SnapshotStats { len: self.tree.len(), height: self.tree.height(), black_height: self.tree.black_height() }
+    }
+
+    ///取出快照中的树，之后修改时按需复制共享的节点
+    pub fn into_tree(self) -> PersistentRedBlackTree<K, V> {
+        self.tree
+    }
+}
+
+impl<K, V> Deref for TreeSnapshot<K, V> {
+    type Target = PersistentRedBlackTree<K, V>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.tree
+    }
+}
+
+impl<K, V> Clone for TreeSnapshot<K, V> {
+    fn clone(&self) -> Self {
+        TreeSnapshot { tree: self.tree.clone() }
+    }
+}
+
+impl<K: Ord + fmt::Debug, V: fmt::Debug> fmt::Debug for TreeSnapshot<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.tree, f)
+    }
+}
+
+impl<K: Ord, V> SyncRedBlackTree<K, V> {
+    pub fn new() -> Self {
+        Self::from_tree(PersistentRedBlackTree::new())
+    }
+
+    pub fn from_tree(tree: PersistentRedBlackTree<K, V>) -> Self {
+        SyncRedBlackTree { inner: Arc::new(Shared { current: RwLock::new(tree), writer: Mutex::new(()) }) }
+    }
+
+    /// 捕获当前的根，O(1)
+    /// len、stats、validate、iter等在同一个快照上调用时结果互相一致
+    pub fn snapshot(&self) -> TreeSnapshot<K, V> {
+        //换根只替换树本身，不会留下修改到一半的状态，锁中毒时仍可读取
+        let tree = self.inner.current.read().unwrap_or_else(PoisonError::into_inner).clone();
+        TreeSnapshot { tree }
+    }
+
+    pub fn len(&self) -> usize {
+        self.snapshot().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.snapshot().is_empty()
+    }
+
+    ///同snapshot().stats()
+    pub fn stats(&self) -> SnapshotStats {
+        self.snapshot().stats()
+    }
+
+    ///校验当前快照
+    pub fn validate(&self) -> Result<(), InvariantViolation<K>>
+    where
+        K: Clone,
+    {
+        self.snapshot().validate()
+    }
+
+    pub fn contains(&self, key: &K) -> bool {
+        self.snapshot().contains(key)
+    }
+
+    ///快照随调用结束释放，无法返回借用，返回值的拷贝
+    pub fn get(&self, key: &K) -> Option<V>
+    where
+        V: Clone,
+    {
+        self.snapshot().get(key).cloned()
+    }
+}
+
+/// 修改操作
+/// 新根与快照共享未修改的节点，修改路径上的节点需要复制，因此要求K: Clone、V: Clone
+impl<K: Ord + Clone, V: Clone> SyncRedBlackTree<K, V> {
+    /// 在当前树的克隆上执行f，完成后换入新根，多次修改作为一个整体对其他线程可见
+    /// f panic时新根被丢弃，树保持调用前的状态
+    pub fn write<R, F: FnOnce(&mut PersistentRedBlackTree<K, V>) -> R>(&self, f: F) -> R {
+        //写锁只保证写操作串行，panic的写操作没有换入新根，锁中毒时树仍完整
+        let _writer = self.inner.writer.lock().unwrap_or_else(PoisonError::into_inner);
+        let mut next = self.snapshot().into_tree();
+        let result = f(&mut next);
+        *self.inner.current.write().unwrap_or_else(PoisonError::into_inner) = next;
+        result
+    }
+
+    pub fn clear(&self) {
+        self.write(|tree| tree.clear());
+    }
+
+    ///插入键值对，键已存在时忽略
+    pub fn insert(&self, key: K, value: V) {
+        self.write(|tree| tree.insert(key, value));
+    }
+
+    ///插入或替换，返回旧值
+    pub fn insert_or_replace(&self, key: K, value: V) -> Option<V> {
+        self.write(|tree| tree.insert_or_replace(key, value))
+    }
+
+    ///删除键并返回值
+    pub fn remove(&self, key: &K) -> Option<V> {
+        self.write(|tree| tree.remove(key))
+    }
+
+    pub fn pop_first(&self) -> Option<(K, V)> {
+        self.write(|tree| tree.pop_first())
+    }
+
+    pub fn pop_last(&self) -> Option<(K, V)> {
+        self.write(|tree| tree.pop_last())
+    }
+}
+
+impl<K, V> SyncRedBlackTree<K, V> {
+    ///最后一个句柄取出树，还有其他句柄时返回Err(self)
+    pub fn try_unwrap(self) -> Result<PersistentRedBlackTree<K, V>, Self> {
+        match Arc::try_unwrap(self.inner) {
+            Ok(shared) => Ok(shared.current.into_inner().unwrap_or_else(PoisonError::into_inner)),
+            Err(inner) => Err(SyncRedBlackTree { inner }),
+        }
+    }
+}
+
+impl<K, V> Clone for SyncRedBlackTree<K, V> {
+    fn clone(&self) -> Self {
+        SyncRedBlackTree { inner: Arc::clone(&self.inner) }
+    }
+}
+
+impl<K: Ord, V> Default for SyncRedBlackTree<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Ord + fmt::Debug, V: fmt::Debug> fmt::Debug for SyncRedBlackTree<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.snapshot(), f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use super::{SnapshotStats, SyncRedBlackTree};
+    use crate::data_structure::red_black_tree::PersistentRedBlackTree;
+
+    #[test]
+    fn is_send_and_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<PersistentRedBlackTree<u32, String>>();
+        assert_send_sync::<SyncRedBlackTree<u32, String>>();
+    }
+
+    #[test]
+    fn shared_across_threads() {
+        let tree = SyncRedBlackTree::new();
+        let handles: Vec<_> = (0..4u32)
+            .map(|thread_index| {
+                let tree = tree.clone();
+                thread::spawn(move || {
+                    for key in (thread_index..4_000).step_by(4) {
+                        tree.insert(key, key * 2);
+                    }
+                    for key in (thread_index..4_000).step_by(8) {
+                        assert_eq!(tree.remove(&key), Some(key * 2));
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        assert_eq!(tree.len(), 2_000);
+        assert_eq!(tree.validate(), Ok(()));
+        assert_eq!(tree.get(&1), None);
+        assert_eq!(tree.get(&5), Some(10));
+        let tree = tree.try_unwrap().unwrap();
+        assert_eq!(tree.validate(), Ok(()));
+        assert!(tree.iter().all(|(key, value)| key % 8 >= 4 && *value == key * 2));
+    }
+
+    #[test]
+    fn snapshot_does_not_block_or_see_later_writes() {
+        let tree = SyncRedBlackTree::new();
+        for key in 0..100u32 {
+            tree.insert(key, key);
+        }
+        let snapshot = tree.snapshot();
+        let stats = snapshot.stats();
+        assert_eq!(stats.len, 100);
+        assert_eq!(stats, SnapshotStats { len: snapshot.len(), height: snapshot.height(), black_height: snapshot.black_height() });
+        //快照存在期间其他线程照常写入
+        let writer = {
+            let tree = tree.clone();
+            thread::spawn(move || {
+                for key in 0..50u32 {
+                    tree.remove(&key);
+                }
+                tree.insert(1_000, 0);
+            })
+        };
+        writer.join().unwrap();
+        assert_eq!((snapshot.len(), snapshot.iter().count(), snapshot.stats()), (100, 100, stats));
+        assert_eq!(snapshot.validate(), Ok(()));
+        assert_eq!((snapshot.first(), snapshot.get(&1_000)), (Some((&0, &0)), None));
+        let current = tree.snapshot();
+        assert_eq!((current.len(), current.first()), (51, Some((&50, &50))));
+        assert_eq!(current.validate(), Ok(()));
+    }
+
+    #[test]
+    fn panicking_write_keeps_the_previous_root() {
+        let tree = SyncRedBlackTree::new();
+        tree.insert(1u32, 1u32);
+        let result = thread::scope(|scope| {
+            scope
+                .spawn(|| {
+                    tree.write(|inner| {
+                        inner.insert(2, 2);
+                        panic!("abort write");
+                    })
+                })
+                .join()
+        });
+        assert!(result.is_err());
+        assert_eq!((tree.len(), tree.get(&2)), (1, None));
+        tree.insert(3, 3);
+        assert_eq!(tree.len(), 2);
+    }
+}
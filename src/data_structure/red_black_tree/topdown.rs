@@ -0,0 +1,553 @@
+//! 自顶向下的红黑树
+//! 插入与删除都在一次下降中完成调平(下降途中染色、旋转)，无需回溯，因此节点不记录父节点
+//! 节点存放在Vec中，子节点为u32下标，link[0]为左、link[1]为右，按方向对称处理
+//! 参考Julienne Walker的自顶向下算法，用HEAD表示根之上的哨兵，其右子节点即为根
+
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::fmt;
+use core::ops::RangeBounds;
+
+use super::super::ordered_map::{after_start, before_end};
+use super::{Color, InvariantViolation};
+
+///空下标，相当于空指针
+const NIL: u32 = u32::MAX;
+///根之上的哨兵，不占槽位
+const HEAD: u32 = u32::MAX - 1;
+
+const LEFT: usize = 0;
+const RIGHT: usize = 1;
+
+struct TopDownNode<K, V> {
+    key: K,
+    value: V,
+    link: [u32; 2],
+    color: Color,
+}
+
+///槽位，空闲槽位记录下一个空闲槽位
+enum Slot<K, V> {
+    Occupied(TopDownNode<K, V>),
+    Vacant { next_free: u32 },
+}
+
+pub struct RedBlackTreeTopDown<K, V> {
+    slots: Vec<Slot<K, V>>,
+    root: u32,
+    //空闲链表头
+    free: u32,
+    len: usize,
+}
+
+impl<K: Ord, V> RedBlackTreeTopDown<K, V> {
+    pub fn new() -> Self {
+        RedBlackTreeTopDown { slots: Vec::new(), root: NIL, free: NIL, len: 0 }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    ///清空树，槽位一并释放
+    pub fn clear(&mut self) {
+        self.slots.clear();
+        self.root = NIL;
+        self.free = NIL;
+        self.len = 0;
+    }
+
+    fn node(&self, index: u32) -> &TopDownNode<K, V> {
+        match &self.slots[index as usize] {
+            Slot::Occupied(node) => node,
+            Slot::Vacant { .. } => unreachable!("link to a free slot"),
+        }
+    }
+
+    fn node_mut(&mut self, index: u32) -> &mut TopDownNode<K, V> {
+        match &mut self.slots[index as usize] {
+            Slot::Occupied(node) => node,
+            Slot::Vacant { .. } => unreachable!("link to a free slot"),
+        }
+    }
+
+    ///子节点，哨兵只有右子节点(根)
+    fn child(&self, index: u32, dir: usize) -> u32 {
+        match index {
+            HEAD if dir == RIGHT => self.root,
+            HEAD => NIL,
+            _ => self.node(index).link[dir],
+        }
+    }
+
+    fn set_child(&mut self, index: u32, dir: usize, son: u32) {
+        if index == HEAD {
+            self.root = son;
+        } else {
+            self.node_mut(index).link[dir] = son;
+        }
+    }
+
+    ///空下标与哨兵视为黑色
+    fn is_red(&self, index: u32) -> bool {
+        index != NIL && index != HEAD && self.node(index).color == Color::Red
+    }
+
+    fn paint(&mut self, index: u32, color: Color) {
+        self.node_mut(index).color = color;
+    }
+
+    ///分配槽位，优先复用空闲槽位
+    fn allocate(&mut self, node: TopDownNode<K, V>) -> u32 {
+        if self.free != NIL {
+            let index = self.free;
+            self.free = match self.slots[index as usize] {
+                Slot::Vacant { next_free } => next_free,
+                Slot::Occupied(_) => unreachable!("free list points to an occupied slot"),
+            };
+            self.slots[index as usize] = Slot::Occupied(node);
+            index
+        } else {
+            assert!(self.slots.len() < HEAD as usize, "arena is full");
+            self.slots.push(Slot::Occupied(node));
+            (self.slots.len() - 1) as u32
+        }
+    }
+
+    ///释放槽位，取出节点
+    fn release(&mut self, index: u32) -> TopDownNode<K, V> {
+        let slot = core::mem::replace(&mut self.slots[index as usize], Slot::Vacant { next_free: self.free });
+        self.free = index;
+        match slot {
+            Slot::Occupied(node) => node,
+            Slot::Vacant { .. } => unreachable!("release a free slot"),
+        }
+    }
+
+    fn find(&self, key: &K) -> u32 {
+        let mut cur = self.root;
+        while cur != NIL {
+            let node = self.node(cur);
+            cur = match key.cmp(&node.key) {
+                Ordering::Equal => return cur,
+                Ordering::Less => node.link[LEFT],
+                Ordering::Greater => node.link[RIGHT],
+            };
+        }
+        NIL
+    }
+
+    ///沿dir方向走到底
+    fn extreme(&self, mut index: u32, dir: usize) -> u32 {
+        while self.node(index).link[dir] != NIL {
+            index = self.node(index).link[dir];
+        }
+        index
+    }
+
+    /// 单旋转，top的反方向子节点上升并返回
+    /// 上升节点染黑，top染红
+    fn rotate_single(&mut self, top: u32, dir: usize) -> u32 {
+        let save = self.node(top).link[1 - dir];
+        let inner = self.node(save).link[dir];
+        self.node_mut(top).link[1 - dir] = inner;
+        self.node_mut(save).link[dir] = top;
+        self.paint(top, Color::Red);
+        self.paint(save, Color::Black);
+        save
+    }
+
+    ///双旋转，先旋转反方向子节点，再旋转top
+    fn rotate_double(&mut self, top: u32, dir: usize) -> u32 {
+        let son = self.node(top).link[1 - dir];
+        let raised = self.rotate_single(son, 1 - dir);
+        self.node_mut(top).link[1 - dir] = raised;
+        self.rotate_single(top, dir)
+    }
+
+    /// 插入键值对，键已存在时忽略
+    /// 下降时遇到两个子节点都为红色的节点即翻转颜色，由此产生的父子连续红立即旋转消除
+    /// 到达空位时新节点的叔节点必为黑色，插入后最多再旋转一次
+    pub fn insert(&mut self, key: K, value: V) {
+        if self.root == NIL {
+            self.root = self.allocate(TopDownNode { key, value, link: [NIL, NIL], color: Color::Black });
+            self.len += 1;
+            return;
+        }
+        let mut entry = Some((key, value));
+        let mut inserted = NIL;
+        //great为曾祖节点，grand为爷节点，parent为父节点，cur为当前节点
+        let (mut great, mut grand, mut parent, mut cur) = (HEAD, NIL, NIL, self.root);
+        let (mut dir, mut last) = (LEFT, LEFT);
+        loop {
+            if cur == NIL {
+                let (key, value) = entry.take().expect("inserted twice");
+                cur = self.allocate(TopDownNode { key, value, link: [NIL, NIL], color: Color::Red });
+                self.set_child(parent, dir, cur);
+                inserted = cur;
+                self.len += 1;
+            } else if self.is_red(self.node(cur).link[LEFT]) && self.is_red(self.node(cur).link[RIGHT]) {
+                //颜色翻转
+                let [left, right] = self.node(cur).link;
+                self.paint(cur, Color::Red);
+                self.paint(left, Color::Black);
+                self.paint(right, Color::Black);
+            }
+            //父子连续红，爷节点必存在
+            if self.is_red(cur) && self.is_red(parent) {
+                let side = usize::from(self.child(great, RIGHT) == grand);
+                let top = if cur == self.node(parent).link[last] {
+                    self.rotate_single(grand, 1 - last)
+                } else {
+                    self.rotate_double(grand, 1 - last)
+                };
+                self.set_child(great, side, top);
+            }
+            if cur == inserted {
+                break;
+            }
+            let ordering = match &entry {
+                Some((key, _)) => key.cmp(&self.node(cur).key),
+                None => Ordering::Equal,
+            };
+            if ordering == Ordering::Equal {
+                break;
+            }
+            last = dir;
+            dir = usize::from(ordering == Ordering::Greater);
+            if grand != NIL {
+                great = grand;
+            }
+            grand = parent;
+            parent = cur;
+            cur = self.node(cur).link[dir];
+        }
+        let root = self.root;
+        self.paint(root, Color::Black);
+    }
+
+    ///删除键，键不存在时忽略
+    pub fn delete(&mut self, key: &K) {
+        self.remove(key);
+    }
+
+    ///删除键并返回值
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        self.remove_by(|node| key.cmp(&node.key)).map(|(_, value)| value)
+    }
+
+    pub fn pop_first(&mut self) -> Option<(K, V)> {
+        //沿最左路径下降，没有左子节点的即为最小节点
+        self.remove_by(|node| if node.link[LEFT] == NIL { Ordering::Equal } else { Ordering::Less })
+    }
+
+    pub fn pop_last(&mut self) -> Option<(K, V)> {
+        self.remove_by(|node| if node.link[RIGHT] == NIL { Ordering::Equal } else { Ordering::Greater })
+    }
+
+    /// 自顶向下删除，compare返回目标相对节点的顺序
+    /// 下降时保证当前节点或其子节点为红色，到达底部时删除的节点必为红色或有红色子节点
+    /// 命中后继续在左子树中寻找前驱，以前驱的键值对取代命中节点，实际摘除的是前驱所在节点
+    fn remove_by<F: FnMut(&TopDownNode<K, V>) -> Ordering>(&mut self, mut compare: F) -> Option<(K, V)> {
+        if self.root == NIL {
+            return None;
+        }
+        let (mut parent, mut cur) = (NIL, HEAD);
+        let mut found = NIL;
+        let mut dir = RIGHT;
+        while self.child(cur, dir) != NIL {
+            let last = dir;
+            let grand = parent;
+            parent = cur;
+            cur = self.child(cur, dir);
+            //命中后只在其左子树中下降，其中的节点都小于目标
+            let ordering = if found == NIL { compare(self.node(cur)) } else { Ordering::Greater };
+            dir = usize::from(ordering == Ordering::Greater);
+            if ordering == Ordering::Equal {
+                found = cur;
+            }
+            if self.is_red(cur) || self.is_red(self.node(cur).link[dir]) {
+                continue;
+            }
+            let opposite = self.node(cur).link[1 - dir];
+            if self.is_red(opposite) {
+                //反方向子节点为红，旋转使当前节点变红
+                let top = self.rotate_single(cur, dir);
+                self.set_child(parent, last, top);
+                parent = top;
+                continue;
+            }
+            let sibling = self.child(parent, 1 - last);
+            if sibling == NIL {
+                continue;
+            }
+            let (sibling_far, sibling_near) = (self.node(sibling).link[1 - last], self.node(sibling).link[last]);
+            if !self.is_red(sibling_far) && !self.is_red(sibling_near) {
+                //兄弟的子节点都为黑色，颜色翻转
+                self.paint(parent, Color::Black);
+                self.paint(sibling, Color::Red);
+                self.paint(cur, Color::Red);
+            } else {
+                let side = usize::from(self.child(grand, RIGHT) == parent);
+                let top = if self.is_red(sibling_near) {
+                    self.rotate_double(parent, last)
+                } else {
+                    self.rotate_single(parent, last)
+                };
+                self.set_child(grand, side, top);
+                //旋转后保持原有黑高，当前节点变红
+                self.paint(cur, Color::Red);
+                self.paint(top, Color::Red);
+                let [left, right] = self.node(top).link;
+                self.paint(left, Color::Black);
+                self.paint(right, Color::Black);
+            }
+        }
+        if found == NIL {
+            let root = self.root;
+            self.paint(root, Color::Black);
+            return None;
+        }
+        //摘除cur，其至多有一个子节点
+        let son = self.node(cur).link[usize::from(self.node(cur).link[LEFT] == NIL)];
+        let side = usize::from(self.child(parent, RIGHT) == cur);
+        self.set_child(parent, side, son);
+        let mut removed = self.release(cur);
+        if found != cur {
+            let node = self.node_mut(found);
+            core::mem::swap(&mut node.key, &mut removed.key);
+            core::mem::swap(&mut node.value, &mut removed.value);
+        }
+        self.len -= 1;
+        if self.root != NIL {
+            let root = self.root;
+            self.paint(root, Color::Black);
+        }
+        Some((removed.key, removed.value))
+    }
+
+    pub fn get(&self, key: &K) -> Option<&V> {
+        let index = self.find(key);
+        (index != NIL).then(|| &self.node(index).value)
+    }
+
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        let index = self.find(key);
+        (index != NIL).then(|| &mut self.node_mut(index).value)
+    }
+
+    pub fn contains(&self, key: &K) -> bool {
+        self.find(key) != NIL
+    }
+
+    fn entry(&self, index: u32) -> (&K, &V) {
+        let node = self.node(index);
+        (&node.key, &node.value)
+    }
+
+    pub fn first(&self) -> Option<(&K, &V)> {
+        (self.root != NIL).then(|| self.entry(self.extreme(self.root, LEFT)))
+    }
+
+    pub fn last(&self) -> Option<(&K, &V)> {
+        (self.root != NIL).then(|| self.entry(self.extreme(self.root, RIGHT)))
+    }
+
+    /// 中序迭代，产出借用
+    /// 没有父节点，前后两端各用一个栈记录未访问的祖先
+    pub fn iter(&self) -> TopDownIter<'_, K, V> {
+        let mut iter = TopDownIter { tree: self, front: Vec::new(), back: Vec::new(), remaining: Some(self.len) };
+        iter.descend(self.root, LEFT);
+        iter.descend(self.root, RIGHT);
+        iter
+    }
+
+    /// 按键升序迭代范围内的条目，O(log n)定位两端
+    /// front只压入满足下界的祖先，back只压入满足上界的祖先
+    pub fn range<R: RangeBounds<K>>(&self, range: R) -> TopDownIter<'_, K, V> {
+        let mut iter = TopDownIter { tree: self, front: Vec::new(), back: Vec::new(), remaining: None };
+        let mut cur = self.root;
+        while cur != NIL {
+            let node = self.node(cur);
+            if after_start(range.start_bound(), &node.key) {
+                iter.front.push(cur);
+                cur = node.link[LEFT];
+            } else {
+                cur = node.link[RIGHT];
+            }
+        }
+        cur = self.root;
+        while cur != NIL {
+            let node = self.node(cur);
+            if before_end(range.end_bound(), &node.key) {
+                iter.back.push(cur);
+                cur = node.link[RIGHT];
+            } else {
+                cur = node.link[LEFT];
+            }
+        }
+        //范围内没有节点，或起点在终点之后
+        match (iter.front.last(), iter.back.last()) {
+            (Some(&first), Some(&last)) if self.node(first).key <= self.node(last).key => {}
+            _ => iter.finish(),
+        }
+        iter
+    }
+
+    /// 校验红黑树性质，与RedBlackTree::validate相同
+    /// 没有父节点与子树节点数量，不检查ParentPointer与SubtreeSize
+    pub fn validate(&self) -> Result<(), InvariantViolation<K>>
+    where
+        K: Clone,
+    {
+        if self.is_red(self.root) {
+            return Err(InvariantViolation::RedRoot);
+        }
+        let mut count = 0;
+        self.validate_node(self.root, None, None, &mut count)?;
+        if count != self.len {
+            return Err(InvariantViolation::LengthMismatch { expected: self.len, actual: count });
+        }
+        Ok(())
+    }
+
+    ///递归校验子树，返回子树黑高(空节点黑高为1)
+    fn validate_node(&self, index: u32, lower: Option<&K>, upper: Option<&K>, count: &mut usize) -> Result<usize, InvariantViolation<K>>
+    where
+        K: Clone,
+    {
+        if index == NIL {
+            return Ok(1);
+        }
+        *count += 1;
+        let node = self.node(index);
+        if lower.is_some_and(|lower| node.key <= *lower) || upper.is_some_and(|upper| node.key >= *upper) {
+            return Err(InvariantViolation::OutOfOrder { key: node.key.clone() });
+        }
+        if node.color == Color::Red && node.link.iter().any(|son| self.is_red(*son)) {
+            return Err(InvariantViolation::RedRed { key: node.key.clone() });
+        }
+        let left = self.validate_node(node.link[LEFT], lower, Some(&node.key), count)?;
+        let right = self.validate_node(node.link[RIGHT], Some(&node.key), upper, count)?;
+        if left != right {
+            return Err(InvariantViolation::BlackHeight { key: node.key.clone(), left, right });
+        }
+        Ok(left + usize::from(node.color == Color::Black))
+    }
+}
+
+impl<K: Ord, V> Default for RedBlackTreeTopDown<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Ord + fmt::Debug, V: fmt::Debug> fmt::Debug for RedBlackTreeTopDown<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+/// 中序迭代器
+/// front栈顶为下一个产出的节点，back栈顶为最后一个产出的节点，两端相遇时迭代结束
+/// remaining为剩余数量，范围迭代时未知
+pub struct TopDownIter<'a, K, V> {
+    tree: &'a RedBlackTreeTopDown<K, V>,
+    front: Vec<u32>,
+    back: Vec<u32>,
+    remaining: Option<usize>,
+}
+
+impl<K: Ord, V> TopDownIter<'_, K, V> {
+    ///从index沿dir方向下降，途经节点入栈
+    fn descend(&mut self, mut index: u32, dir: usize) {
+        let stack = if dir == LEFT { &mut self.front } else { &mut self.back };
+        while index != NIL {
+            stack.push(index);
+            index = self.tree.node(index).link[dir];
+        }
+    }
+
+    ///弹出dir一端的下一个节点，其反方向子树沿dir方向入栈
+    fn advance(&mut self, dir: usize) -> Option<u32> {
+        let (stack, other) = if dir == LEFT { (&mut self.front, &self.back) } else { (&mut self.back, &self.front) };
+        let cur = stack.pop()?;
+        let met = other.last() == Some(&cur);
+        if let Some(remaining) = &mut self.remaining {
+            *remaining -= 1;
+        }
+        //与另一端相遇，这是最后一个节点
+        if met {
+            self.finish();
+        } else {
+            self.descend(self.tree.node(cur).link[1 - dir], dir);
+        }
+        Some(cur)
+    }
+
+    fn finish(&mut self) {
+        self.front.clear();
+        self.back.clear();
+        self.remaining = Some(0);
+    }
+}
+
+impl<'a, K: Ord, V> Iterator for TopDownIter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.advance(LEFT).map(|cur| self.tree.entry(cur))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self.remaining {
+            Some(remaining) => (remaining, Some(remaining)),
+            None => (usize::from(!self.front.is_empty()), None),
+        }
+    }
+}
+
+impl<K: Ord, V> DoubleEndedIterator for TopDownIter<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.advance(RIGHT).map(|cur| self.tree.entry(cur))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    use super::RedBlackTreeTopDown;
+
+    #[test]
+    fn matches_btree_map_model() {
+        let mut rng = StdRng::seed_from_u64(2053);
+        let mut tree = RedBlackTreeTopDown::new();
+        let mut model = BTreeMap::new();
+        for step in 0..20_000 {
+            let key = rng.gen_range(0..500);
+            match rng.gen_range(0..20) {
+                0..=10 => {
+                    tree.insert(key, step);
+                    model.entry(key).or_insert(step);
+                }
+                11 => assert_eq!(tree.pop_first(), model.pop_first()),
+                12 => assert_eq!(tree.pop_last(), model.pop_last()),
+                _ => assert_eq!(tree.remove(&key), model.remove(&key)),
+            }
+            assert_eq!(tree.validate(), Ok(()), "step {}", step);
+            if step % 100 == 0 {
+                assert!(tree.iter().eq(model.iter()));
+                assert!(tree.iter().rev().eq(model.iter().rev()));
+            }
+        }
+        assert!(tree.slots.len() <= 500);
+    }
+}
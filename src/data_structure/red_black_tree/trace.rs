@@ -0,0 +1,237 @@
+//! 单步回放
+//! 以观察者记录一次插入或删除中的每个结构事件，连同操作前的树形状，可逐步还原每个中间状态
+//! 回放在只含键与颜色的影子树上按事件重做插入、删除、旋转与变色，删除时后继节点的取代规则与RedBlackTree一致
+
+use alloc::boxed::Box;
+use alloc::rc::Rc;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+
+use super::{Color, Node, Observer, RedBlackTree};
+
+/// 结构事件，语义见Observer
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum TreeEvent<K> {
+    Insert { key: K },
+    Delete { key: K },
+    RotateLeft { key: K },
+    RotateRight { key: K },
+    Recolor { key: K, color: Color },
+}
+
+/// 记录事件的观察者
+/// 可直接注册到树上记录任意多次操作
+pub struct Recorder<K> {
+    events: RefCell<Vec<TreeEvent<K>>>,
+}
+
+impl<K> Recorder<K> {
+    pub fn new() -> Self {
+        Recorder { events: RefCell::new(Vec::new()) }
+    }
+
+    ///取出已记录的事件
+    pub fn take_events(&self) -> Vec<TreeEvent<K>> {
+        self.events.take()
+    }
+}
+
+impl<K> Default for Recorder<K> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Clone> Observer<K> for Recorder<K> {
+    fn on_insert(&self, key: &K) {
+        self.events.borrow_mut().push(TreeEvent::Insert { key: key.clone() });
+    }
+
+    fn on_delete(&self, key: &K) {
+        self.events.borrow_mut().push(TreeEvent::Delete { key: key.clone() });
+    }
+
+    fn on_rotate_left(&self, key: &K) {
+        self.events.borrow_mut().push(TreeEvent::RotateLeft { key: key.clone() });
+    }
+
+    fn on_rotate_right(&self, key: &K) {
+        self.events.borrow_mut().push(TreeEvent::RotateRight { key: key.clone() });
+    }
+
+    fn on_recolor(&self, key: &K, color: Color) {
+        self.events.borrow_mut().push(TreeEvent::Recolor { key: key.clone(), color });
+    }
+}
+
+/// 影子树的节点，只含键与颜色
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct TraceNode<K> {
+    pub key: K,
+    pub color: Color,
+    pub left: Option<Box<TraceNode<K>>>,
+    pub right: Option<Box<TraceNode<K>>>,
+}
+
+///影子树，空树为None
+pub type TraceShape<K> = Option<Box<TraceNode<K>>>;
+
+/// 一次操作的记录
+/// initial为操作前的形状，events为操作中依次发生的事件
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct Trace<K> {
+    pub initial: TraceShape<K>,
+    pub events: Vec<TreeEvent<K>>,
+}
+
+impl<K: Ord + Clone> Trace<K> {
+    /// 回放，返回每个事件之后的形状，与events一一对应
+    /// 最后一个形状即操作完成后的树
+    pub fn replay(&self) -> Vec<TraceShape<K>> {
+        let mut shape = self.initial.clone();
+        self.events
+            .iter()
+            .map(|event| {
+                apply(&mut shape, event);
+                shape.clone()
+            })
+            .collect()
+    }
+}
+
+///在影子树上重做一个事件
+fn apply<K: Ord + Clone>(shape: &mut TraceShape<K>, event: &TreeEvent<K>) {
+    match event {
+        TreeEvent::Insert { key } => {
+            let mut slot = shape;
+            //插入空树时直接成为黑色的根
+            let color = if slot.is_none() { Color::Black } else { Color::Red };
+            while let Some(node) = slot {
+                slot = if *key < node.key { &mut node.left } else { &mut node.right };
+            }
+            *slot = Some(Box::new(TraceNode { key: key.clone(), color, left: None, right: None }));
+        }
+        TreeEvent::Delete { key } => {
+            let slot = slot_of(shape, key);
+            let mut target = slot.take().expect("traced key is in the shape");
+            *slot = match (target.left.take(), target.right.take()) {
+                (None, None) => None,
+                (Some(son), None) | (None, Some(son)) => Some(son),
+                (Some(left), Some(right)) => {
+                    //后继节点取代删除节点的位置与颜色，后继节点的右子节点取代后继节点的位置与颜色
+                    let mut right = Some(right);
+                    let mut successor_slot = &mut right;
+                    while successor_slot.as_ref().is_some_and(|node| node.left.is_some()) {
+                        successor_slot = &mut successor_slot.as_mut().expect("checked above").left;
+                    }
+                    let mut successor = successor_slot.take().expect("right subtree is not empty");
+                    *successor_slot = successor.right.take();
+                    if let Some(successor_right) = successor_slot {
+                        successor_right.color = successor.color;
+                    }
+                    successor.left = Some(left);
+                    successor.right = right;
+                    successor.color = target.color;
+                    Some(successor)
+                }
+            };
+        }
+        TreeEvent::RotateLeft { key } => {
+            let slot = slot_of(shape, key);
+            let mut node = slot.take().expect("traced key is in the shape");
+            let mut son = node.right.take().expect("rotated node has a right child");
+            node.right = son.left.take();
+            son.left = Some(node);
+            *slot = Some(son);
+        }
+        TreeEvent::RotateRight { key } => {
+            let slot = slot_of(shape, key);
+            let mut node = slot.take().expect("traced key is in the shape");
+            let mut son = node.left.take().expect("rotated node has a left child");
+            node.left = son.right.take();
+            son.right = Some(node);
+            *slot = Some(son);
+        }
+        TreeEvent::Recolor { key, color } => {
+            slot_of(shape, key).as_mut().expect("traced key is in the shape").color = *color;
+        }
+    }
+}
+
+///键所在的位置
+fn slot_of<'a, K: Ord>(mut slot: &'a mut TraceShape<K>, key: &K) -> &'a mut TraceShape<K> {
+    while slot.as_ref().is_some_and(|node| node.key != *key) {
+        let node = slot.as_mut().expect("checked above");
+        slot = if *key < node.key { &mut node.left } else { &mut node.right };
+    }
+    slot
+}
+
+impl<K: Ord + Clone + 'static, V> RedBlackTree<K, V> {
+    /// 插入并记录过程
+    /// 记录期间以内置的记录器代替已注册的观察者，结束后恢复
+    pub fn trace_insert(&mut self, key: K, value: V) -> Trace<K> {
+        self.trace(|tree| tree.insert(key, value))
+    }
+
+    ///删除并记录过程，键不存在时事件为空
+    pub fn trace_delete<Q: Ord + ?Sized>(&mut self, key: &Q) -> Trace<K>
+    where
+        K: core::borrow::Borrow<Q>,
+    {
+        self.trace(|tree| tree.delete(key))
+    }
+
+    fn trace<F: FnOnce(&mut Self)>(&mut self, operation: F) -> Trace<K> {
+        let initial = self.trace_shape();
+        let recorder = Rc::new(Recorder::new());
+        let previous = self.observer.replace(Rc::clone(&recorder) as Rc<dyn Observer<K>>);
+        operation(self);
+        self.observer = previous;
+        Trace { initial, events: recorder.take_events() }
+    }
+
+    ///当前形状
+    pub fn trace_shape(&self) -> TraceShape<K> {
+        fn shape_of<K: Clone, V>(node_option: &Option<Rc<RefCell<Node<K, V>>>>) -> TraceShape<K> {
+            node_option.as_ref().map(|node_ref| {
+                let node = node_ref.borrow();
+                Box::new(TraceNode { key: node.key.clone(), color: node.color, left: shape_of(&node.left), right: shape_of(&node.right) })
+            })
+        }
+        shape_of(&self.root)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{Color, RedBlackTree};
+    use super::TreeEvent;
+
+    #[test]
+    fn replay_reaches_final_shape() {
+        let mut tree = RedBlackTree::new();
+        tree.insert(1, ());
+        tree.insert(2, ());
+        let trace = tree.trace_insert(3, ());
+        assert_eq!(trace.events, [
+            TreeEvent::Insert { key: 3 },
+            TreeEvent::RotateLeft { key: 1 },
+            TreeEvent::Recolor { key: 1, color: Color::Red },
+            TreeEvent::Recolor { key: 2, color: Color::Black },
+        ]);
+        let states = trace.replay();
+        assert_eq!(states.len(), 4);
+        assert_eq!(states.last().unwrap(), &tree.trace_shape());
+
+        for key in 4..40 {
+            tree.insert(key, ());
+        }
+        for key in [20, 1, 39, 7, 8, 9] {
+            let trace = tree.trace_delete(&key);
+            assert_eq!(trace.events[0], TreeEvent::Delete { key });
+            assert_eq!(trace.replay().last().unwrap(), &tree.trace_shape());
+        }
+        assert!(tree.trace_delete(&100).events.is_empty());
+    }
+}
@@ -0,0 +1,252 @@
+use alloc::rc::Rc;
+use core::cell::RefCell;
+use core::error::Error;
+use core::fmt;
+
+use super::{Color, Node, RedBlackTree};
+
+/// 红黑树性质被破坏的情况
+/// key为出问题的节点的键
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum InvariantViolation<K> {
+    ///根节点为红色
+    RedRoot,
+    ///不满足二叉搜索树的顺序
+    OutOfOrder { key: K },
+    ///红色节点有红色子节点
+    RedRed { key: K },
+    ///左右子树黑高不相等
+    BlackHeight { key: K, left: usize, right: usize },
+    ///子节点的父节点指针没有指向该节点
+    ParentPointer { key: K },
+    ///记录的子树节点数量与实际数量不符
+    SubtreeSize { key: K, expected: usize, actual: usize },
+    ///记录的节点数量与实际数量不符
+    LengthMismatch { expected: usize, actual: usize },
+    ///左倾红黑树中右子节点为红色
+    RightLeaningRed { key: K },
+    ///AVL树中左右子树高度差超过1
+    HeightImbalance { key: K, left: usize, right: usize },
+    ///AVL树中记录的子树高度与实际高度不符
+    SubtreeHeight { key: K, expected: usize, actual: usize },
+    ///树堆中节点的优先级高于父节点
+    PriorityOrder { key: K },
+    ///记录的附加信息与由子节点重新计算的结果不符
+    StaleAugment { key: K },
+}
+
+impl<K: fmt::Debug> fmt::Display for InvariantViolation<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InvariantViolation::RedRoot => write!(f, "root is red"),
+            InvariantViolation::OutOfOrder { key } => write!(f, "key {:?} is out of order", key),
+            InvariantViolation::RedRed { key } => write!(f, "red node {:?} has a red child", key),
+            InvariantViolation::BlackHeight { key, left, right } => {
+                write!(f, "black height mismatch at {:?}: left {}, right {}", key, left, right)
+            }
+            InvariantViolation::SubtreeSize { key, expected, actual } => {
+                write!(f, "node {:?} records subtree size {} but holds {}", key, expected, actual)
+            }
+            InvariantViolation::ParentPointer { key } => write!(f, "node {:?} has a wrong parent pointer", key),
+            InvariantViolation::LengthMismatch { expected, actual } => {
+                write!(f, "len is {} but tree holds {} nodes", expected, actual)
+            }
+            InvariantViolation::RightLeaningRed { key } => write!(f, "node {:?} has a red right child", key),
+            InvariantViolation::HeightImbalance { key, left, right } => {
+                write!(f, "height imbalance at {:?}: left {}, right {}", key, left, right)
+            }
+            InvariantViolation::SubtreeHeight { key, expected, actual } => {
+                write!(f, "node {:?} records height {} but has {}", key, expected, actual)
+            }
+            InvariantViolation::PriorityOrder { key } => write!(f, "node {:?} has a higher priority than its parent", key),
+            InvariantViolation::StaleAugment { key } => write!(f, "node {:?} has a stale augmented value", key),
+        }
+    }
+}
+
+impl<K: fmt::Debug> Error for InvariantViolation<K> {}
+
+impl<T> InvariantViolation<T> {
+    ///转换出问题的节点的表示
+    pub(super) fn map_key<U, F: Fn(T) -> U>(self, f: F) -> InvariantViolation<U> {
+        match self {
+            InvariantViolation::RedRoot => InvariantViolation::RedRoot,
+            InvariantViolation::OutOfOrder { key } => InvariantViolation::OutOfOrder { key: f(key) },
+            InvariantViolation::RedRed { key } => InvariantViolation::RedRed { key: f(key) },
+            InvariantViolation::BlackHeight { key, left, right } => InvariantViolation::BlackHeight { key: f(key), left, right },
+            InvariantViolation::ParentPointer { key } => InvariantViolation::ParentPointer { key: f(key) },
+            InvariantViolation::SubtreeSize { key, expected, actual } => InvariantViolation::SubtreeSize { key: f(key), expected, actual },
+            InvariantViolation::LengthMismatch { expected, actual } => InvariantViolation::LengthMismatch { expected, actual },
+            InvariantViolation::RightLeaningRed { key } => InvariantViolation::RightLeaningRed { key: f(key) },
+            InvariantViolation::HeightImbalance { key, left, right } => InvariantViolation::HeightImbalance { key: f(key), left, right },
+            InvariantViolation::SubtreeHeight { key, expected, actual } => InvariantViolation::SubtreeHeight { key: f(key), expected, actual },
+            InvariantViolation::PriorityOrder { key } => InvariantViolation::PriorityOrder { key: f(key) },
+            InvariantViolation::StaleAugment { key } => InvariantViolation::StaleAugment { key: f(key) },
+        }
+    }
+}
+
+///出问题的节点
+type NodeRef<K, V> = Rc<RefCell<Node<K, V>>>;
+
+impl<K: Ord + Clone, V> RedBlackTree<K, V> {
+    /// 校验红黑树性质
+    /// 1.二叉搜索树顺序
+    /// 2.根节点为黑色
+    /// 3.不存在连续的红色节点
+    /// 4.所有路径黑高相等
+    /// 5.父节点指针正确
+    /// 6.子树节点数量正确
+    /// 7.节点数量与len一致
+    pub fn validate(&self) -> Result<(), InvariantViolation<K>> {
+        self.check_invariants().map_err(|violation| {
+            violation.map_key(|node_rc| {
+                let key = node_rc.borrow().key.clone();
+                key
+            })
+        })
+    }
+}
+
+impl<K: Ord, V> RedBlackTree<K, V> {
+    ///同validate，以节点本身标记出问题的位置，不要求K: Clone
+    fn check_invariants(&self) -> Result<(), InvariantViolation<NodeRef<K, V>>> {
+        let mut count = 0;
+        if let Some(root_ref) = &self.root {
+            let root = root_ref.borrow();
+            if root.color == Color::Red {
+                return Err(InvariantViolation::RedRoot);
+            }
+            if root.parent.as_ref().and_then(|parent_weak| parent_weak.upgrade()).is_some() {
+                return Err(InvariantViolation::ParentPointer { key: Rc::clone(root_ref) });
+            }
+        }
+        Self::validate_node(&self.root, None, None, &mut count)?;
+        if count != self.len {
+            return Err(InvariantViolation::LengthMismatch { expected: self.len, actual: count });
+        }
+        Ok(())
+    }
+
+    ///递归校验子树，返回子树黑高(空节点黑高为1)
+    fn validate_node(node_option: &Option<NodeRef<K, V>>, lower: Option<&K>, upper: Option<&K>, count: &mut usize) -> Result<usize, InvariantViolation<NodeRef<K, V>>> {
+        let node_ref = match node_option {
+            None => return Ok(1),
+            Some(node_ref) => node_ref,
+        };
+        let count_before = *count;
+        *count += 1;
+        let node = node_ref.borrow();
+        if lower.is_some_and(|lower| node.key <= *lower) || upper.is_some_and(|upper| node.key >= *upper) {
+            return Err(InvariantViolation::OutOfOrder { key: Rc::clone(node_ref) });
+        }
+        for son_ref in [&node.left, &node.right].into_iter().flatten() {
+            let son = son_ref.borrow();
+            let parent_ok = match son.parent.as_ref().and_then(|parent_weak| parent_weak.upgrade()) {
+                Some(parent_ref) => Rc::ptr_eq(&parent_ref, node_ref),
+                None => false,
+            };
+            if !parent_ok {
+                return Err(InvariantViolation::ParentPointer { key: Rc::clone(son_ref) });
+            }
+            if node.color == Color::Red && son.color == Color::Red {
+                return Err(InvariantViolation::RedRed { key: Rc::clone(node_ref) });
+            }
+        }
+        let left = Self::validate_node(&node.left, lower, Some(&node.key), count)?;
+        let right = Self::validate_node(&node.right, Some(&node.key), upper, count)?;
+        if left != right {
+            return Err(InvariantViolation::BlackHeight { key: Rc::clone(node_ref), left, right });
+        }
+        if node.size != *count - count_before {
+            return Err(InvariantViolation::SubtreeSize { key: Rc::clone(node_ref), expected: node.size, actual: *count - count_before });
+        }
+        Ok(left + usize::from(node.color == Color::Black))
+    }
+
+    /// 严格模式：每次修改后全量校验，违反时panic并打印树结构
+    /// 插入删除不要求K: Debug，以中序序号代替键，出问题的节点以<==标出
+    #[cfg(all(feature = "strict", debug_assertions))]
+    pub(super) fn assert_invariants(&self) {
+        if let Err(violation) = self.check_invariants() {
+            let mut dump = alloc::string::String::new();
+            let mut position = None;
+            let culprit = match &violation {
+                InvariantViolation::OutOfOrder { key }
+                | InvariantViolation::RedRed { key }
+                | InvariantViolation::BlackHeight { key, .. }
+                | InvariantViolation::ParentPointer { key }
+                | InvariantViolation::SubtreeSize { key, .. }
+                | InvariantViolation::RightLeaningRed { key }
+                | InvariantViolation::HeightImbalance { key, .. }
+                | InvariantViolation::SubtreeHeight { key, .. }
+                | InvariantViolation::PriorityOrder { key }
+                | InvariantViolation::StaleAugment { key } => Some(key),
+                InvariantViolation::RedRoot | InvariantViolation::LengthMismatch { .. } => None,
+            };
+            Self::dump_node(&self.root, "T", 0, 0, culprit, &mut position, &mut dump);
+            let violation = violation.map_key(|_| Position(position.unwrap_or_default()));
+            panic!("red black tree invariant violated: {}, len {}\n{}", violation, self.len, dump);
+        }
+    }
+
+    ///先序逐行输出子树，缩进表示深度，offset为子树最小节点的中序序号
+    #[cfg(all(feature = "strict", debug_assertions))]
+    fn dump_node(node_option: &Option<NodeRef<K, V>>, side: &str, depth: usize, offset: usize, culprit: Option<&NodeRef<K, V>>, position: &mut Option<usize>, dump: &mut alloc::string::String) {
+        use core::fmt::Write;
+
+        let Some(node_ref) = node_option else {
+            return;
+        };
+        let node = node_ref.borrow();
+        let index = offset + Self::count_nodes(&node.left);
+        let color = if node.color == Color::Red { 'R' } else { 'B' };
+        let mut mark = "";
+        if culprit.is_some_and(|culprit| Rc::ptr_eq(culprit, node_ref)) {
+            *position = Some(index);
+            mark = " <==";
+        }
+        let _ = writeln!(dump, "{}{} {}#{} size={} count={}{}", "  ".repeat(depth), side, color, index, node.size, node.count, mark);
+        Self::dump_node(&node.left, "L", depth + 1, offset, culprit, position, dump);
+        Self::dump_node(&node.right, "R", depth + 1, index + 1, culprit, position, dump);
+    }
+
+    ///实际节点数量，不依赖可能已损坏的size
+    #[cfg(all(feature = "strict", debug_assertions))]
+    fn count_nodes(node_option: &Option<NodeRef<K, V>>) -> usize {
+        node_option.as_ref().map_or(0, |node_ref| {
+            let node = node_ref.borrow();
+            Self::count_nodes(&node.left) + 1 + Self::count_nodes(&node.right)
+        })
+    }
+}
+
+///严格模式下以中序序号标识节点
+#[cfg(all(feature = "strict", debug_assertions))]
+struct Position(usize);
+
+#[cfg(all(feature = "strict", debug_assertions))]
+impl fmt::Debug for Position {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{}", self.0)
+    }
+}
+
+#[cfg(all(test, feature = "strict", debug_assertions))]
+mod tests {
+    use super::super::RedBlackTree;
+
+    #[test]
+    #[should_panic(expected = "node #1 records subtree size 101 but holds 5")]
+    fn strict_mode_panics_on_violation() {
+        let mut tree = RedBlackTree::new();
+        for key in 0..4 {
+            tree.insert(key, ());
+        }
+        //根节点1的子树节点数量
+        if let Some(root_ref) = &tree.root {
+            root_ref.borrow_mut().size = 100;
+        }
+        tree.insert(10, ());
+    }
+}
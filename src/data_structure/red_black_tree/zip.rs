@@ -0,0 +1,51 @@
+use core::cmp::Ordering;
+use core::iter::Peekable;
+
+use super::Iter;
+
+/// 有序合并的结果
+/// Left 仅树中存在，Right 仅外部序列中存在，Both 两者都存在
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum EitherOrBoth<L, R> {
+    Left(L),
+    Right(R),
+    Both(L, R),
+}
+
+/// 树与外部有序序列同步遍历的迭代器
+/// 外部序列必须按键升序排列
+pub struct ZipSorted<'a, K: Ord + Clone, V: Clone, V2, I: Iterator<Item = (K, V2)>> {
+    tree_iter: Peekable<Iter<'a, K, V>>,
+    other: Peekable<I>,
+}
+
+impl<'a, K: Ord + Clone, V: Clone, V2, I: Iterator<Item = (K, V2)>> ZipSorted<'a, K, V, V2, I> {
+    pub(super) fn new(tree_iter: Iter<'a, K, V>, other: I) -> Self {
+        ZipSorted {
+            tree_iter: tree_iter.peekable(),
+            other: other.peekable(),
+        }
+    }
+}
+
+impl<K: Ord + Clone, V: Clone, V2, I: Iterator<Item = (K, V2)>> Iterator for ZipSorted<'_, K, V, V2, I> {
+    type Item = EitherOrBoth<(K, V), (K, V2)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let ordering = match (self.tree_iter.peek(), self.other.peek()) {
+            (None, None) => return None,
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (Some((key, _)), Some((other_key, _))) => key.cmp(other_key),
+        };
+        match ordering {
+            Ordering::Less => self.tree_iter.next().map(EitherOrBoth::Left),
+            Ordering::Greater => self.other.next().map(EitherOrBoth::Right),
+            Ordering::Equal => {
+                let entry = self.tree_iter.next()?;
+                let other = self.other.next()?;
+                Some(EitherOrBoth::Both(entry, other))
+            }
+        }
+    }
+}
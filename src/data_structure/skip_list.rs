@@ -0,0 +1,479 @@
+//! 跳表
+//! 多层有序链表，每个节点以1/4的概率晋升到上一层，期望O(log n)查找，以随机性代替旋转调平
+//! 节点存放在Vec中，链接为u32下标，删除的槽位串成空闲链表复用
+//! 最底层另有后向链接，支持从尾部反向迭代
+
+use alloc::vec::Vec;
+use core::fmt;
+use core::ops::RangeBounds;
+
+use super::ordered_map::{after_start, before_end};
+use super::red_black_tree::{InvariantViolation, RandomSource};
+
+///空下标，相当于空指针
+const NIL: u32 = u32::MAX;
+///表头，不占槽位，各层的起点
+const HEAD: u32 = u32::MAX - 1;
+///最大层数，4^16个节点以内足够
+const MAX_LEVEL: usize = 16;
+///默认种子
+const DEFAULT_SEED: u64 = 0x5C1F_7E57_0DD5_EED5;
+
+struct SkipNode<K, V> {
+    key: K,
+    value: V,
+    //各层的后继，长度即节点层数
+    forward: Vec<u32>,
+    //最底层的前驱，第一个节点为NIL
+    backward: u32,
+}
+
+///槽位，空闲槽位记录下一个空闲槽位
+enum Slot<K, V> {
+    Occupied(SkipNode<K, V>),
+    Vacant { next_free: u32 },
+}
+
+pub struct SkipList<K, V> {
+    slots: Vec<Slot<K, V>>,
+    //表头各层的后继
+    head: [u32; MAX_LEVEL],
+    //当前使用的层数
+    level: usize,
+    tail: u32,
+    //空闲链表头
+    free: u32,
+    len: usize,
+    //层数的随机源，与RedBlackTree相同
+    random: RandomSource,
+}
+
+impl<K: Ord, V> SkipList<K, V> {
+    pub fn new() -> Self {
+        Self::with_seed(DEFAULT_SEED)
+    }
+
+    ///以种子创建，相同种子与相同操作序列得到相同的结构
+    pub fn with_seed(seed: u64) -> Self {
+        SkipList { slots: Vec::new(), head: [NIL; MAX_LEVEL], level: 0, tail: NIL, free: NIL, len: 0, random: RandomSource::seeded(seed) }
+    }
+
+    ///以种子重置层数的随机源，之后插入的节点的层数随之确定
+    pub fn set_random_seed(&mut self, seed: u64) {
+        self.random = RandomSource::seeded(seed);
+    }
+
+    ///注入外部随机数生成器作为层数的来源
+    #[cfg(feature = "rand")]
+    pub fn set_rng<R: rand::RngCore + 'static>(&mut self, rng: R) {
+        self.random = RandomSource::Injected(alloc::boxed::Box::new(rng));
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    ///清空，槽位一并释放
+    pub fn clear(&mut self) {
+        self.slots.clear();
+        self.head = [NIL; MAX_LEVEL];
+        self.level = 0;
+        self.tail = NIL;
+        self.free = NIL;
+        self.len = 0;
+    }
+
+    fn node(&self, index: u32) -> &SkipNode<K, V> {
+        match &self.slots[index as usize] {
+            Slot::Occupied(node) => node,
+            Slot::Vacant { .. } => unreachable!("link to a free slot"),
+        }
+    }
+
+    fn node_mut(&mut self, index: u32) -> &mut SkipNode<K, V> {
+        match &mut self.slots[index as usize] {
+            Slot::Occupied(node) => node,
+            Slot::Vacant { .. } => unreachable!("link to a free slot"),
+        }
+    }
+
+    ///index在level层的后继，index可以是表头
+    fn next(&self, index: u32, level: usize) -> u32 {
+        if index == HEAD {
+            self.head[level]
+        } else {
+            self.node(index).forward[level]
+        }
+    }
+
+    fn set_next(&mut self, index: u32, level: usize, next: u32) {
+        if index == HEAD {
+            self.head[level] = next;
+        } else {
+            self.node_mut(index).forward[level] = next;
+        }
+    }
+
+    ///分配槽位，优先复用空闲槽位
+    fn allocate(&mut self, node: SkipNode<K, V>) -> u32 {
+        if self.free != NIL {
+            let index = self.free;
+            self.free = match self.slots[index as usize] {
+                Slot::Vacant { next_free } => next_free,
+                Slot::Occupied(_) => unreachable!("free list points to an occupied slot"),
+            };
+            self.slots[index as usize] = Slot::Occupied(node);
+            index
+        } else {
+            assert!(self.slots.len() < HEAD as usize, "skip list is full");
+            self.slots.push(Slot::Occupied(node));
+            (self.slots.len() - 1) as u32
+        }
+    }
+
+    ///释放槽位，取出节点
+    fn release(&mut self, index: u32) -> SkipNode<K, V> {
+        let slot = core::mem::replace(&mut self.slots[index as usize], Slot::Vacant { next_free: self.free });
+        self.free = index;
+        match slot {
+            Slot::Occupied(node) => node,
+            Slot::Vacant { .. } => unreachable!("release a free slot"),
+        }
+    }
+
+    /// 随机层数，每晋升一层的概率为1/4
+    /// 随机数两个末尾的0比特对应一次晋升
+    fn random_level(&mut self) -> usize {
+        (1 + self.random.next_u64().trailing_zeros() as usize / 2).min(MAX_LEVEL)
+    }
+
+    ///每层最后一个小于key的节点(可能是表头)
+    fn predecessors(&self, key: &K) -> [u32; MAX_LEVEL] {
+        let mut update = [HEAD; MAX_LEVEL];
+        let mut cur = HEAD;
+        for level in (0..self.level).rev() {
+            loop {
+                let next = self.next(cur, level);
+                if next == NIL || self.node(next).key >= *key {
+                    break;
+                }
+                cur = next;
+            }
+            update[level] = cur;
+        }
+        update
+    }
+
+    ///各层的前驱，以及键所在的节点(不存在为NIL)
+    fn locate(&self, key: &K) -> ([u32; MAX_LEVEL], u32) {
+        let update = self.predecessors(key);
+        let candidate = if self.level == 0 { NIL } else { self.next(update[0], 0) };
+        if candidate != NIL && self.node(candidate).key == *key {
+            (update, candidate)
+        } else {
+            (update, NIL)
+        }
+    }
+
+    fn find(&self, key: &K) -> u32 {
+        self.locate(key).1
+    }
+
+    ///插入键值对，键已存在时保留原值
+    pub fn insert(&mut self, key: K, value: V) {
+        self.insert_with(key, value, false);
+    }
+
+    ///插入或替换，返回旧值
+    pub fn insert_or_replace(&mut self, key: K, value: V) -> Option<V> {
+        self.insert_with(key, value, true)
+    }
+
+    /// 插入，返回未保留的值
+    /// 键已存在时replace为true则替换并返回旧值，否则返回新值
+    fn insert_with(&mut self, key: K, value: V, replace: bool) -> Option<V> {
+        let (mut update, existing) = self.locate(&key);
+        if existing != NIL {
+            return if replace { Some(core::mem::replace(&mut self.node_mut(existing).value, value)) } else { Some(value) };
+        }
+        let level = self.random_level();
+        //新的层以表头为前驱
+        if level > self.level {
+            update[self.level..level].fill(HEAD);
+            self.level = level;
+        }
+        let backward = if update[0] == HEAD { NIL } else { update[0] };
+        let forward = (0..level).map(|level| self.next(update[level], level)).collect();
+        let index = self.allocate(SkipNode { key, value, forward, backward });
+        for (level, predecessor) in update.iter().enumerate().take(level) {
+            self.set_next(*predecessor, level, index);
+        }
+        match self.node(index).forward[0] {
+            NIL => self.tail = index,
+            next => self.node_mut(next).backward = index,
+        }
+        self.len += 1;
+        None
+    }
+
+    ///摘除节点，update为各层的前驱
+    fn unlink(&mut self, target: u32, update: &[u32; MAX_LEVEL]) -> (K, V) {
+        let node = self.release(target);
+        for (level, next) in node.forward.iter().enumerate() {
+            self.set_next(update[level], level, *next);
+        }
+        match node.forward[0] {
+            NIL => self.tail = node.backward,
+            next => self.node_mut(next).backward = node.backward,
+        }
+        //最高层空了则降低层数
+        while self.level > 0 && self.head[self.level - 1] == NIL {
+            self.level -= 1;
+        }
+        self.len -= 1;
+        (node.key, node.value)
+    }
+
+    ///删除键，键不存在时忽略
+    pub fn delete(&mut self, key: &K) {
+        self.remove(key);
+    }
+
+    ///删除键并返回值
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let (update, target) = self.locate(key);
+        if target == NIL {
+            return None;
+        }
+        Some(self.unlink(target, &update).1)
+    }
+
+    pub fn pop_first(&mut self) -> Option<(K, V)> {
+        //第一个节点在每一层的前驱都是表头
+        (self.len != 0).then(|| self.unlink(self.head[0], &[HEAD; MAX_LEVEL]))
+    }
+
+    pub fn pop_last(&mut self) -> Option<(K, V)> {
+        if self.tail == NIL {
+            return None;
+        }
+        let update = self.predecessors(&self.node(self.tail).key);
+        Some(self.unlink(self.tail, &update))
+    }
+
+    pub fn get(&self, key: &K) -> Option<&V> {
+        let index = self.find(key);
+        (index != NIL).then(|| &self.node(index).value)
+    }
+
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        let index = self.find(key);
+        (index != NIL).then(|| &mut self.node_mut(index).value)
+    }
+
+    pub fn contains(&self, key: &K) -> bool {
+        self.find(key) != NIL
+    }
+
+    fn entry(&self, index: u32) -> (&K, &V) {
+        let node = self.node(index);
+        (&node.key, &node.value)
+    }
+
+    pub fn first(&self) -> Option<(&K, &V)> {
+        (self.len != 0).then(|| self.entry(self.head[0]))
+    }
+
+    pub fn last(&self) -> Option<(&K, &V)> {
+        (self.tail != NIL).then(|| self.entry(self.tail))
+    }
+
+    ///沿最底层顺序迭代，产出借用
+    pub fn iter(&self) -> SkipIter<'_, K, V> {
+        let front = if self.len == 0 { NIL } else { self.head[0] };
+        SkipIter { list: self, front, back: self.tail }
+    }
+
+    /// 按键升序迭代范围内的条目，两端各自逐层下降定位，期望O(log n)
+    /// 起点为第一个满足下界的节点，终点为最后一个满足上界的节点
+    pub fn range<R: RangeBounds<K>>(&self, range: R) -> SkipIter<'_, K, V> {
+        if self.len == 0 {
+            return SkipIter { list: self, front: NIL, back: NIL };
+        }
+        let (mut before, mut last) = (HEAD, HEAD);
+        for level in (0..self.level).rev() {
+            while self.next(before, level) != NIL && !after_start(range.start_bound(), &self.node(self.next(before, level)).key) {
+                before = self.next(before, level);
+            }
+            while self.next(last, level) != NIL && before_end(range.end_bound(), &self.node(self.next(last, level)).key) {
+                last = self.next(last, level);
+            }
+        }
+        let front = self.next(before, 0);
+        //范围内没有节点，或起点在终点之后
+        if front == NIL || last == HEAD || self.node(front).key > self.node(last).key {
+            return SkipIter { list: self, front: NIL, back: NIL };
+        }
+        SkipIter { list: self, front, back: last }
+    }
+
+    /// 校验跳表结构：各层严格升序，最底层的后向链接与前向链接一致，节点数量正确
+    /// 后向链接错误报告为ParentPointer
+    pub fn validate(&self) -> Result<(), InvariantViolation<K>>
+    where
+        K: Clone,
+    {
+        for level in 0..self.level {
+            let (mut previous, mut cur, mut count) = (NIL, self.head[level], 0);
+            while cur != NIL {
+                let node = self.node(cur);
+                count += 1;
+                if previous != NIL && self.node(previous).key >= node.key {
+                    return Err(InvariantViolation::OutOfOrder { key: node.key.clone() });
+                }
+                if level == 0 && node.backward != previous {
+                    return Err(InvariantViolation::ParentPointer { key: node.key.clone() });
+                }
+                previous = cur;
+                cur = node.forward[level];
+            }
+            if level == 0 && (count != self.len || previous != self.tail) {
+                return Err(InvariantViolation::LengthMismatch { expected: self.len, actual: count });
+            }
+        }
+        if self.level == 0 && self.len != 0 {
+            return Err(InvariantViolation::LengthMismatch { expected: self.len, actual: 0 });
+        }
+        Ok(())
+    }
+}
+
+impl<K: Ord, V> Default for SkipList<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Ord + fmt::Debug, V: fmt::Debug> fmt::Debug for SkipList<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+/// 迭代器，front为下一个产出的节点，back为最后一个产出的节点，二者相遇时迭代结束
+pub struct SkipIter<'a, K, V> {
+    list: &'a SkipList<K, V>,
+    front: u32,
+    back: u32,
+}
+
+impl<'a, K: Ord, V> Iterator for SkipIter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.front == NIL {
+            return None;
+        }
+        let cur = self.front;
+        if cur == self.back {
+            self.front = NIL;
+            self.back = NIL;
+        } else {
+            self.front = self.list.node(cur).forward[0];
+        }
+        Some(self.list.entry(cur))
+    }
+}
+
+impl<K: Ord, V> DoubleEndedIterator for SkipIter<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.back == NIL {
+            return None;
+        }
+        let cur = self.back;
+        if cur == self.front {
+            self.front = NIL;
+            self.back = NIL;
+        } else {
+            self.back = self.list.node(cur).backward;
+        }
+        Some(self.list.entry(cur))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use super::SkipList;
+
+    #[derive(Debug, Clone)]
+    enum Op {
+        Insert(u8, u32),
+        InsertOrReplace(u8, u32),
+        Delete(u8),
+        PopFirst,
+        PopLast,
+    }
+
+    fn op() -> impl Strategy<Value = Op> {
+        prop_oneof![
+            4 => (any::<u8>(), any::<u32>()).prop_map(|(key, value)| Op::Insert(key % 64, value)),
+            2 => (any::<u8>(), any::<u32>()).prop_map(|(key, value)| Op::InsertOrReplace(key % 64, value)),
+            4 => any::<u8>().prop_map(|key| Op::Delete(key % 64)),
+            1 => Just(Op::PopFirst),
+            1 => Just(Op::PopLast),
+        ]
+    }
+
+    proptest! {
+        #[test]
+        fn matches_btree_map(ops in vec(op(), 0..300)) {
+            let mut list = SkipList::new();
+            let mut model = BTreeMap::new();
+            for op in ops {
+                match op {
+                    Op::Insert(key, value) => {
+                        list.insert(key, value);
+                        model.entry(key).or_insert(value);
+                    }
+                    Op::InsertOrReplace(key, value) => prop_assert_eq!(list.insert_or_replace(key, value), model.insert(key, value)),
+                    Op::Delete(key) => prop_assert_eq!(list.remove(&key), model.remove(&key)),
+                    Op::PopFirst => prop_assert_eq!(list.pop_first(), model.pop_first()),
+                    Op::PopLast => prop_assert_eq!(list.pop_last(), model.pop_last()),
+                }
+                prop_assert_eq!(list.validate(), Ok(()));
+                prop_assert_eq!(list.len(), model.len());
+                prop_assert!(list.iter().eq(model.iter()));
+                prop_assert!(list.iter().rev().eq(model.iter().rev()));
+            }
+        }
+    }
+
+    ///注入相同状态的随机数生成器，各节点的层数相同
+    #[cfg(feature = "rand")]
+    #[test]
+    fn injected_levels_are_reproducible() {
+        let levels = || {
+            let mut list = SkipList::new();
+            list.set_rng(StdRng::seed_from_u64(9));
+            for key in 0..200u32 {
+                list.insert(key, ());
+            }
+            assert_eq!(list.validate(), Ok(()));
+            (0..200).map(|key| list.node(list.find(&key)).forward.len()).collect::<Vec<_>>()
+        };
+        let first = levels();
+        assert_eq!(first, levels());
+        assert!(first.iter().any(|level| *level > 1));
+    }
+}
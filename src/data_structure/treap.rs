@@ -0,0 +1,447 @@
+//! 树堆(Treap)
+//! 键满足二叉搜索树顺序，随机优先级满足大根堆顺序，期望高度O(log n)
+//! 所有修改都归结为按键拆分(split)与有序合并(merge)，比红黑树的拆分、拼接简单得多
+//! 节点记录子树节点数量，拆分后两侧的数量无需遍历即可得到
+
+use alloc::boxed::Box;
+use core::cmp::Ordering;
+use core::fmt;
+use core::ops::RangeBounds;
+
+use super::inorder::{BinaryNode, InorderIter};
+use super::red_black_tree::{InvariantViolation, RandomSource};
+
+///默认种子
+const DEFAULT_SEED: u64 = 0x7EA9_5EED_0B5E_55ED;
+
+type Link<K, V> = Option<Box<TreapNode<K, V>>>;
+
+struct TreapNode<K, V> {
+    key: K,
+    value: V,
+    left: Link<K, V>,
+    right: Link<K, V>,
+    priority: u64,
+    //子树节点数量(含自身)
+    size: usize,
+}
+
+impl<K, V> BinaryNode for TreapNode<K, V> {
+    type Key = K;
+    type Value = V;
+
+    fn entry(&self) -> (&K, &V) {
+        (&self.key, &self.value)
+    }
+
+    fn left(&self) -> Option<&Self> {
+        self.left.as_deref()
+    }
+
+    fn right(&self) -> Option<&Self> {
+        self.right.as_deref()
+    }
+}
+
+pub struct Treap<K, V> {
+    root: Link<K, V>,
+    //优先级的随机源，与RedBlackTree相同
+    random: RandomSource,
+}
+
+fn size<K, V>(link: &Link<K, V>) -> usize {
+    link.as_ref().map_or(0, |node| node.size)
+}
+
+fn update_size<K, V>(node: &mut TreapNode<K, V>) {
+    node.size = size(&node.left) + size(&node.right) + 1;
+}
+
+///拆分为小于key与大于等于key的两棵树
+fn split<K: Ord, V>(link: Link<K, V>, key: &K) -> (Link<K, V>, Link<K, V>) {
+    let Some(mut node) = link else {
+        return (None, None);
+    };
+    if node.key < *key {
+        let (less, rest) = split(node.right.take(), key);
+        node.right = less;
+        update_size(&mut node);
+        (Some(node), rest)
+    } else {
+        let (less, rest) = split(node.left.take(), key);
+        node.left = rest;
+        update_size(&mut node);
+        (less, Some(node))
+    }
+}
+
+///合并两棵树，left的键都小于right的键，优先级高者为根
+fn merge<K, V>(left: Link<K, V>, right: Link<K, V>) -> Link<K, V> {
+    match (left, right) {
+        (None, right) => right,
+        (left, None) => left,
+        (Some(mut left), Some(mut right)) => {
+            if left.priority >= right.priority {
+                left.right = merge(left.right.take(), Some(right));
+                update_size(&mut left);
+                Some(left)
+            } else {
+                right.left = merge(Some(left), right.left.take());
+                update_size(&mut right);
+                Some(right)
+            }
+        }
+    }
+}
+
+///删除key，以其左右子树的合并取代该节点
+fn remove_node<K: Ord, V>(link: &mut Link<K, V>, key: &K) -> Option<(K, V)> {
+    let node = link.as_mut()?;
+    let removed = match key.cmp(&node.key) {
+        Ordering::Less => remove_node(&mut node.left, key),
+        Ordering::Greater => remove_node(&mut node.right, key),
+        Ordering::Equal => {
+            let mut node = link.take().expect("matched node");
+            *link = merge(node.left.take(), node.right.take());
+            return Some((node.key, node.value));
+        }
+    };
+    if removed.is_some() {
+        update_size(node);
+    }
+    removed
+}
+
+///删除dir一侧的端点，端点只可能有另一侧的子树
+fn remove_extreme<K, V>(link: &mut Link<K, V>, leftmost: bool) -> Option<(K, V)> {
+    let node = link.as_mut()?;
+    let next = if leftmost { &mut node.left } else { &mut node.right };
+    if next.is_some() {
+        let removed = remove_extreme(next, leftmost);
+        update_size(node);
+        return removed;
+    }
+    let mut node = link.take().expect("checked above");
+    *link = if leftmost { node.right.take() } else { node.left.take() };
+    Some((node.key, node.value))
+}
+
+impl<K: Ord, V> Treap<K, V> {
+    pub fn new() -> Self {
+        Self::with_seed(DEFAULT_SEED)
+    }
+
+    ///以种子创建，相同种子与相同操作序列得到相同的形状
+    pub fn with_seed(seed: u64) -> Self {
+        Treap { root: None, random: RandomSource::seeded(seed) }
+    }
+
+    ///以种子重置优先级的随机源，之后插入的节点的优先级随之确定
+    pub fn set_random_seed(&mut self, seed: u64) {
+        self.random = RandomSource::seeded(seed);
+    }
+
+    ///注入外部随机数生成器作为优先级的来源
+    #[cfg(feature = "rand")]
+    pub fn set_rng<R: rand::RngCore + 'static>(&mut self, rng: R) {
+        self.random = RandomSource::Injected(Box::new(rng));
+    }
+
+    pub fn len(&self) -> usize {
+        size(&self.root)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    pub fn clear(&mut self) {
+        self.root = None;
+    }
+
+    ///插入键值对，键已存在时保留原值
+    pub fn insert(&mut self, key: K, value: V) {
+        if !self.contains(&key) {
+            self.insert_new(key, value);
+        }
+    }
+
+    ///插入或替换，返回旧值
+    pub fn insert_or_replace(&mut self, key: K, value: V) -> Option<V> {
+        match self.get_mut(&key) {
+            Some(old) => Some(core::mem::replace(old, value)),
+            None => {
+                self.insert_new(key, value);
+                None
+            }
+        }
+    }
+
+    ///插入不存在的键：按键拆分后把新节点夹在中间合并
+    fn insert_new(&mut self, key: K, value: V) {
+        let (less, rest) = split(self.root.take(), &key);
+        let priority = self.random.next_u64();
+        let node = Box::new(TreapNode { key, value, left: None, right: None, priority, size: 1 });
+        self.root = merge(merge(less, Some(node)), rest);
+    }
+
+    ///删除键，键不存在时忽略
+    pub fn delete(&mut self, key: &K) {
+        self.remove(key);
+    }
+
+    ///删除键并返回值
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        remove_node(&mut self.root, key).map(|(_, value)| value)
+    }
+
+    pub fn pop_first(&mut self) -> Option<(K, V)> {
+        remove_extreme(&mut self.root, true)
+    }
+
+    pub fn pop_last(&mut self) -> Option<(K, V)> {
+        remove_extreme(&mut self.root, false)
+    }
+
+    /// 拆分，本树保留小于key的键，返回其余键
+    /// 期望O(log n)
+    pub fn split_off(&mut self, key: &K) -> Self {
+        let (less, rest) = split(self.root.take(), key);
+        self.root = less;
+        //拆出的树以本树的下一个随机数为种子
+        Treap { root: rest, random: RandomSource::seeded(self.random.next_u64()) }
+    }
+
+    /// 移入other的全部键值对，other变为空树
+    /// other的键都大于本树的键(或都小于)时直接合并，期望O(log n)，否则逐个插入
+    pub fn append(&mut self, other: &mut Self) {
+        let before = match (self.last(), other.first()) {
+            (Some((last, _)), Some((first, _))) => last < first,
+            _ => true,
+        };
+        let after = match (self.first(), other.last()) {
+            (Some((first, _)), Some((last, _))) => last < first,
+            _ => true,
+        };
+        if before {
+            self.root = merge(self.root.take(), other.root.take());
+        } else if after {
+            self.root = merge(other.root.take(), self.root.take());
+        } else {
+            while let Some((key, value)) = other.pop_first() {
+                self.insert_or_replace(key, value);
+            }
+        }
+    }
+
+    fn find(&self, key: &K) -> Option<&TreapNode<K, V>> {
+        let mut cur = self.root.as_deref();
+        while let Some(node) = cur {
+            cur = match key.cmp(&node.key) {
+                Ordering::Equal => return Some(node),
+                Ordering::Less => node.left.as_deref(),
+                Ordering::Greater => node.right.as_deref(),
+            };
+        }
+        None
+    }
+
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.find(key).map(|node| &node.value)
+    }
+
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        let mut cur = self.root.as_deref_mut();
+        while let Some(node) = cur {
+            cur = match key.cmp(&node.key) {
+                Ordering::Equal => return Some(&mut node.value),
+                Ordering::Less => node.left.as_deref_mut(),
+                Ordering::Greater => node.right.as_deref_mut(),
+            };
+        }
+        None
+    }
+
+    pub fn contains(&self, key: &K) -> bool {
+        self.find(key).is_some()
+    }
+
+    pub fn first(&self) -> Option<(&K, &V)> {
+        self.iter().next()
+    }
+
+    pub fn last(&self) -> Option<(&K, &V)> {
+        self.iter().next_back()
+    }
+
+    ///中序迭代，产出借用
+    pub fn iter(&self) -> TreapIter<'_, K, V> {
+        TreapIter { iter: InorderIter::new(self.root.as_deref(), self.len()) }
+    }
+
+    ///按键升序迭代范围内的条目，两端沿查找路径定位，O(log n)
+    pub fn range<R: RangeBounds<K>>(&self, range: R) -> TreapIter<'_, K, V> {
+        TreapIter { iter: InorderIter::range(self.root.as_deref(), range) }
+    }
+
+    ///校验树堆性质：键的顺序、优先级的堆顺序、子树节点数量
+    pub fn validate(&self) -> Result<(), InvariantViolation<K>>
+    where
+        K: Clone,
+    {
+        Self::validate_node(&self.root, None, None, u64::MAX).map(|_| ())
+    }
+
+    ///递归校验子树，返回子树节点数量
+    fn validate_node(link: &Link<K, V>, lower: Option<&K>, upper: Option<&K>, parent_priority: u64) -> Result<usize, InvariantViolation<K>>
+    where
+        K: Clone,
+    {
+        let Some(node) = link else {
+            return Ok(0);
+        };
+        if lower.is_some_and(|lower| node.key <= *lower) || upper.is_some_and(|upper| node.key >= *upper) {
+            return Err(InvariantViolation::OutOfOrder { key: node.key.clone() });
+        }
+        if node.priority > parent_priority {
+            return Err(InvariantViolation::PriorityOrder { key: node.key.clone() });
+        }
+        let left = Self::validate_node(&node.left, lower, Some(&node.key), node.priority)?;
+        let right = Self::validate_node(&node.right, Some(&node.key), upper, node.priority)?;
+        let actual = left + right + 1;
+        if node.size != actual {
+            return Err(InvariantViolation::SubtreeSize { key: node.key.clone(), expected: node.size, actual });
+        }
+        Ok(actual)
+    }
+}
+
+impl<K: Ord, V> Default for Treap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Ord + fmt::Debug, V: fmt::Debug> fmt::Debug for Treap<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+/// 中序迭代器
+pub struct TreapIter<'a, K, V> {
+    iter: InorderIter<'a, TreapNode<K, V>>,
+}
+
+impl<'a, K, V> Iterator for TreapIter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<K, V> DoubleEndedIterator for TreapIter<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.iter.next_back()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use super::Treap;
+
+    #[derive(Debug, Clone)]
+    enum Op {
+        Insert(u8, u32),
+        InsertOrReplace(u8, u32),
+        Delete(u8),
+        PopFirst,
+        PopLast,
+        SplitAppend(u8),
+    }
+
+    fn op() -> impl Strategy<Value = Op> {
+        prop_oneof![
+            4 => (any::<u8>(), any::<u32>()).prop_map(|(key, value)| Op::Insert(key % 64, value)),
+            2 => (any::<u8>(), any::<u32>()).prop_map(|(key, value)| Op::InsertOrReplace(key % 64, value)),
+            4 => any::<u8>().prop_map(|key| Op::Delete(key % 64)),
+            1 => Just(Op::PopFirst),
+            1 => Just(Op::PopLast),
+            1 => any::<u8>().prop_map(|key| Op::SplitAppend(key % 64)),
+        ]
+    }
+
+    proptest! {
+        #[test]
+        fn matches_btree_map(ops in vec(op(), 0..300)) {
+            let mut tree = Treap::new();
+            let mut model = BTreeMap::new();
+            for op in ops {
+                match op {
+                    Op::Insert(key, value) => {
+                        tree.insert(key, value);
+                        model.entry(key).or_insert(value);
+                    }
+                    Op::InsertOrReplace(key, value) => prop_assert_eq!(tree.insert_or_replace(key, value), model.insert(key, value)),
+                    Op::Delete(key) => prop_assert_eq!(tree.remove(&key), model.remove(&key)),
+                    Op::PopFirst => prop_assert_eq!(tree.pop_first(), model.pop_first()),
+                    Op::PopLast => prop_assert_eq!(tree.pop_last(), model.pop_last()),
+                    Op::SplitAppend(key) => {
+                        let mut right = tree.split_off(&key);
+                        let model_right = model.split_off(&key);
+                        prop_assert_eq!(right.len(), model_right.len());
+                        prop_assert!(right.iter().eq(model_right.iter()));
+                        //先把右半部分移入再移回，分别走两种合并方向
+                        right.append(&mut tree);
+                        tree.append(&mut right);
+                        model.extend(model_right);
+                        prop_assert!(right.is_empty());
+                    }
+                }
+                prop_assert_eq!(tree.validate(), Ok(()));
+                prop_assert_eq!(tree.len(), model.len());
+                prop_assert!(tree.iter().eq(model.iter()));
+            }
+        }
+    }
+    ///相同的种子或注入相同状态的随机数生成器，得到相同的优先级
+    #[cfg(feature = "rand")]
+    #[test]
+    fn injected_priorities_are_reproducible() {
+        let priorities = |tree: &Treap<u32, ()>| {
+            let mut stack: Vec<_> = tree.root.as_deref().into_iter().collect();
+            let mut result = Vec::new();
+            while let Some(node) = stack.pop() {
+                result.push((node.key, node.priority));
+                stack.extend(node.left.as_deref().into_iter().chain(node.right.as_deref()));
+            }
+            result
+        };
+        let build = |configure: &dyn Fn(&mut Treap<u32, ()>)| {
+            let mut tree = Treap::new();
+            configure(&mut tree);
+            for key in 0..100 {
+                tree.insert(key, ());
+            }
+            tree
+        };
+        let seeded = build(&|tree| tree.set_random_seed(42));
+        assert_eq!(priorities(&seeded), priorities(&build(&|tree| tree.set_random_seed(42))));
+        assert_ne!(priorities(&seeded), priorities(&Treap::new()));
+        let injected = build(&|tree| tree.set_rng(StdRng::seed_from_u64(42)));
+        assert_eq!(priorities(&injected), priorities(&build(&|tree| tree.set_rng(StdRng::seed_from_u64(42)))));
+        assert_eq!(injected.validate(), Ok(()));
+    }
+}
@@ -1,44 +1,63 @@
+//! 演示程序
+//! 随机插入、删除同时作用于红黑树和HashMap，比对大小并在结束时校验红黑树性质
+//! 不依赖任何特性，cargo run即可运行；完整的命令行见rbt，cargo run --features cli --bin rbt -- --help
+
 use std::collections::HashMap;
-use rand::Rng;
-use crate::data_structure::red_black_tree::{RedBlackTree};
+use std::process::ExitCode;
+
+use red_black_tree::data_structure::red_black_tree::RedBlackTree;
 
-pub mod data_structure;
+///操作次数
+const OPS: u64 = 100_000;
+///键的取值范围为1..=KEY_RANGE
+const KEY_RANGE: u64 = 100_000;
 
-fn main() {
+///xorshift64，演示无需rand即可得到可复现的随机序列
+struct XorShift(u64);
+
+impl XorShift {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+}
+
+fn main() -> ExitCode {
+    let mut rng = XorShift(0x2545_f491_4f6c_dd1d);
     //红黑树
     let mut rbt = RedBlackTree::new();
     //辅助验证 HashMap
     let mut map = HashMap::new();
-    //随机数生成器
-    let mut rng = rand::thread_rng();
-    //插入次数
-    let mut count = 0;
     //插入阶段
-    for _ in 0..100_000 {
-        let random_number = rng.gen_range(1..=100_000);
-        rbt.insert(random_number);
-        map.insert(random_number, random_number);
-        count += 1;
-        // rbt.preorder_traversal();
-        println!("size={}==={}==={}==={}", rbt.size(), map.len(), count, random_number);
-        if rbt.size() != map.len() {
-            println!("插入逻辑出错了");
-            return;
+    for _ in 0..OPS {
+        let key = rng.next() % KEY_RANGE + 1;
+        rbt.insert(key, key);
+        map.insert(key, key);
+        if rbt.len() != map.len() {
+            eprintln!("插入逻辑出错了: insert {}", key);
+            return ExitCode::FAILURE;
+        }
+    }
+    println!("插入{}次，不同的键{}个，树高{}", OPS, rbt.len(), rbt.height());
+    //删除阶段，删除约一半的键
+    let mut keys: Vec<u64> = map.keys().copied().collect();
+    keys.sort_unstable();
+    for key in keys.into_iter().filter(|_| rng.next() & 1 == 0) {
+        rbt.delete(&key);
+        map.remove(&key);
+        if rbt.len() != map.len() {
+            eprintln!("删除逻辑出错了: delete {}", key);
+            return ExitCode::FAILURE;
         }
     }
-    //删除阶段
-    while !map.is_empty() {
-        let keys: Vec<_> = map.keys().cloned().collect();
-        let index_to_delete = rng.gen_range(0..keys.len());
-        let key_to_delete = keys[index_to_delete];
-        //删除
-        rbt.delete(key_to_delete);
-        map.remove(&key_to_delete);
-        // rbt.preorder_traversal();
-        println!("size={}==={}", rbt.size(), map.len());
-        if rbt.size() != map.len() {
-            println!("删除逻辑出错了");
-            return;
+    println!("删除后剩余{}个，树高{}", rbt.len(), rbt.height());
+    match rbt.validate() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(violation) => {
+            eprintln!("{}", violation);
+            ExitCode::FAILURE
         }
     }
-}
\ No newline at end of file
+}
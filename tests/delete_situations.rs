@@ -0,0 +1,286 @@
+//! 删除调平各情况的回归用例
+//! 以保留结构的序列化精确构造树的形状与颜色，删除指定的黑色叶子后校验红黑树性质与内容
+//! 并以观察者记录调平时判定的情况，确认用例确实走到了对应分支
+//! 形状用简写描述：`B20(R10,B30(-,R35))`，B/R为颜色，括号内为左右子树，`-`为空
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use red_black_tree::data_structure::red_black_tree::{DeleteRecursionSituation, DeleteSituation, Observer, RedBlackTree};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+#[derive(Serialize, Deserialize)]
+struct Shaped {
+    #[serde(with = "red_black_tree::data_structure::red_black_tree::serde_structure")]
+    tree: RedBlackTree<u32, u32>,
+}
+
+///解析简写为先序节点记录
+fn parse(input: &mut &str, records: &mut Vec<Value>) -> bool {
+    if let Some(rest) = input.strip_prefix('-') {
+        *input = rest;
+        return false;
+    }
+    let color = match &input[..1] {
+        "B" => "Black",
+        "R" => "Red",
+        other => panic!("unknown color {}", other),
+    };
+    let digits = input[1..].find(|c: char| !c.is_ascii_digit()).map_or(input.len(), |end| end + 1);
+    let key: u32 = input[1..digits].parse().unwrap();
+    *input = &input[digits..];
+    let index = records.len();
+    records.push(json!({ "key": key, "value": key, "color": color, "left": false, "right": false }));
+    if let Some(rest) = input.strip_prefix('(') {
+        *input = rest;
+        let left = parse(input, records);
+        *input = input.strip_prefix(',').expect("missing ,");
+        let right = parse(input, records);
+        *input = input.strip_prefix(')').expect("missing )");
+        records[index]["left"] = json!(left);
+        records[index]["right"] = json!(right);
+    }
+    true
+}
+
+fn build(shape: &str) -> RedBlackTree<u32, u32> {
+    let mut records = Vec::new();
+    let mut input = shape;
+    parse(&mut input, &mut records);
+    assert!(input.is_empty(), "trailing input in {}", shape);
+    let shaped: Shaped = serde_json::from_value(json!({ "tree": records })).unwrap_or_else(|error| panic!("{}: {}", shape, error));
+    shaped.tree
+}
+
+///调平时判定的情况
+#[derive(PartialEq, Clone, Copy, Debug)]
+enum Situation {
+    Delete(DeleteSituation),
+    Recursion(DeleteRecursionSituation),
+}
+
+impl From<DeleteSituation> for Situation {
+    fn from(situation: DeleteSituation) -> Self {
+        Situation::Delete(situation)
+    }
+}
+
+impl From<DeleteRecursionSituation> for Situation {
+    fn from(situation: DeleteRecursionSituation) -> Self {
+        Situation::Recursion(situation)
+    }
+}
+
+#[derive(Default)]
+struct Situations(RefCell<Vec<Situation>>);
+
+impl Observer<u32> for Situations {
+    fn on_delete_situation(&self, _key: &u32, situation: DeleteSituation) {
+        self.0.borrow_mut().push(situation.into());
+    }
+
+    fn on_delete_recursion_situation(&self, _key: &u32, situation: DeleteRecursionSituation) {
+        self.0.borrow_mut().push(situation.into());
+    }
+}
+
+/// 删除key后校验性质与内容，以及调平判定的情况
+/// 删除情况应为首个判定，递归情况应为首个向上传递的判定
+/// 同一形状下再逐个删除其余每个键，覆盖连带的其他情况
+fn assert_delete<S: Into<Situation>>(shape: &str, key: u32, expected: S) {
+    let expected = expected.into();
+    let mut tree = build(shape);
+    let mut keys: Vec<u32> = tree.iter().map(|(key, _)| key).collect();
+    assert!(keys.contains(&key), "{} does not contain {}", shape, key);
+    let situations = Rc::new(Situations::default());
+    tree.set_observer(Rc::clone(&situations));
+    tree.delete(&key);
+    let seen = situations.0.take();
+    let judged = match expected {
+        Situation::Delete(_) => seen.first(),
+        Situation::Recursion(_) => seen.iter().find(|situation| matches!(situation, Situation::Recursion(_))),
+    };
+    assert_eq!(judged, Some(&expected), "{} delete {}: {:?}", shape, key, seen);
+    keys.retain(|other| *other != key);
+    assert_eq!(tree.validate(), Ok(()), "{} delete {}", shape, key);
+    assert!(tree.iter().map(|(key, _)| key).eq(keys.iter().copied()), "{} delete {}", shape, key);
+    for other in keys {
+        let mut tree = build(shape);
+        tree.delete(&other);
+        assert_eq!(tree.validate(), Ok(()), "{} delete {}", shape, other);
+        assert_eq!(tree.get(&other), None);
+    }
+}
+
+//删除黑色叶子，父节点为红色，兄弟节点为黑色
+//第一个字母为父节点颜色，第二个为删除节点所在侧，其余为兄弟节点的子节点(R红色，E不存在，W任意)
+
+#[test]
+fn rlrr() {
+    assert_delete("B50(R20(B10,B30(R25,R35)),B70)", 10, DeleteSituation::RLRR);
+}
+
+#[test]
+fn rlre() {
+    assert_delete("B50(R20(B10,B30(R25,-)),B70)", 10, DeleteSituation::RLRE);
+}
+
+#[test]
+fn rler() {
+    assert_delete("B50(R20(B10,B30(-,R35)),B70)", 10, DeleteSituation::RLER);
+}
+
+#[test]
+fn rlee() {
+    assert_delete("B50(R20(B10,B30),B70)", 10, DeleteSituation::RLEE);
+}
+
+#[test]
+fn rrrr() {
+    assert_delete("B50(R20(B10(R5,R15),B30),B70)", 30, DeleteSituation::RRRR);
+}
+
+#[test]
+fn rrer() {
+    assert_delete("B50(R20(B10(-,R15),B30),B70)", 30, DeleteSituation::RRER);
+}
+
+#[test]
+fn rrre() {
+    assert_delete("B50(R20(B10(R5,-),B30),B70)", 30, DeleteSituation::RRRE);
+}
+
+#[test]
+fn rree() {
+    assert_delete("B50(R20(B10,B30),B70)", 30, DeleteSituation::RREE);
+}
+
+//父节点为黑色
+
+#[test]
+fn blr() {
+    assert_delete("B20(B10,R40(B30,B50))", 10, DeleteSituation::BLR);
+    //兄弟的左子节点带有红色子节点，旋转后需要插入调平
+    assert_delete("B20(B10,R40(B30(R25,R35),B50))", 10, DeleteSituation::BLR);
+}
+
+#[test]
+fn blbrw() {
+    assert_delete("B20(B10,B30(R25,-))", 10, DeleteSituation::BLBRW);
+    assert_delete("B20(B10,B30(R25,R35))", 10, DeleteSituation::BLBRW);
+}
+
+#[test]
+fn blber() {
+    assert_delete("B20(B10,B30(-,R35))", 10, DeleteSituation::BLBER);
+}
+
+#[test]
+fn blbee() {
+    assert_delete("B20(B10,B30)", 10, DeleteSituation::BLBEE);
+}
+
+#[test]
+fn brr() {
+    assert_delete("B40(R20(B10,B30),B50)", 50, DeleteSituation::BRR);
+    assert_delete("B40(R20(B10,B30(R25,R35)),B50)", 50, DeleteSituation::BRR);
+}
+
+#[test]
+fn brbwr() {
+    assert_delete("B20(B10(-,R15),B30)", 30, DeleteSituation::BRBWR);
+    assert_delete("B20(B10(R5,R15),B30)", 30, DeleteSituation::BRBWR);
+}
+
+#[test]
+fn brbre() {
+    assert_delete("B20(B10(R5,-),B30)", 30, DeleteSituation::BRBRE);
+}
+
+#[test]
+fn brbee() {
+    assert_delete("B20(B10,B30)", 30, DeleteSituation::BRBEE);
+}
+
+//黑高减少后向上传递，失衡节点为B20(或B80)，其兄弟节点黑高为2
+//第一个字母为失衡节点所在侧，第二个为父节点颜色，其余为兄弟节点及其子节点颜色(W任意)
+
+#[test]
+fn lrbw() {
+    assert_delete("B100(R50(B20(B10,B30),B80(B70,B90)),B150(B125,B175))", 10, DeleteRecursionSituation::LRBW);
+    assert_delete("B100(R50(B20(B10,B30),B80(B70,R90(B85,B95))),B150(B125,B175))", 10, DeleteRecursionSituation::LRBW);
+}
+
+#[test]
+fn lrrb() {
+    assert_delete("B100(R50(B20(B10,B30),B80(R70(B65,B75),B90)),B150(B125,B175))", 10, DeleteRecursionSituation::LRRB);
+}
+
+#[test]
+fn lrrr() {
+    assert_delete("B100(R50(B20(B10,B30),B80(R70(B65,B75),R90(B85,B95))),B150(B125,B175))", 10, DeleteRecursionSituation::LRRR);
+}
+
+#[test]
+fn lbbbb() {
+    assert_delete("B50(B20(B10,B30),B80(B70,B90))", 10, DeleteRecursionSituation::LBBBB);
+    //继续向上传递
+    assert_delete("B100(B50(B20(B10,B30),B80(B70,B90)),B150(B125(B120,B130),B175(B170,B180)))", 10, DeleteRecursionSituation::LBBBB);
+}
+
+#[test]
+fn lbbwr() {
+    assert_delete("B50(B20(B10,B30),B80(B70,R90(B85,B95)))", 10, DeleteRecursionSituation::LBBWR);
+    assert_delete("B50(B20(B10,B30),B80(R70(B65,B75),R90(B85,B95)))", 10, DeleteRecursionSituation::LBBWR);
+}
+
+#[test]
+fn lbbrb() {
+    assert_delete("B50(B20(B10,B30),B80(R70(B65,B75),B90))", 10, DeleteRecursionSituation::LBBRB);
+}
+
+#[test]
+fn lbr() {
+    assert_delete("B50(B20(B10,B30),R80(B70(B65,B75),B90(B85,B95)))", 10, DeleteRecursionSituation::LBR);
+    assert_delete("B50(B20(B10,B30),R80(B70(R65(B62,B67),B75),B90(B85,B95)))", 10, DeleteRecursionSituation::LBR);
+}
+
+#[test]
+fn rrwb() {
+    assert_delete("B100(B50(B25,B75),R150(B120(B110,B130),B180(B170,B190)))", 190, DeleteRecursionSituation::RRWB);
+    assert_delete("B100(B50(B25,B75),R150(B120(R110(B105,B115),B130),B180(B170,B190)))", 190, DeleteRecursionSituation::RRWB);
+}
+
+#[test]
+fn rrbr() {
+    assert_delete("B100(B50(B25,B75),R150(B120(B110,R130(B125,B135)),B180(B170,B190)))", 190, DeleteRecursionSituation::RRBR);
+}
+
+#[test]
+fn rrrr_recursion() {
+    assert_delete("B100(B50(B25,B75),R150(B120(R110(B105,B115),R130(B125,B135)),B180(B170,B190)))", 190, DeleteRecursionSituation::RRRR);
+}
+
+#[test]
+fn rbbbb() {
+    assert_delete("B50(B20(B10,B30),B80(B70,B90))", 90, DeleteRecursionSituation::RBBBB);
+    assert_delete("B100(B50(B25(B20,B30),B75(B70,B80)),B150(B120(B110,B130),B180(B170,B190)))", 190, DeleteRecursionSituation::RBBBB);
+}
+
+#[test]
+fn rbbrw() {
+    assert_delete("B50(B20(R10(B5,B15),B30),B80(B70,B90))", 90, DeleteRecursionSituation::RBBRW);
+    assert_delete("B50(B20(R10(B5,B15),R30(B25,B35)),B80(B70,B90))", 90, DeleteRecursionSituation::RBBRW);
+}
+
+#[test]
+fn rbbbr() {
+    assert_delete("B50(B20(B10,R30(B25,B35)),B80(B70,B90))", 90, DeleteRecursionSituation::RBBBR);
+}
+
+#[test]
+fn rbr() {
+    assert_delete("B50(R20(B10(B5,B15),B30(B25,B35)),B80(B70,B90))", 90, DeleteRecursionSituation::RBR);
+    assert_delete("B50(R20(B10(B5,B15),B30(B25,R35(B33,B37))),B80(B70,B90))", 90, DeleteRecursionSituation::RBR);
+}